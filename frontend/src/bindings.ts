@@ -43,8 +43,8 @@ export const commands = {
         return await TAURI_INVOKE("get_lobby_state");
     },
     /**
-     * (Screen: Lobby) HOST ONLY: Push new settings to everyone, does nothing on clients. Returns the
-     * new lobby state
+     * (Screen: Lobby) HOST ONLY: Push new settings to everyone and save them as the last used
+     * settings, does nothing on clients. Returns the new lobby state
      */
     async hostUpdateSettings(settings: GameSettings): Promise<null> {
         return await TAURI_INVOKE("host_update_settings", { settings });
@@ -57,36 +57,39 @@ export const commands = {
     },
     /**
      * (Screen: Lobby) HOST ONLY: Start the game, stops anyone else from joining and switched screen
-     * to AppScreen::Game.
+     * to AppScreen::Game. Fails if everyone is on the same team.
      */
     async hostStartGame(): Promise<null> {
         return await TAURI_INVOKE("host_start_game");
     },
     /**
-     * (Screen: Game) Mark this player as caught, this player will become a seeker. Returns the new game state
+     * (Screen: Game) Mark this player as caught, this player will become a seeker. `by` is the seeker
+     * that caught this player, if known. Fails if this player is in a safe zone.
      */
-    async markCaught(): Promise<null> {
-        return await TAURI_INVOKE("mark_caught");
+    async markCaught(by: string | null): Promise<null> {
+        return await TAURI_INVOKE("mark_caught", { by });
     },
     /**
      * (Screen: Game) Grab a powerup on the map, this should be called when the user is *in range* of
-     * the powerup. Returns the new game state after rolling for the powerup
+     * the powerup. Returns whether the grab succeeded, it will fail if the game has a grab radius set
+     * and the player is too far from the powerup.
      */
-    async grabPowerup(): Promise<null> {
+    async grabPowerup(): Promise<boolean> {
         return await TAURI_INVOKE("grab_powerup");
     },
     /**
      * (Screen: Game) Use the currently held powerup in the player's held_powerup. Does nothing if the
-     * player has none. Returns the updated game state
+     * player has none. Fails without using it up if the powerup can't be used in the player's
+     * current role.
      */
     async activatePowerup(): Promise<null> {
         return await TAURI_INVOKE("activate_powerup");
     },
     /**
      * (Screen: Menu) Check if a room code is valid to join, use this before starting a game
-     * for faster error checking.
+     * for faster error checking. Tells apart a bad code from not being able to reach the server.
      */
-    async checkRoomCode(code: string): Promise<boolean> {
+    async checkRoomCode(code: string): Promise<RoomStatus> {
         return await TAURI_INVOKE("check_room_code", { code });
     },
     /**
@@ -104,10 +107,10 @@ export const commands = {
         return await TAURI_INVOKE("replay_game", { id });
     },
     /**
-     * (Screen: Menu) Get a list of all previously played games, returns of list of DateTimes that represent when
-     * each game started, use this as a key
+     * (Screen: Menu) Get a list of all previously played games, returns a list of each game's id
+     * and when it started, most recent first. Use the id as a key.
      */
-    async listGameHistories(): Promise<string[]> {
+    async listGameHistories(): Promise<[string, string][]> {
         return await TAURI_INVOKE("list_game_histories");
     },
     /**
@@ -124,7 +127,8 @@ export const commands = {
         return await TAURI_INVOKE("get_game_settings");
     },
     /**
-     * (Screen: Game) Get the current state of the game.
+     * (Screen: Game) Get the current state of the game, `players` will have display names filled in
+     * from the profiles for this game.
      */
     async getGameState(): Promise<GameUiState> {
         return await TAURI_INVOKE("get_game_state");
@@ -134,6 +138,478 @@ export const commands = {
      */
     async completeSetup(profile: PlayerProfile): Promise<null> {
         return await TAURI_INVOKE("complete_setup", { profile });
+    },
+    /**
+     * (Screen: Game) Ask the other players to resend their caught state and pings, use this if the
+     * game state seems out of sync (e.g. on pull-to-refresh)
+     */
+    async resyncGameState(): Promise<null> {
+        return await TAURI_INVOKE("resync_game_state");
+    },
+    /**
+     * (Screen: Replay) Get every powerup that spawned in the game being replayed, with when it
+     * despawned and who grabbed it.
+     */
+    async getReplayPowerups(): Promise<PowerupTimelineEntry[]> {
+        return await TAURI_INVOKE("get_replay_powerups");
+    },
+    /**
+     * (Screen: Replay) Get seekers ranked by how many players they caught in the game being
+     * replayed, most catches first. Empty if catch scores weren't tracked for the game.
+     */
+    async getReplayCatchRanking(): Promise<[string, number][]> {
+        return await TAURI_INVOKE("get_replay_catch_ranking");
+    },
+    /**
+     * (Screen: Game) HOST ONLY: End the game early for everyone. Unlike quitting, the game will still
+     * go through post-game sync and be saved for replay.
+     */
+    async hostEndGame(): Promise<null> {
+        return await TAURI_INVOKE("host_end_game");
+    },
+    /**
+     * (Screen: Game) Give up and become a seeker, this will end the game if this player is the last
+     * hider. Fails if this player is already a seeker.
+     */
+    async concedeGame(): Promise<null> {
+        return await TAURI_INVOKE("concede_game");
+    },
+    /**
+     * (Screen: Menu) Get the settings last used to host a game, use this to prefill settings when
+     * creating a lobby. Returns the default settings if none have been saved yet.
+     */
+    async getLastSettings(): Promise<GameSettings> {
+        return await TAURI_INVOKE("get_last_settings");
+    },
+    /**
+     * (Screen: Menu) Save settings as a named preset, overwriting any preset with the same name.
+     * Fails if the settings are invalid, the error will list every problem.
+     */
+    async saveSettingsPreset(name: string, settings: GameSettings): Promise<null> {
+        return await TAURI_INVOKE("save_settings_preset", { name, settings });
+    },
+    /**
+     * (Screen: Menu) Get the names of all saved settings presets in alphabetical order
+     */
+    async listSettingsPresets(): Promise<string[]> {
+        return await TAURI_INVOKE("list_settings_presets");
+    },
+    /**
+     * (Screen: Menu) Get the settings saved in the preset with the given name
+     */
+    async loadSettingsPreset(name: string): Promise<GameSettings> {
+        return await TAURI_INVOKE("load_settings_preset", { name });
+    },
+    /**
+     * (Screen: Menu) Delete the preset with the given name
+     */
+    async deleteSettingsPreset(name: string): Promise<null> {
+        return await TAURI_INVOKE("delete_settings_preset", { name });
+    },
+    /**
+     * Get which game events send an OS notification while the app is in the background
+     */
+    async getNotificationPreferences(): Promise<NotificationPreferences> {
+        return await TAURI_INVOKE("get_notification_preferences");
+    },
+    /**
+     * Set which game events send an OS notification while the app is in the background
+     */
+    async setNotificationPreferences(prefs: NotificationPreferences): Promise<null> {
+        return await TAURI_INVOKE("set_notification_preferences", { prefs });
+    },
+    /**
+     * (Screen: Game) HOST ONLY: Change settings mid-game for everyone. Settings that have already
+     * taken effect (e.g. hiding time once seekers are released) can't be changed, the error will
+     * list every problem.
+     */
+    async hostAmendSettings(settings: GameSettings): Promise<null> {
+        return await TAURI_INVOKE("host_amend_settings", { settings });
+    },
+    /**
+     * (Screen: GameOver) Get the results of the game that just ended
+     */
+    async getGameResults(): Promise<AppGameHistory> {
+        return await TAURI_INVOKE("get_game_results");
+    },
+    /**
+     * (Screen: GameOver) Leave the results screen and go back to the menu
+     */
+    async dismissGameResults(): Promise<null> {
+        return await TAURI_INVOKE("dismiss_game_results");
+    },
+    /**
+     * (Screen: GameOver) HOST ONLY: Bring everyone from the game that just ended back to a lobby
+     * with the same players and settings. Everyone will switch to [AppScreen::Lobby].
+     */
+    async hostStartRematch(): Promise<null> {
+        return await TAURI_INVOKE("host_start_rematch");
+    },
+    /**
+     * (Screen: Lobby) Check that location permission is granted and we can get a GPS fix, asks
+     * for permission if the user hasn't been asked yet. Use this before starting a game.
+     */
+    async checkLocationPermissionAndFix(): Promise<LocationReadiness> {
+        return await TAURI_INVOKE("check_location_permission_and_fix");
+    },
+    /**
+     * (Screen: Game) Free every player in jail, the local player must be a hider near the jail.
+     * Returns the IDs of the players that were freed.
+     */
+    async attemptJailbreak(): Promise<string[]> {
+        return await TAURI_INVOKE("attempt_jailbreak");
+    },
+    /**
+     * (Screen: Menu) Export the game history specified by id as a self-contained JSON replay bundle
+     * that can be shared and imported on another device
+     */
+    async exportReplayBundle(id: string): Promise<string> {
+        return await TAURI_INVOKE("export_replay_bundle", { id });
+    },
+    /**
+     * (Screen: Menu) Import a replay bundle made by [export_replay_bundle] into the game history,
+     * returns the id of the imported game. Errors if a game with the same id already exists.
+     */
+    async importReplay(json: string): Promise<string> {
+        return await TAURI_INVOKE("import_replay", { json });
+    },
+    /**
+     * Get the STUN/TURN servers used to connect to other players, [Option::None] if the defaults
+     * are being used
+     */
+    async getIceServers(): Promise<IceServerSettings | null> {
+        return await TAURI_INVOKE("get_ice_servers");
+    },
+    /**
+     * Set the STUN/TURN servers used to connect to other players, set to `null` to go back to the
+     * defaults. Players on strict networks may not be able to connect to others without a TURN
+     * server. Takes effect the next time a lobby is joined.
+     */
+    async setIceServers(iceServers: IceServerSettings | null): Promise<null> {
+        return await TAURI_INVOKE("set_ice_servers", { iceServers });
+    },
+    /**
+     * (Screen: Game) Get a map of player IDs to whether that player is a seeker. A lighter
+     * alternative to [get_game_state] for updates that only need to know who's been caught, use
+     * [get_game_state] for the initial load.
+     */
+    async getCaughtState(): Promise<Partial<{ [key in string]: boolean }>> {
+        return await TAURI_INVOKE("get_caught_state");
+    },
+    /**
+     * (Screen: Lobby) Set the color of our map pin and scoreboard row for this lobby, must be a hex
+     * color like `#ff0000`. Pass `null` to clear it.
+     */
+    async setLobbyColor(color: string | null): Promise<null> {
+        return await TAURI_INVOKE("set_lobby_color", { color });
+    },
+    /**
+     * (Screen: Lobby) HOST ONLY: Give every player a unique color, players keep the color they
+     * picked unless someone else already has it
+     */
+    async hostAssignColors(): Promise<null> {
+        return await TAURI_INVOKE("host_assign_colors");
+    },
+    /**
+     * (Screen: Menu) Join a game that has already started as a spectator, the host must have
+     * enabled [GameSettings::allow_spectators]. This triggers a screen change to [AppScreen::Game]
+     */
+    async startSpectating(joinCode: string): Promise<null> {
+        return await TAURI_INVOKE("start_spectating", { joinCode });
+    },
+    /**
+     * (Screen: Menu) Get room codes this device recently joined and when **in UTC**, most recent
+     * first. Use this to offer quickly rejoining friends.
+     */
+    async recentRoomCodes(): Promise<[string, string][]> {
+        return await TAURI_INVOKE("recent_room_codes");
+    },
+    /**
+     * (Screen: Replay) Get the IDs of every player with a location track in the game being
+     * replayed, for picking who to follow.
+     */
+    async replayPlayerIds(): Promise<string[]> {
+        return await TAURI_INVOKE("replay_player_ids");
+    },
+    /**
+     * (Screen: Replay) Get the location track of a single player in the game being replayed, for
+     * following them without loading everyone else's track.
+     */
+    async getReplayPlayerTrack(id: string): Promise<[string, Location][]> {
+        return await TAURI_INVOKE("get_replay_player_track", { id });
+    },
+    /**
+     * (Screen: Game) Take back marking this player as caught in case it was a mis-tap. Only possible
+     * until [GameUiState::undo_catch_until], fails after that.
+     */
+    async undoCaught(): Promise<null> {
+        return await TAURI_INVOKE("undo_caught");
+    },
+    /**
+     * (Screen: Menu) Check if a room code is the right length and only uses characters room codes
+     * can have, without any network calls. Use this to reject obviously bad input early,
+     * [check_room_code] still needs to be used to check the room exists.
+     */
+    async isValidRoomCodeFormat(code: string): Promise<boolean> {
+        return await TAURI_INVOKE("is_valid_room_code_format", { code });
+    },
+    /**
+     * (Screen: Menu) Explain what the given settings will do during a game in plain terms, use this
+     * to show hosts a preview before they start
+     */
+    async describeSettings(settings: GameSettings): Promise<SettingsSummary> {
+        return await TAURI_INVOKE("describe_settings", { settings });
+    },
+    /**
+     * (Screen: Replay) Get the end-of-game awards for the game being replayed, an award is null if
+     * nobody earned it.
+     */
+    async getReplayAwards(): Promise<GameAwards> {
+        return await TAURI_INVOKE("get_replay_awards");
+    },
+    /**
+     * (Screen: Lobby) Send our profile to everyone again and ask them to send theirs back, use this
+     * if players are missing from the lobby
+     */
+    async resyncLobbyProfiles(): Promise<null> {
+        return await TAURI_INVOKE("resync_lobby_profiles");
+    },
+    /**
+     * (Screen: Replay) Get the game state as it was after the first `index` events of the game being
+     * replayed, for stepping through a game event by event. Fails with `NotFound` if the game
+     * doesn't have that many events.
+     */
+    async replayStep(index: number): Promise<GameUiState> {
+        return await TAURI_INVOKE("replay_step", { index });
+    },
+    /**
+     * (Screen: Menu) Get the game that was in progress when the app last closed (e.g. the OS killed
+     * the app while it was in the background), if any. Use this to offer resuming it with
+     * [resume_interrupted_game], which checks the game is still going.
+     */
+    async getInterruptedGame(): Promise<InterruptedGameInfo | null> {
+        return await TAURI_INVOKE("get_interrupted_game");
+    },
+    /**
+     * (Screen: Menu) Reconnect to the game that was in progress when the app last closed and pick up
+     * where we left off. Fails with `NotFound` if there's no game to resume or its room is gone, in
+     * which case it's forgotten. This triggers a screen change to [AppScreen::Game]
+     */
+    async resumeInterruptedGame(): Promise<null> {
+        return await TAURI_INVOKE("resume_interrupted_game");
+    },
+    /**
+     * (Screen: Menu) Forget the game that was in progress when the app last closed
+     */
+    async discardInterruptedGame(): Promise<null> {
+        return await TAURI_INVOKE("discard_interrupted_game");
+    },
+    /**
+     * Send a sample notification so the user can check notifications work before a game, asks for
+     * permission if the user hasn't been asked yet
+     */
+    async sendTestNotification(): Promise<NotificationTestResult> {
+        return await TAURI_INVOKE("send_test_notification");
+    },
+    /**
+     * (Screen: Game) Get the IDs of players still connected to the game, sorted. Anyone in
+     * [get_profiles] that's missing here has dropped out.
+     */
+    async getActivePlayers(): Promise<string[]> {
+        return await TAURI_INVOKE("get_active_players");
+    },
+    /**
+     * (Screen: Game) Instantly reveal the local player's location to everyone, outside the
+     * normal ping schedule. Only hiders can, and only [GameSettings::panic_pings] times a game.
+     */
+    async panicPing(): Promise<null> {
+        return await TAURI_INVOKE("panic_ping");
+    },
+    /**
+     * (Screen: Game) Drop a pin on the map for the other seekers with an optional label, e.g.
+     * "checking the north park". Only seekers can, and only seekers will see it in `seeker_pins`.
+     */
+    async dropSeekerPin(location: Location, label: string | null): Promise<null> {
+        return await TAURI_INVOKE("drop_seeker_pin", { location, label });
+    },
+    /**
+     * Get the directory map tiles downloaded by [prefetch_map_tiles] are kept in, tiles are
+     * stored as `{z}/{x}/{y}.png` under it. The map should load tiles through the `tiles` URI scheme
+     * (`convertFileSrc("{z}/{x}/{y}.png", "tiles")`) instead, which reads this cache and downloads
+     * any tiles missing from it.
+     */
+    async getMapTileCacheDir(): Promise<string> {
+        return await TAURI_INVOKE("get_map_tile_cache_dir");
+    },
+    /**
+     * Get the URL map tiles are downloaded from, with `{z}`, `{x}`, and `{y}` where the tile goes
+     */
+    async getTileServer(): Promise<string> {
+        return await TAURI_INVOKE("get_tile_server");
+    },
+    /**
+     * Set the URL map tiles are downloaded from, it needs `{z}`, `{x}`, and `{y}` where the tile
+     * goes. Set to `null` to go back to OpenStreetMap. Tiles already downloaded are kept.
+     */
+    async setTileServer(tileServer: string | null): Promise<null> {
+        return await TAURI_INVOKE("set_tile_server", { tileServer });
+    },
+    /**
+     * (Screen: Lobby, Game) Download map tiles covering `bounds` for every zoom level in
+     * `zoom_range` (inclusive) so the map works offline, leave `bounds` out to use the play area.
+     * Sends MapTilesProgress events as tiles finish and returns the final progress. Tiles already
+     * downloaded are skipped and downloads are throttled, so this can take a while. Fails if the
+     * area needs too many tiles.
+     */
+    async prefetchMapTiles(
+        bounds: TileBounds | null,
+        zoomRange: [number, number]
+    ): Promise<MapTilesProgress> {
+        return await TAURI_INVOKE("prefetch_map_tiles", { bounds, zoomRange });
+    },
+    /**
+     * (Screen: Setup, Menu) Delete the profile, settings, presets, game histories, and recent room
+     * codes, then go back to AppScreen::Setup. `confirm` must be true, this can't be undone.
+     */
+    async resetAppData(confirm: boolean): Promise<null> {
+        return await TAURI_INVOKE("reset_app_data", { confirm });
+    },
+    /**
+     * Get whether every event in a game is written to a log file for debugging
+     */
+    async getLogGameEvents(): Promise<boolean> {
+        return await TAURI_INVOKE("get_log_game_events");
+    },
+    /**
+     * Set whether every event in a game is written to a log file for debugging, takes effect from
+     * the next game
+     */
+    async setLogGameEvents(enabled: boolean): Promise<null> {
+        return await TAURI_INVOKE("set_log_game_events", { enabled });
+    },
+    /**
+     * Get the path of the event log for the most recent game, for attaching to bug reports. `null`
+     * if no games have been logged, see [set_log_game_events].
+     */
+    async getEventLogPath(): Promise<string | null> {
+        return await TAURI_INVOKE("get_event_log_path");
+    },
+    /**
+     * (Screen: Menu) Get the signaling server's version, use this before hosting or joining to warn
+     * the user if the server isn't `compatible` with this version of the app. Errors with `Network`
+     * if the server can't be reached or is too old to report its version.
+     */
+    async signalingServerInfo(): Promise<ServerInfo> {
+        return await TAURI_INVOKE("signaling_server_info");
+    },
+    /**
+     * (Screen: Game) Get roughly how far the local player has moved this game in meters, GPS
+     * glitches that jump implausibly far are left out
+     */
+    async getDistanceTraveled(): Promise<number> {
+        return await TAURI_INVOKE("get_distance_traveled");
+    },
+    /**
+     * (Screen: Lobby) Get the play area, powerup locations, safe zones, and jail from the lobby's
+     * settings for previewing on a map. Only call again when [LobbyState]'s `map_revision` changes.
+     */
+    async getLobbyMapPreview(): Promise<LobbyMapPreview> {
+        return await TAURI_INVOKE("get_lobby_map_preview");
+    },
+    /**
+     * (Screen: Game) HOST ONLY: Ping every hider right now regardless of the ping schedule, or every
+     * seeker if [GameSettings::reverse_pings] is on
+     */
+    async hostPingAll(): Promise<null> {
+        return await TAURI_INVOKE("host_ping_all");
+    },
+    /**
+     * Get whether every event in a game is streamed out for spectator dashboards, see
+     * [get_event_stream_info]
+     */
+    async getStreamGameEvents(): Promise<boolean> {
+        return await TAURI_INVOKE("get_stream_game_events");
+    },
+    /**
+     * Set whether every event in a game is streamed out for spectator dashboards, takes effect from
+     * the next game
+     */
+    async setStreamGameEvents(enabled: boolean): Promise<null> {
+        return await TAURI_INVOKE("set_stream_game_events", { enabled });
+    },
+    /**
+     * Get the URL and token a spectator dashboard on this device can connect to for the game event
+     * stream, starting it if it isn't running. `null` if streaming is turned off, see
+     * [set_stream_game_events].
+     *
+     * The stream is Server-Sent Events, each event's data is a JSON object of the form
+     * `{ "at": <UTC timestamp>, "direction": "Sent" | "Received", "event": GameEvent }`.
+     */
+    async getEventStreamInfo(): Promise<EventStreamInfo | null> {
+        return await TAURI_INVOKE("get_event_stream_info");
+    },
+    /**
+     * (Screen: Menu) Host a new lobby with a room code of the host's choosing instead of a random
+     * one. Fails with `Validation` if the code can't be a room code, or `RoomCodeTaken` if the server
+     * won't create a room with it. This triggers a screen change to [AppScreen::Lobby]
+     */
+    async hostLobbyWithCode(code: string, settings: GameSettings): Promise<null> {
+        return await TAURI_INVOKE("host_lobby_with_code", { code, settings });
+    },
+    /**
+     * Get the location accuracy modes this device can switch between, empty if it can't provide
+     * locations at all
+     */
+    async getLocationAccuracyModes(): Promise<LocationAccuracy[]> {
+        return await TAURI_INVOKE("get_location_accuracy_modes");
+    },
+    /**
+     * Get how accurate (and battery hungry) locations should be during games
+     */
+    async getLocationAccuracy(): Promise<LocationAccuracy> {
+        return await TAURI_INVOKE("get_location_accuracy");
+    },
+    /**
+     * Set how accurate (and battery hungry) locations should be during games, takes effect from the
+     * next game
+     */
+    async setLocationAccuracy(accuracy: LocationAccuracy): Promise<null> {
+        return await TAURI_INVOKE("set_location_accuracy", { accuracy });
+    },
+    /**
+     * (Screen: Game) DEBUG BUILDS ONLY: Run a game tick as if it were `now` **in UTC**, to step
+     * through timing (hiding time, pings, powerups) without waiting. Doesn't exist in release builds.
+     */
+    async debugForceTick(now: string): Promise<null> {
+        return await TAURI_INVOKE("debug_force_tick", { now });
+    },
+    /**
+     * DEBUG BUILDS ONLY: Get whether games only tick when [debug_force_tick] is called
+     */
+    async debugGetManualTick(): Promise<boolean> {
+        return await TAURI_INVOKE("debug_get_manual_tick");
+    },
+    /**
+     * DEBUG BUILDS ONLY: Set whether games only tick when [debug_force_tick] is called instead of
+     * every second, for stepping through a game deterministically. Takes effect from the next game.
+     */
+    async debugSetManualTick(enabled: boolean): Promise<null> {
+        return await TAURI_INVOKE("debug_set_manual_tick", { enabled });
+    },
+    /**
+     * DEBUG BUILDS ONLY: Get whether lobbies connect in-process instead of through the signaling
+     * server
+     */
+    async debugGetLoopbackTransport(): Promise<boolean> {
+        return await TAURI_INVOKE("debug_get_loopback_transport");
+    },
+    /**
+     * DEBUG BUILDS ONLY: Set whether lobbies connect in-process instead of through the signaling
+     * server, for trying out lobbies and games without a server. Only lobbies in this app can be
+     * joined this way. Takes effect from the next lobby.
+     */
+    async debugSetLoopbackTransport(enabled: boolean): Promise<null> {
+        return await TAURI_INVOKE("debug_set_loopback_transport", { enabled });
     }
 };
 
@@ -143,34 +619,139 @@ export const events = __makeEvents__<{
     changeScreen: ChangeScreen;
     gameStateUpdate: GameStateUpdate;
     lobbyStateUpdate: LobbyStateUpdate;
+    seekersReleasedEvent: SeekersReleasedEvent;
+    playerJoinedLobby: PlayerJoinedLobby;
+    playerLeftLobby: PlayerLeftLobby;
+    incompatiblePlayerInLobby: IncompatiblePlayerInLobby;
+    mapTilesProgress: MapTilesProgress;
 }>({
     changeScreen: "change-screen",
     gameStateUpdate: "game-state-update",
-    lobbyStateUpdate: "lobby-state-update"
+    lobbyStateUpdate: "lobby-state-update",
+    seekersReleasedEvent: "seekers-released-event",
+    playerJoinedLobby: "player-joined-lobby",
+    playerLeftLobby: "player-left-lobby",
+    incompatiblePlayerInLobby: "incompatible-player-in-lobby",
+    mapTilesProgress: "map-tiles-progress"
 });
 
 /** user-defined constants **/
 
 /** user-defined types **/
 
+/**
+ * An error returned from a command, lets the frontend act differently depending on what went
+ * wrong
+ */
+export type AppError =
+    /**
+     * The app isn't on the screen this command is meant for
+     */
+    | "WrongScreen"
+    /**
+     * Couldn't reach the server or other players
+     */
+    | { Network: string }
+    /**
+     * Given input didn't make sense, contains a list of human-readable problems
+     */
+    | { Validation: string[] }
+    /**
+     * The requested thing doesn't exist
+     */
+    | "NotFound"
+    /**
+     * The room code the host asked for is already being used by another room
+     */
+    | "RoomCodeTaken"
+    /**
+     * Any other error
+     */
+    | { Other: string };
 export type AppGameHistory = {
     history: GameHistory;
     profiles: Partial<{ [key in string]: PlayerProfile }>;
     settings: GameSettings;
 };
-export type AppScreen = "Setup" | "Menu" | "Lobby" | "Game" | "Replay";
+export type AppScreen = "Setup" | "Menu" | "Lobby" | "Game" | "GameOver" | "Replay";
+/**
+ * Why a player became a seeker
+ */
+export type CatchReason =
+    /**
+     * The player was found by a seeker
+     */
+    | "Caught"
+    /**
+     * The player gave up
+     */
+    | "Conceded";
 /**
  * The app is changing screens, contains the screen it's switching to
  */
 export type ChangeScreen = AppScreen;
+/**
+ * How to connect to a [GameEventStream]
+ */
+export type EventStreamInfo = {
+    /**
+     * URL to open an `EventSource` on, includes the token
+     */
+    url: string;
+    /**
+     * Token connections need to pass as the `token` query parameter
+     */
+    token: string;
+};
+/**
+ * Fun superlatives for the end of a game, each is the winning player and their value.
+ * An award is [Option::None] if nobody earned it.
+ */
+export type GameAwards = {
+    /**
+     * Player that traveled the furthest, in meters
+     */
+    most_distance: [string, number] | null;
+    /**
+     * Hider that stayed hidden the longest, in seconds
+     */
+    longest_survival: [string, number] | null;
+    /**
+     * Seeker that caught the most players
+     */
+    most_catches: [string, number] | null;
+    /**
+     * Player that grabbed the most powerups
+     */
+    most_powerups: [string, number] | null;
+};
 /**
  * An event used between players to update state
  */
 export type GameEvent =
     /**
-     * A player has been caught and is now a seeker, contains the ID of the caught player
+     * A player has been caught and is now a seeker
      */
-    | { PlayerCaught: string }
+    | {
+          PlayerCaught: {
+              /**
+               * ID of the caught player
+               */
+              player: string;
+              /**
+               * ID of the seeker that caught them, [Option::None] if the player reported it themselves.
+               *
+               * Histories saved before this field existed won't have it, so default to
+               * [Option::None] when deserializing.
+               */
+              by: string | null;
+              /**
+               * Why the player became a seeker, older histories will default to
+               * [CatchReason::Caught]
+               */
+              reason: CatchReason;
+          };
+      }
     /**
      * Public ping from a player revealing location
      */
@@ -188,51 +769,281 @@ export type GameEvent =
      * Contains location history of the given player, used after the game to sync location
      * histories
      */
-    | { PostGameSync: [string, [string, Location][]] };
-export type GameHistory = {
-    my_id: string;
-    game_started: string;
-    game_ended: string;
-    events: [string, GameEvent][];
-    locations: [string, [string, Location][]][];
-};
-/**
- * Settings for the game, host is the only person able to change these
- */
-export type GameSettings = {
+    | { PostGameSync: [string, [string, Location][]] }
+    /**
+     * The host ended the game early, contains the ID of the host
+     */
+    | { ForceEnd: string }
+    /**
+     * A powerup spawned at the given location. Players normally roll spawns locally with the
+     * shared seed and this is only recorded in the event history for replays. With
+     * [crate::GameSettings::powerup_near_hiders_radius_meters] set the host picks the location
+     * and sends this to everyone.
+     */
+    | { PowerupSpawn: Location }
+    /**
+     * A player suspects they've missed events and is asking everyone to send a [GameEvent::FullSync],
+     * contains the ID of the player requesting
+     */
+    | { RequestSync: string }
+    /**
+     * Authoritative caught state and active pings of the sending player, sent in response to
+     * [GameEvent::RequestSync]
+     */
+    | {
+          FullSync: [Partial<{ [key in string]: boolean }>, Partial<{ [key in string]: PlayerPing }>];
+      }
+    /**
+     * A hider used a [crate::PowerUpType::FreezeSeekers], seekers are frozen until the given
+     * time **in UTC**
+     */
+    | { SeekersFrozen: string }
+    /**
+     * The host changed settings mid-game, only settings that haven't taken effect yet may
+     * differ from the current ones
+     */
+    | { SettingsAmendment: GameSettings }
+    /**
+     * A hider broke the given player out of jail, they're a hider again
+     */
+    | { PlayerFreed: string }
+    /**
+     * Sent by the host to a spectator that just connected so they can start watching the game
+     */
+    | {
+          SpectatorSync: {
+              settings: GameSettings;
+              host: string;
+              /**
+               * So the spectator saves the game's history under the same ID as everyone else
+               */
+              game_id: string;
+              /**
+               * When the game started **in UTC**, so the spectator's timers line up with the host's
+               */
+              game_started: string;
+              caught_state: Partial<{ [key in string]: boolean }>;
+              pings: Partial<{ [key in string]: PlayerPing }>;
+          };
+      }
+    /**
+     * A player took back marking themselves caught shortly after doing it, they're a hider again
+     */
+    | {
+          UncaughtCorrection: {
+              /**
+               * ID of the player that's a hider again
+               */
+              player: string;
+              /**
+               * The seeker that was credited with the catch, they lose the point
+               */
+              by: string | null;
+          };
+      }
+    /**
+     * A player that dropped out picked the game back up from a [crate::GameSnapshot] on a new
+     * connection. They were removed when they disconnected so this adds them back.
+     */
+    | {
+          PlayerRejoined: {
+              /**
+               * ID the player had in the game, not their new connection's ID
+               */
+              player: string;
+              /**
+               * Whether they're a seeker
+               */
+              seeker: boolean;
+          };
+      }
+    /**
+     * A seeker dropped a map pin for the other seekers with an optional label, only ever sent
+     * to seekers, see [crate::Game::drop_seeker_pin]
+     */
+    | { SeekerPin: [Location, string | null] };
+export type GameHistory = {
+    my_id: string;
+    /**
+     * Unique ID of this game, histories saved before this existed are given a new one when
+     * loaded
+     */
+    id: string;
+    game_started: string;
+    game_ended: string;
+    events: [string, GameEvent][];
+    locations: [string, [string, Location][]][];
+    /**
+     * Number of players each seeker caught, empty if catch scores weren't tracked
+     */
+    catches_by: Partial<{ [key in string]: number }>;
+    /**
+     * Longest catch streak of each seeker, empty if catch scores weren't tracked
+     */
+    max_streaks: Partial<{ [key in string]: number }>;
+    /**
+     * Players that started the game as seekers, empty for histories saved before this was
+     * tracked
+     */
+    initial_seekers: string[];
+    /**
+     * ID of the player that hosted the game, [Option::None] for histories saved before this was
+     * tracked
+     */
+    host: string | null;
+};
+/**
+ * Which part of the game we're in, see [GameUiState::phase]
+ */
+export type GamePhase =
+    /**
+     * Seekers are locked while the hiders hide
+     */
+    | "Hiding"
+    /**
+     * Seekers have been released and are looking for hiders
+     */
+    | "Active"
+    /**
+     * The game is over, histories are being synced or have been
+     */
+    | "Ended";
+/**
+ * Settings for the game, host is the only person able to change these
+ */
+export type GameSettings = {
+    /**
+     * The random seed used for shared rng, the host replaces this with a fresh one when each game
+     * starts
+     */
+    random_seed: number;
+    /**
+     * The number of seconds to wait before seekers are allowed to go
+     */
+    hiding_time_seconds: number;
+    /**
+     * Condition to wait for global pings to begin
+     */
+    ping_start: PingStartCondition;
+    /**
+     * Time between pings after the condition is met, see [GameSettings::first_ping_immediate]
+     * for when the first one happens
+     */
+    ping_minutes_interval: number;
+    /**
+     * Condition for powerups to start spawning
+     */
+    powerup_start: PingStartCondition;
+    /**
+     * Chance every minute of a powerup spawning, out of 100
+     */
+    powerup_chance: number;
+    /**
+     * Hard cooldown between powerups spawning
+     */
+    powerup_minutes_cooldown: number;
+    /**
+     * Locations that powerups may spawn at
+     */
+    powerup_locations: Location[];
+    /**
+     * Whether to award seekers points for each player they catch
+     */
+    track_catch_scores: boolean;
+    /**
+     * Whether seekers get a "warmer/colder" hint for each hider between pings, based on the
+     * hider's last ping
+     */
+    hot_cold_hints: boolean;
+    /**
+     * How close (in meters) a player needs to be to a powerup to grab it, [Option::None] to let
+     * players grab powerups from anywhere
+     */
+    powerup_grab_radius_meters: number | null;
+    /**
+     * Spawn powerups at a random point within this many meters of the hiders' last pings
+     * instead of at [GameSettings::powerup_locations], so they stay reachable in games that move
+     * around. The host picks the point and sends it to everyone. [Option::None] to only use
+     * [GameSettings::powerup_locations].
+     */
+    powerup_near_hiders_radius_meters: number | null;
+    /**
+     * How long (in seconds) seekers stay frozen after a hider uses a
+     * [crate::PowerUpType::FreezeSeekers] powerup
+     */
+    freeze_seconds: number;
+    /**
+     * Where caught players go to wait to be freed, required for
+     * [GameSettings::enable_jailbreak]
+     */
+    jail_location: Location | null;
+    /**
+     * Whether hiders can free caught players by going to [GameSettings::jail_location]
+     */
+    enable_jailbreak: boolean;
+    /**
+     * Areas (center and radius in meters) where hiders can't be caught
+     */
+    safe_zones: [Location, number][];
+    /**
+     * Replace display names and profile pictures with generic labels for the game, for
+     * streaming or privacy
+     */
+    anonymize_profiles: boolean;
+    /**
+     * Let people join the room to watch the game after it starts
+     */
+    allow_spectators: boolean;
+    /**
+     * Vertices of the area players should stay in, in order. Leaving it only warns the player,
+     * it's up to house rules what happens then. [Option::None] for no boundary.
+     */
+    play_area: Location[] | null;
+    /**
+     * Randomly pick this many players to be seekers when the game starts, ignoring the teams
+     * players picked. [Option::None] to use the teams players picked.
+     */
+    auto_seeker_count: number | null;
     /**
-     * The random seed used for shared rng
+     * Who can see hiders' pings, pings of seekers (e.g. from
+     * [crate::PowerUpType::PingAllSeekers]) are always shown to everyone
      */
-    random_seed: number;
+    ping_visible_to: PingVisibility;
     /**
-     * The number of seconds to wait before seekers are allowed to go
+     * How many times each hider can instantly reveal their location to everyone with a panic
+     * ping, outside the normal ping schedule. 0 to turn panic pings off.
      */
-    hiding_time_seconds: number;
+    panic_pings: number;
     /**
-     * Condition to wait for global pings to begin
+     * Whether the host is on a team, if not they only referee: they watch the game like a
+     * spectator but can still end it or change settings
      */
-    ping_start: PingStartCondition;
+    host_plays: boolean;
     /**
-     * Time between pings after the condition is met (first ping is either after the interval or
-     * instantly after the condition is met depending on the condition)
+     * Ping seekers instead of hiders, so hiders always know roughly where seekers are and can
+     * move around freely
      */
-    ping_minutes_interval: number;
+    reverse_pings: boolean;
     /**
-     * Condition for powerups to start spawning
+     * Whether the first ping happens as soon as [GameSettings::ping_start] is met, otherwise
+     * it's one [GameSettings::ping_minutes_interval] later
      */
-    powerup_start: PingStartCondition;
+    first_ping_immediate: boolean;
     /**
-     * Chance every minute of a powerup spawning, out of 100
+     * Ping hiders that stay in one spot for this many minutes after seekers are released, and
+     * keep pinging them every minute until they move. [Option::None] to let hiders camp.
      */
-    powerup_chance: number;
+    reveal_after_minutes: number | null;
     /**
-     * Hard cooldown between powerups spawning
+     * Take pings off the map once they're this many seconds old so nobody chases a hider that's
+     * long gone. [Option::None] to keep each ping until the player is pinged again.
      */
-    powerup_minutes_cooldown: number;
+    ping_expiry_seconds: number | null;
     /**
-     * Locations that powerups may spawn at
+     * How many seconds to wait for a player whose connection dropped to come back before
+     * they're removed from the game, 0 to remove them right away
      */
-    powerup_locations: Location[];
+    disconnect_grace_seconds: number;
 };
 /**
  * The state of the game has changed
@@ -246,10 +1057,44 @@ export type GameUiState = {
      * ID of the local player
      */
     my_id: string;
+    /**
+     * ID of the host, they can end the game early
+     */
+    host: string;
     /**
      * A map of player IDs to whether that player is a seeker
      */
     caught_state: Partial<{ [key in string]: boolean }>;
+    /**
+     * Every player in the game with their display name and role, sorted by ID
+     */
+    players: PlayerView[];
+    /**
+     * Caught players waiting in jail to be freed, sorted by ID. Empty if jailbreaks aren't
+     * enabled
+     */
+    jailed: string[];
+    /**
+     * A map of hider IDs to whether they're in a safe zone based on the latest location we know
+     * of (our own location for us, their last ping for others). Hiders we don't know the
+     * location of are left out
+     */
+    in_safe_zone: Partial<{ [key in string]: boolean }>;
+    /**
+     * A map of seeker IDs to how many players they've caught, empty if catch scores aren't
+     * being tracked
+     */
+    catches_by: Partial<{ [key in string]: number }>;
+    /**
+     * A map of seeker IDs to how many players they've caught in a row, each catch within a few
+     * minutes of the last. Empty if catch scores aren't being tracked
+     */
+    current_streaks: Partial<{ [key in string]: number }>;
+    /**
+     * A map of hider IDs to whether we're getting warmer or colder relative to their last ping,
+     * empty unless we're a seeker and hot/cold hints are enabled
+     */
+    proximity_trends: Partial<{ [key in string]: ProximityTrend }>;
     /**
      * A powerup that is available on the map
      */
@@ -258,6 +1103,11 @@ export type GameUiState = {
      * A map of player IDs to an active ping on them
      */
     pings: Partial<{ [key in string]: PlayerPing }>;
+    /**
+     * A map of player IDs to when their active ping happened **in UTC**, players that haven't
+     * been pinged are left out
+     */
+    last_seen: Partial<{ [key in string]: string }>;
     /**
      * When the game was started **in UTC**
      */
@@ -266,10 +1116,29 @@ export type GameUiState = {
      * When the game ended, when this is Option::Some, the game has ended
      */
     game_ended: string | null;
+    /**
+     * Which part of the game we're in, use this instead of working it out from
+     * [GameUiState::seekers_started] and [GameUiState::game_ended]
+     */
+    phase: GamePhase;
     /**
      * The last time all hiders were pinged **in UTC**
      */
     last_global_ping: string | null;
+    /**
+     * Whether global pings have started, same as [GameUiState::ping_phase] being
+     * [PingPhase::Active]
+     */
+    pings_active: boolean;
+    /**
+     * Whether global pings have started, use this to tell the early game apart from being
+     * between pings
+     */
+    ping_phase: PingPhase;
+    /**
+     * When hiders will next be pinged **in UTC**, [Option::None] if pings haven't started
+     */
+    next_global_ping: string | null;
     /**
      * The last time a powerup was spawned **in UTC**
      */
@@ -278,10 +1147,124 @@ export type GameUiState = {
      * The [PowerUpType] the local player is holding
      */
     held_powerup: PowerUpType | null;
+    /**
+     * How many more times the local player can reveal themselves with a panic ping
+     */
+    panic_pings_left: number;
+    /**
+     * When the seekers will be (or were) released **in UTC**, use this to count down the hiding
+     * phase
+     */
+    seekers_release_at: string;
     /**
      * When the seekers were allowed to start **in UTC**
      */
     seekers_started: string | null;
+    /**
+     * When seekers will be unfrozen **in UTC**, [Option::None] if they aren't frozen
+     */
+    frozen_until: string | null;
+    /**
+     * Whether we're only watching the game, we won't be in `caught_state`
+     */
+    spectating: boolean;
+    /**
+     * Until when **in UTC** we can take back marking ourselves caught, [Option::None] if
+     * there's nothing to undo
+     */
+    undo_catch_until: string | null;
+    /**
+     * Whether our latest location is outside [GameSettings::play_area], always false if there
+     * isn't one
+     */
+    out_of_bounds: boolean;
+    /**
+     * Whether we're being pinged every minute for staying in one spot too long, see
+     * [GameSettings::reveal_after_minutes]
+     */
+    revealed_for_camping: boolean;
+    /**
+     * Map pins seekers dropped for each other, always empty for hiders
+     */
+    seeker_pins: SeekerPin[];
+    /**
+     * Players that left the game on purpose, sorted by ID. Anyone else missing from
+     * [GameUiState::caught_state] dropped out.
+     */
+    left_players: string[];
+    /**
+     * Players whose connection dropped that we're still waiting on to reconnect, sorted by ID.
+     * They're still in [GameUiState::caught_state] until
+     * [GameSettings::disconnect_grace_seconds] runs out.
+     */
+    reconnecting_players: string[];
+};
+/**
+ * STUN/TURN server to connect to other players through, needed on networks with strict NATs
+ */
+export type IceServerSettings = {
+    /**
+     * `stun:`, `turn:` or `turns:` URLs of the servers
+     */
+    urls: string[];
+    username: string | null;
+    credential: string | null;
+};
+/**
+ * A player joined the lobby with a version of the app we can't play with, they're being
+ * ignored and won't be in the game
+ */
+export type IncompatiblePlayerInLobby = {
+    id: string;
+    /**
+     * Their protocol version
+     */
+    theirs: number;
+    /**
+     * Our protocol version
+     */
+    ours: number;
+};
+/**
+ * What the menu needs to offer resuming an [InterruptedGame]
+ */
+export type InterruptedGameInfo = {
+    /**
+     * Code of the room the game was in
+     */
+    join_code: string;
+    /**
+     * When the game was last saved **in UTC**
+     */
+    saved_at: string;
+};
+/**
+ * Everything from the lobby's [GameSettings] that goes on a map, for previewing the game area
+ * before it starts. Kept out of [LobbyState] updates, check [LobbyState]'s `map_revision` to know
+ * when to get it again.
+ */
+export type LobbyMapPreview = {
+    /**
+     * See [GameSettings::play_area]
+     */
+    play_area: Location[] | null;
+    /**
+     * See [GameSettings::powerup_locations]
+     */
+    powerup_locations: Location[];
+    /**
+     * See [GameSettings::safe_zones]
+     */
+    safe_zones: [Location, number][];
+    /**
+     * See [GameSettings::jail_location]
+     */
+    jail_location: Location | null;
+    /**
+     * Smallest area containing everything above, for fitting the map to it. [Option::None] if
+     * there's nothing to show.
+     */
+    bounds: TileBounds | null;
 };
 export type LobbyState = {
     profiles: Partial<{ [key in string]: PlayerProfile }>;
@@ -292,7 +1275,16 @@ export type LobbyState = {
     teams: Partial<{ [key in string]: boolean }>;
     self_id: string;
     is_host: boolean;
+    /**
+     * Everything in [LobbyMapPreview] is left out when serialized so big location lists aren't
+     * sent with every update, get those from the preview instead
+     */
     settings: GameSettings;
+    /**
+     * Goes up whenever anything in [LobbyMapPreview] changes, so the map only needs to be
+     * fetched again when this does
+     */
+    map_revision: number;
 };
 /**
  * The state of the lobby has changed
@@ -315,6 +1307,110 @@ export type Location = {
      */
     heading: number | null;
 };
+/**
+ * How hard the device should try for an accurate location, lower accuracy saves battery
+ */
+export type LocationAccuracy =
+    /**
+     * Use GPS and always get a fresh fix, the most accurate but the hardest on the battery
+     */
+    | "High"
+    /**
+     * Let the device use cheaper sources like Wi-Fi and cell towers and reuse fixes up to 30
+     * seconds old, good enough for casual games
+     */
+    | "Balanced";
+/**
+ * Whether this device can provide locations for a game
+ */
+export type LocationReadiness =
+    /**
+     * Permission is granted and we got a fix
+     */
+    | "Ready"
+    /**
+     * The user didn't allow location access
+     */
+    | "PermissionDenied"
+    /**
+     * Permission is granted but we couldn't get a fix, the user may need to go outside or turn
+     * on location services
+     */
+    | "NoFix"
+    /**
+     * This device can't provide locations at all
+     */
+    | "Unsupported";
+/**
+ * Progress of a [prefetch_tiles] run, sent as an event after every tile and returned at the end
+ */
+export type MapTilesProgress = {
+    /**
+     * Tiles that are now in the cache, including ones that already were
+     */
+    done: number;
+    /**
+     * Tiles that couldn't be downloaded
+     */
+    failed: number;
+    /**
+     * Tiles in the area being fetched
+     */
+    total: number;
+};
+/**
+ * Which game events should send an OS notification while the app isn't focused
+ */
+export type NotificationPreferences = {
+    /**
+     * Notify when seekers are released
+     */
+    seekers_released: boolean;
+    /**
+     * Notify when our location was revealed to everyone
+     */
+    pinged: boolean;
+    /**
+     * Notify when a powerup spawns
+     */
+    powerup_spawned: boolean;
+    /**
+     * Notify when the game ends
+     */
+    game_ended: boolean;
+    /**
+     * Notify when we leave the play area
+     */
+    out_of_bounds: boolean;
+};
+/**
+ * What happened when trying to send a test notification
+ */
+export type NotificationTestResult =
+    /**
+     * The notification was sent, the user should see it
+     */
+    | "Sent"
+    /**
+     * The user didn't allow notifications
+     */
+    | "PermissionDenied"
+    /**
+     * Permission is granted but the notification couldn't be sent
+     */
+    | "Failed";
+/**
+ * Whether global pings have begun, see [GameSettings::ping_start]
+ */
+export type PingPhase =
+    /**
+     * The start condition hasn't been met yet, nobody has been pinged
+     */
+    | "NotStarted"
+    /**
+     * Pings are happening every [GameSettings::ping_minutes_interval]
+     */
+    | "Active";
 /**
  * The starting condition for global pings to begin
  */
@@ -331,12 +1427,40 @@ export type PingStartCondition =
      * Don't wait at all, ping location after seekers are released
      */
     | "Instant";
+/**
+ * Who gets to see hiders' pings on the map, in a reverse game ([GameSettings::reverse_pings])
+ * the teams are swapped
+ */
+export type PingVisibility =
+    /**
+     * Only seekers see hiders' pings, hiders only see their own
+     */
+    | "SeekersOnly"
+    /**
+     * Everyone sees every ping
+     */
+    | "Everyone";
 /**
  * An on-map ping of a player
  */
+/**
+ * A new player joined the lobby, only sent the first time we hear about them
+ */
+export type PlayerJoinedLobby = { id: string; name: string };
+/**
+ * A player left the lobby
+ */
+export type PlayerLeftLobby = {
+    id: string;
+    /**
+     * Whether they left on purpose, otherwise they probably lost connection
+     */
+    on_purpose: boolean;
+};
 export type PlayerPing = {
     /**
-     * Location of the ping
+     * Location of the ping, if the player's GPS didn't give a heading it will be derived from
+     * the direction they were moving
      */
     loc: Location;
     /**
@@ -351,8 +1475,46 @@ export type PlayerPing = {
      * The actual player that initialized this ping
      */
     real_player: string;
+    /**
+     * Whether this ping shows someone other than who made it, e.g. a seeker pinged as a hider
+     * with [PowerUpType::PingSeeker]. Only revealed after the game, see [PlayerPing::masked].
+     */
+    spoofed: boolean;
+};
+export type PlayerProfile = {
+    display_name: string;
+    pfp_base64: string | null;
+    /**
+     * Units the player wants distances shown in
+     */
+    units: Units;
+    /**
+     * Color to draw this player's map pin and scoreboard row in, as a hex string like `#ff0000`
+     */
+    color: string | null;
+};
+/**
+ * A player in the game along with their role, meant for rendering lists of players in the UI
+ */
+export type PlayerView = {
+    /**
+     * ID of the player
+     */
+    id: string;
+    /**
+     * Display name of the player, [Option::None] if we don't have a profile for them
+     */
+    display_name: string | null;
+    /**
+     * Whether this player is a seeker
+     */
+    seeker: boolean;
+    /**
+     * Hex color for this player's map pin and scoreboard row, [Option::None] if they don't have
+     * one
+     */
+    color: string | null;
 };
-export type PlayerProfile = { display_name: string; pfp_base64: string | null };
 /**
  * Type of powerup
  */
@@ -368,7 +1530,141 @@ export type PowerUpType =
     /**
      * Ping another random hider instantly
      */
-    | "ForcePingOther";
+    | "ForcePingOther"
+    /**
+     * Freeze all seekers in place for [crate::GameSettings::freeze_seconds]
+     */
+    | "FreezeSeekers";
+/**
+ * A single powerup that appeared on the map during a game
+ */
+export type PowerupTimelineEntry = {
+    /**
+     * Where the powerup spawned
+     */
+    location: Location;
+    /**
+     * When the powerup spawned **in UTC**
+     */
+    spawned_at: string;
+    /**
+     * When the powerup was grabbed or replaced by a new one, [Option::None] if it was still
+     * up when the game ended
+     */
+    despawned_at: string | null;
+    /**
+     * The player that grabbed this powerup, [Option::None] if nobody did
+     */
+    grabbed_by: string | null;
+};
+/**
+ * Whether a seeker is getting closer to or further from a hider's last ping
+ */
+export type ProximityTrend =
+    /**
+     * Not enough movement since the last ping to tell
+     */
+    | "Unknown"
+    /**
+     * Getting closer
+     */
+    | "Warmer"
+    /**
+     * Getting further away
+     */
+    | "Colder";
+/**
+ * Whether a room can be joined, according to the signaling server
+ */
+export type RoomStatus =
+    /**
+     * The room exists and can be joined
+     */
+    | "Exists"
+    /**
+     * There's no room with this code, or its game already started. For
+     * [started_room_status], there's no room with this code or its game hasn't started.
+     */
+    | "NotFound"
+    /**
+     * Couldn't get an answer from the signaling server
+     */
+    | "ServerUnreachable";
+/**
+ * A map pin a seeker dropped for the other seekers, see [crate::Game::drop_seeker_pin]
+ */
+export type SeekerPin = {
+    location: Location;
+    /**
+     * What the pin is for, e.g. "checking the north park"
+     */
+    label: string | null;
+};
+/**
+ * Seekers have just been released, this is only sent once per game
+ */
+export type SeekersReleasedEvent = null;
+/**
+ * Version info of the signaling server, see [server_info]
+ */
+export type ServerInfo = {
+    /**
+     * Version of the signaling server itself
+     */
+    server_version: string;
+    /**
+     * The server's [PROTOCOL_VERSION]
+     */
+    protocol_version: number;
+    /**
+     * Whether the server's protocol version matches ours, hosting or joining will probably
+     * fail if not
+     */
+    compatible: boolean;
+};
+/**
+ * Plain-language explanation of what a set of [GameSettings] will do during a game, meant for
+ * showing hosts before they start
+ */
+export type SettingsSummary = {
+    /**
+     * How long hiders get before seekers are released
+     */
+    hiding: string;
+    /**
+     * When pings start and how often they happen after that
+     */
+    pings: string;
+    /**
+     * When powerups start spawning and how often, or that they won't
+     */
+    powerups: string;
+    /**
+     * One line for each optional rule that's turned on
+     */
+    extras: string[];
+    /**
+     * Settings that are allowed but probably a mistake, see [GameSettings::warnings]
+     */
+    warnings: string[];
+};
+/**
+ * A rectangle on the map, in degrees
+ */
+export type TileBounds = { north: number; south: number; east: number; west: number };
+/**
+ * Units to show distances in, distances are always stored in meters and only converted for
+ * display (see `formatDistance` in the frontend)
+ */
+export type Units =
+    /**
+     * Meters and kilometers
+     */
+    | "Metric"
+    /**
+     * Feet and miles
+     */
+    | "Imperial";
 
 /** tauri-specta globals **/
 
@@ -6,6 +6,7 @@ import SetupScreen from "./SetupScreen";
 import MenuScreen from "./MenuScreen";
 import LobbyScreen from "./LobbyScreen";
 import GameScreen from "./GameScreen";
+import GameOverScreen from "./GameOverScreen";
 
 function ScreenRouter({ screen }: { screen: AppScreen }) {
     switch (screen) {
@@ -17,6 +18,8 @@ function ScreenRouter({ screen }: { screen: AppScreen }) {
             return <LobbyScreen />;
         case "Game":
             return <GameScreen />;
+        case "GameOver":
+            return <GameOverScreen />;
         default:
             return <p>???</p>;
     }
@@ -0,0 +1,64 @@
+import React from "react";
+import { commands } from "@/bindings";
+import { errorMessage } from "@/lib/errors";
+import { sharedSwrConfig } from "@/lib/hooks";
+import useSWR from "swr";
+
+export default function GameOverScreen() {
+    const { data: results } = useSWR(
+        "fetch-game-results",
+        commands.getGameResults,
+        sharedSwrConfig
+    );
+
+    const dismiss = async () => {
+        await commands.dismissGameResults();
+    };
+
+    const rematch = async () => {
+        try {
+            await commands.hostStartRematch();
+        } catch (e) {
+            window.alert(`Couldn't start a rematch: ${errorMessage(e)}`);
+        }
+    };
+
+    const isHost = results.history.host === results.history.my_id;
+
+    const name = (id: string) => results.profiles[id]?.display_name ?? id;
+
+    const ranking = Object.entries(results.history.catches_by).sort(
+        ([, a], [, b]) => (b ?? 0) - (a ?? 0)
+    );
+
+    return (
+        <>
+            <h2>Game Over!</h2>
+            <p>
+                Started: {results.history.game_started}
+                <br />
+                Players: {results.history.locations.length}
+            </p>
+            {ranking.length > 0 && (
+                <>
+                    <h2>Catches</h2>
+                    <ol>
+                        {ranking.map(([id, catches]) => (
+                            <li key={id}>
+                                {name(id)}: {catches}
+                                {(results.history.max_streaks[id] ?? 0) > 1 &&
+                                    ` (best streak ${results.history.max_streaks[id]})`}
+                            </li>
+                        ))}
+                    </ol>
+                </>
+            )}
+            {isHost ? (
+                <button onClick={rematch}>Rematch</button>
+            ) : (
+                <small>Waiting for the host to start a rematch...</small>
+            )}
+            <button onClick={dismiss}>Back To Menu</button>
+        </>
+    );
+}
@@ -1,6 +1,9 @@
 import React from "react";
 import { commands } from "@/bindings";
+import { errorMessage } from "@/lib/errors";
 import { sharedSwrConfig, useTauriEvent } from "@/lib/hooks";
+import { formatDistance } from "@/lib/units";
+import { getCurrentPosition } from "@tauri-apps/plugin-geolocation";
 import useSWR from "swr";
 
 export default function GameScreen() {
@@ -12,27 +15,118 @@ export default function GameScreen() {
         sharedSwrConfig
     );
 
+    const { data: distanceTraveled, mutate: refreshDistance } = useSWR(
+        "game-distance-traveled",
+        commands.getDistanceTraveled
+    );
+
     useTauriEvent("gameStateUpdate", () => {
         mutate();
+        refreshDistance();
     });
 
     const isSeeker = gameState.caught_state[gameState.my_id];
+    // Spectators aren't in caught_state, they can only watch
+    const isHider = !isSeeker && !gameState.spectating;
+    const units = profiles?.[gameState.my_id]?.units ?? "Metric";
+    const hiding = gameState.phase === "Hiding";
+
+    const [now, setNow] = React.useState(Date.now());
+    // Seeker that caught us, empty if we don't know who it was
+    const [caughtBy, setCaughtBy] = React.useState("");
+
+    const seekers = Object.keys(gameState.caught_state).filter(
+        (k) => gameState.caught_state[k] && k !== gameState.my_id
+    );
+
+    React.useEffect(() => {
+        if (!hiding) return;
+        const interval = setInterval(() => setNow(Date.now()), 1000);
+        return () => clearInterval(interval);
+    }, [hiding]);
+
+    const secondsUntilRelease = Math.max(
+        0,
+        Math.ceil((Date.parse(gameState.seekers_release_at) - now) / 1000)
+    );
 
     const markCaught = async () => {
         if (!isSeeker) {
-            await commands.markCaught();
+            try {
+                await commands.markCaught(caughtBy === "" ? null : caughtBy);
+            } catch (e) {
+                window.alert(`Couldn't mark caught: ${errorMessage(e)}`);
+            }
+        }
+    };
+
+    const undoCaught = async () => {
+        try {
+            await commands.undoCaught();
+        } catch (e) {
+            window.alert(`Too late to undo: ${errorMessage(e)}`);
+        }
+    };
+
+    const concede = async () => {
+        if (!isSeeker && window.confirm("Give up and become a seeker?")) {
+            try {
+                await commands.concedeGame();
+            } catch (e) {
+                window.alert(`Couldn't concede: ${errorMessage(e)}`);
+            }
         }
     };
 
     const grabPowerup = async () => {
         if (gameState.available_powerup !== null) {
-            await commands.grabPowerup();
+            const grabbed = await commands.grabPowerup();
+            if (!grabbed) {
+                window.alert("You're too far away from the powerup");
+            }
         }
     };
 
     const activatePowerup = async () => {
         if (gameState.held_powerup !== null && gameState.held_powerup !== "PingSeeker") {
-            await commands.activatePowerup();
+            try {
+                await commands.activatePowerup();
+            } catch (e) {
+                window.alert(`Couldn't use powerup: ${errorMessage(e)}`);
+            }
+        }
+    };
+
+    const panicPing = async () => {
+        try {
+            await commands.panicPing();
+        } catch (e) {
+            window.alert(`Couldn't panic ping: ${errorMessage(e)}`);
+        }
+    };
+
+    const dropSeekerPin = async () => {
+        const label = window.prompt("Label for the pin (optional)");
+        if (label === null) {
+            return;
+        }
+        try {
+            const { coords } = await getCurrentPosition();
+            await commands.dropSeekerPin(
+                { lat: coords.latitude, long: coords.longitude, heading: null },
+                label
+            );
+        } catch (e) {
+            window.alert(`Couldn't drop pin: ${errorMessage(e)}`);
+        }
+    };
+
+    const jailbreak = async () => {
+        try {
+            const freed = await commands.attemptJailbreak();
+            window.alert(`Freed ${freed.length} player(s) from jail`);
+        } catch (e) {
+            window.alert(`Couldn't free anyone: ${errorMessage(e)}`);
         }
     };
 
@@ -40,28 +134,110 @@ export default function GameScreen() {
         await commands.quitToMenu();
     };
 
-    if (gameState.game_ended) {
+    const pingAll = async () => {
+        try {
+            await commands.hostPingAll();
+        } catch (e) {
+            window.alert(`Couldn't ping everyone: ${errorMessage(e)}`);
+        }
+    };
+
+    const endGame = async () => {
+        await commands.hostEndGame();
+    };
+
+    if (gameState.phase === "Ended") {
         return <h2>Game Over! Syncing histories...</h2>;
-    } else if (isSeeker && gameState.seekers_started === null) {
-        return <h2>Waiting for hiders to hide...</h2>;
+    } else if (isSeeker && hiding) {
+        return <h2>Locked! Waiting for hiders to hide... ({secondsUntilRelease}s)</h2>;
     } else {
         return (
             <>
+                {isSeeker && gameState.frozen_until !== null && (
+                    <h2>Frozen until {gameState.frozen_until}, stay where you are!</h2>
+                )}
+                {gameState.undo_catch_until !== null && (
+                    <button onClick={undoCaught}>Undo, I wasn&apos;t caught</button>
+                )}
+                {gameState.spectating && <h2>Spectating</h2>}
+                {!gameState.spectating && distanceTraveled !== undefined && (
+                    <p>Distance traveled: {formatDistance(distanceTraveled, units)}</p>
+                )}
+                {!isSeeker && hiding && <h2>Seekers released in {secondsUntilRelease}s</h2>}
+                {gameState.out_of_bounds && (
+                    <h2>You&apos;re outside the play area, head back in!</h2>
+                )}
+                {gameState.revealed_for_camping && (
+                    <h2>You&apos;ve been in one spot too long, you&apos;re being pinged until you move!</h2>
+                )}
+                {!isSeeker && gameState.in_safe_zone[gameState.my_id] && (
+                    <h2>You're in a safe zone, seekers can't catch you here</h2>
+                )}
                 <h2>Hiders Left</h2>
                 {Object.keys(gameState.caught_state)
                     .filter((k) => !gameState.caught_state[k])
                     .map((key) => (
-                        <li key={key}>{profiles?.[key]?.display_name ?? key}</li>
+                        <li key={key} style={{ color: profiles?.[key]?.color ?? undefined }}>
+                            {profiles?.[key]?.display_name ?? key}
+                            {gameState.reconnecting_players.includes(key) &&
+                                " (disconnected, reconnecting...)"}
+                        </li>
                     ))}
-                {!isSeeker && <button onClick={markCaught}>I got caught!</button>}
+                {isHider && (
+                    <>
+                        <select value={caughtBy} onChange={(e) => setCaughtBy(e.target.value)}>
+                            <option value="">Caught by...</option>
+                            {seekers.map((id) => (
+                                <option key={id} value={id}>
+                                    {profiles?.[id]?.display_name ?? id}
+                                </option>
+                            ))}
+                        </select>
+                        <button onClick={markCaught}>I got caught!</button>
+                        <button onClick={concede}>Give Up</button>
+                        {gameState.panic_pings_left > 0 && (
+                            <button onClick={panicPing}>
+                                Panic Ping ({gameState.panic_pings_left} left)
+                            </button>
+                        )}
+                    </>
+                )}
+                {isSeeker && (
+                    <>
+                        <h2>Seeker Pins</h2>
+                        {gameState.seeker_pins.map((pin, i) => (
+                            <li key={i}>
+                                {pin.label ?? "Pin"}: {JSON.stringify(pin.location)}
+                            </li>
+                        ))}
+                        <button onClick={dropSeekerPin}>Drop Pin Here</button>
+                    </>
+                )}
+                {gameState.jailed.length > 0 && (
+                    <>
+                        <h2>In Jail</h2>
+                        {gameState.jailed.map((id) => (
+                            <li key={id}>{profiles?.[id]?.display_name ?? id}</li>
+                        ))}
+                        {isHider && <button onClick={jailbreak}>Break Them Out!</button>}
+                    </>
+                )}
                 <h2>Pings</h2>
-                {gameState.last_global_ping !== null ? (
+                {gameState.ping_phase === "Active" ? (
                     <>
                         <p>Last Ping: {gameState.last_global_ping}</p>
+                        {gameState.next_global_ping && (
+                            <p>Next Ping: {gameState.next_global_ping}</p>
+                        )}
                         {Object.entries(gameState.pings)
                             .filter(([key, v]) => key && v !== undefined)
                             .map(([k, v]) => (
-                                <li key={k}>
+                                <li
+                                    key={k}
+                                    style={{
+                                        color: profiles?.[v!.display_player]?.color ?? undefined
+                                    }}
+                                >
                                     {profiles?.[v!.display_player]?.display_name ??
                                         v!.display_player}
                                     : {v && JSON.stringify(v.loc)}
@@ -75,7 +251,7 @@ export default function GameScreen() {
                 {gameState.last_powerup_spawn === null && (
                     <small>Powerups haven&apos;t started yet</small>
                 )}
-                {gameState.available_powerup && (
+                {gameState.available_powerup && !gameState.spectating && (
                     <p>
                         Powerup Available: {JSON.stringify(gameState.available_powerup)}{" "}
                         <button onClick={grabPowerup}>Grab!</button>
@@ -90,6 +266,12 @@ export default function GameScreen() {
                     </p>
                 )}
                 <h2>Quit</h2>
+                {gameState.host === gameState.my_id && (
+                    <>
+                        <button onClick={pingAll}>Ping Everyone Now</button>
+                        <button onClick={endGame}>End Game For Everyone</button>
+                    </>
+                )}
                 <button onClick={quitToMenu}>Quit To Menu</button>
             </>
         );
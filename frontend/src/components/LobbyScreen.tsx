@@ -1,6 +1,8 @@
 import React from "react";
 import { commands } from "@/bindings";
+import { errorMessage } from "@/lib/errors";
 import { sharedSwrConfig, useTauriEvent } from "@/lib/hooks";
+import { previewTiles, tileUrl } from "@/lib/tiles";
 import useSWR from "swr";
 
 export default function LobbyScreen() {
@@ -14,12 +16,91 @@ export default function LobbyScreen() {
         mutate();
     });
 
+    // Keyed on the revision so the preview is only fetched again when the map changes
+    const { data: mapPreview } = useSWR(
+        ["lobby-map-preview", lobbyState.map_revision],
+        commands.getLobbyMapPreview
+    );
+
+    const [notice, setNotice] = React.useState<string | null>(null);
+    const [tilesProgress, setTilesProgress] = React.useState<string | null>(null);
+
+    useTauriEvent("mapTilesProgress", ({ done, failed, total }) => {
+        setTilesProgress(`Saving map... ${done + failed}/${total}`);
+    });
+
+    useTauriEvent("playerJoinedLobby", ({ name }) => {
+        setNotice(`${name} joined`);
+    });
+
+    useTauriEvent("playerLeftLobby", ({ id, on_purpose }) => {
+        const name = lobbyState.profiles[id]?.display_name ?? "A player";
+        setNotice(on_purpose ? `${name} left` : `${name} disconnected`);
+    });
+
+    useTauriEvent("incompatiblePlayerInLobby", ({ theirs, ours }) => {
+        setNotice(
+            theirs > ours
+                ? "A player with a newer version of the app tried to join, update to play with them"
+                : "A player with an older version of the app tried to join, they need to update"
+        );
+    });
+
     const setSeeker = async (seeker: boolean) => {
         await commands.switchTeams(seeker);
     };
 
+    const setColor = async (color: string) => {
+        try {
+            await commands.setLobbyColor(color);
+        } catch (e) {
+            window.alert(`Couldn't set color: ${errorMessage(e)}`);
+        }
+    };
+
+    const assignColors = async () => {
+        try {
+            await commands.hostAssignColors();
+        } catch (e) {
+            window.alert(`Couldn't assign colors: ${errorMessage(e)}`);
+        }
+    };
+
+    const resyncProfiles = async () => {
+        try {
+            await commands.resyncLobbyProfiles();
+        } catch (e) {
+            window.alert(`Couldn't resync players: ${errorMessage(e)}`);
+        }
+    };
+
     const startGame = async () => {
-        await commands.hostStartGame();
+        const readiness = await commands.checkLocationPermissionAndFix();
+        if (
+            readiness !== "Ready" &&
+            !window.confirm(`Your location isn't working (${readiness}), start anyway?`)
+        ) {
+            return;
+        }
+        try {
+            await commands.hostStartGame();
+        } catch (e) {
+            window.alert(`Couldn't start game: ${errorMessage(e)}`);
+        }
+    };
+
+    const saveMap = async () => {
+        try {
+            const { done, failed } = await commands.prefetchMapTiles(null, [12, 16]);
+            setTilesProgress(
+                failed === 0
+                    ? `Map saved for offline use (${done} tiles)`
+                    : `Map saved, ${failed} tile(s) couldn't be downloaded`
+            );
+        } catch (e) {
+            setTilesProgress(null);
+            window.alert(`Couldn't save map: ${errorMessage(e)}`);
+        }
     };
 
     const quit = async () => {
@@ -33,18 +114,79 @@ export default function LobbyScreen() {
     return (
         <>
             <h2>Join Code: {lobbyState.join_code}</h2>
+            {notice && <small>{notice}</small>}
 
             {lobbyState.is_host && <button onClick={startGame}>Start Game</button>}
 
-            <button onClick={() => setSeeker(true)}>Become Seeker</button>
-            <button onClick={() => setSeeker(false)}>Become Hider</button>
+            {lobbyState.is_host && !lobbyState.settings.host_plays ? (
+                <p>You&apos;re refereeing, you&apos;ll watch the game instead of playing</p>
+            ) : lobbyState.settings.auto_seeker_count !== null ? (
+                <p>
+                    {lobbyState.settings.auto_seeker_count} random seeker(s) will be picked when
+                    the game starts
+                </p>
+            ) : (
+                <>
+                    <button onClick={() => setSeeker(true)}>Become Seeker</button>
+                    <button onClick={() => setSeeker(false)}>Become Hider</button>
+                </>
+            )}
+
+            <label>
+                Color
+                <input
+                    type="color"
+                    value={lobbyState.profiles[lobbyState.self_id]?.color ?? "#000000"}
+                    onChange={(e) => setColor(e.target.value)}
+                />
+            </label>
+            {lobbyState.is_host && <button onClick={assignColors}>Assign Colors</button>}
+            <button onClick={resyncProfiles}>Missing Players? Resync</button>
+
+            {mapPreview?.bounds && (
+                <>
+                    <h3>Map</h3>
+                    <ul>
+                        {mapPreview.play_area && (
+                            <li>Play area with {mapPreview.play_area.length} corners</li>
+                        )}
+                        <li>{mapPreview.powerup_locations.length} powerup location(s)</li>
+                        <li>{mapPreview.safe_zones.length} safe zone(s)</li>
+                        {mapPreview.jail_location && <li>A jail</li>}
+                    </ul>
+                    <div style={{ lineHeight: 0 }}>
+                        {previewTiles(mapPreview.bounds, 3).map((row) => (
+                            <div key={`${row[0].z}/${row[0].y}`}>
+                                {row.map((tile) => (
+                                    <img
+                                        key={tile.x}
+                                        src={tileUrl(tile)}
+                                        width={96}
+                                        height={96}
+                                        alt=""
+                                    />
+                                ))}
+                            </div>
+                        ))}
+                    </div>
+                    {mapPreview.play_area && (
+                        <button onClick={saveMap}>Save Map for Offline Use</button>
+                    )}
+                    {tilesProgress && <small>{tilesProgress}</small>}
+                </>
+            )}
 
             <h3>Seekers</h3>
             <ul>
                 {Object.keys(lobbyState.teams)
                     .filter((k) => lobbyState.teams[k])
                     .map((key) => (
-                        <li key={key}>{lobbyState.profiles[key]?.display_name ?? key}</li>
+                        <li
+                            key={key}
+                            style={{ color: lobbyState.profiles[key]?.color ?? undefined }}
+                        >
+                            {lobbyState.profiles[key]?.display_name ?? key}
+                        </li>
                     ))}
             </ul>
             <h3>Hiders</h3>
@@ -52,7 +194,12 @@ export default function LobbyScreen() {
                 {Object.keys(lobbyState.teams)
                     .filter((k) => !lobbyState.teams[k])
                     .map((key) => (
-                        <li key={key}>{lobbyState.profiles[key]?.display_name ?? key}</li>
+                        <li
+                            key={key}
+                            style={{ color: lobbyState.profiles[key]?.color ?? undefined }}
+                        >
+                            {lobbyState.profiles[key]?.display_name ?? key}
+                        </li>
                     ))}
             </ul>
             <button onClick={quit}>Quit to Menu</button>
@@ -1,11 +1,13 @@
-import { commands, GameSettings } from "@/bindings";
+import { commands, GameSettings, LocationAccuracy, Units } from "@/bindings";
+import { errorMessage } from "@/lib/errors";
 import { sharedSwrConfig } from "@/lib/hooks";
 import React from "react";
 import useSWR from "swr";
 
 // Temp settings for now.
 const settings: GameSettings = {
-    random_seed: 21341234,
+    // The host picks a fresh seed when the game starts
+    random_seed: 0,
     hiding_time_seconds: 10,
     ping_start: "Instant",
     ping_minutes_interval: 1,
@@ -18,11 +20,32 @@ const settings: GameSettings = {
             long: 0,
             heading: null
         }
-    ]
+    ],
+    track_catch_scores: false,
+    hot_cold_hints: false,
+    powerup_grab_radius_meters: null,
+    powerup_near_hiders_radius_meters: null,
+    freeze_seconds: 60,
+    jail_location: null,
+    enable_jailbreak: false,
+    safe_zones: [],
+    anonymize_profiles: false,
+    allow_spectators: false,
+    play_area: null,
+    auto_seeker_count: null,
+    ping_visible_to: "Everyone",
+    panic_pings: 0,
+    host_plays: true,
+    reverse_pings: false,
+    first_ping_immediate: false,
+    reveal_after_minutes: null,
+    ping_expiry_seconds: null,
+    disconnect_grace_seconds: 30
 };
 
 export default function MenuScreen() {
     const [roomCode, setRoomCode] = React.useState("");
+    const [customRoomCode, setCustomRoomCode] = React.useState("");
     const [newName, setName] = React.useState("");
 
     const { data: profile, mutate: setProfile } = useSWR(
@@ -30,26 +53,126 @@ export default function MenuScreen() {
         commands.getProfile,
         sharedSwrConfig
     );
-    const { data: gameHistory } = useSWR(
+    const { data: gameHistory, mutate: refreshGameHistory } = useSWR(
         "list-game-history",
         commands.listGameHistories,
         sharedSwrConfig
     );
 
+    const { data: recentRoomCodes } = useSWR(
+        "recent-room-codes",
+        commands.recentRoomCodes,
+        sharedSwrConfig
+    );
+
+    const { data: interruptedGame, mutate: refreshInterruptedGame } = useSWR(
+        "get-interrupted-game",
+        commands.getInterruptedGame,
+        sharedSwrConfig
+    );
+
+    const { data: logGameEvents, mutate: setLogGameEvents } = useSWR(
+        "log-game-events",
+        commands.getLogGameEvents,
+        sharedSwrConfig
+    );
+
+    const { data: streamGameEvents, mutate: setStreamGameEvents } = useSWR(
+        "stream-game-events",
+        commands.getStreamGameEvents,
+        sharedSwrConfig
+    );
+
+    const { data: locationAccuracyModes } = useSWR(
+        "location-accuracy-modes",
+        commands.getLocationAccuracyModes,
+        sharedSwrConfig
+    );
+
+    const { data: locationAccuracy, mutate: setLocationAccuracy } = useSWR(
+        "location-accuracy",
+        commands.getLocationAccuracy,
+        sharedSwrConfig
+    );
+
+    const { data: manualTick, mutate: setManualTick } = useSWR(
+        import.meta.env.DEV ? "manual-tick" : null,
+        commands.debugGetManualTick,
+        sharedSwrConfig
+    );
+
+    const { data: loopbackTransport, mutate: setLoopbackTransport } = useSWR(
+        import.meta.env.DEV ? "loopback-transport" : null,
+        commands.debugGetLoopbackTransport,
+        sharedSwrConfig
+    );
+
+    const { data: settingsSummary } = useSWR(
+        "describe-settings",
+        () => commands.describeSettings(settings),
+        sharedSwrConfig
+    );
+
+    const confirmServerCompatible = async () => {
+        try {
+            const info = await commands.signalingServerInfo();
+            if (!info.compatible) {
+                const message = `The server is running an incompatible version (${info.server_version}), connecting will probably fail. Try anyway?`;
+                return window.confirm(message);
+            }
+        } catch {
+            // Couldn't tell, hosting or joining will report any problem reaching the server
+        }
+        return true;
+    };
+
     const onStartGame = async (code: string | null) => {
+        if (!(await confirmServerCompatible())) {
+            return;
+        }
         if (code) {
+            if (!(await commands.isValidRoomCodeFormat(code))) {
+                window.alert("Invalid Join Code");
+                return;
+            }
             try {
-                const validCode = await commands.checkRoomCode(code);
-                if (!validCode) {
+                const status = await commands.checkRoomCode(code);
+                if (status === "NotFound") {
                     window.alert("Invalid Join Code");
                     return;
+                } else if (status === "ServerUnreachable") {
+                    window.alert("Couldn't reach the server, check your connection and try again");
+                    return;
                 }
             } catch (e) {
-                window.alert(`Failed to connect to Server ${e}`);
+                window.alert(`Failed to connect to Server ${errorMessage(e)}`);
                 return;
             }
         }
-        await commands.startLobby(code, settings);
+        try {
+            await commands.startLobby(code, settings);
+        } catch (e) {
+            window.alert(`Couldn't start the lobby: ${errorMessage(e)}`);
+        }
+    };
+
+    const onHostWithCode = async (code: string) => {
+        if (!(await confirmServerCompatible())) {
+            return;
+        }
+        try {
+            await commands.hostLobbyWithCode(code, settings);
+        } catch (e) {
+            window.alert(`Couldn't host with that code: ${errorMessage(e)}`);
+        }
+    };
+
+    const onSpectate = async (code: string) => {
+        try {
+            await commands.startSpectating(code);
+        } catch (e) {
+            window.alert(`Couldn't spectate: ${errorMessage(e)}`);
+        }
     };
 
     const onSaveProfile = async () => {
@@ -57,6 +180,145 @@ export default function MenuScreen() {
         setProfile({ ...profile, display_name: newName });
     };
 
+    const onSetUnits = async (units: Units) => {
+        await commands.updateProfile({ ...profile, units });
+        setProfile({ ...profile, units });
+    };
+
+    const onExportReplay = async (id: string) => {
+        try {
+            const bundle = await commands.exportReplayBundle(id);
+            await navigator.clipboard.writeText(bundle);
+            window.alert("Replay copied to clipboard");
+        } catch (e) {
+            window.alert(`Couldn't export replay: ${errorMessage(e)}`);
+        }
+    };
+
+    const onSetTurnServer = async () => {
+        const url = window.prompt("TURN server URL (turn:host:port)");
+        if (!url) return;
+        const username = window.prompt("Username (leave blank for none)") || null;
+        const credential = window.prompt("Password (leave blank for none)") || null;
+        try {
+            await commands.setIceServers({ urls: [url], username, credential });
+        } catch (e) {
+            window.alert(`Couldn't save server: ${errorMessage(e)}`);
+        }
+    };
+
+    const onResetIceServers = async () => {
+        await commands.setIceServers(null);
+    };
+
+    const onSetTileServer = async () => {
+        const current = await commands.getTileServer();
+        const url = window.prompt("Map tile server URL, with {z}, {x}, and {y}", current);
+        if (!url) return;
+        try {
+            await commands.setTileServer(url);
+        } catch (e) {
+            window.alert(`Couldn't save tile server: ${errorMessage(e)}`);
+        }
+    };
+
+    const onResetTileServer = async () => {
+        await commands.setTileServer(null);
+    };
+
+    const onResumeGame = async () => {
+        try {
+            await commands.resumeInterruptedGame();
+        } catch (e) {
+            if (e === "NotFound") {
+                window.alert("That game is over, everyone else has left the room");
+                refreshInterruptedGame();
+            } else {
+                window.alert(`Couldn't resume game: ${errorMessage(e)}`);
+            }
+        }
+    };
+
+    const onDiscardGame = async () => {
+        await commands.discardInterruptedGame();
+        refreshInterruptedGame();
+    };
+
+    const onTestNotification = async () => {
+        const result = await commands.sendTestNotification();
+        if (result === "PermissionDenied") {
+            window.alert("Notifications aren't allowed, turn them on in your device's settings");
+        } else if (result === "Failed") {
+            window.alert("Couldn't send a notification");
+        }
+    };
+
+    const onToggleLogGameEvents = async (enabled: boolean) => {
+        await commands.setLogGameEvents(enabled);
+        setLogGameEvents(enabled);
+    };
+
+    const onToggleStreamGameEvents = async (enabled: boolean) => {
+        await commands.setStreamGameEvents(enabled);
+        setStreamGameEvents(enabled);
+    };
+
+    const onSetLocationAccuracy = async (accuracy: LocationAccuracy) => {
+        await commands.setLocationAccuracy(accuracy);
+        setLocationAccuracy(accuracy);
+    };
+
+    const onShowEventStream = async () => {
+        const info = await commands.getEventStreamInfo();
+        window.alert(
+            info
+                ? `Connect your dashboard to ${info.url}`
+                : "Turn on streaming game events first"
+        );
+    };
+
+    const onToggleManualTick = async (enabled: boolean) => {
+        await commands.debugSetManualTick(enabled);
+        setManualTick(enabled);
+    };
+
+    const onToggleLoopbackTransport = async (enabled: boolean) => {
+        await commands.debugSetLoopbackTransport(enabled);
+        setLoopbackTransport(enabled);
+    };
+
+    const onShowEventLog = async () => {
+        try {
+            const path = await commands.getEventLogPath();
+            window.alert(path ? `Latest game log: ${path}` : "No games have been logged yet");
+        } catch (e) {
+            window.alert(`Couldn't find the game log: ${errorMessage(e)}`);
+        }
+    };
+
+    const onResetAppData = async () => {
+        const message = "Delete your profile, settings, and game history? This can't be undone.";
+        if (!window.confirm(message)) {
+            return;
+        }
+        try {
+            await commands.resetAppData(true);
+        } catch (e) {
+            window.alert(`Couldn't reset app data: ${errorMessage(e)}`);
+        }
+    };
+
+    const onImportReplay = async () => {
+        const json = window.prompt("Paste a replay to import");
+        if (!json) return;
+        try {
+            await commands.importReplay(json);
+            refreshGameHistory();
+        } catch (e) {
+            window.alert(`Couldn't import replay: ${errorMessage(e)}`);
+        }
+    };
+
     return (
         <>
             {profile.pfp_base64 && (
@@ -64,8 +326,44 @@ export default function MenuScreen() {
             )}
             <h2>Welcome, {profile.display_name}</h2>
             <hr />
+            {interruptedGame && (
+                <div>
+                    <p>
+                        Your game in room {interruptedGame.join_code} was interrupted (last saved{" "}
+                        {new Date(interruptedGame.saved_at).toLocaleTimeString()})
+                    </p>
+                    <button onClick={onResumeGame}>Resume Game</button>
+                    <button onClick={onDiscardGame}>Discard</button>
+                </div>
+            )}
             <h3>Play</h3>
             <button onClick={() => onStartGame(null)}>Start Lobby</button>
+            <div>
+                <input
+                    value={customRoomCode}
+                    placeholder="Custom Room Code"
+                    onChange={(e) => setCustomRoomCode(e.target.value)}
+                />
+                <button
+                    onClick={() => onHostWithCode(customRoomCode)}
+                    disabled={customRoomCode === ""}
+                >
+                    Start Lobby With Code
+                </button>
+            </div>
+            <ul>
+                <li>{settingsSummary.hiding}</li>
+                <li>{settingsSummary.pings}</li>
+                <li>{settingsSummary.powerups}</li>
+                {settingsSummary.extras.map((extra) => (
+                    <li key={extra}>{extra}</li>
+                ))}
+                {settingsSummary.warnings.map((warning) => (
+                    <li key={warning}>
+                        <strong>Warning: {warning}</strong>
+                    </li>
+                ))}
+            </ul>
             <div>
                 <input
                     value={roomCode}
@@ -75,7 +373,23 @@ export default function MenuScreen() {
                 <button onClick={() => onStartGame(roomCode)} disabled={roomCode === ""}>
                     Join Lobby
                 </button>
+                <button onClick={() => onSpectate(roomCode)} disabled={roomCode === ""}>
+                    Spectate
+                </button>
             </div>
+            {recentRoomCodes.length > 0 && (
+                <>
+                    <h4>Recent Rooms</h4>
+                    <ul>
+                        {recentRoomCodes.map(([code, joined]) => (
+                            <li key={code}>
+                                {code} ({joined}){" "}
+                                <button onClick={() => onStartGame(code)}>Rejoin</button>
+                            </li>
+                        ))}
+                    </ul>
+                </>
+            )}
             <hr />
             <h3>Edit Profile</h3>
             <input
@@ -84,13 +398,84 @@ export default function MenuScreen() {
                 onChange={(e) => setName(e.target.value)}
             />
             <button onClick={onSaveProfile}>Save</button>
+            <select value={profile.units} onChange={(e) => onSetUnits(e.target.value as Units)}>
+                <option value="Metric">Metric</option>
+                <option value="Imperial">Imperial</option>
+            </select>
+            {locationAccuracyModes && locationAccuracyModes.length > 0 && (
+                <select
+                    value={locationAccuracy ?? "High"}
+                    onChange={(e) => onSetLocationAccuracy(e.target.value as LocationAccuracy)}
+                >
+                    {locationAccuracyModes.map((mode) => (
+                        <option key={mode} value={mode}>
+                            {mode === "High" ? "High Accuracy" : "Battery Saver"}
+                        </option>
+                    ))}
+                </select>
+            )}
+            <button onClick={onTestNotification}>Test Notifications</button>
+            <hr />
+            <h3>Connection</h3>
+            <small>If you can&apos;t connect to other players, try setting a TURN server</small>
+            <button onClick={onSetTurnServer}>Set TURN Server</button>
+            <button onClick={onResetIceServers}>Use Default Servers</button>
+            <small>Maps come from OpenStreetMap unless you set another tile server</small>
+            <button onClick={onSetTileServer}>Set Tile Server</button>
+            <button onClick={onResetTileServer}>Use OpenStreetMap</button>
             <hr />
             <h3>Previous Games</h3>
+            <button onClick={onImportReplay}>Import Replay</button>
             <ul>
-                {gameHistory.map((time) => (
-                    <li key={time}>{time}</li>
+                {gameHistory.map(([id, time]) => (
+                    <li key={id}>
+                        {time} <button onClick={() => onExportReplay(id)}>Export</button>
+                    </li>
                 ))}
             </ul>
+            <hr />
+            <h3>Debugging</h3>
+            <label>
+                <input
+                    type="checkbox"
+                    checked={logGameEvents ?? false}
+                    onChange={(e) => onToggleLogGameEvents(e.target.checked)}
+                />
+                Log game events
+            </label>
+            <button onClick={onShowEventLog}>Show Game Log</button>
+            <label>
+                <input
+                    type="checkbox"
+                    checked={streamGameEvents ?? false}
+                    onChange={(e) => onToggleStreamGameEvents(e.target.checked)}
+                />
+                Stream game events to a dashboard
+            </label>
+            <button onClick={onShowEventStream}>Show Dashboard Link</button>
+            {import.meta.env.DEV && (
+                <label>
+                    <input
+                        type="checkbox"
+                        checked={manualTick ?? false}
+                        onChange={(e) => onToggleManualTick(e.target.checked)}
+                    />
+                    Only tick games manually
+                </label>
+            )}
+            {import.meta.env.DEV && (
+                <label>
+                    <input
+                        type="checkbox"
+                        checked={loopbackTransport ?? false}
+                        onChange={(e) => onToggleLoopbackTransport(e.target.checked)}
+                    />
+                    Connect lobbies in-process instead of through the server
+                </label>
+            )}
+            <hr />
+            <h3>Reset</h3>
+            <button onClick={onResetAppData}>Reset App Data</button>
         </>
     );
 }
@@ -5,7 +5,12 @@ export default function SetupScreen() {
     const [displayName, setName] = React.useState("User");
 
     const onSave = async () => {
-        const profile = { display_name: displayName, pfp_base64: null } as PlayerProfile;
+        const profile = {
+            display_name: displayName,
+            pfp_base64: null,
+            units: "Metric",
+            color: null
+        } as PlayerProfile;
         await commands.completeSetup(profile);
     };
 
@@ -0,0 +1,20 @@
+import { AppError } from "@/bindings";
+
+/**
+ *  Turn an error thrown by a command into something to show the user
+ */
+export function errorMessage(e: unknown): string {
+    const err = e as AppError;
+    if (err === "WrongScreen") {
+        return "Not on the right screen for this action";
+    } else if (err === "NotFound") {
+        return "Not found";
+    } else if (err === "RoomCodeTaken") {
+        return "That room code is already in use";
+    } else if (typeof err === "object" && err !== null) {
+        if ("Network" in err) return `Network error: ${err.Network}`;
+        if ("Validation" in err) return err.Validation.join("\n");
+        if ("Other" in err) return err.Other;
+    }
+    return `${e}`;
+}
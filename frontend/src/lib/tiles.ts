@@ -0,0 +1,53 @@
+import { convertFileSrc } from "@tauri-apps/api/core";
+import { TileBounds } from "@/bindings";
+
+/** Web mercator can't show anything past this latitude, mirrors `MAX_LATITUDE` in the backend */
+const MAX_LATITUDE = 85.05112878;
+
+/** Highest zoom a map preview will use, past this tiles are mostly empty streets */
+const MAX_PREVIEW_ZOOM = 17;
+
+export type TileCoord = { z: number; x: number; y: number };
+
+/**
+ *  Tile containing the given point at the given zoom, mirrors `TileCoord::containing` in the
+ *  backend
+ */
+function containing(lat: number, long: number, z: number): TileCoord {
+    const n = 2 ** z;
+    const rad = (Math.max(-MAX_LATITUDE, Math.min(MAX_LATITUDE, lat)) * Math.PI) / 180;
+    const x = Math.floor(((long + 180) / 360) * n);
+    const y = Math.floor(((1 - Math.asinh(Math.tan(rad)) / Math.PI) / 2) * n);
+    return { z, x: Math.max(0, Math.min(n - 1, x)), y: Math.max(0, Math.min(n - 1, y)) };
+}
+
+/**
+ *  Rows of tiles covering `bounds` at the highest zoom that needs no more than `maxAcross` tiles
+ *  in either direction
+ */
+export function previewTiles(bounds: TileBounds, maxAcross: number): TileCoord[][] {
+    for (let z = MAX_PREVIEW_ZOOM; z >= 0; z--) {
+        const nw = containing(bounds.north, bounds.west, z);
+        const se = containing(bounds.south, bounds.east, z);
+        if (z === 0 || (se.x - nw.x < maxAcross && se.y - nw.y < maxAcross)) {
+            const rows = [];
+            for (let y = nw.y; y <= se.y; y++) {
+                const row = [];
+                for (let x = nw.x; x <= se.x; x++) {
+                    row.push({ z, x, y });
+                }
+                rows.push(row);
+            }
+            return rows;
+        }
+    }
+    return [];
+}
+
+/**
+ *  URL to load a tile from, this goes through the backend so tiles downloaded for offline use are
+ *  shown and any others are downloaded and kept
+ */
+export function tileUrl(tile: TileCoord): string {
+    return convertFileSrc(`${tile.z}/${tile.x}/${tile.y}.png`, "tiles");
+}
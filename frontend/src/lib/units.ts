@@ -0,0 +1,19 @@
+import { Units } from "@/bindings";
+
+const FEET_PER_METER = 3.28084;
+const FEET_PER_MILE = 5280;
+
+/**
+ *  Format a distance in meters for display in the player's preferred units, switching to km or
+ *  miles for long distances. Distances always come from the backend in meters.
+ */
+export function formatDistance(meters: number, units: Units): string {
+    if (units === "Imperial") {
+        const feet = meters * FEET_PER_METER;
+        return feet >= FEET_PER_MILE / 10
+            ? `${(feet / FEET_PER_MILE).toFixed(1)} mi`
+            : `${feet.toFixed(0)} ft`;
+    } else {
+        return meters >= 1000 ? `${(meters / 1000).toFixed(1)} km` : `${meters.toFixed(0)} m`;
+    }
+}
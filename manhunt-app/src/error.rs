@@ -0,0 +1,46 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+/// An error returned from a command, lets the frontend act differently depending on what went
+/// wrong
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub enum AppError {
+    /// The app isn't on the screen this command is meant for
+    WrongScreen,
+    /// Couldn't reach the server or other players
+    Network(String),
+    /// Given input didn't make sense, contains a list of human-readable problems
+    Validation(Vec<String>),
+    /// The requested thing doesn't exist
+    NotFound,
+    /// The room code the host asked for is already being used by another room
+    RoomCodeTaken,
+    /// Any other error
+    Other(String),
+}
+
+impl Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AppError::WrongScreen => write!(f, "Not on the right screen for this action"),
+            AppError::Network(why) => write!(f, "Network error: {why}"),
+            AppError::Validation(errors) => write!(f, "{}", errors.join("\n")),
+            AppError::NotFound => write!(f, "Not found"),
+            AppError::RoomCodeTaken => write!(f, "That room code is already in use"),
+            AppError::Other(why) => write!(f, "{why}"),
+        }
+    }
+}
+
+impl From<anyhow::Error> for AppError {
+    fn from(value: anyhow::Error) -> Self {
+        Self::Other(value.to_string())
+    }
+}
+
+impl From<Vec<String>> for AppError {
+    fn from(value: Vec<String>) -> Self {
+        Self::Validation(value)
+    }
+}
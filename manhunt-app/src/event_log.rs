@@ -0,0 +1,148 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::PathBuf,
+    sync::{Arc, Mutex, Weak},
+    time::Duration,
+};
+
+use anyhow::Context;
+use chrono::Utc;
+use log::{error, warn};
+use manhunt_logic::{EventDirection, GameEvent, UtcDT};
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::settings::read_log_game_events_from_store;
+
+type Result<T = (), E = anyhow::Error> = std::result::Result<T, E>;
+
+/// Directory under the app's log directory game event logs are kept in
+const EVENT_LOG_DIR: &str = "game-events";
+
+/// How often buffered events are written out to the log file
+const FLUSH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// One line of a game event log, also what [crate::event_stream::GameEventStream] sends
+#[derive(Serialize)]
+pub(crate) struct LogLine<'a> {
+    at: UtcDT,
+    direction: EventDirection,
+    event: &'a GameEvent,
+}
+
+impl<'a> LogLine<'a> {
+    pub(crate) fn now(direction: EventDirection, event: &'a GameEvent) -> Self {
+        Self {
+            at: Utc::now(),
+            direction,
+            event,
+        }
+    }
+}
+
+type LogWriter = Mutex<BufWriter<File>>;
+
+/// Every [GameEvent] sent and received during a game, written to its own file as one JSON object
+/// per line. This is for debugging desyncs, see [crate::settings::read_log_game_events_from_store].
+pub struct GameEventLog {
+    writer: Arc<LogWriter>,
+}
+
+fn flush(writer: &LogWriter) {
+    let mut file = writer.lock().unwrap_or_else(|e| e.into_inner());
+    if let Err(why) = file.flush() {
+        warn!("Failed to flush game event log: {why:?}");
+    }
+}
+
+/// Flush the log every [FLUSH_INTERVAL] until it's dropped, so events from a quiet stretch of
+/// the game still make it to the file
+fn flush_periodically(writer: Weak<LogWriter>) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+        // The first tick is immediate, nothing to flush yet
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let Some(writer) = writer.upgrade() else {
+                break;
+            };
+            flush(&writer);
+        }
+    });
+}
+
+fn event_log_dir(app: &AppHandle) -> Result<PathBuf> {
+    Ok(app
+        .path()
+        .app_log_dir()
+        .context("Failed to find log directory")?
+        .join(EVENT_LOG_DIR))
+}
+
+/// Path of the most recently started game event log, [Option::None] if no games have been logged
+pub fn latest_event_log_path(app: &AppHandle) -> Result<Option<PathBuf>> {
+    let dir = event_log_dir(app)?;
+    if !dir.exists() {
+        return Ok(None);
+    }
+
+    let latest = std::fs::read_dir(dir)
+        .context("Failed to read event log directory")?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "jsonl"))
+        // File names start with when the game started so the greatest is the latest
+        .max();
+
+    Ok(latest)
+}
+
+impl GameEventLog {
+    /// Start a new log file for a game
+    pub fn create(app: &AppHandle) -> Result<Self> {
+        let dir = event_log_dir(app)?;
+        std::fs::create_dir_all(&dir).context("Failed to create event log directory")?;
+
+        let name = format!("{}.jsonl", Utc::now().format("%Y-%m-%dT%H-%M-%S%.3fZ"));
+        let file = File::create(dir.join(name)).context("Failed to create event log")?;
+
+        let writer = Arc::new(Mutex::new(BufWriter::new(file)));
+        flush_periodically(Arc::downgrade(&writer));
+
+        Ok(Self { writer })
+    }
+
+    /// Start a new log file if the user turned on logging game events
+    pub fn create_if_enabled(app: &AppHandle) -> Option<Self> {
+        if !read_log_game_events_from_store(app) {
+            return None;
+        }
+
+        Self::create(app)
+            .inspect_err(|why| error!("Failed to start game event log: {why:?}"))
+            .ok()
+    }
+
+    /// Add an event to the log, buffered events are written out every [FLUSH_INTERVAL] and when
+    /// the log is dropped
+    pub fn record(&self, direction: EventDirection, event: &GameEvent) {
+        let line = LogLine::now(direction, event);
+
+        let mut file = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+
+        let res = serde_json::to_writer(&mut *file, &line)
+            .map_err(std::io::Error::from)
+            .and_then(|_| file.write_all(b"\n"));
+
+        if let Err(why) = res {
+            warn!("Failed to log game event: {why:?}");
+        }
+    }
+}
+
+impl Drop for GameEventLog {
+    fn drop(&mut self) {
+        flush(&self.writer);
+    }
+}
@@ -0,0 +1,260 @@
+use std::{
+    net::Ipv4Addr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::{Context, bail};
+use log::{error, info, warn};
+use manhunt_logic::{EventDirection, GameEvent};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, async_runtime::JoinHandle};
+use tokio::{
+    io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    sync::broadcast,
+};
+use uuid::Uuid;
+
+use crate::{event_log::LogLine, settings::read_stream_game_events_from_store};
+
+type Result<T = (), E = anyhow::Error> = std::result::Result<T, E>;
+
+/// How many events a slow dashboard can fall behind by before it starts missing some
+const STREAM_BUFFER: usize = 256;
+
+/// How long a connection has to send its whole request before it's dropped
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Longest request or header line accepted, anything longer isn't a dashboard
+const MAX_REQUEST_LINE_LEN: usize = 4096;
+
+/// The running event stream, if there is one. Kept across games so dashboards don't need a new
+/// URL and token every game.
+pub type EventStreamHandle = Mutex<Option<Arc<GameEventStream>>>;
+
+/// How to connect to a [GameEventStream]
+#[derive(Serialize, Deserialize, Clone, Debug, specta::Type)]
+pub struct EventStreamInfo {
+    /// URL to open an `EventSource` on, includes the token
+    pub url: String,
+    /// Token connections need to pass as the `token` query parameter
+    pub token: String,
+}
+
+/// Every [GameEvent] sent and received during a game as
+/// [Server-Sent Events](https://html.spec.whatwg.org/multipage/server-sent-events.html), for
+/// spectator dashboards. Only listens on localhost and turns away connections without the token.
+///
+/// Connect with `GET /events?token=<token>`, each event's data is one JSON object in the same
+/// format as a line of [crate::event_log::GameEventLog]:
+/// `{ "at": <UTC timestamp>, "direction": "Sent" | "Received", "event": <GameEvent> }`.
+pub struct GameEventStream {
+    events: broadcast::Sender<String>,
+    info: EventStreamInfo,
+    server: JoinHandle<()>,
+}
+
+/// Response header for an accepted connection, allows any origin since the token is what keeps
+/// rogue pages out
+const STREAM_HEADER: &[u8] = b"HTTP/1.1 200 OK\r\n\
+    Content-Type: text/event-stream\r\n\
+    Cache-Control: no-cache\r\n\
+    Access-Control-Allow-Origin: *\r\n\r\n";
+
+const UNAUTHORIZED: &[u8] = b"HTTP/1.1 401 Unauthorized\r\nContent-Length: 0\r\n\r\n";
+
+/// Read one line of the request, failing if it's longer than [MAX_REQUEST_LINE_LEN]
+async fn read_request_line<R: AsyncBufRead + Unpin>(
+    stream: &mut R,
+    line: &mut String,
+) -> Result<usize> {
+    let read = (&mut *stream)
+        .take(MAX_REQUEST_LINE_LEN as u64)
+        .read_line(line)
+        .await?;
+    if read == MAX_REQUEST_LINE_LEN && !line.ends_with('\n') {
+        bail!("Request line too long");
+    }
+    Ok(read)
+}
+
+async fn read_request<R: AsyncBufRead + Unpin>(stream: &mut R, token: &str) -> Result<bool> {
+    let mut request_line = String::new();
+    read_request_line(stream, &mut request_line).await?;
+
+    // Skip the headers, we don't need any of them
+    let mut header = String::new();
+    while read_request_line(stream, &mut header).await? > 2 {
+        header.clear();
+    }
+
+    let target = request_line.split_whitespace().nth(1).unwrap_or_default();
+    Ok(target
+        .strip_prefix("/events?token=")
+        .is_some_and(|given| given == token))
+}
+
+/// Read the request and check it's for the event stream with the right token. Fails if the
+/// request doesn't arrive within [REQUEST_TIMEOUT].
+async fn check_request<R: AsyncBufRead + Unpin>(stream: &mut R, token: &str) -> Result<bool> {
+    tokio::time::timeout(REQUEST_TIMEOUT, read_request(stream, token))
+        .await
+        .context("Timed out reading request")?
+}
+
+async fn serve_client(
+    stream: TcpStream,
+    token: String,
+    mut events: broadcast::Receiver<String>,
+) -> Result {
+    let mut stream = BufReader::new(stream);
+
+    if !check_request(&mut stream, &token).await? {
+        stream.write_all(UNAUTHORIZED).await?;
+        return Ok(());
+    }
+
+    stream.write_all(STREAM_HEADER).await?;
+    stream.flush().await?;
+
+    loop {
+        match events.recv().await {
+            Ok(line) => {
+                stream
+                    .write_all(format!("data: {line}\n\n").as_bytes())
+                    .await?;
+                stream.flush().await?;
+            }
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                warn!("Event stream client fell behind, skipped {missed} events");
+            }
+            Err(broadcast::error::RecvError::Closed) => return Ok(()),
+        }
+    }
+}
+
+impl GameEventStream {
+    /// Start listening on a random localhost port
+    fn start() -> Result<Self> {
+        let listener = std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .context("Failed to bind event stream")?;
+        listener
+            .set_nonblocking(true)
+            .context("Failed to set up event stream")?;
+        let addr = listener.local_addr().context("Failed to get address")?;
+
+        let token = Uuid::new_v4().simple().to_string();
+        let (events, _) = broadcast::channel(STREAM_BUFFER);
+
+        let server = tauri::async_runtime::spawn({
+            let events = events.clone();
+            let token = token.clone();
+            async move {
+                let listener = match TcpListener::from_std(listener) {
+                    Ok(listener) => listener,
+                    Err(why) => {
+                        error!("Failed to start event stream: {why:?}");
+                        return;
+                    }
+                };
+                loop {
+                    match listener.accept().await {
+                        Ok((stream, peer)) => {
+                            let rx = events.subscribe();
+                            let token = token.clone();
+                            tauri::async_runtime::spawn(async move {
+                                if let Err(why) = serve_client(stream, token, rx).await {
+                                    info!("Event stream client {peer} disconnected: {why:?}");
+                                }
+                            });
+                        }
+                        Err(why) => warn!("Failed to accept event stream client: {why:?}"),
+                    }
+                }
+            }
+        });
+
+        info!("Streaming game events on {addr}");
+
+        Ok(Self {
+            events,
+            info: EventStreamInfo {
+                url: format!("http://{addr}/events?token={token}"),
+                token,
+            },
+            server,
+        })
+    }
+
+    pub fn info(&self) -> &EventStreamInfo {
+        &self.info
+    }
+
+    /// Send an event to every connected dashboard
+    pub fn record(&self, direction: EventDirection, event: &GameEvent) {
+        if self.events.receiver_count() == 0 {
+            return;
+        }
+
+        // Dashboards are often on screen for everyone to see, don't give away spoofed pings
+        let event = event.masked();
+        match serde_json::to_string(&LogLine::now(direction, &event)) {
+            Ok(line) => {
+                // Only fails if everyone disconnected since we checked
+                let _ = self.events.send(line);
+            }
+            Err(why) => warn!("Failed to serialize event for stream: {why:?}"),
+        }
+    }
+}
+
+impl Drop for GameEventStream {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+/// The event stream if the user turned it on, starting it if it isn't running yet. Stops the
+/// stream if it was turned off.
+pub fn event_stream_if_enabled(app: &AppHandle) -> Option<Arc<GameEventStream>> {
+    let handle = app.state::<EventStreamHandle>();
+    let mut current = handle.lock().unwrap_or_else(|e| e.into_inner());
+
+    if !read_stream_game_events_from_store(app) {
+        current.take();
+        return None;
+    }
+
+    if current.is_none() {
+        match GameEventStream::start() {
+            Ok(stream) => *current = Some(Arc::new(stream)),
+            Err(why) => error!("Failed to start game event stream: {why:?}"),
+        }
+    }
+
+    current.clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(request: &str) -> Result<bool> {
+        tauri::async_runtime::block_on(check_request(&mut request.as_bytes(), "abc"))
+    }
+
+    #[test]
+    fn test_check_request() {
+        assert!(check("GET /events?token=abc HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap());
+        assert!(!check("GET /events?token=xyz HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap());
+        assert!(!check("GET /events HTTP/1.1\r\n\r\n").unwrap());
+        assert!(!check("").unwrap());
+
+        let long_header = format!(
+            "GET /events?token=abc HTTP/1.1\r\nX-Junk: {}\r\n\r\n",
+            "a".repeat(MAX_REQUEST_LINE_LEN)
+        );
+        assert!(check(&long_header).is_err());
+    }
+}
@@ -1,16 +1,82 @@
-use anyhow::Context;
+use anyhow::{Context, bail};
+use log::{info, warn};
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, result::Result as StdResult, sync::Arc};
 use tauri::{AppHandle, Runtime};
 use tauri_plugin_store::{Store, StoreExt};
 use uuid::Uuid;
 
-use manhunt_logic::{GameHistory, GameSettings, PlayerProfile};
+use manhunt_logic::{
+    GameAwards, GameHistory, GameId, GameSettings, GameUiState, Location, PlayerProfile,
+    PowerupTimelineEntry,
+    storage::{from_stored, to_stored},
+};
 
 use crate::UtcDT;
 
 type Result<T = (), E = anyhow::Error> = StdResult<T, E>;
 
+/// Value of [ReplayBundle::format], used to tell replay bundles apart from other JSON files
+const REPLAY_BUNDLE_FORMAT: &str = "manhunt-replay";
+
+/// Bump this whenever a change to [AppGameHistory] would stop older bundles from importing
+const REPLAY_BUNDLE_VERSION: u32 = 1;
+
+/// A game history packed up to be shared and opened elsewhere, this is what gets exported and
+/// imported as JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayBundle {
+    /// Always [REPLAY_BUNDLE_FORMAT]
+    format: String,
+    /// The [REPLAY_BUNDLE_VERSION] this bundle was made with
+    version: u32,
+    /// When the bundle was exported
+    exported_at: UtcDT,
+    replay: AppGameHistory,
+}
+
+impl ReplayBundle {
+    pub fn new(replay: AppGameHistory) -> Self {
+        Self {
+            format: REPLAY_BUNDLE_FORMAT.to_string(),
+            version: REPLAY_BUNDLE_VERSION,
+            exported_at: chrono::Utc::now(),
+            replay,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).context("Failed to serialize replay bundle")
+    }
+
+    /// Parse a bundle made by [ReplayBundle::to_json], checking it's actually a replay bundle
+    /// this version of the app understands
+    pub fn from_json(json: &str) -> Result<Self> {
+        let bundle: Self = serde_json::from_str(json).context("Failed to parse replay bundle")?;
+        if bundle.format != REPLAY_BUNDLE_FORMAT {
+            bail!("Not a replay bundle");
+        }
+        if bundle.version > REPLAY_BUNDLE_VERSION {
+            bail!(
+                "Replay bundle is from a newer version of the app (version {})",
+                bundle.version
+            );
+        }
+        Ok(bundle)
+    }
+
+    pub fn into_replay(self) -> AppGameHistory {
+        self.replay
+    }
+}
+
+/// Store of every saved history keyed by its [GameId]
+const HISTORY_STORE: &str = "histories.json";
+
+/// Store of when each saved history's game started keyed by its [GameId], so listing histories
+/// doesn't need to load all their events and locations
+const HISTORY_INDEX_STORE: &str = "history_index.json";
+
 #[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
 pub struct AppGameHistory {
     history: GameHistory,
@@ -31,38 +97,136 @@ impl AppGameHistory {
         }
     }
 
+    pub fn powerup_timeline(&self) -> Vec<PowerupTimelineEntry> {
+        self.history.powerup_timeline()
+    }
+
+    pub fn catch_ranking(&self) -> Vec<(Uuid, u32)> {
+        self.history.catch_ranking()
+    }
+
+    /// See [GameHistory::replay_step], names and colors are filled in from the saved profiles
+    pub fn replay_step(&self, index: usize) -> Option<GameUiState> {
+        let mut ui_state = self.history.replay_step(self.settings.clone(), index)?;
+        ui_state.resolve_profiles(&self.profiles);
+        Some(ui_state)
+    }
+
+    pub fn awards(&self) -> GameAwards {
+        self.history.awards()
+    }
+
+    pub fn player_ids(&self) -> Vec<Uuid> {
+        self.history.player_ids()
+    }
+
+    pub fn player_track(&self, id: Uuid) -> Option<Vec<(UtcDT, Location)>> {
+        self.history.player_track(id).map(|track| track.to_vec())
+    }
+
+    /// Unique ID of the game, histories are stored under this
+    pub fn id(&self) -> GameId {
+        self.history.id
+    }
+
+    pub fn game_started(&self) -> UtcDT {
+        self.history.game_started
+    }
+
     fn get_store<R: Runtime>(app: &AppHandle<R>) -> Result<Arc<Store<R>>> {
-        app.store("histories.json")
+        app.store(HISTORY_STORE)
             .context("Failed to get history store")
     }
 
-    pub fn ls_histories(app: &AppHandle) -> Result<Vec<UtcDT>> {
+    fn get_index_store<R: Runtime>(app: &AppHandle<R>) -> Result<Arc<Store<R>>> {
+        app.store(HISTORY_INDEX_STORE)
+            .context("Failed to get history index store")
+    }
+
+    /// Bring histories saved by older versions of the app up to date, call this once on startup
+    /// before any other history functions.
+    ///
+    /// Histories used to be keyed by when the game started, which two games can share, those
+    /// are moved over to be keyed by their [GameId] (they're given one when loaded). Any history
+    /// missing from the index is added to it. Histories that can't be read are logged and left
+    /// alone instead of stopping the rest from being migrated.
+    pub fn migrate_store<R: Runtime>(app: &AppHandle<R>) -> Result {
         let store = Self::get_store(app)?;
+        let index = Self::get_index_store(app)?;
+        for key in store.keys() {
+            let timestamp_key = serde_json::from_str::<UtcDT>(&key).is_ok();
+            if !timestamp_key && index.has(&key) {
+                continue;
+            }
+            let Some(val) = store.get(&key) else {
+                continue;
+            };
+            let history = match from_stored::<AppGameHistory>(val) {
+                Ok(history) => history,
+                Err(why) => {
+                    warn!("Skipping unreadable game history {key}: {why:?}");
+                    continue;
+                }
+            };
+            if timestamp_key {
+                info!("Moving game history {key} to {}", history.id());
+                let serialized = to_stored(&history).context("Failed to serialize history")?;
+                store.set(history.id().to_string(), serialized);
+                store.delete(&key);
+            }
+            history.add_to_index(&index)?;
+        }
+        Ok(())
+    }
+
+    fn add_to_index<R: Runtime>(&self, index: &Store<R>) -> Result {
+        let started = serde_json::to_value(self.game_started())
+            .context("Failed to serialize game start time")?;
+        index.set(self.id().to_string(), started);
+        Ok(())
+    }
 
-        let mut histories = store
-            .keys()
+    /// Every saved game as its ID and when it started, most recent first
+    pub fn ls_histories(app: &AppHandle) -> Result<Vec<(GameId, UtcDT)>> {
+        let index = Self::get_index_store(app)?;
+
+        let mut histories = index
+            .entries()
             .into_iter()
-            .filter_map(|k| serde_json::from_str::<UtcDT>(&k).ok())
+            .filter_map(|(k, v)| {
+                let id = Uuid::parse_str(&k).ok()?;
+                let started = serde_json::from_value::<UtcDT>(v).ok()?;
+                Some((id, started))
+            })
             .collect::<Vec<_>>();
 
-        histories.sort_unstable_by(|a, b| a.cmp(b).reverse());
+        histories.sort_unstable_by(|a, b| a.1.cmp(&b.1).reverse());
 
         Ok(histories)
     }
 
-    pub fn get_history(app: &AppHandle, dt: UtcDT) -> Result<AppGameHistory> {
+    pub fn get_history(app: &AppHandle, id: GameId) -> Result<AppGameHistory> {
+        let store = Self::get_store(app)?;
+        let val = store.get(id.to_string()).context("Key not found")?;
+        from_stored(val).context("Failed to deserialize game history")
+    }
+
+    pub fn has_history(app: &AppHandle, id: GameId) -> Result<bool> {
         let store = Self::get_store(app)?;
-        let key = serde_json::to_string(&dt).context("Failed to make key")?;
-        let val = store.get(key).context("Key not found")?;
-        serde_json::from_value(val).context("Failed to deserialize game history")
+        Ok(store.has(id.to_string()))
     }
 
     pub fn save_history(&self, app: &AppHandle) -> Result {
         let store = Self::get_store(app)?;
-        let serialized = serde_json::to_value(self).context("Failed to serialize history")?;
-        let key =
-            serde_json::to_string(&self.history.game_started).context("Failed to make key")?;
-        store.set(key, serialized);
+        let serialized = to_stored(self).context("Failed to serialize history")?;
+        store.set(self.id().to_string(), serialized);
+        self.add_to_index(&Self::get_index_store(app)?)
+    }
+
+    /// Delete every saved game history
+    pub fn clear_histories(app: &AppHandle) -> Result {
+        Self::get_store(app)?.clear();
+        Self::get_index_store(app)?.clear();
         Ok(())
     }
 }
@@ -1,13 +1,23 @@
+mod error;
+mod event_log;
+mod event_stream;
 mod history;
 mod location;
+mod notifications;
 mod profiles;
+mod settings;
+mod snapshot;
 mod state;
+mod tiles;
 
 use std::collections::HashMap;
 
-use log::LevelFilter;
-use manhunt_logic::{GameSettings, GameUiState, LobbyState, PlayerProfile, UtcDT};
-use manhunt_transport::room_exists;
+use log::{LevelFilter, error};
+use manhunt_logic::{
+    GameAwards, GameId, GameSettings, GameUiState, LobbyMapPreview, LobbyState, Location,
+    PlayerProfile, PowerupTimelineEntry, SettingsSummary, TileBounds, UtcDT,
+};
+use manhunt_transport::{RoomStatus, ServerInfo, is_valid_code_format, room_status, server_info};
 use tauri::{AppHandle, Manager, State};
 use tauri_specta::{ErrorHandlingMode, collect_commands, collect_events};
 use tokio::sync::RwLock;
@@ -16,12 +26,40 @@ use uuid::Uuid;
 use std::result::Result as StdResult;
 
 use crate::{
-    history::AppGameHistory,
-    profiles::{read_profile_from_store, write_profile_to_store},
-    state::{AppScreen, AppState, AppStateHandle, ChangeScreen, GameStateUpdate, LobbyStateUpdate},
+    error::AppError,
+    event_log::latest_event_log_path,
+    event_stream::{EventStreamHandle, EventStreamInfo, event_stream_if_enabled},
+    history::{AppGameHistory, ReplayBundle},
+    location::{LocationAccuracy, LocationReadiness, check_location_readiness},
+    notifications::{NotificationTestResult, send_sample_notification},
+    profiles::{
+        read_profile_from_store, read_recent_room_codes_from_store, write_profile_to_store,
+    },
+    settings::{
+        IceServerSettings, NotificationPreferences, delete_preset, list_presets,
+        read_ice_servers_from_store, read_last_settings_from_store,
+        read_location_accuracy_from_store, read_log_game_events_from_store,
+        read_notification_prefs_from_store, read_preset, read_stream_game_events_from_store,
+        read_tile_server_from_store, write_ice_servers_to_store, write_last_settings_to_store,
+        write_location_accuracy_to_store, write_log_game_events_to_store,
+        write_notification_prefs_to_store, write_preset, write_stream_game_events_to_store,
+        write_tile_server_to_store,
+    },
+    snapshot::{
+        InterruptedGameInfo, clear_interrupted_game_from_store, read_interrupted_game_from_store,
+    },
+    state::{
+        AppScreen, AppState, AppStateHandle, ChangeScreen, GameStateUpdate,
+        IncompatiblePlayerInLobby, LobbyStateUpdate, PlayerJoinedLobby, PlayerLeftLobby,
+        SeekersReleasedEvent,
+    },
+    tiles::{
+        MapTilesProgress, TILE_PROTOCOL, check_prefetch, check_tile_server, prefetch_tiles,
+        serve_tile, tile_cache_dir,
+    },
 };
 
-type Result<T = (), E = String> = StdResult<T, E>;
+type Result<T = (), E = AppError> = StdResult<T, E>;
 
 // == GENERAL / FLOW COMMANDS ==
 
@@ -35,6 +73,7 @@ async fn get_current_screen(state: State<'_, AppStateHandle>) -> Result<AppScree
         AppState::Menu(_player_profile) => AppScreen::Menu,
         AppState::Lobby(_lobby) => AppScreen::Lobby,
         AppState::Game(_game, _profiles) => AppScreen::Game,
+        AppState::GameOver(_, _) => AppScreen::GameOver,
         AppState::Replay(_) => AppScreen::Replay,
     })
 }
@@ -48,6 +87,73 @@ async fn quit_to_menu(app: AppHandle, state: State<'_, AppStateHandle>) -> Resul
     Ok(())
 }
 
+#[tauri::command]
+#[specta::specta]
+/// Get the directory map tiles downloaded by [prefetch_map_tiles] are kept in, tiles are
+/// stored as `{z}/{x}/{y}.png` under it. The map should load tiles through the `tiles` URI scheme
+/// (`convertFileSrc("{z}/{x}/{y}.png", "tiles")`) instead, which reads this cache and downloads
+/// any tiles missing from it.
+fn get_map_tile_cache_dir(app: AppHandle) -> Result<String> {
+    Ok(tile_cache_dir(&app)?.to_string_lossy().into_owned())
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Get the URL map tiles are downloaded from, with `{z}`, `{x}`, and `{y}` where the tile goes
+fn get_tile_server(app: AppHandle) -> Result<String> {
+    Ok(read_tile_server_from_store(&app))
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Set the URL map tiles are downloaded from, it needs `{z}`, `{x}`, and `{y}` where the tile
+/// goes. Set to `null` to go back to OpenStreetMap. Tiles already downloaded are kept.
+fn set_tile_server(tile_server: Option<String>, app: AppHandle) -> Result {
+    if let Some(tile_server) = &tile_server {
+        check_tile_server(tile_server)?;
+    }
+    write_tile_server_to_store(&app, tile_server);
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Lobby, Game) Download map tiles covering `bounds` for every zoom level in
+/// `zoom_range` (inclusive) so the map works offline, leave `bounds` out to use the play area.
+/// Sends MapTilesProgress events as tiles finish and returns the final progress. Tiles already
+/// downloaded are skipped and downloads are throttled, so this can take a while. Fails if the
+/// area needs too many tiles.
+async fn prefetch_map_tiles(
+    bounds: Option<TileBounds>,
+    zoom_range: (u8, u8),
+    app: AppHandle,
+    state: State<'_, AppStateHandle>,
+) -> Result<MapTilesProgress> {
+    let bounds = match bounds {
+        Some(bounds) => bounds,
+        None => {
+            let lobby = state.read().await.get_lobby();
+            let settings = match lobby {
+                Ok(lobby) => lobby.clone_settings().await,
+                Err(_) => state.read().await.get_game()?.clone_settings().await,
+            };
+            settings
+                .play_area
+                .as_ref()
+                .and_then(TileBounds::around)
+                .ok_or_else(|| {
+                    AppError::Validation(vec!["This game doesn't have a play area".to_string()])
+                })?
+        }
+    };
+    let zooms = zoom_range.0..=zoom_range.1;
+    let total = check_prefetch(&bounds, &zooms)?;
+    let server = read_tile_server_from_store(&app);
+    prefetch_tiles(&app, &server, bounds, zooms, total)
+        .await
+        .map_err(|err| AppError::Network(format!("{err:#}")))
+}
+
 // == AppState::Setup COMMANDS
 
 #[tauri::command]
@@ -58,6 +164,7 @@ async fn complete_setup(
     app: AppHandle,
     state: State<'_, AppStateHandle>,
 ) -> Result {
+    profile.validate()?;
     state.write().await.complete_setup(&app, profile)
 }
 
@@ -74,26 +181,248 @@ async fn get_profile(state: State<'_, AppStateHandle>) -> Result<PlayerProfile>
 
 #[tauri::command]
 #[specta::specta]
-/// (Screen: Menu) Get a list of all previously played games, returns of list of DateTimes that represent when
-/// each game started, use this as a key
-fn list_game_histories(app: AppHandle) -> Result<Vec<UtcDT>> {
+/// (Screen: Menu) Get the settings last used to host a game, use this to prefill settings when
+/// creating a lobby. Returns the default settings if none have been saved yet.
+fn get_last_settings(app: AppHandle) -> Result<GameSettings> {
+    Ok(read_last_settings_from_store(&app).unwrap_or_default())
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Menu) Explain what the given settings will do during a game in plain terms, use this
+/// to show hosts a preview before they start
+fn describe_settings(settings: GameSettings) -> Result<SettingsSummary> {
+    Ok(settings.describe())
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Menu) Save settings as a named preset, overwriting any preset with the same name.
+/// Fails if the settings are invalid, the error will list every problem.
+fn save_settings_preset(name: String, settings: GameSettings, app: AppHandle) -> Result {
+    settings.validate()?;
+    write_preset(&app, &name, settings)?;
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Menu) Get the names of all saved settings presets in alphabetical order
+fn list_settings_presets(app: AppHandle) -> Result<Vec<String>> {
+    Ok(list_presets(&app)?)
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Menu) Get the settings saved in the preset with the given name
+fn load_settings_preset(name: String, app: AppHandle) -> Result<GameSettings> {
+    read_preset(&app, &name)?.ok_or(AppError::NotFound)
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Menu) Delete the preset with the given name
+fn delete_settings_preset(name: String, app: AppHandle) -> Result {
+    if delete_preset(&app, &name)? {
+        Ok(())
+    } else {
+        Err(AppError::NotFound)
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Get which game events send an OS notification while the app is in the background
+fn get_notification_preferences(app: AppHandle) -> Result<NotificationPreferences> {
+    Ok(read_notification_prefs_from_store(&app))
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Set which game events send an OS notification while the app is in the background
+fn set_notification_preferences(prefs: NotificationPreferences, app: AppHandle) -> Result {
+    write_notification_prefs_to_store(&app, prefs);
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Send a sample notification so the user can check notifications work before a game, asks for
+/// permission if the user hasn't been asked yet
+fn send_test_notification(app: AppHandle) -> Result<NotificationTestResult> {
+    Ok(send_sample_notification(&app))
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Get whether every event in a game is written to a log file for debugging
+fn get_log_game_events(app: AppHandle) -> Result<bool> {
+    Ok(read_log_game_events_from_store(&app))
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Set whether every event in a game is written to a log file for debugging, takes effect from
+/// the next game
+fn set_log_game_events(enabled: bool, app: AppHandle) -> Result {
+    write_log_game_events_to_store(&app, enabled);
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Get whether every event in a game is streamed out for spectator dashboards, see
+/// [get_event_stream_info]
+fn get_stream_game_events(app: AppHandle) -> Result<bool> {
+    Ok(read_stream_game_events_from_store(&app))
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Set whether every event in a game is streamed out for spectator dashboards, takes effect from
+/// the next game
+fn set_stream_game_events(enabled: bool, app: AppHandle) -> Result {
+    write_stream_game_events_to_store(&app, enabled);
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Get the URL and token a spectator dashboard on this device can connect to for the game event
+/// stream, starting it if it isn't running. `null` if streaming is turned off, see
+/// [set_stream_game_events].
+///
+/// The stream is Server-Sent Events, each event's data is a JSON object of the form
+/// `{ "at": <UTC timestamp>, "direction": "Sent" | "Received", "event": GameEvent }`.
+fn get_event_stream_info(app: AppHandle) -> Result<Option<EventStreamInfo>> {
+    Ok(event_stream_if_enabled(&app).map(|stream| stream.info().clone()))
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Get the location accuracy modes this device can switch between, empty if it can't provide
+/// locations at all
+fn get_location_accuracy_modes() -> Result<Vec<LocationAccuracy>> {
+    Ok(LocationAccuracy::available())
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Get how accurate (and battery hungry) locations should be during games
+fn get_location_accuracy(app: AppHandle) -> Result<LocationAccuracy> {
+    Ok(read_location_accuracy_from_store(&app))
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Set how accurate (and battery hungry) locations should be during games, takes effect from the
+/// next game
+fn set_location_accuracy(accuracy: LocationAccuracy, app: AppHandle) -> Result {
+    write_location_accuracy_to_store(&app, accuracy);
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Get the path of the event log for the most recent game, for attaching to bug reports. `null`
+/// if no games have been logged, see [set_log_game_events].
+fn get_event_log_path(app: AppHandle) -> Result<Option<String>> {
+    Ok(latest_event_log_path(&app)?.map(|path| path.to_string_lossy().into_owned()))
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Get the STUN/TURN servers used to connect to other players, [Option::None] if the defaults
+/// are being used
+fn get_ice_servers(app: AppHandle) -> Result<Option<IceServerSettings>> {
+    Ok(read_ice_servers_from_store(&app))
+}
+
+#[tauri::command]
+#[specta::specta]
+/// Set the STUN/TURN servers used to connect to other players, set to `null` to go back to the
+/// defaults. Players on strict networks may not be able to connect to others without a TURN
+/// server. Takes effect the next time a lobby is joined.
+fn set_ice_servers(ice_servers: Option<IceServerSettings>, app: AppHandle) -> Result {
+    if let Some(ice_servers) = &ice_servers {
+        ice_servers.validate()?;
+    }
+    write_ice_servers_to_store(&app, ice_servers);
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Menu) Get a list of all previously played games, returns a list of each game's id
+/// and when it started, most recent first. Use the id as a key.
+fn list_game_histories(app: AppHandle) -> Result<Vec<(GameId, UtcDT)>> {
     AppGameHistory::ls_histories(&app)
-        .map_err(|err| err.context("Failed to get game histories").to_string())
+        .map_err(|err| err.context("Failed to get game histories").into())
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Menu) Export the game history specified by id as a self-contained JSON replay bundle
+/// that can be shared and imported on another device
+fn export_replay_bundle(id: GameId, app: AppHandle) -> Result<String> {
+    let history = AppGameHistory::get_history(&app, id)
+        .map_err(|err| err.context("Failed to read game history"))?;
+    ReplayBundle::new(history).to_json().map_err(AppError::from)
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Menu) Import a replay bundle made by [export_replay_bundle] into the game history,
+/// returns the id of the imported game. Errors if a game with the same id already exists.
+fn import_replay(json: String, app: AppHandle) -> Result<GameId> {
+    let history = ReplayBundle::from_json(&json)
+        .map_err(|err| AppError::Validation(vec![format!("{err:#}")]))?
+        .into_replay();
+    let id = history.id();
+    if AppGameHistory::has_history(&app, id)? {
+        return Err(AppError::Validation(vec![
+            "This game has already been saved".to_string(),
+        ]));
+    }
+    history
+        .save_history(&app)
+        .map_err(|err| err.context("Failed to save imported replay"))?;
+    Ok(id)
 }
 
 #[tauri::command]
 #[specta::specta]
 /// (Screen: Menu) Go to the game replay screen to replay the game history specified by id
-async fn replay_game(id: UtcDT, app: AppHandle, state: State<'_, AppStateHandle>) -> Result {
+async fn replay_game(id: GameId, app: AppHandle, state: State<'_, AppStateHandle>) -> Result {
     state.write().await.replay_game(&app, id)
 }
 
 #[tauri::command]
 #[specta::specta]
 /// (Screen: Menu) Check if a room code is valid to join, use this before starting a game
-/// for faster error checking.
-async fn check_room_code(code: &str) -> Result<bool> {
-    room_exists(code).await.map_err(|err| err.to_string())
+/// for faster error checking. Tells apart a bad code from not being able to reach the server.
+async fn check_room_code(code: &str) -> Result<RoomStatus> {
+    Ok(room_status(code).await)
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Menu) Get the signaling server's version, use this before hosting or joining to warn
+/// the user if the server isn't `compatible` with this version of the app. Errors with `Network`
+/// if the server can't be reached or is too old to report its version.
+async fn signaling_server_info() -> Result<ServerInfo> {
+    server_info()
+        .await
+        .map_err(|err| AppError::Network(format!("{err:#}")))
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Menu) Check if a room code is the right length and only uses characters room codes
+/// can have, without any network calls. Use this to reject obviously bad input early,
+/// [check_room_code] still needs to be used to check the room exists.
+fn is_valid_room_code_format(code: &str) -> bool {
+    is_valid_code_format(code)
 }
 
 #[tauri::command]
@@ -104,6 +433,7 @@ async fn update_profile(
     app: AppHandle,
     state: State<'_, AppStateHandle>,
 ) -> Result {
+    new_profile.validate()?;
     write_profile_to_store(&app, new_profile.clone());
     let mut state = state.write().await;
     let profile = state.get_menu_mut()?;
@@ -115,19 +445,84 @@ async fn update_profile(
 #[specta::specta]
 /// (Screen: Menu) Start/Join a new lobby, set `join_code` to `null` to be host,
 /// set it to a join code to be a client. This triggers a screen change to [AppScreen::Lobby]
-async fn start_lobby(
-    app: AppHandle,
-    join_code: Option<String>,
-    settings: GameSettings,
-    state: State<'_, AppStateHandle>,
-) -> Result {
-    let mut state = state.write().await;
-    state.start_lobby(join_code, app, settings).await;
+async fn start_lobby(app: AppHandle, join_code: Option<String>, settings: GameSettings) -> Result {
+    AppState::start_lobby(app, join_code, None, settings).await
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Menu) Host a new lobby with a room code of the host's choosing instead of a random
+/// one. Fails with `Validation` if the code can't be a room code, or `RoomCodeTaken` if the server
+/// won't create a room with it. This triggers a screen change to [AppScreen::Lobby]
+async fn host_lobby_with_code(app: AppHandle, code: String, settings: GameSettings) -> Result {
+    AppState::start_lobby(app, None, Some(code), settings).await
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Menu) Get room codes this device recently joined and when **in UTC**, most recent
+/// first. Use this to offer quickly rejoining friends.
+async fn recent_room_codes(app: AppHandle) -> Result<Vec<(String, UtcDT)>> {
+    Ok(read_recent_room_codes_from_store(&app))
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Setup, Menu) Delete the profile, settings, presets, game histories, and recent room
+/// codes, then go back to AppScreen::Setup. `confirm` must be true, this can't be undone.
+async fn reset_app_data(confirm: bool, app: AppHandle, state: State<'_, AppStateHandle>) -> Result {
+    if !confirm {
+        return Err(AppError::Validation(vec![
+            "Resetting app data must be confirmed".to_string(),
+        ]));
+    }
+    state.write().await.reset_app_data(&app)
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Menu) Join a game that has already started as a spectator, the host must have
+/// enabled [GameSettings::allow_spectators]. This triggers a screen change to [AppScreen::Game]
+async fn start_spectating(app: AppHandle, join_code: String) -> Result {
+    AppState::start_spectating(app, &join_code).await
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Menu) Get the game that was in progress when the app last closed (e.g. the OS killed
+/// the app while it was in the background), if any. Use this to offer resuming it with
+/// [resume_interrupted_game], which checks the game is still going.
+async fn get_interrupted_game(app: AppHandle) -> Result<Option<InterruptedGameInfo>> {
+    Ok(read_interrupted_game_from_store(&app).map(|game| game.info()))
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Menu) Reconnect to the game that was in progress when the app last closed and pick up
+/// where we left off. Fails with `NotFound` if there's no game to resume or its room is gone, in
+/// which case it's forgotten. This triggers a screen change to [AppScreen::Game]
+async fn resume_interrupted_game(app: AppHandle) -> Result {
+    AppState::resume_interrupted_game(app).await
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Menu) Forget the game that was in progress when the app last closed
+async fn discard_interrupted_game(app: AppHandle) -> Result {
+    clear_interrupted_game_from_store(&app);
     Ok(())
 }
 
 // AppState::Lobby COMMANDS
 
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Lobby) Check that location permission is granted and we can get a GPS fix, asks
+/// for permission if the user hasn't been asked yet. Use this before starting a game.
+async fn check_location_permission_and_fix(app: AppHandle) -> Result<LocationReadiness> {
+    Ok(check_location_readiness(&app))
+}
+
 #[tauri::command]
 #[specta::specta]
 /// (Screen: Lobby) Get the current state of the lobby, call after receiving an update event
@@ -136,6 +531,15 @@ async fn get_lobby_state(state: State<'_, AppStateHandle>) -> Result<LobbyState>
     Ok(lobby.clone_state().await)
 }
 
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Lobby) Get the play area, powerup locations, safe zones, and jail from the lobby's
+/// settings for previewing on a map. Only call again when [LobbyState]'s `map_revision` changes.
+async fn get_lobby_map_preview(state: State<'_, AppStateHandle>) -> Result<LobbyMapPreview> {
+    let lobby = state.read().await.get_lobby()?;
+    Ok(lobby.map_preview().await)
+}
+
 #[tauri::command]
 #[specta::specta]
 /// (Screen: Lobby) Switch teams between seekers and hiders, returns the new [LobbyState]
@@ -147,10 +551,50 @@ async fn switch_teams(seeker: bool, state: State<'_, AppStateHandle>) -> Result
 
 #[tauri::command]
 #[specta::specta]
-/// (Screen: Lobby) HOST ONLY: Push new settings to everyone, does nothing on clients. Returns the
-/// new lobby state
-async fn host_update_settings(settings: GameSettings, state: State<'_, AppStateHandle>) -> Result {
+/// (Screen: Lobby) Set the color of our map pin and scoreboard row for this lobby, must be a hex
+/// color like `#ff0000`. Pass `null` to clear it.
+async fn set_lobby_color(color: Option<String>, state: State<'_, AppStateHandle>) -> Result {
+    let lobby = state.read().await.get_lobby()?;
+    lobby
+        .set_color(color)
+        .await
+        .map_err(|err| AppError::Validation(vec![err.to_string()]))
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Lobby) Send our profile to everyone again and ask them to send theirs back, use this
+/// if players are missing from the lobby
+async fn resync_lobby_profiles(state: State<'_, AppStateHandle>) -> Result {
+    let lobby = state.read().await.get_lobby()?;
+    lobby.resync_profiles().await.map_err(AppError::from)
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Lobby) HOST ONLY: Give every player a unique color, players keep the color they
+/// picked unless someone else already has it
+async fn host_assign_colors(state: State<'_, AppStateHandle>) -> Result {
+    let lobby = state.read().await.get_lobby()?;
+    lobby
+        .assign_colors()
+        .await
+        .map_err(|err| AppError::Validation(vec![err.to_string()]))
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Lobby) HOST ONLY: Push new settings to everyone and save them as the last used
+/// settings, does nothing on clients. Returns the new lobby state
+async fn host_update_settings(
+    settings: GameSettings,
+    app: AppHandle,
+    state: State<'_, AppStateHandle>,
+) -> Result {
     let lobby = state.read().await.get_lobby()?;
+    if lobby.is_host() {
+        write_last_settings_to_store(&app, &settings);
+    }
     lobby.update_settings(settings).await;
     Ok(())
 }
@@ -158,9 +602,16 @@ async fn host_update_settings(settings: GameSettings, state: State<'_, AppStateH
 #[tauri::command]
 #[specta::specta]
 /// (Screen: Lobby) HOST ONLY: Start the game, stops anyone else from joining and switched screen
-/// to AppScreen::Game.
-async fn host_start_game(state: State<'_, AppStateHandle>) -> Result {
-    state.read().await.get_lobby()?.start_game().await;
+/// to AppScreen::Game. Fails if everyone is on the same team.
+async fn host_start_game(app: AppHandle, state: State<'_, AppStateHandle>) -> Result {
+    let lobby = state.read().await.get_lobby()?;
+    lobby
+        .start_game()
+        .await
+        .map_err(|err| AppError::Validation(vec![err.to_string()]))?;
+    if lobby.is_host() {
+        write_last_settings_to_store(&app, &lobby.clone_settings().await);
+    }
     Ok(())
 }
 
@@ -184,40 +635,235 @@ async fn get_game_settings(state: State<'_, AppStateHandle>) -> Result<GameSetti
 
 #[tauri::command]
 #[specta::specta]
-/// (Screen: Game) Get the current state of the game.
+/// (Screen: Game) Get a map of player IDs to whether that player is a seeker. A lighter
+/// alternative to [get_game_state] for updates that only need to know who's been caught, use
+/// [get_game_state] for the initial load.
+async fn get_caught_state(state: State<'_, AppStateHandle>) -> Result<HashMap<Uuid, bool>> {
+    Ok(state.read().await.get_game()?.get_caught_state().await)
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Game) Get the IDs of players still connected to the game, sorted. Anyone in
+/// [get_profiles] that's missing here has dropped out.
+async fn get_active_players(state: State<'_, AppStateHandle>) -> Result<Vec<Uuid>> {
+    Ok(state.read().await.get_game()?.get_active_players().await)
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Game) Get roughly how far the local player has moved this game in meters, GPS
+/// glitches that jump implausibly far are left out
+async fn get_distance_traveled(state: State<'_, AppStateHandle>) -> Result<f64> {
+    Ok(state.read().await.get_game()?.distance_traveled().await)
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Game) Instantly reveal the local player's location to everyone, outside the
+/// normal ping schedule. Only hiders can, and only [GameSettings::panic_pings] times a game.
+async fn panic_ping(state: State<'_, AppStateHandle>) -> Result {
+    let game = state.read().await.get_game()?;
+    game.panic_ping().await.map_err(AppError::from)
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Game) Drop a pin on the map for the other seekers with an optional label, e.g.
+/// "checking the north park". Only seekers can, and only seekers will see it in `seeker_pins`.
+async fn drop_seeker_pin(
+    location: Location,
+    label: Option<String>,
+    state: State<'_, AppStateHandle>,
+) -> Result {
+    let game = state.read().await.get_game()?;
+    game.drop_seeker_pin(location, label)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Game) Get the current state of the game, `players` will have display names filled in
+/// from the profiles for this game.
 async fn get_game_state(state: State<'_, AppStateHandle>) -> Result<GameUiState> {
-    Ok(state.read().await.get_game()?.get_ui_state().await)
+    let state = state.read().await;
+    let mut ui_state = state.get_game()?.get_ui_state().await;
+    ui_state.resolve_profiles(state.get_profiles()?);
+    Ok(ui_state)
 }
 
 #[tauri::command]
 #[specta::specta]
-/// (Screen: Game) Mark this player as caught, this player will become a seeker. Returns the new game state
-async fn mark_caught(state: State<'_, AppStateHandle>) -> Result {
+/// (Screen: Game) Mark this player as caught, this player will become a seeker. `by` is the seeker
+/// that caught this player, if known. Fails if this player is in a safe zone.
+async fn mark_caught(by: Option<Uuid>, state: State<'_, AppStateHandle>) -> Result {
     let game = state.read().await.get_game()?;
-    game.mark_caught().await;
-    Ok(())
+    game.mark_caught(by).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Game) Take back marking this player as caught in case it was a mis-tap. Only possible
+/// until [GameUiState::undo_catch_until], fails after that.
+async fn undo_caught(state: State<'_, AppStateHandle>) -> Result {
+    let game = state.read().await.get_game()?;
+    game.undo_caught().await.map_err(AppError::from)
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Game) Give up and become a seeker, this will end the game if this player is the last
+/// hider. Fails if this player is already a seeker.
+async fn concede_game(state: State<'_, AppStateHandle>) -> Result {
+    let game = state.read().await.get_game()?;
+    game.concede().await.map_err(AppError::from)
 }
 
 #[tauri::command]
 #[specta::specta]
 /// (Screen: Game) Grab a powerup on the map, this should be called when the user is *in range* of
-/// the powerup. Returns the new game state after rolling for the powerup
-async fn grab_powerup(state: State<'_, AppStateHandle>) -> Result {
+/// the powerup. Returns whether the grab succeeded, it will fail if the game has a grab radius set
+/// and the player is too far from the powerup.
+async fn grab_powerup(state: State<'_, AppStateHandle>) -> Result<bool> {
     let game = state.read().await.get_game()?;
-    game.get_powerup().await;
-    Ok(())
+    Ok(game.get_powerup().await)
 }
 
 #[tauri::command]
 #[specta::specta]
 /// (Screen: Game) Use the currently held powerup in the player's held_powerup. Does nothing if the
-/// player has none. Returns the updated game state
+/// player has none. Fails without using it up if the powerup can't be used in the player's
+/// current role.
 async fn activate_powerup(state: State<'_, AppStateHandle>) -> Result {
     let game = state.read().await.get_game()?;
-    game.use_powerup().await;
+    game.use_powerup().await.map_err(AppError::from)
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Game) Ask the other players to resend their caught state and pings, use this if the
+/// game state seems out of sync (e.g. on pull-to-refresh)
+async fn resync_game_state(state: State<'_, AppStateHandle>) -> Result {
+    let game = state.read().await.get_game()?;
+    game.request_resync().await;
     Ok(())
 }
 
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Game) Free every player in jail, the local player must be a hider near the jail.
+/// Returns the IDs of the players that were freed.
+async fn attempt_jailbreak(state: State<'_, AppStateHandle>) -> Result<Vec<Uuid>> {
+    let game = state.read().await.get_game()?;
+    game.attempt_jailbreak().await.map_err(AppError::from)
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Game) HOST ONLY: End the game early for everyone. Unlike quitting, the game will still
+/// go through post-game sync and be saved for replay.
+async fn host_end_game(state: State<'_, AppStateHandle>) -> Result {
+    let game = state.read().await.get_game()?;
+    game.force_end().await.map_err(AppError::from)
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Game) HOST ONLY: Ping every hider right now regardless of the ping schedule, or every
+/// seeker if [GameSettings::reverse_pings] is on
+async fn host_ping_all(state: State<'_, AppStateHandle>) -> Result {
+    let game = state.read().await.get_game()?;
+    game.ping_all_hiders().await.map_err(AppError::from)
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Game) HOST ONLY: Change settings mid-game for everyone. Settings that have already
+/// taken effect (e.g. hiding time once seekers are released) can't be changed, the error will
+/// list every problem.
+async fn host_amend_settings(settings: GameSettings, state: State<'_, AppStateHandle>) -> Result {
+    let game = state.read().await.get_game()?;
+    game.amend_settings(settings).await.map_err(AppError::from)
+}
+
+#[cfg(debug_assertions)]
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Game) DEBUG BUILDS ONLY: Run a game tick as if it were `now` **in UTC**, to step
+/// through timing (hiding time, pings, powerups) without waiting. Doesn't exist in release builds.
+async fn debug_force_tick(now: UtcDT, state: State<'_, AppStateHandle>) -> Result {
+    let game = state.read().await.get_game()?;
+    game.force_tick(now).await;
+    Ok(())
+}
+
+#[cfg(debug_assertions)]
+#[tauri::command]
+#[specta::specta]
+/// DEBUG BUILDS ONLY: Get whether games only tick when [debug_force_tick] is called
+fn debug_get_manual_tick(app: AppHandle) -> Result<bool> {
+    Ok(crate::settings::read_manual_tick_from_store(&app))
+}
+
+#[cfg(debug_assertions)]
+#[tauri::command]
+#[specta::specta]
+/// DEBUG BUILDS ONLY: Set whether games only tick when [debug_force_tick] is called instead of
+/// every second, for stepping through a game deterministically. Takes effect from the next game.
+fn debug_set_manual_tick(enabled: bool, app: AppHandle) -> Result {
+    crate::settings::write_manual_tick_to_store(&app, enabled);
+    Ok(())
+}
+
+#[cfg(debug_assertions)]
+#[tauri::command]
+#[specta::specta]
+/// DEBUG BUILDS ONLY: Get whether lobbies connect in-process instead of through the signaling
+/// server
+fn debug_get_loopback_transport(app: AppHandle) -> Result<bool> {
+    Ok(crate::settings::read_loopback_transport_from_store(&app))
+}
+
+#[cfg(debug_assertions)]
+#[tauri::command]
+#[specta::specta]
+/// DEBUG BUILDS ONLY: Set whether lobbies connect in-process instead of through the signaling
+/// server, for trying out lobbies and games without a server. Only lobbies in this app can be
+/// joined this way. Takes effect from the next lobby.
+fn debug_set_loopback_transport(enabled: bool, app: AppHandle) -> Result {
+    crate::settings::write_loopback_transport_to_store(&app, enabled);
+    Ok(())
+}
+
+// AppState::GameOver COMMANDS
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: GameOver) Get the results of the game that just ended
+async fn get_game_results(state: State<'_, AppStateHandle>) -> Result<AppGameHistory> {
+    state.read().await.get_game_results()
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: GameOver) Leave the results screen and go back to the menu
+async fn dismiss_game_results(app: AppHandle, state: State<'_, AppStateHandle>) -> Result {
+    let mut state = state.write().await;
+    state.get_game_results()?;
+    state.quit_to_menu(app).await;
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: GameOver) HOST ONLY: Bring everyone from the game that just ended back to a lobby
+/// with the same players and settings. Everyone will switch to [AppScreen::Lobby].
+async fn host_start_rematch(state: State<'_, AppStateHandle>) -> Result {
+    let lobby = state.read().await.get_rematch_lobby()?;
+    lobby.start_rematch().await.map_err(AppError::from)
+}
+
 // AppState::Replay COMMANDS
 
 #[tauri::command]
@@ -228,10 +874,92 @@ async fn get_current_replay_history(state: State<'_, AppStateHandle>) -> Result<
     state.read().await.get_replay()
 }
 
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Replay) Get every powerup that spawned in the game being replayed, with when it
+/// despawned and who grabbed it.
+async fn get_replay_powerups(
+    state: State<'_, AppStateHandle>,
+) -> Result<Vec<PowerupTimelineEntry>> {
+    Ok(state.read().await.get_replay()?.powerup_timeline())
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Replay) Get seekers ranked by how many players they caught in the game being
+/// replayed, most catches first. Empty if catch scores weren't tracked for the game.
+async fn get_replay_catch_ranking(state: State<'_, AppStateHandle>) -> Result<Vec<(Uuid, u32)>> {
+    Ok(state.read().await.get_replay()?.catch_ranking())
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Replay) Get the IDs of every player with a location track in the game being
+/// replayed, for picking who to follow.
+async fn replay_player_ids(state: State<'_, AppStateHandle>) -> Result<Vec<Uuid>> {
+    Ok(state.read().await.get_replay()?.player_ids())
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Replay) Get the location track of a single player in the game being replayed, for
+/// following them without loading everyone else's track.
+async fn get_replay_player_track(
+    id: Uuid,
+    state: State<'_, AppStateHandle>,
+) -> Result<Vec<(UtcDT, Location)>> {
+    state
+        .read()
+        .await
+        .get_replay()?
+        .player_track(id)
+        .ok_or(AppError::NotFound)
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Replay) Get the end-of-game awards for the game being replayed, an award is null if
+/// nobody earned it.
+async fn get_replay_awards(state: State<'_, AppStateHandle>) -> Result<GameAwards> {
+    Ok(state.read().await.get_replay()?.awards())
+}
+
+#[tauri::command]
+#[specta::specta]
+/// (Screen: Replay) Get the game state as it was after the first `index` events of the game being
+/// replayed, for stepping through a game event by event. Fails with `NotFound` if the game
+/// doesn't have that many events.
+async fn replay_step(index: u32, state: State<'_, AppStateHandle>) -> Result<GameUiState> {
+    state
+        .read()
+        .await
+        .get_replay()?
+        .replay_step(index as usize)
+        .ok_or(AppError::NotFound)
+}
+
+/// [collect_commands] with the given commands, plus commands that only exist in debug builds
+macro_rules! app_commands {
+    ($($command:ident),* $(,)?) => {{
+        #[cfg(debug_assertions)]
+        let commands = collect_commands![
+            $($command,)*
+            debug_force_tick,
+            debug_get_manual_tick,
+            debug_set_manual_tick,
+            debug_get_loopback_transport,
+            debug_set_loopback_transport
+        ];
+        #[cfg(not(debug_assertions))]
+        let commands = collect_commands![$($command),*];
+        commands
+    }};
+}
+
 pub fn mk_specta() -> tauri_specta::Builder {
     tauri_specta::Builder::<tauri::Wry>::new()
         .error_handling(ErrorHandlingMode::Throw)
-        .commands(collect_commands![
+        .commands(app_commands![
             start_lobby,
             get_profile,
             quit_to_menu,
@@ -252,11 +980,77 @@ pub fn mk_specta() -> tauri_specta::Builder {
             get_game_settings,
             get_game_state,
             complete_setup,
+            resync_game_state,
+            get_replay_powerups,
+            get_replay_catch_ranking,
+            host_end_game,
+            concede_game,
+            get_last_settings,
+            save_settings_preset,
+            list_settings_presets,
+            load_settings_preset,
+            delete_settings_preset,
+            get_notification_preferences,
+            set_notification_preferences,
+            host_amend_settings,
+            get_game_results,
+            dismiss_game_results,
+            host_start_rematch,
+            check_location_permission_and_fix,
+            attempt_jailbreak,
+            export_replay_bundle,
+            import_replay,
+            get_ice_servers,
+            set_ice_servers,
+            get_caught_state,
+            set_lobby_color,
+            host_assign_colors,
+            start_spectating,
+            recent_room_codes,
+            replay_player_ids,
+            get_replay_player_track,
+            undo_caught,
+            is_valid_room_code_format,
+            describe_settings,
+            get_replay_awards,
+            resync_lobby_profiles,
+            replay_step,
+            get_interrupted_game,
+            resume_interrupted_game,
+            discard_interrupted_game,
+            send_test_notification,
+            get_active_players,
+            panic_ping,
+            drop_seeker_pin,
+            get_map_tile_cache_dir,
+            get_tile_server,
+            set_tile_server,
+            prefetch_map_tiles,
+            reset_app_data,
+            get_log_game_events,
+            set_log_game_events,
+            get_event_log_path,
+            signaling_server_info,
+            get_distance_traveled,
+            get_lobby_map_preview,
+            host_ping_all,
+            get_stream_game_events,
+            set_stream_game_events,
+            get_event_stream_info,
+            host_lobby_with_code,
+            get_location_accuracy_modes,
+            get_location_accuracy,
+            set_location_accuracy,
         ])
         .events(collect_events![
             ChangeScreen,
             GameStateUpdate,
-            LobbyStateUpdate
+            LobbyStateUpdate,
+            SeekersReleasedEvent,
+            PlayerJoinedLobby,
+            PlayerLeftLobby,
+            IncompatiblePlayerInLobby,
+            MapTilesProgress
         ])
 }
 
@@ -277,11 +1071,23 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_geolocation::init())
         .plugin(tauri_plugin_store::Builder::default().build())
+        .register_asynchronous_uri_scheme_protocol(TILE_PROTOCOL, |ctx, request, responder| {
+            let app = ctx.app_handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let server = read_tile_server_from_store(&app);
+                responder.respond(serve_tile(&app, &server, request.uri().path()).await);
+            });
+        })
         .invoke_handler(builder.invoke_handler())
         .manage(state)
+        .manage(EventStreamHandle::default())
         .setup(move |app| {
             builder.mount_events(app);
 
+            if let Err(why) = AppGameHistory::migrate_store(app.handle()) {
+                error!("Failed to migrate game histories: {why:?}");
+            }
+
             let handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
                 if let Some(profile) = read_profile_from_store(&handle) {
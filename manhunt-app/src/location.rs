@@ -1,25 +1,125 @@
-use tauri::AppHandle;
-use tauri_plugin_geolocation::{GeolocationExt, PositionOptions};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, plugin::PermissionState};
+use tauri_plugin_geolocation::{GeolocationExt, PermissionType, PositionOptions};
 
 use manhunt_logic::{Location, LocationService};
 
-pub struct TauriLocation(AppHandle);
+use crate::settings::read_location_accuracy_from_store;
+
+/// Whether this device can provide locations for a game
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+pub enum LocationReadiness {
+    /// Permission is granted and we got a fix
+    Ready,
+    /// The user didn't allow location access
+    PermissionDenied,
+    /// Permission is granted but we couldn't get a fix, the user may need to go outside or turn
+    /// on location services
+    NoFix,
+    /// This device can't provide locations at all
+    Unsupported,
+}
+
+/// How hard the device should try for an accurate location, lower accuracy saves battery
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, specta::Type)]
+pub enum LocationAccuracy {
+    /// Use GPS and always get a fresh fix, the most accurate but the hardest on the battery
+    #[default]
+    High,
+    /// Let the device use cheaper sources like Wi-Fi and cell towers and reuse fixes up to 30
+    /// seconds old, good enough for casual games
+    Balanced,
+}
+
+impl LocationAccuracy {
+    /// Modes this device can switch between, empty if it can't provide locations at all
+    pub fn available() -> Vec<Self> {
+        // The desktop version of the plugin only returns placeholder values
+        if cfg!(desktop) {
+            Vec::new()
+        } else {
+            vec![Self::High, Self::Balanced]
+        }
+    }
+
+    fn position_options(self) -> PositionOptions {
+        match self {
+            Self::High => PositionOptions {
+                enable_high_accuracy: true,
+                timeout: 10000, // Unused in our case, set to default
+                maximum_age: 2000,
+            },
+            Self::Balanced => PositionOptions {
+                enable_high_accuracy: false,
+                timeout: 10000,
+                maximum_age: 30000,
+            },
+        }
+    }
+}
+
+/// Gets locations from the geolocation plugin at the [LocationAccuracy] the player picked when
+/// the game started
+pub struct TauriLocation {
+    app: AppHandle,
+    accuracy: LocationAccuracy,
+}
 
 impl TauriLocation {
     pub fn new(app: AppHandle) -> Self {
-        Self(app)
+        let accuracy = read_location_accuracy_from_store(&app);
+        Self { app, accuracy }
     }
 }
 
-const OPTIONS: PositionOptions = PositionOptions {
-    enable_high_accuracy: true,
-    timeout: 10000, // Unused in our case, set to default
-    maximum_age: 2000,
-};
+/// Check location permission (asking for it if we haven't yet) and try to get a fix
+pub fn check_location_readiness(app: &AppHandle) -> LocationReadiness {
+    // The desktop version of the plugin only returns placeholder values
+    if cfg!(desktop) {
+        return LocationReadiness::Unsupported;
+    }
+
+    let geo = app.geolocation();
+
+    let mut status = match geo.check_permissions() {
+        Ok(status) => status,
+        Err(why) => {
+            eprintln!("Failed to check location permission: {why:?}");
+            return LocationReadiness::Unsupported;
+        }
+    };
+
+    if matches!(
+        status.location,
+        PermissionState::Prompt | PermissionState::PromptWithRationale
+    ) {
+        match geo.request_permissions(Some(vec![PermissionType::Location])) {
+            Ok(new_status) => status = new_status,
+            Err(why) => eprintln!("Failed to request location permission: {why:?}"),
+        }
+    }
+
+    if status.location != PermissionState::Granted {
+        return LocationReadiness::PermissionDenied;
+    }
+
+    let options = read_location_accuracy_from_store(app).position_options();
+    match geo.get_current_position(Some(options)) {
+        Ok(_) => LocationReadiness::Ready,
+        Err(why) => {
+            eprintln!("Failed to get a location fix: {why:?}");
+            LocationReadiness::NoFix
+        }
+    }
+}
 
 impl LocationService for TauriLocation {
     fn get_loc(&self) -> Option<Location> {
-        match self.0.geolocation().get_current_position(Some(OPTIONS)) {
+        match self
+            .app
+            .geolocation()
+            .get_current_position(Some(self.accuracy.position_options()))
+        {
             Ok(pos) => {
                 let coords = pos.coords;
                 let loc = Location {
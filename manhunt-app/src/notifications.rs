@@ -0,0 +1,56 @@
+use log::error;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, plugin::PermissionState};
+use tauri_plugin_notification::NotificationExt;
+
+/// What happened when trying to send a test notification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+pub enum NotificationTestResult {
+    /// The notification was sent, the user should see it
+    Sent,
+    /// The user didn't allow notifications
+    PermissionDenied,
+    /// Permission is granted but the notification couldn't be sent
+    Failed,
+}
+
+/// Send a sample notification so the user can check notifications work, asks for permission if
+/// we haven't yet
+pub fn send_sample_notification(app: &AppHandle) -> NotificationTestResult {
+    let notification = app.notification();
+
+    let mut permission = match notification.permission_state() {
+        Ok(permission) => permission,
+        Err(why) => {
+            error!("Failed to check notification permission: {why:?}");
+            return NotificationTestResult::Failed;
+        }
+    };
+
+    if matches!(
+        permission,
+        PermissionState::Prompt | PermissionState::PromptWithRationale
+    ) {
+        match notification.request_permission() {
+            Ok(new_permission) => permission = new_permission,
+            Err(why) => error!("Failed to request notification permission: {why:?}"),
+        }
+    }
+
+    if permission != PermissionState::Granted {
+        return NotificationTestResult::PermissionDenied;
+    }
+
+    match notification
+        .builder()
+        .title("Test Notification")
+        .body("Notifications are working!")
+        .show()
+    {
+        Ok(_) => NotificationTestResult::Sent,
+        Err(why) => {
+            error!("Failed to send test notification: {why:?}");
+            NotificationTestResult::Failed
+        }
+    }
+}
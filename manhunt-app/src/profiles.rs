@@ -1,15 +1,22 @@
-use manhunt_logic::PlayerProfile;
+use chrono::Utc;
+use manhunt_logic::{
+    PlayerProfile, UtcDT,
+    storage::{from_stored, to_stored},
+};
 use tauri::AppHandle;
 use tauri_plugin_store::StoreExt;
 
 const STORE_NAME: &str = "profile";
 
+/// How many recently joined room codes to remember
+const RECENT_ROOM_CODES_LEN: usize = 10;
+
 pub fn read_profile_from_store(app: &AppHandle) -> Option<PlayerProfile> {
     let store = app.store(STORE_NAME).expect("Couldn't Create Store");
 
     let profile = store
         .get("profile")
-        .and_then(|v| serde_json::from_value::<PlayerProfile>(v).ok());
+        .and_then(|v| from_stored::<PlayerProfile>(v).ok());
 
     store.close_resource();
 
@@ -19,6 +26,39 @@ pub fn read_profile_from_store(app: &AppHandle) -> Option<PlayerProfile> {
 pub fn write_profile_to_store(app: &AppHandle, profile: PlayerProfile) {
     let store = app.store(STORE_NAME).expect("Couldn't create store");
 
-    let value = serde_json::to_value(profile).expect("Failed to serialize");
+    let value = to_stored(&profile).expect("Failed to serialize");
     store.set("profile", value);
 }
+
+/// Room codes this device recently joined along with when, most recent first
+pub fn read_recent_room_codes_from_store(app: &AppHandle) -> Vec<(String, UtcDT)> {
+    let store = app.store(STORE_NAME).expect("Couldn't Create Store");
+
+    let codes = store
+        .get("recent_room_codes")
+        .and_then(|v| serde_json::from_value::<Vec<(String, UtcDT)>>(v).ok())
+        .unwrap_or_default();
+
+    store.close_resource();
+
+    codes
+}
+
+/// Remember that we just joined a room, moving it to the front if we've joined it before
+pub fn add_recent_room_code(app: &AppHandle, code: &str) {
+    let mut codes = read_recent_room_codes_from_store(app);
+    codes.retain(|(c, _)| c != code);
+    codes.insert(0, (code.to_string(), Utc::now()));
+    codes.truncate(RECENT_ROOM_CODES_LEN);
+
+    let store = app.store(STORE_NAME).expect("Couldn't create store");
+
+    let value = serde_json::to_value(codes).expect("Failed to serialize");
+    store.set("recent_room_codes", value);
+}
+
+/// Forget the profile and recent room codes
+pub fn clear_profile_store(app: &AppHandle) {
+    let store = app.store(STORE_NAME).expect("Couldn't create store");
+    store.clear();
+}
@@ -0,0 +1,355 @@
+use std::{collections::BTreeMap, result::Result as StdResult};
+
+use anyhow::{Context, bail};
+use manhunt_logic::{GameAlert, GameSettings};
+use manhunt_transport::IceServerConfig;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+use crate::{location::LocationAccuracy, tiles::DEFAULT_TILE_SERVER};
+
+type Result<T = (), E = anyhow::Error> = StdResult<T, E>;
+
+const STORE_NAME: &str = "settings";
+
+type Presets = BTreeMap<String, GameSettings>;
+
+/// Which game events should send an OS notification while the app isn't focused
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, specta::Type)]
+#[serde(default)]
+pub struct NotificationPreferences {
+    /// Notify when seekers are released
+    pub seekers_released: bool,
+    /// Notify when our location was revealed to everyone
+    pub pinged: bool,
+    /// Notify when a powerup spawns
+    pub powerup_spawned: bool,
+    /// Notify when the game ends
+    pub game_ended: bool,
+    /// Notify when we leave the play area
+    pub out_of_bounds: bool,
+}
+
+impl Default for NotificationPreferences {
+    fn default() -> Self {
+        Self {
+            seekers_released: true,
+            pinged: true,
+            powerup_spawned: true,
+            game_ended: true,
+            out_of_bounds: true,
+        }
+    }
+}
+
+impl NotificationPreferences {
+    pub fn wants(&self, alert: GameAlert) -> bool {
+        match alert {
+            GameAlert::SeekersReleased => self.seekers_released,
+            GameAlert::Pinged => self.pinged,
+            GameAlert::PowerupSpawned => self.powerup_spawned,
+            GameAlert::GameEnded => self.game_ended,
+            GameAlert::OutOfBounds => self.out_of_bounds,
+        }
+    }
+}
+
+/// STUN/TURN server to connect to other players through, needed on networks with strict NATs
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub struct IceServerSettings {
+    /// `stun:`, `turn:` or `turns:` URLs of the servers
+    pub urls: Vec<String>,
+    pub username: Option<String>,
+    pub credential: Option<String>,
+}
+
+impl IceServerSettings {
+    pub fn validate(&self) -> StdResult<(), Vec<String>> {
+        let mut errors = Vec::new();
+        if self.urls.is_empty() {
+            errors.push("At least one server URL is needed".to_string());
+        }
+        for url in self.urls.iter() {
+            if !["stun:", "turn:", "turns:"]
+                .iter()
+                .any(|scheme| url.starts_with(scheme))
+            {
+                errors.push(format!(
+                    "\"{url}\" should start with stun:, turn:, or turns:"
+                ));
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl From<IceServerSettings> for IceServerConfig {
+    fn from(value: IceServerSettings) -> Self {
+        Self {
+            urls: value.urls,
+            username: value.username,
+            credential: value.credential,
+        }
+    }
+}
+
+pub fn read_last_settings_from_store(app: &AppHandle) -> Option<GameSettings> {
+    let store = app.store(STORE_NAME).expect("Couldn't Create Store");
+
+    let settings = store
+        .get("last_settings")
+        .and_then(|v| serde_json::from_value::<GameSettings>(v).ok());
+
+    store.close_resource();
+
+    settings
+}
+
+pub fn write_last_settings_to_store(app: &AppHandle, settings: &GameSettings) {
+    let store = app.store(STORE_NAME).expect("Couldn't create store");
+
+    let value = serde_json::to_value(settings).expect("Failed to serialize");
+    store.set("last_settings", value);
+}
+
+pub fn read_notification_prefs_from_store(app: &AppHandle) -> NotificationPreferences {
+    let store = app.store(STORE_NAME).expect("Couldn't Create Store");
+
+    let prefs = store
+        .get("notification_preferences")
+        .and_then(|v| serde_json::from_value::<NotificationPreferences>(v).ok())
+        .unwrap_or_default();
+
+    store.close_resource();
+
+    prefs
+}
+
+pub fn write_notification_prefs_to_store(app: &AppHandle, prefs: NotificationPreferences) {
+    let store = app.store(STORE_NAME).expect("Couldn't create store");
+
+    let value = serde_json::to_value(prefs).expect("Failed to serialize");
+    store.set("notification_preferences", value);
+}
+
+/// Get the saved STUN/TURN servers, [Option::None] to use the defaults
+pub fn read_ice_servers_from_store(app: &AppHandle) -> Option<IceServerSettings> {
+    let store = app.store(STORE_NAME).expect("Couldn't Create Store");
+
+    let ice_servers = store
+        .get("ice_servers")
+        .and_then(|v| serde_json::from_value::<Option<IceServerSettings>>(v).ok())
+        .flatten();
+
+    store.close_resource();
+
+    ice_servers
+}
+
+pub fn write_ice_servers_to_store(app: &AppHandle, ice_servers: Option<IceServerSettings>) {
+    let store = app.store(STORE_NAME).expect("Couldn't create store");
+
+    let value = serde_json::to_value(ice_servers).expect("Failed to serialize");
+    store.set("ice_servers", value);
+}
+
+/// Get the URL template map tiles are downloaded from, [DEFAULT_TILE_SERVER] unless another
+/// was set
+pub fn read_tile_server_from_store(app: &AppHandle) -> String {
+    let store = app.store(STORE_NAME).expect("Couldn't Create Store");
+
+    let tile_server = store
+        .get("tile_server")
+        .and_then(|v| serde_json::from_value::<String>(v).ok())
+        .unwrap_or_else(|| DEFAULT_TILE_SERVER.to_string());
+
+    store.close_resource();
+
+    tile_server
+}
+
+pub fn write_tile_server_to_store(app: &AppHandle, tile_server: Option<String>) {
+    let store = app.store(STORE_NAME).expect("Couldn't create store");
+
+    match tile_server {
+        Some(tile_server) => store.set("tile_server", tile_server),
+        None => {
+            store.delete("tile_server");
+        }
+    }
+}
+
+/// Whether to write every game event to a log file for debugging, see
+/// [crate::event_log::GameEventLog]
+pub fn read_log_game_events_from_store(app: &AppHandle) -> bool {
+    let store = app.store(STORE_NAME).expect("Couldn't Create Store");
+
+    let enabled = store
+        .get("log_game_events")
+        .and_then(|v| serde_json::from_value::<bool>(v).ok())
+        .unwrap_or_default();
+
+    store.close_resource();
+
+    enabled
+}
+
+pub fn write_log_game_events_to_store(app: &AppHandle, enabled: bool) {
+    let store = app.store(STORE_NAME).expect("Couldn't create store");
+
+    store.set("log_game_events", enabled);
+}
+
+/// Whether to stream every game event to spectator dashboards, see
+/// [crate::event_stream::GameEventStream]
+pub fn read_stream_game_events_from_store(app: &AppHandle) -> bool {
+    let store = app.store(STORE_NAME).expect("Couldn't Create Store");
+
+    let enabled = store
+        .get("stream_game_events")
+        .and_then(|v| serde_json::from_value::<bool>(v).ok())
+        .unwrap_or_default();
+
+    store.close_resource();
+
+    enabled
+}
+
+pub fn write_stream_game_events_to_store(app: &AppHandle, enabled: bool) {
+    let store = app.store(STORE_NAME).expect("Couldn't create store");
+
+    store.set("stream_game_events", enabled);
+}
+
+/// Get how accurate (and battery hungry) locations should be, see [LocationAccuracy]
+pub fn read_location_accuracy_from_store(app: &AppHandle) -> LocationAccuracy {
+    let store = app.store(STORE_NAME).expect("Couldn't Create Store");
+
+    let accuracy = store
+        .get("location_accuracy")
+        .and_then(|v| serde_json::from_value::<LocationAccuracy>(v).ok())
+        .unwrap_or_default();
+
+    store.close_resource();
+
+    accuracy
+}
+
+pub fn write_location_accuracy_to_store(app: &AppHandle, accuracy: LocationAccuracy) {
+    let store = app.store(STORE_NAME).expect("Couldn't create store");
+
+    let value = serde_json::to_value(accuracy).expect("Failed to serialize");
+    store.set("location_accuracy", value);
+}
+
+/// DEBUG BUILDS ONLY: Whether games should only tick when
+/// [crate::debug_force_tick] is called instead of on an interval
+#[cfg(debug_assertions)]
+pub fn read_manual_tick_from_store(app: &AppHandle) -> bool {
+    let store = app.store(STORE_NAME).expect("Couldn't Create Store");
+
+    let enabled = store
+        .get("manual_tick")
+        .and_then(|v| serde_json::from_value::<bool>(v).ok())
+        .unwrap_or_default();
+
+    store.close_resource();
+
+    enabled
+}
+
+#[cfg(debug_assertions)]
+pub fn write_manual_tick_to_store(app: &AppHandle, enabled: bool) {
+    let store = app.store(STORE_NAME).expect("Couldn't create store");
+
+    store.set("manual_tick", enabled);
+}
+
+/// DEBUG BUILDS ONLY: Whether lobbies should connect over an in-process
+/// [manhunt_transport::LoopbackTransport] instead of through the signaling server
+#[cfg(debug_assertions)]
+pub fn read_loopback_transport_from_store(app: &AppHandle) -> bool {
+    let store = app.store(STORE_NAME).expect("Couldn't Create Store");
+
+    let enabled = store
+        .get("loopback_transport")
+        .and_then(|v| serde_json::from_value::<bool>(v).ok())
+        .unwrap_or_default();
+
+    store.close_resource();
+
+    enabled
+}
+
+#[cfg(debug_assertions)]
+pub fn write_loopback_transport_to_store(app: &AppHandle, enabled: bool) {
+    let store = app.store(STORE_NAME).expect("Couldn't create store");
+
+    store.set("loopback_transport", enabled);
+}
+
+/// Forget every saved setting, including presets
+pub fn clear_settings_store(app: &AppHandle) {
+    let store = app.store(STORE_NAME).expect("Couldn't create store");
+    store.clear();
+}
+
+fn read_presets(app: &AppHandle) -> Result<Presets> {
+    let store = app
+        .store(STORE_NAME)
+        .context("Failed to get settings store")?;
+
+    let presets = store
+        .get("presets")
+        .map(serde_json::from_value::<Presets>)
+        .transpose()
+        .context("Failed to deserialize presets")?
+        .unwrap_or_default();
+
+    Ok(presets)
+}
+
+fn write_presets(app: &AppHandle, presets: &Presets) -> Result {
+    let store = app
+        .store(STORE_NAME)
+        .context("Failed to get settings store")?;
+    let value = serde_json::to_value(presets).context("Failed to serialize presets")?;
+    store.set("presets", value);
+    Ok(())
+}
+
+/// List the names of all saved presets in alphabetical order
+pub fn list_presets(app: &AppHandle) -> Result<Vec<String>> {
+    read_presets(app).map(|presets| presets.into_keys().collect())
+}
+
+/// Get the preset with the given name, [Option::None] if there isn't one
+pub fn read_preset(app: &AppHandle, name: &str) -> Result<Option<GameSettings>> {
+    Ok(read_presets(app)?.remove(name))
+}
+
+/// Save a preset, overwriting any preset with the same name
+pub fn write_preset(app: &AppHandle, name: &str, settings: GameSettings) -> Result {
+    let name = name.trim();
+    if name.is_empty() {
+        bail!("Preset name can't be empty");
+    }
+    let mut presets = read_presets(app)?;
+    presets.insert(name.to_string(), settings);
+    write_presets(app, &presets)
+}
+
+/// Delete the preset with the given name, returns whether it existed
+pub fn delete_preset(app: &AppHandle, name: &str) -> Result<bool> {
+    let mut presets = read_presets(app)?;
+    if presets.remove(name).is_none() {
+        return Ok(false);
+    }
+    write_presets(app, &presets)?;
+    Ok(true)
+}
@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+use manhunt_logic::{GameSnapshot, PlayerProfile, UtcDT};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+use uuid::Uuid;
+
+const STORE_NAME: &str = "snapshot";
+
+/// A game that was in progress when the app last closed, saved every so often while playing so
+/// it can be picked back up with [manhunt_logic::Game::resume]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InterruptedGame {
+    pub join_code: String,
+    pub snapshot: GameSnapshot,
+    /// Profiles shown during the game, these may be anonymized
+    pub profiles: HashMap<Uuid, PlayerProfile>,
+    /// Actual profiles of everyone in the game, for the rematch lobby
+    pub real_profiles: HashMap<Uuid, PlayerProfile>,
+    pub saved_at: UtcDT,
+}
+
+/// What the menu needs to offer resuming an [InterruptedGame]
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub struct InterruptedGameInfo {
+    /// Code of the room the game was in
+    pub join_code: String,
+    /// When the game was last saved **in UTC**
+    pub saved_at: UtcDT,
+}
+
+impl InterruptedGame {
+    pub fn new(
+        join_code: String,
+        snapshot: GameSnapshot,
+        profiles: HashMap<Uuid, PlayerProfile>,
+        real_profiles: HashMap<Uuid, PlayerProfile>,
+    ) -> Self {
+        Self {
+            join_code,
+            snapshot,
+            profiles,
+            real_profiles,
+            saved_at: Utc::now(),
+        }
+    }
+
+    pub fn info(&self) -> InterruptedGameInfo {
+        InterruptedGameInfo {
+            join_code: self.join_code.clone(),
+            saved_at: self.saved_at,
+        }
+    }
+}
+
+pub fn read_interrupted_game_from_store(app: &AppHandle) -> Option<InterruptedGame> {
+    let store = app.store(STORE_NAME).expect("Couldn't Create Store");
+
+    let game = store
+        .get("game")
+        .and_then(|v| serde_json::from_value::<InterruptedGame>(v).ok());
+
+    store.close_resource();
+
+    game
+}
+
+pub fn write_interrupted_game_to_store(app: &AppHandle, game: &InterruptedGame) {
+    let store = app.store(STORE_NAME).expect("Couldn't create store");
+
+    let value = serde_json::to_value(game).expect("Failed to serialize");
+    store.set("game", value);
+}
+
+/// Forget the interrupted game, call this once a game ends cleanly or the user quits it
+pub fn clear_interrupted_game_from_store(app: &AppHandle) {
+    let store = app.store(STORE_NAME).expect("Couldn't create store");
+    store.delete("game");
+}
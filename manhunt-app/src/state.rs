@@ -3,22 +3,39 @@ use std::{collections::HashMap, marker::PhantomData, sync::Arc, time::Duration};
 use anyhow::Context;
 use log::{error, info, warn};
 use manhunt_logic::{
-    Game as BaseGame, GameSettings, Lobby as BaseLobby, PlayerProfile, StartGameInfo,
-    StateUpdateSender, UtcDT,
+    DisconnectReason, EventDirection, Game as BaseGame, GameAlert, GameEvent, GameId, GameSettings,
+    Lobby as BaseLobby, LobbyAlert, PEER_PROTOCOL_VERSION, PlayerProfile, StartGameInfo,
+    StateUpdateSender, Transport, UtcDT, anonymize_profiles,
+};
+use manhunt_transport::{
+    AnyTransport, IceServerConfig, MatchboxTransport, ROOM_CODE_LEN, RoomCodeTakenError,
+    RoomStatus, is_valid_code_format, request_room_code, room_status, started_room_status,
 };
-use manhunt_transport::{MatchboxTransport, request_room_code};
 use serde::{Deserialize, Serialize};
 use tauri::{AppHandle, Manager};
 use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+use tauri_plugin_notification::NotificationExt;
 use tauri_specta::Event;
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
 use crate::{
     Result,
+    error::AppError,
+    event_log::GameEventLog,
+    event_stream::{GameEventStream, event_stream_if_enabled},
     history::AppGameHistory,
     location::TauriLocation,
-    profiles::{read_profile_from_store, write_profile_to_store},
+    profiles::{
+        add_recent_room_code, clear_profile_store, read_profile_from_store, write_profile_to_store,
+    },
+    settings::{
+        clear_settings_store, read_ice_servers_from_store, read_notification_prefs_from_store,
+    },
+    snapshot::{
+        InterruptedGame, clear_interrupted_game_from_store, read_interrupted_game_from_store,
+        write_interrupted_game_to_store,
+    },
 };
 
 /// The state of the game has changed
@@ -29,14 +46,83 @@ pub struct GameStateUpdate;
 #[derive(Serialize, Deserialize, Clone, Default, Debug, specta::Type, tauri_specta::Event)]
 pub struct LobbyStateUpdate;
 
+/// Seekers have just been released, this is only sent once per game
+#[derive(Serialize, Deserialize, Clone, Default, Debug, specta::Type, tauri_specta::Event)]
+pub struct SeekersReleasedEvent;
+
+/// A new player joined the lobby, only sent the first time we hear about them
+#[derive(Serialize, Deserialize, Clone, Debug, specta::Type, tauri_specta::Event)]
+pub struct PlayerJoinedLobby {
+    pub id: Uuid,
+    pub name: String,
+}
+
+/// A player left the lobby
+#[derive(Serialize, Deserialize, Clone, Debug, specta::Type, tauri_specta::Event)]
+pub struct PlayerLeftLobby {
+    pub id: Uuid,
+    /// Whether they left on purpose, otherwise they probably lost connection
+    pub on_purpose: bool,
+}
+
+/// A player joined the lobby with a version of the app we can't play with, they're being
+/// ignored and won't be in the game
+#[derive(Serialize, Deserialize, Clone, Debug, specta::Type, tauri_specta::Event)]
+pub struct IncompatiblePlayerInLobby {
+    pub id: Uuid,
+    /// Their protocol version
+    pub theirs: u32,
+    /// Our protocol version
+    pub ours: u32,
+}
+
 pub struct TauriStateUpdateSender<E: Clone + Default + Event + Serialize>(
     AppHandle,
     PhantomData<E>,
+    Option<GameEventLog>,
+    Option<Arc<GameEventStream>>,
 );
 
 impl<E: Serialize + Clone + Default + Event> TauriStateUpdateSender<E> {
     fn new(app: &AppHandle) -> Self {
-        Self(app.clone(), PhantomData)
+        Self(app.clone(), PhantomData, None, None)
+    }
+
+    fn app_focused(&self) -> bool {
+        self.0
+            .webview_windows()
+            .values()
+            .any(|w| w.is_focused().unwrap_or(false))
+    }
+
+    /// Send an OS notification for an alert, only if the app is in the background and the user
+    /// wants to be notified about it
+    fn notify(&self, alert: GameAlert) {
+        if self.app_focused() || !read_notification_prefs_from_store(&self.0).wants(alert) {
+            return;
+        }
+
+        let (title, body) = match alert {
+            GameAlert::SeekersReleased => ("Seekers Released", "The seekers are on the hunt!"),
+            GameAlert::Pinged => (
+                "You've Been Pinged",
+                "Your location was revealed to everyone",
+            ),
+            GameAlert::PowerupSpawned => ("Powerup Spawned", "A powerup appeared on the map"),
+            GameAlert::GameEnded => ("Game Over", "The game has ended"),
+            GameAlert::OutOfBounds => ("Out of Bounds", "You've left the play area"),
+        };
+
+        if let Err(why) = self
+            .0
+            .notification()
+            .builder()
+            .title(title)
+            .body(body)
+            .show()
+        {
+            error!("Error sending {alert:?} notification: {why:?}");
+        }
     }
 }
 
@@ -46,16 +132,77 @@ impl<E: Serialize + Clone + Default + Event> StateUpdateSender for TauriStateUpd
             error!("Error sending Game state update to UI: {why:?}");
         }
     }
+
+    fn send_alert(&self, alert: GameAlert) {
+        if alert == GameAlert::SeekersReleased
+            && let Err(why) = SeekersReleasedEvent.emit(&self.0)
+        {
+            error!("Error sending {alert:?} alert to UI: {why:?}");
+        }
+        self.notify(alert);
+    }
+
+    fn send_lobby_alert(&self, alert: LobbyAlert) {
+        let res = match alert.clone() {
+            LobbyAlert::PlayerJoined { id, name } => PlayerJoinedLobby { id, name }.emit(&self.0),
+            LobbyAlert::PlayerLeft(id) => PlayerLeftLobby {
+                id,
+                on_purpose: true,
+            }
+            .emit(&self.0),
+            LobbyAlert::PlayerDisconnected(id) => PlayerLeftLobby {
+                id,
+                on_purpose: false,
+            }
+            .emit(&self.0),
+            LobbyAlert::IncompatiblePeer {
+                id,
+                protocol_version,
+            } => IncompatiblePlayerInLobby {
+                id,
+                theirs: protocol_version,
+                ours: PEER_PROTOCOL_VERSION,
+            }
+            .emit(&self.0),
+        };
+        if let Err(why) = res {
+            error!("Error sending {alert:?} alert to UI: {why:?}");
+        }
+    }
+
+    fn log_event(&self, direction: EventDirection, event: &GameEvent) {
+        if let Some(log) = self.2.as_ref() {
+            log.record(direction, event);
+        }
+        if let Some(stream) = self.3.as_ref() {
+            stream.record(direction, event);
+        }
+    }
+}
+
+impl TauriStateUpdateSender<GameStateUpdate> {
+    /// Sender for a game, also logs and streams the game's events if the user turned those on
+    fn for_game(app: &AppHandle) -> Self {
+        Self(
+            app.clone(),
+            PhantomData,
+            GameEventLog::create_if_enabled(app),
+            event_stream_if_enabled(app),
+        )
+    }
 }
 
-type Game = BaseGame<TauriLocation, MatchboxTransport, TauriStateUpdateSender<GameStateUpdate>>;
-type Lobby = BaseLobby<MatchboxTransport, TauriStateUpdateSender<LobbyStateUpdate>>;
+type Game = BaseGame<TauriLocation, AnyTransport, TauriStateUpdateSender<GameStateUpdate>>;
+type Lobby = BaseLobby<AnyTransport, TauriStateUpdateSender<LobbyStateUpdate>>;
 
 pub enum AppState {
     Setup,
     Menu(PlayerProfile),
     Lobby(Arc<Lobby>),
     Game(Arc<Game>, HashMap<Uuid, PlayerProfile>),
+    /// Results of the game that just ended, along with a lobby on the same connection in case
+    /// the host starts a rematch
+    GameOver(AppGameHistory, Arc<Lobby>),
     Replay(AppGameHistory),
 }
 
@@ -65,6 +212,7 @@ pub enum AppScreen {
     Menu,
     Lobby,
     Game,
+    GameOver,
     Replay,
 }
 
@@ -72,10 +220,65 @@ pub type AppStateHandle = RwLock<AppState>;
 
 const GAME_TICK_RATE: Duration = Duration::from_secs(1);
 
+/// Tick rate used in manual tick mode, long enough that the game only ever ticks once at the
+/// start and then whenever it's forced to
+#[cfg(debug_assertions)]
+const MANUAL_TICK_RATE: Duration = Duration::from_secs(u64::MAX);
+
+/// How often to save the game in progress so it can be resumed if the app closes
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How many times a non-host player will try to reconnect to a lobby after a transport error
+const LOBBY_RECONNECT_TRIES: u32 = 2;
+
 /// The app is changing screens, contains the screen it's switching to
 #[derive(Serialize, Deserialize, Clone, Debug, specta::Type, tauri_specta::Event)]
 pub struct ChangeScreen(AppScreen);
 
+/// How often games should tick, in debug builds this can be switched to manual tick mode, see
+/// [crate::settings::read_manual_tick_from_store]
+fn game_tick_rate(app: &AppHandle) -> Duration {
+    #[cfg(debug_assertions)]
+    if crate::settings::read_manual_tick_from_store(app) {
+        return MANUAL_TICK_RATE;
+    }
+    #[cfg(not(debug_assertions))]
+    let _ = app;
+    GAME_TICK_RATE
+}
+
+/// Get a code for a new room from the server. With the debug loopback transport on (see
+/// [crate::settings::read_loopback_transport_from_store]) the code is made up locally instead.
+async fn new_room_code(app: &AppHandle) -> anyhow::Result<String> {
+    #[cfg(debug_assertions)]
+    if crate::settings::read_loopback_transport_from_store(app) {
+        let mut code = Uuid::new_v4().simple().to_string();
+        code.truncate(ROOM_CODE_LEN);
+        return Ok(code.to_ascii_uppercase());
+    }
+    #[cfg(not(debug_assertions))]
+    let _ = app;
+    request_room_code().await
+}
+
+/// Connect to a lobby's room through the signaling server. In debug builds this can be switched
+/// to a [manhunt_transport::LoopbackTransport] that only reaches lobbies in this process, see
+/// [crate::settings::read_loopback_transport_from_store].
+async fn connect_to_lobby(
+    app: &AppHandle,
+    code: &str,
+    host: bool,
+) -> anyhow::Result<Arc<AnyTransport>> {
+    #[cfg(debug_assertions)]
+    if crate::settings::read_loopback_transport_from_store(app) {
+        let transport = manhunt_transport::LoopbackTransport::new(code, host)?;
+        return Ok(Arc::new(transport.into()));
+    }
+    let ice_servers = read_ice_servers_from_store(app).map(IceServerConfig::from);
+    let transport = MatchboxTransport::new_with_ice_servers(code, host, ice_servers).await?;
+    Ok(Arc::new(transport.into()))
+}
+
 fn error_dialog(app: &AppHandle, msg: &str) {
     app.dialog()
         .message(msg)
@@ -87,36 +290,92 @@ impl AppState {
     pub async fn start_game(&mut self, app: AppHandle, start: StartGameInfo) {
         if let AppState::Lobby(lobby) = self {
             let transport = lobby.clone_transport();
-            let profiles = lobby.clone_profiles().await;
+            let join_code = lobby.join_code().to_string();
+            let real_profiles = lobby.clone_profiles().await;
+            // Worked out once here so labels stay the same for the whole game
+            let profiles = if start.settings.anonymize_profiles {
+                anonymize_profiles(&real_profiles, &start.initial_caught_state)
+            } else {
+                real_profiles.clone()
+            };
             let location = TauriLocation::new(app.clone());
-            let state_updates = TauriStateUpdateSender::new(&app);
+            let state_updates = TauriStateUpdateSender::for_game(&app);
             let game = Arc::new(Game::new(
-                GAME_TICK_RATE,
+                game_tick_rate(&app),
                 start,
                 transport,
                 location,
                 state_updates,
             ));
             *self = AppState::Game(game.clone(), profiles.clone());
-            Self::game_loop(app.clone(), game, profiles);
+            Self::game_loop(app.clone(), game, profiles, real_profiles, join_code);
             Self::emit_screen_change(&app, AppScreen::Game);
         }
     }
 
-    fn game_loop(app: AppHandle, game: Arc<Game>, profiles: HashMap<Uuid, PlayerProfile>) {
+    fn game_loop(
+        app: AppHandle,
+        game: Arc<Game>,
+        profiles: HashMap<Uuid, PlayerProfile>,
+        real_profiles: HashMap<Uuid, PlayerProfile>,
+        join_code: String,
+    ) {
         tokio::spawn(async move {
-            let res = game.main_loop().await;
+            let res = if game.is_spectator().await {
+                game.main_loop().await
+            } else {
+                tokio::select! {
+                    res = game.main_loop() => res,
+                    _ = Self::snapshot_loop(&app, &game, &join_code, &profiles, &real_profiles) => {
+                        unreachable!("Snapshot loop never returns")
+                    }
+                }
+            };
+            // Keep the snapshot if we lost connection so the user can try resuming
+            if res.is_ok() {
+                clear_interrupted_game_from_store(&app);
+            }
             let state_handle = app.state::<AppStateHandle>();
-            let mut state = state_handle.write().await;
             match res {
                 Ok(Some(history)) => {
-                    let history =
-                        AppGameHistory::new(history, profiles, game.clone_settings().await);
+                    // Everything that awaits on the game or touches disk is done before taking
+                    // the state lock so commands aren't stalled behind it
+                    let settings = game.clone_settings().await;
+                    let is_host = game.is_host().await;
+                    let is_spectator = game.is_spectator().await;
+                    let history = AppGameHistory::new(history, profiles.clone(), settings.clone());
                     if let Err(why) = history.save_history(&app) {
                         error!("Failed to save game history: {why:?}");
                         error_dialog(&app, "Failed to save the history of this game");
                     }
-                    state.quit_to_menu(app.clone()).await;
+
+                    let mut state = state_handle.write().await;
+                    // The user may have quit while post-game sync was finishing up
+                    let current = matches!(&*state, AppState::Game(g, _) if Arc::ptr_eq(g, &game));
+                    // A refereeing host is a spectator but still needs to bring everyone back
+                    if current && (!is_spectator || is_host) {
+                        let rematch = Lobby::rematch(
+                            &join_code,
+                            is_host,
+                            real_profiles,
+                            settings,
+                            TauriStateUpdateSender::new(&app),
+                            game.clone_transport(),
+                        );
+                        *state = AppState::GameOver(history, rematch.clone());
+                        Self::rematch_loop(app.clone(), rematch);
+                        Self::emit_screen_change(&app, AppScreen::GameOver);
+                    } else {
+                        if current {
+                            // Spectators can't join a rematch, show them the replay instead
+                            *state = AppState::Replay(history);
+                            Self::emit_screen_change(&app, AppScreen::Replay);
+                        }
+                        drop(state);
+                        game.clone_transport()
+                            .disconnect_with_reason(DisconnectReason::GameOver)
+                            .await;
+                    }
                 }
                 Ok(None) => {
                     info!("User quit game");
@@ -127,7 +386,58 @@ impl AppState {
                         .message(format!("Connection Error: {why}"))
                         .kind(MessageDialogKind::Error)
                         .show(|_| {});
-                    state.quit_to_menu(app.clone()).await;
+                    let mut state = state_handle.write().await;
+                    // Don't kick the user out of whatever they moved on to
+                    if matches!(&*state, AppState::Game(g, _) if Arc::ptr_eq(g, &game)) {
+                        state.quit_to_menu(app.clone()).await;
+                    }
+                }
+            }
+        });
+    }
+
+    /// Save the game every [SNAPSHOT_INTERVAL] so it can be resumed with
+    /// [AppState::resume_interrupted_game] if the app closes mid-game
+    async fn snapshot_loop(
+        app: &AppHandle,
+        game: &Game,
+        join_code: &str,
+        profiles: &HashMap<Uuid, PlayerProfile>,
+        real_profiles: &HashMap<Uuid, PlayerProfile>,
+    ) {
+        let mut interval = tokio::time::interval(SNAPSHOT_INTERVAL);
+        loop {
+            interval.tick().await;
+            let interrupted = InterruptedGame::new(
+                join_code.to_string(),
+                game.snapshot().await,
+                profiles.clone(),
+                real_profiles.clone(),
+            );
+            write_interrupted_game_to_store(app, &interrupted);
+        }
+    }
+
+    fn rematch_loop(app: AppHandle, lobby: Arc<Lobby>) {
+        tokio::spawn(async move {
+            let res = lobby.wait_for_rematch().await;
+            let state_handle = app.state::<AppStateHandle>();
+            let mut state = state_handle.write().await;
+            match res {
+                Ok(true) => {
+                    if matches!(&*state, AppState::GameOver(_, l) if Arc::ptr_eq(l, &lobby)) {
+                        info!("Starting rematch");
+                        *state = AppState::Lobby(lobby.clone());
+                        Self::lobby_loop(app.clone(), lobby);
+                        Self::emit_screen_change(&app, AppScreen::Lobby);
+                    }
+                }
+                Ok(false) => {
+                    info!("User left game results");
+                }
+                Err(why) => {
+                    // Not worth bothering the user over, they just can't rematch
+                    warn!("Lost connection waiting for rematch: {why:?}");
                 }
             }
         });
@@ -136,14 +446,14 @@ impl AppState {
     pub fn get_menu(&self) -> Result<&PlayerProfile> {
         match self {
             AppState::Menu(player_profile) => Ok(player_profile),
-            _ => Err("Not on menu screen".to_string()),
+            _ => Err(AppError::WrongScreen),
         }
     }
 
     pub fn get_menu_mut(&mut self) -> Result<&mut PlayerProfile> {
         match self {
             AppState::Menu(player_profile) => Ok(player_profile),
-            _ => Err("Not on menu screen".to_string()),
+            _ => Err(AppError::WrongScreen),
         }
     }
 
@@ -151,7 +461,7 @@ impl AppState {
         if let AppState::Lobby(lobby) = self {
             Ok(lobby.clone())
         } else {
-            Err("Not on lobby screen".to_string())
+            Err(AppError::WrongScreen)
         }
     }
 
@@ -159,7 +469,7 @@ impl AppState {
         if let AppState::Game(game, _) = self {
             Ok(game.clone())
         } else {
-            Err("Not on game screen".to_string())
+            Err(AppError::WrongScreen)
         }
     }
 
@@ -167,7 +477,23 @@ impl AppState {
         if let AppState::Game(_, profiles) = self {
             Ok(profiles)
         } else {
-            Err("Not on game screen".to_string())
+            Err(AppError::WrongScreen)
+        }
+    }
+
+    pub fn get_game_results(&self) -> Result<AppGameHistory> {
+        if let AppState::GameOver(history, _) = self {
+            Ok(history.clone())
+        } else {
+            Err(AppError::WrongScreen)
+        }
+    }
+
+    pub fn get_rematch_lobby(&self) -> Result<Arc<Lobby>> {
+        if let AppState::GameOver(_, lobby) = self {
+            Ok(lobby.clone())
+        } else {
+            Err(AppError::WrongScreen)
         }
     }
 
@@ -175,7 +501,7 @@ impl AppState {
         if let AppState::Replay(history) = self {
             Ok(history.clone())
         } else {
-            Err("Not on replay screen".to_string())
+            Err(AppError::WrongScreen)
         }
     }
 
@@ -192,33 +518,114 @@ impl AppState {
             Self::emit_screen_change(app, AppScreen::Menu);
             Ok(())
         } else {
-            Err("Must be on the Setup screen".to_string())
+            Err(AppError::WrongScreen)
         }
     }
 
-    pub fn replay_game(&mut self, app: &AppHandle, id: UtcDT) -> Result {
+    /// Wipe everything saved on this device and go back to setup, only works from the setup and
+    /// menu screens so a lobby or game isn't left running
+    pub fn reset_app_data(&mut self, app: &AppHandle) -> Result {
+        if !matches!(self, AppState::Setup | AppState::Menu(_)) {
+            return Err(AppError::WrongScreen);
+        }
+
+        clear_profile_store(app);
+        clear_settings_store(app);
+        clear_interrupted_game_from_store(app);
+        AppGameHistory::clear_histories(app).context("Failed to clear game histories")?;
+
+        *self = AppState::Setup;
+        Self::emit_screen_change(app, AppScreen::Setup);
+        Ok(())
+    }
+
+    pub fn replay_game(&mut self, app: &AppHandle, id: GameId) -> Result {
         if let AppState::Menu(_) = self {
-            let history = AppGameHistory::get_history(app, id)
-                .context("Failed to read history")
-                .map_err(|e| e.to_string())?;
+            let history = AppGameHistory::get_history(app, id).context("Failed to read history")?;
             *self = AppState::Replay(history);
             Self::emit_screen_change(app, AppScreen::Replay);
             Ok(())
         } else {
-            Err("Not on menu screen".to_string())
+            Err(AppError::WrongScreen)
         }
     }
 
+    /// Try to connect to the same room as `lobby` with a new transport, returns [Option::None]
+    /// if the room no longer exists and there's no point in retrying
+    async fn reconnect_lobby(app: &AppHandle, lobby: &Lobby) -> anyhow::Result<Option<Arc<Lobby>>> {
+        match room_status(lobby.join_code()).await {
+            RoomStatus::NotFound => return Ok(None),
+            RoomStatus::Exists => {}
+            // Could just be the network, try anyway
+            RoomStatus::ServerUnreachable => warn!("Couldn't check if lobby still exists"),
+        }
+        let state_updates = TauriStateUpdateSender::<LobbyStateUpdate>::new(app);
+        let transport = connect_to_lobby(app, lobby.join_code(), lobby.is_host())
+            .await
+            .context("Failed to connect to lobby")?;
+        Ok(Some(
+            lobby
+                .reconnect_with_transport(state_updates, transport)
+                .await,
+        ))
+    }
+
     fn lobby_loop(app: AppHandle, lobby: Arc<Lobby>) {
         tokio::spawn(async move {
-            let res = lobby.main_loop().await;
+            let mut lobby = lobby;
+            let mut tries = 0;
+
+            let res = 'lobby: loop {
+                let res = lobby.main_loop().await;
+
+                // Hosts can't reconnect as their room is closed when they leave
+                if res.is_ok() || lobby.is_host() {
+                    break res;
+                }
+
+                while tries < LOBBY_RECONNECT_TRIES {
+                    tries += 1;
+                    warn!("Lobby connection lost, reconnecting ({tries}/{LOBBY_RECONNECT_TRIES})");
+                    match Self::reconnect_lobby(&app, &lobby).await {
+                        Ok(Some(new_lobby)) => {
+                            let state_handle = app.state::<AppStateHandle>();
+                            let mut state = state_handle.write().await;
+                            if matches!(&*state, AppState::Lobby(l) if Arc::ptr_eq(l, &lobby)) {
+                                *state = AppState::Lobby(new_lobby.clone());
+                                lobby = new_lobby;
+                                continue 'lobby;
+                            } else {
+                                // User left the lobby while we were reconnecting
+                                drop(state);
+                                new_lobby
+                                    .clone_transport()
+                                    .disconnect_with_reason(DisconnectReason::Quit)
+                                    .await;
+                                return;
+                            }
+                        }
+                        Ok(None) => {
+                            warn!("Lobby no longer exists, not reconnecting");
+                            break 'lobby res;
+                        }
+                        Err(why) => {
+                            warn!("Failed to reconnect to lobby: {why:?}");
+                        }
+                    }
+                }
+
+                break res;
+            };
+
             let app_game = app.clone();
             let state_handle = app.state::<AppStateHandle>();
-            let mut state = state_handle.write().await;
             match res {
                 Ok(Some(start)) => {
                     info!("Starting Game");
-                    state.start_game(app_game, start).await;
+                    let mut state = state_handle.write().await;
+                    if matches!(&*state, AppState::Lobby(l) if Arc::ptr_eq(l, &lobby)) {
+                        state.start_game(app_game, start).await;
+                    }
                 }
                 Ok(None) => {
                     info!("User quit lobby");
@@ -226,48 +633,206 @@ impl AppState {
                 Err(why) => {
                     error!("Lobby Error: {why}");
                     error_dialog(&app_game, &format!("Error joining the lobby: {why}"));
-                    state.quit_to_menu(app_game).await;
+                    let mut state = state_handle.write().await;
+                    // Don't kick the user out of whatever they moved on to
+                    if matches!(&*state, AppState::Lobby(l) if Arc::ptr_eq(l, &lobby)) {
+                        state.quit_to_menu(app_game).await;
+                    }
                 }
             }
         });
     }
 
+    /// Make sure a room code the host picked looks like a generated code, returns it uppercased.
+    /// Whether another room has it is only known once the server is asked to create the room.
+    fn check_room_code(code: &str) -> Result<String> {
+        if is_valid_code_format(code) {
+            Ok(code.to_ascii_uppercase())
+        } else {
+            Err(AppError::Validation(vec![format!(
+                "Room codes need to be {ROOM_CODE_LEN} letters or numbers"
+            )]))
+        }
+    }
+
+    /// Host or join a lobby. Hosts get a code from the server unless they asked for
+    /// `desired_code`. Fails with [AppError::RoomCodeTaken] if the server won't create a room
+    /// with the code because another room has it. The state lock isn't held while connecting.
     pub async fn start_lobby(
-        &mut self,
-        join_code: Option<String>,
         app: AppHandle,
+        join_code: Option<String>,
+        desired_code: Option<String>,
         settings: GameSettings,
-    ) {
-        if let AppState::Menu(profile) = self {
-            let host = join_code.is_none();
-            let room_code = if let Some(code) = join_code {
-                code.to_ascii_uppercase()
+    ) -> Result {
+        let profile = {
+            let state_handle = app.state::<AppStateHandle>();
+            if let AppState::Menu(profile) = &*state_handle.read().await {
+                profile.clone()
             } else {
-                match request_room_code().await {
-                    Ok(code) => code,
-                    Err(why) => {
-                        error_dialog(&app, &format!("Couldn't create a lobby\n\n{why:?}"));
-                        return;
-                    }
-                }
-            };
-            let state_updates = TauriStateUpdateSender::<LobbyStateUpdate>::new(&app);
-            let lobby =
-                Lobby::new(&room_code, host, profile.clone(), settings, state_updates).await;
-            match lobby {
-                Ok(lobby) => {
-                    *self = AppState::Lobby(lobby.clone());
-                    Self::lobby_loop(app.clone(), lobby);
-                    Self::emit_screen_change(&app, AppScreen::Lobby);
-                }
+                return Err(AppError::WrongScreen);
+            }
+        };
+        let host = join_code.is_none();
+        let room_code = if let Some(code) = join_code {
+            code.to_ascii_uppercase()
+        } else if let Some(code) = desired_code {
+            Self::check_room_code(&code)?
+        } else {
+            match new_room_code(&app).await {
+                Ok(code) => code,
                 Err(why) => {
-                    error_dialog(
-                        &app,
-                        &format!("Couldn't connect you to the lobby\n\n{why:?}"),
-                    );
+                    error_dialog(&app, &format!("Couldn't create a lobby\n\n{why:?}"));
+                    return Ok(());
                 }
             }
+        };
+        let state_updates = TauriStateUpdateSender::<LobbyStateUpdate>::new(&app);
+        let lobby = connect_to_lobby(&app, &room_code, host)
+            .await
+            .context("Failed to connect to lobby")
+            .map(|transport| {
+                Arc::new(Lobby::new_with_transport(
+                    &room_code,
+                    host,
+                    profile,
+                    settings,
+                    state_updates,
+                    transport,
+                ))
+            });
+        match lobby {
+            Ok(lobby) => {
+                Self::leave_menu_for(&app, AppState::Lobby(lobby.clone())).await?;
+                if !host {
+                    add_recent_room_code(&app, &room_code);
+                }
+                Self::lobby_loop(app.clone(), lobby);
+                Self::emit_screen_change(&app, AppScreen::Lobby);
+            }
+            Err(why) if host && why.downcast_ref::<RoomCodeTakenError>().is_some() => {
+                return Err(AppError::RoomCodeTaken);
+            }
+            Err(why) => {
+                error_dialog(
+                    &app,
+                    &format!("Couldn't connect you to the lobby\n\n{why:?}"),
+                );
+            }
         }
+        Ok(())
+    }
+
+    /// Check we're on the menu before connecting somewhere, without holding on to the state lock
+    async fn ensure_on_menu(app: &AppHandle) -> Result {
+        let state_handle = app.state::<AppStateHandle>();
+        if let AppState::Menu(_) = &*state_handle.read().await {
+            Ok(())
+        } else {
+            Err(AppError::WrongScreen)
+        }
+    }
+
+    /// Swap in the state we connected to with the lock released, only if we're still on the
+    /// menu. If the user moved on while we were connecting, the new connection is closed.
+    async fn leave_menu_for(app: &AppHandle, new_state: AppState) -> Result {
+        let state_handle = app.state::<AppStateHandle>();
+        let mut state = state_handle.write().await;
+        if let AppState::Menu(_) = &*state {
+            *state = new_state;
+            return Ok(());
+        }
+        drop(state);
+
+        let transport = match new_state {
+            AppState::Lobby(lobby) => lobby.clone_transport(),
+            AppState::Game(game, _) => game.clone_transport(),
+            _ => return Err(AppError::WrongScreen),
+        };
+        transport
+            .disconnect_with_reason(DisconnectReason::Quit)
+            .await;
+        Err(AppError::WrongScreen)
+    }
+
+    /// Join a game that has already started as a spectator, the host must have allowed
+    /// spectators. The state lock isn't held while connecting, which can take a while, see
+    /// [Game::spectate]. This triggers a screen change to [AppScreen::Game].
+    pub async fn start_spectating(app: AppHandle, join_code: &str) -> Result {
+        Self::ensure_on_menu(&app).await?;
+        let room_code = join_code.to_ascii_uppercase();
+        let ice_servers = read_ice_servers_from_store(&app).map(IceServerConfig::from);
+        let transport = MatchboxTransport::new_spectator(&room_code, ice_servers)
+            .await
+            .map_err(|why| AppError::Network(format!("{why:?}")))?;
+        let transport = Arc::new(AnyTransport::from(transport));
+        let game = Game::spectate(
+            game_tick_rate(&app),
+            transport,
+            TauriLocation::new(app.clone()),
+            TauriStateUpdateSender::for_game(&app),
+        )
+        .await
+        .map_err(|why| AppError::Network(why.to_string()))?;
+        let game = Arc::new(game);
+        // TODO: Get profiles from the host so spectators see names instead of IDs
+        Self::leave_menu_for(&app, AppState::Game(game.clone(), HashMap::new())).await?;
+        Self::game_loop(app.clone(), game, HashMap::new(), HashMap::new(), room_code);
+        Self::emit_screen_change(&app, AppScreen::Game);
+        Ok(())
+    }
+
+    /// Pick back up the game that was in progress when the app last closed, reconnecting to its
+    /// room. The state lock isn't held while reconnecting. This triggers a screen change to
+    /// [AppScreen::Game].
+    pub async fn resume_interrupted_game(app: AppHandle) -> Result {
+        Self::ensure_on_menu(&app).await?;
+        let interrupted = read_interrupted_game_from_store(&app).ok_or(AppError::NotFound)?;
+        match started_room_status(&interrupted.join_code).await {
+            RoomStatus::Exists => {}
+            RoomStatus::NotFound => {
+                // The game ended or everyone left while we were gone, nothing to go back to
+                clear_interrupted_game_from_store(&app);
+                return Err(AppError::NotFound);
+            }
+            RoomStatus::ServerUnreachable => {
+                return Err(AppError::Network(
+                    "Couldn't reach the server to check the game is still going".to_string(),
+                ));
+            }
+        }
+        let ice_servers = read_ice_servers_from_store(&app).map(IceServerConfig::from);
+        let transport = MatchboxTransport::new_rejoin(
+            &interrupted.join_code,
+            interrupted.snapshot.player_id(),
+            ice_servers,
+        )
+        .await
+        .map_err(|why| AppError::Network(format!("{why:?}")))?;
+        let transport = Arc::new(AnyTransport::from(transport));
+        let game = Arc::new(
+            Game::resume(
+                game_tick_rate(&app),
+                interrupted.snapshot,
+                transport,
+                TauriLocation::new(app.clone()),
+                TauriStateUpdateSender::for_game(&app),
+            )
+            .await,
+        );
+        Self::leave_menu_for(
+            &app,
+            AppState::Game(game.clone(), interrupted.profiles.clone()),
+        )
+        .await?;
+        Self::game_loop(
+            app.clone(),
+            game,
+            interrupted.profiles,
+            interrupted.real_profiles,
+            interrupted.join_code,
+        );
+        Self::emit_screen_change(&app, AppScreen::Game);
+        Ok(())
     }
 
     pub async fn quit_to_menu(&mut self, app: AppHandle) {
@@ -285,6 +850,10 @@ impl AppState {
                 game.quit_game().await;
                 read_profile_from_store(&app)
             }
+            AppState::GameOver(_, lobby) => {
+                lobby.quit_lobby().await;
+                read_profile_from_store(&app)
+            }
             AppState::Replay(_) => read_profile_from_store(&app),
         };
         let screen = if let Some(profile) = profile {
@@ -0,0 +1,228 @@
+use std::{
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{Context, bail};
+use log::warn;
+use manhunt_logic::{MAX_TILE_ZOOM, TileBounds, TileCoord};
+use serde::{Deserialize, Serialize};
+use tauri::{
+    AppHandle, Manager,
+    http::{Response, StatusCode, header::CONTENT_TYPE},
+};
+use tauri_specta::Event;
+use tokio::time::Instant;
+
+type Result<T = (), E = anyhow::Error> = std::result::Result<T, E>;
+
+/// Where tiles are downloaded from unless another server is set, see [check_tile_server]
+pub const DEFAULT_TILE_SERVER: &str = "https://tile.openstreetmap.org/{z}/{x}/{y}.png";
+
+/// Tile servers (OpenStreetMap's especially) ask apps to identify themselves and give a way to
+/// contact whoever's making the requests
+const TILE_USER_AGENT: &str = concat!(
+    "manhunt-app/",
+    env!("CARGO_PKG_VERSION"),
+    " (+https://github.com/Bwc9876/manhunt-app)"
+);
+
+/// URI scheme the frontend loads map tiles through, see [serve_tile]
+pub const TILE_PROTOCOL: &str = "tiles";
+
+/// Least time between tile downloads while prefetching, public tile servers don't allow bulk
+/// downloading so this keeps a prefetch to a trickle
+const PREFETCH_REQUEST_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Most tiles one prefetch may download, keeps a too-large area or zoom range from hammering
+/// the tile server and filling up the device
+pub const MAX_PREFETCH_TILES: u64 = 2500;
+
+/// Directory under the app's cache directory tiles are kept in
+const TILE_CACHE_DIR: &str = "tiles";
+
+/// Progress of a [prefetch_tiles] run, sent as an event after every tile and returned at the end
+#[derive(
+    Serialize, Deserialize, Clone, Copy, Default, Debug, specta::Type, tauri_specta::Event,
+)]
+pub struct MapTilesProgress {
+    /// Tiles that are now in the cache, including ones that already were
+    pub done: u32,
+    /// Tiles that couldn't be downloaded
+    pub failed: u32,
+    /// Tiles in the area being fetched
+    pub total: u32,
+}
+
+/// Check a tile server URL template can be used, returns a list of human-readable problems if not
+pub fn check_tile_server(template: &str) -> std::result::Result<(), Vec<String>> {
+    let mut errors = Vec::new();
+    if !template.starts_with("https://") && !template.starts_with("http://") {
+        errors.push("Tile server URL should start with https://".to_string());
+    }
+    for placeholder in ["{z}", "{x}", "{y}"] {
+        if !template.contains(placeholder) {
+            errors.push(format!("Tile server URL needs a {placeholder} in it"));
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn tile_client() -> Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .user_agent(TILE_USER_AGENT)
+        .build()
+        .context("Failed to create HTTP client")
+}
+
+/// Directory cached tiles are stored in, laid out as `{z}/{x}/{y}.png`
+pub fn tile_cache_dir(app: &AppHandle) -> Result<PathBuf> {
+    Ok(app
+        .path()
+        .app_cache_dir()
+        .context("Failed to find cache directory")?
+        .join(TILE_CACHE_DIR))
+}
+
+/// Check a prefetch is reasonable and get how many tiles it would download, returns a list of
+/// human-readable problems if not
+pub fn check_prefetch(
+    bounds: &TileBounds,
+    zooms: &RangeInclusive<u8>,
+) -> std::result::Result<u32, Vec<String>> {
+    let mut errors = bounds.validate();
+
+    if zooms.is_empty() || *zooms.end() > MAX_TILE_ZOOM {
+        errors.push(format!(
+            "Zoom range must go from low to high and stay at or below {MAX_TILE_ZOOM}"
+        ));
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let total = zooms.clone().map(|z| bounds.tile_count(z)).sum::<u64>();
+
+    if total > MAX_PREFETCH_TILES {
+        Err(vec![format!(
+            "That area needs {total} tiles, only {MAX_PREFETCH_TILES} can be downloaded at once. Try a smaller area or lower zoom."
+        )])
+    } else {
+        Ok(total as u32)
+    }
+}
+
+async fn download_tile(
+    client: &reqwest::Client,
+    server: &str,
+    path: &Path,
+    tile: TileCoord,
+) -> Result<Vec<u8>> {
+    let bytes = client
+        .get(tile.url(server))
+        .send()
+        .await
+        .context("Could not send request")?
+        .error_for_status()
+        .context("Server returned an error")?
+        .bytes()
+        .await
+        .context("Failed to read tile")?;
+
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .context("Failed to create tile directory")?;
+    }
+    tokio::fs::write(&path, &bytes)
+        .await
+        .context("Failed to save tile")?;
+    Ok(bytes.to_vec())
+}
+
+/// Get a tile's image from [tile_cache_dir], downloading it from `server` and caching it if it
+/// isn't there yet. This is what the map loads tiles through.
+pub async fn read_tile(app: &AppHandle, server: &str, tile: TileCoord) -> Result<Vec<u8>> {
+    let path = tile_cache_dir(app)?.join(tile.path());
+    match tokio::fs::read(&path).await {
+        Ok(bytes) => Ok(bytes),
+        Err(_) => download_tile(&tile_client()?, server, &path, tile).await,
+    }
+}
+
+/// Answer a request made to [TILE_PROTOCOL], `path` is a tile's [TileCoord::path] which may have
+/// its slashes percent-encoded (`convertFileSrc` does this). Tiles come from [read_tile].
+pub async fn serve_tile(app: &AppHandle, server: &str, path: &str) -> Response<Vec<u8>> {
+    let path = path.trim_start_matches('/').replace("%2F", "/");
+    let Some(tile) = TileCoord::from_path(&path) else {
+        return Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .body(Vec::new())
+            .unwrap_or_default();
+    };
+    match read_tile(app, server, tile).await {
+        Ok(bytes) => Response::builder()
+            .header(CONTENT_TYPE, "image/png")
+            .body(bytes)
+            .unwrap_or_default(),
+        Err(why) => {
+            warn!("Failed to load map tile {path}: {why:?}");
+            Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(Vec::new())
+                .unwrap_or_default()
+        }
+    }
+}
+
+/// Download every tile covering `bounds` at each zoom in `zooms` into [tile_cache_dir], skipping
+/// ones already there. Downloads are spaced out by [PREFETCH_REQUEST_INTERVAL]. Sends
+/// [MapTilesProgress] as it goes. Use [check_prefetch] first.
+pub async fn prefetch_tiles(
+    app: &AppHandle,
+    server: &str,
+    bounds: TileBounds,
+    zooms: RangeInclusive<u8>,
+    total: u32,
+) -> Result<MapTilesProgress> {
+    let dir = tile_cache_dir(app)?;
+    let client = tile_client()?;
+
+    let mut progress = MapTilesProgress {
+        total,
+        ..Default::default()
+    };
+
+    let mut next_request = Instant::now();
+    for tile in zooms.flat_map(|z| bounds.tiles(z)) {
+        let path = dir.join(tile.path());
+        if tokio::fs::try_exists(&path).await.unwrap_or(false) {
+            progress.done += 1;
+        } else {
+            tokio::time::sleep_until(next_request).await;
+            next_request = Instant::now() + PREFETCH_REQUEST_INTERVAL;
+            match download_tile(&client, server, &path, tile).await {
+                Ok(_) => progress.done += 1,
+                Err(why) => {
+                    warn!("Failed to fetch map tile {}: {why:?}", tile.path());
+                    progress.failed += 1;
+                }
+            }
+        }
+        if let Err(why) = progress.emit(app) {
+            warn!("Failed to emit tile progress: {why:?}");
+        }
+    }
+
+    if progress.done == 0 && progress.failed != 0 {
+        bail!("Couldn't download any map tiles");
+    }
+
+    Ok(progress)
+}
@@ -0,0 +1,13 @@
+//! Constants shared between the signaling server and the clients that talk to it
+
+/// Version of how clients talk to the signaling server. Bump this whenever a change to the server
+/// would break older clients, clients refuse to connect to servers on a different version.
+///
+/// - Version 2: `/room_exists/{code}?started` checks for a started game, older servers ignore it
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// Characters room codes are made out of
+pub const ROOM_CODE_CHAR_POOL: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ1234567890";
+
+/// Length of every room code
+pub const ROOM_CODE_LEN: usize = 6;
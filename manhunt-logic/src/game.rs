@@ -1,17 +1,22 @@
-use anyhow::bail;
+use anyhow::{anyhow, bail};
 use chrono::{DateTime, Utc};
-use std::{sync::Arc, time::Duration};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
 use tokio::sync::{RwLock, RwLockWriteGuard};
 
-use crate::StartGameInfo;
-use crate::{prelude::*, transport::TransportMessage};
+use crate::{LobbyAlert, StartGameInfo};
+use crate::{
+    prelude::*,
+    transport::{DisconnectReason, TransportMessage},
+};
 
 use crate::{
-    game_events::GameEvent,
-    game_state::{GameHistory, GameState, GameUiState},
+    game_events::{CatchReason, GameEvent},
+    game_state::{GameHistory, GameSnapshot, GameState, GameUiState, PlayerPing},
+    location::Location,
     location::LocationService,
     powerups::PowerUpType,
     settings::GameSettings,
@@ -20,11 +25,50 @@ use crate::{
 
 pub type Id = Uuid;
 
+/// Uniquely identifies one game, unlike when it started two games can't share one
+pub type GameId = Uuid;
+
 /// Convenience alias for UTC DT
 pub type UtcDT = DateTime<Utc>;
 
+/// A one-off moment in the game the UI may want to make noise about (e.g. with a notification),
+/// as opposed to general state changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameAlert {
+    /// Seekers have been released, sent exactly once per game
+    SeekersReleased,
+    /// Our location was just revealed to everyone
+    Pinged,
+    /// A powerup spawned on the map
+    PowerupSpawned,
+    /// The game is over and post-game sync has started
+    GameEnded,
+    /// We just left [crate::GameSettings::play_area]
+    OutOfBounds,
+}
+
+/// Whether a logged [GameEvent] was sent by us or received from another player
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EventDirection {
+    Sent,
+    Received,
+}
+
+/// How long to wait for the host to send a [GameEvent::SpectatorSync] in [Game::spectate]
+pub const SPECTATE_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Longest label a seeker can put on a pin with [Game::drop_seeker_pin], in characters
+pub const MAX_SEEKER_PIN_LABEL_CHARS: usize = 60;
+
 pub trait StateUpdateSender {
     fn send_update(&self);
+    /// Send a [GameAlert] to the UI, does nothing by default
+    fn send_alert(&self, _alert: GameAlert) {}
+    /// Send a [LobbyAlert] to the UI, does nothing by default
+    fn send_lobby_alert(&self, _alert: LobbyAlert) {}
+    /// Record a [GameEvent] as it's sent or consumed, for debugging desyncs. Does nothing by
+    /// default
+    fn log_event(&self, _direction: EventDirection, _event: &GameEvent) {}
 }
 
 /// Struct representing an ongoing game, handles communication with
@@ -47,11 +91,13 @@ impl<L: LocationService, T: Transport, S: StateUpdateSender> Game<L, T, S> {
         location: L,
         state_update_sender: S,
     ) -> Self {
-        let state = GameState::new(
+        let mut state = GameState::new(
             start_info.settings,
             transport.self_id(),
+            start_info.host,
             start_info.initial_caught_state,
         );
+        state.set_game_id(start_info.game_id);
 
         Self {
             transport,
@@ -63,19 +109,367 @@ impl<L: LocationService, T: Transport, S: StateUpdateSender> Game<L, T, S> {
         }
     }
 
+    /// Start spectating a game on a transport that joined as a spectator. Waits for the host to
+    /// send a [GameEvent::SpectatorSync], fails if they don't within [SPECTATE_TIMEOUT] (e.g.
+    /// because they didn't allow spectators).
+    pub async fn spectate(
+        interval: Duration,
+        transport: Arc<T>,
+        location: L,
+        state_update_sender: S,
+    ) -> Result<Self> {
+        let wait_for_sync = async {
+            loop {
+                for (_, msg) in transport.receive_messages().await {
+                    match msg {
+                        TransportMessage::Game(event) => {
+                            if let GameEvent::SpectatorSync {
+                                settings,
+                                host,
+                                game_id,
+                                game_started,
+                                caught_state,
+                                pings,
+                            } = *event
+                            {
+                                return Ok(GameState::new_spectator(
+                                    settings,
+                                    transport.self_id(),
+                                    host,
+                                    game_id,
+                                    game_started,
+                                    caught_state,
+                                    pings,
+                                ));
+                            }
+                        }
+                        TransportMessage::Disconnected => bail!("Disconnected"),
+                        TransportMessage::Error(why) => bail!("Transport error: {why}"),
+                        _ => {}
+                    }
+                }
+            }
+        };
+
+        let state = match tokio::time::timeout(SPECTATE_TIMEOUT, wait_for_sync).await {
+            Ok(res) => res,
+            Err(_) => Err(anyhow!("The host didn't let us spectate")),
+        };
+
+        let state = match state {
+            Ok(state) => state,
+            Err(why) => {
+                transport.disconnect().await;
+                return Err(why);
+            }
+        };
+
+        Ok(Self {
+            transport,
+            location,
+            interval,
+            state: RwLock::new(state),
+            state_update_sender,
+            cancel: CancellationToken::new(),
+        })
+    }
+
+    /// Pick a game back up from a [GameSnapshot] on a new transport connected to the same room,
+    /// e.g. after the app crashed. We keep the ID we had in the game even though the transport
+    /// has a new one, everyone else is told to add us back and send what we missed.
+    pub async fn resume(
+        interval: Duration,
+        snapshot: GameSnapshot,
+        transport: Arc<T>,
+        location: L,
+        state_update_sender: S,
+    ) -> Self {
+        let mut state = GameState::from_snapshot(snapshot);
+        state.set_connection(state.id, transport.self_id());
+        let rejoin = GameEvent::PlayerRejoined {
+            player: state.id,
+            seeker: state.is_seeker(),
+        };
+
+        let game = Self {
+            transport,
+            location,
+            interval,
+            state: RwLock::new(state),
+            state_update_sender,
+            cancel: CancellationToken::new(),
+        };
+
+        game.send_event(rejoin).await;
+        game.request_resync().await;
+        game
+    }
+
+    /// Save the current state of the game so it can be picked back up with [Game::resume]
+    pub async fn snapshot(&self) -> GameSnapshot {
+        self.state.read().await.snapshot()
+    }
+
     async fn send_event(&self, event: GameEvent) {
+        self.state_update_sender
+            .log_event(EventDirection::Sent, &event);
         self.transport.send_message(event.into()).await;
     }
 
-    pub async fn mark_caught(&self) {
+    /// Send an event to only the given connections instead of everyone, see
+    /// [GameState::player_connection]
+    async fn send_event_to(&self, connections: &[Id], event: GameEvent) {
+        self.state_update_sender
+            .log_event(EventDirection::Sent, &event);
+        for connection in connections {
+            self.transport
+                .send_message_single(*connection, event.clone().into())
+                .await;
+        }
+    }
+
+    /// Send our [GameEvent::PostGameSync], spectators don't have a history to share
+    async fn send_post_game_sync(&self, state: &GameState, sync: GameEvent) {
+        if !state.is_spectator() {
+            self.send_event(sync).await;
+        }
+    }
+
+    /// Mark the local player as caught, optionally specifying the seeker that caught them. This
+    /// can be taken back with [Game::undo_caught] for a few seconds in case it was a mis-tap.
+    /// Fails if we're in one of the [GameSettings::safe_zones], see [GameState::is_catchable].
+    pub async fn mark_caught(&self, by: Option<Id>) -> Result {
+        let state = self.state.read().await;
+        if state.is_spectator() {
+            bail!("Spectators can't be caught");
+        }
+        if !state.is_catchable(state.id) {
+            bail!("You can't be caught while in a safe zone");
+        }
+        drop(state);
+        self.become_seeker(by, CatchReason::Caught).await;
+        Ok(())
+    }
+
+    /// Take back marking ourselves caught, only possible for a few seconds after
+    /// [Game::mark_caught] and before the game ends
+    pub async fn undo_caught(&self) -> Result {
         let mut state = self.state.write().await;
+        let Some(by) = state.take_pending_catch(Self::get_now()) else {
+            bail!("There's no catch to undo");
+        };
+        let id = state.id;
+        state.uncatch_player(id, by);
+        let event = GameEvent::UncaughtCorrection { player: id, by };
+        state.event_history.push((Utc::now(), event.clone()));
+        drop(state);
+
+        self.send_event(event).await;
+        self.state_update_sender.send_update();
+        Ok(())
+    }
+
+    /// (Hider) Give up and become a seeker, the game will end if we're the last hider
+    pub async fn concede(&self) -> Result {
+        let state = self.state.read().await;
+        if state.is_spectator() {
+            bail!("Spectators can't concede");
+        }
+        if state.is_seeker() {
+            bail!("Only hiders can concede");
+        }
+        drop(state);
+        self.become_seeker(None, CatchReason::Conceded).await;
+        Ok(())
+    }
+
+    async fn become_seeker(&self, by: Option<Id>, reason: CatchReason) {
+        let mut state = self.state.write().await;
+        if state.is_spectator() {
+            return;
+        }
         let id = state.id;
         state.mark_caught(id);
+        if reason == CatchReason::Caught {
+            state.jail_player(id);
+            state.start_pending_catch(by, Self::get_now());
+        }
+        if let Some(by) = by {
+            state.credit_catch(by, Self::get_now());
+        }
         state.remove_ping(id);
         // TODO: Maybe reroll for new powerups (specifically seeker ones) instead of just erasing it
         state.use_powerup();
+        let event = GameEvent::PlayerCaught {
+            player: id,
+            by,
+            reason,
+        };
+        // We never receive our own catch, record it here so our history (and the survival times
+        // and replays built from it) has it. This only goes in the history, the catch was already
+        // credited above so it isn't counted twice.
+        state.event_history.push((Utc::now(), event.clone()));
+        drop(state);
+        self.send_event(event).await;
+    }
+
+    /// (Hider) Free every player in jail, we need to be near [GameSettings::jail_location].
+    /// Returns the players that were freed.
+    pub async fn attempt_jailbreak(&self) -> Result<Vec<Id>> {
+        let mut state = self.state.write().await;
+        if !state.jailbreak_enabled() {
+            bail!("Jailbreaks aren't enabled for this game");
+        }
+        if state.is_seeker() {
+            bail!("Only hiders can break players out of jail");
+        }
+        if !state.in_jail_range() {
+            bail!("You're too far away from the jail");
+        }
+
+        let freed = state.iter_jailed().collect::<Vec<_>>();
+        for player in freed.iter().copied() {
+            state.free_player(player);
+            let event = GameEvent::PlayerFreed(player);
+            state.event_history.push((Utc::now(), event.clone()));
+            self.send_event(event).await;
+        }
+        drop(state);
+
+        self.state_update_sender.send_update();
+        Ok(freed)
+    }
+
+    /// (Hider) Instantly reveal our location to everyone, outside the normal ping schedule.
+    /// Limited to [GameSettings::panic_pings] per game.
+    pub async fn panic_ping(&self) -> Result {
+        let mut state = self.state.write().await;
+        if state.is_spectator() || state.is_seeker() {
+            bail!("Only hiders can panic ping");
+        }
+        if state.panic_pings_left() == 0 {
+            bail!("You don't have any panic pings left");
+        }
+        let Some(ping) = state.use_panic_ping() else {
+            bail!("Couldn't get your location");
+        };
+        drop(state);
+
+        self.send_event(GameEvent::Ping(ping)).await;
+        self.state_update_sender.send_alert(GameAlert::Pinged);
+        self.state_update_sender.send_update();
+        Ok(())
+    }
+
+    /// (Seeker) Drop a pin on the map for the other seekers, e.g. to say where we're searching.
+    /// Hiders are never sent these.
+    pub async fn drop_seeker_pin(&self, location: Location, label: Option<String>) -> Result {
+        let label = label
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty());
+        if label
+            .as_ref()
+            .is_some_and(|l| l.chars().count() > MAX_SEEKER_PIN_LABEL_CHARS)
+        {
+            bail!("Pin labels can be at most {MAX_SEEKER_PIN_LABEL_CHARS} characters");
+        }
+
+        let mut state = self.state.write().await;
+        if state.is_spectator() || !state.is_seeker() {
+            bail!("Only seekers can drop pins");
+        }
+        state.add_seeker_pin(location, label.clone());
+        let event = GameEvent::SeekerPin(location, label);
+        state.event_history.push((Utc::now(), event.clone()));
+        let id = state.id;
+        let seekers = state
+            .iter_seekers()
+            .filter(|seeker| *seeker != id)
+            .map(|seeker| state.player_connection(seeker))
+            .collect::<Vec<_>>();
+        drop(state);
+
+        self.send_event_to(&seekers, event).await;
+        self.state_update_sender.send_update();
+        Ok(())
+    }
+
+    /// (Host) End the game early for everyone, the game will go through post-game sync and
+    /// produce a [GameHistory] as normal. Unlike [Game::quit_game] this keeps the game's history.
+    pub async fn force_end(&self) -> Result {
+        let mut state = self.state.write().await;
+        if !state.is_host() {
+            bail!("Only the host can end the game");
+        }
+        if state.game_ended() {
+            return Ok(());
+        }
+        let id = state.id;
+        state
+            .event_history
+            .push((Utc::now(), GameEvent::ForceEnd(id)));
+        let sync = state.end_game();
+        self.send_event(GameEvent::ForceEnd(id)).await;
+        // A refereeing host has no history to share
+        self.send_post_game_sync(&state, sync).await;
+        drop(state);
+        self.state_update_sender.send_alert(GameAlert::GameEnded);
+        self.state_update_sender.send_update();
+        Ok(())
+    }
+
+    /// (Host) Ping every hider right now regardless of the ping schedule, or every seeker if
+    /// [GameSettings::reverse_pings] is on
+    pub async fn ping_all_hiders(&self) -> Result {
+        let mut state = self.state.write().await;
+        if !state.is_host() {
+            bail!("Only the host can ping everyone");
+        }
+        if state.game_ended() {
+            bail!("The game is already over");
+        }
+        let targets = state.iter_pinged_team().collect::<Vec<_>>();
+        for target in targets {
+            // We won't get our own ForcePing
+            if target == state.id {
+                if let Some(ping) = state.create_self_ping() {
+                    self.send_forced_ping(&mut state, ping).await;
+                }
+            } else {
+                self.send_event(GameEvent::ForcePing(target, None)).await;
+            }
+        }
+        drop(state);
+
+        self.state_update_sender.send_update();
+        Ok(())
+    }
+
+    /// (Host) Change settings mid-game for everyone. Settings that have already taken effect
+    /// can't be changed, the error will list every problem.
+    pub async fn amend_settings(&self, settings: GameSettings) -> Result {
+        let mut state = self.state.write().await;
+        if !state.is_host() {
+            bail!("Only the host can change settings");
+        }
+        state
+            .check_settings_amendment(&settings)
+            .map_err(|errors| anyhow!(errors.join("\n")))?;
+        state.amend_settings(settings.clone());
+        let event = GameEvent::SettingsAmendment(settings);
+        state.event_history.push((Utc::now(), event.clone()));
         drop(state);
-        self.send_event(GameEvent::PlayerCaught(id)).await;
+        self.send_event(event).await;
+        self.state_update_sender.send_update();
+        Ok(())
+    }
+
+    /// Ask all other players to send their caught state and pings, use this when we suspect we
+    /// missed events (e.g. due to a flaky connection).
+    pub async fn request_resync(&self) {
+        // Replies go to our connection, which after a [Game::resume] isn't our ID in the game
+        let id = self.transport.self_id();
+        self.send_event(GameEvent::RequestSync(id)).await;
     }
 
     pub async fn clone_settings(&self) -> GameSettings {
@@ -86,15 +480,46 @@ impl<L: LocationService, T: Transport, S: StateUpdateSender> Game<L, T, S> {
         self.state.read().await.as_ui_state()
     }
 
-    pub async fn get_powerup(&self) {
+    /// Just the caught state of every player, cheaper than [Game::get_ui_state] when that's all
+    /// that's needed
+    pub async fn get_caught_state(&self) -> HashMap<Id, bool> {
+        self.state.read().await.clone_caught_state()
+    }
+
+    /// How far we've moved this game in meters, see [GameState::distance_traveled]
+    pub async fn distance_traveled(&self) -> f64 {
+        self.state.read().await.distance_traveled()
+    }
+
+    /// IDs of the players still connected to the game, see [GameState::active_players]
+    pub async fn get_active_players(&self) -> Vec<Id> {
+        self.state.read().await.active_players()
+    }
+
+    /// Try to grab the powerup on the map, returns whether we got it. Always succeeds if
+    /// [GameSettings::powerup_grab_radius_meters] isn't set.
+    pub async fn get_powerup(&self) -> bool {
         let mut state = self.state.write().await;
+        if state.is_spectator() || !state.in_powerup_range() {
+            return false;
+        }
         state.get_powerup();
         self.send_event(GameEvent::PowerupDespawn(state.id)).await;
+        true
     }
 
-    pub async fn use_powerup(&self) {
+    /// Use the powerup we're holding, does nothing if we aren't holding one. Fails without using
+    /// up the powerup if it can't be used in our current role.
+    pub async fn use_powerup(&self) -> Result {
         let mut state = self.state.write().await;
 
+        if let Some(powerup) = state.peek_powerup()
+            && !powerup.usable_as(state.is_seeker())
+        {
+            let role = if state.is_seeker() { "seeker" } else { "hider" };
+            bail!("This powerup can't be used as a {role}");
+        }
+
         if let Some(powerup) = state.use_powerup() {
             match powerup {
                 PowerUpType::PingSeeker => {}
@@ -103,7 +528,16 @@ impl<L: LocationService, T: Transport, S: StateUpdateSender> Game<L, T, S> {
                         self.send_event(GameEvent::ForcePing(seeker, None)).await;
                     }
                 }
+                PowerUpType::FreezeSeekers => {
+                    let until = state.freeze_end(Self::get_now());
+                    state.freeze_seekers(until);
+                    let event = GameEvent::SeekersFrozen(until);
+                    state.event_history.push((Utc::now(), event.clone()));
+                    self.send_event(event).await;
+                    self.state_update_sender.send_update();
+                }
                 PowerUpType::ForcePingOther => {
+                    // Only we pick the target, everyone else just sees who gets the ForcePing.
                     // Fallback to a seeker if there are no other hiders
                     let target = state.random_other_hider().or_else(|| state.random_seeker());
 
@@ -113,15 +547,24 @@ impl<L: LocationService, T: Transport, S: StateUpdateSender> Game<L, T, S> {
                 }
             }
         }
+
+        Ok(())
     }
 
     async fn consume_event(&self, state: &mut GameState, event: GameEvent) {
+        self.state_update_sender
+            .log_event(EventDirection::Received, &event);
+
+        // Only seekers should be sent these, don't keep one that reached a hider anyway
+        if matches!(event, GameEvent::SeekerPin(..)) && !state.is_seeker() {
+            return;
+        }
+
         if !state.game_ended() {
             state.event_history.push((Utc::now(), event.clone()));
         }
 
         match event {
-            GameEvent::Ping(player_ping) => state.add_ping(player_ping),
             GameEvent::ForcePing(target, display) => {
                 if target != state.id {
                     return;
@@ -134,18 +577,34 @@ impl<L: LocationService, T: Transport, S: StateUpdateSender> Game<L, T, S> {
                 };
 
                 if let Some(ping) = ping {
-                    state.add_ping(ping.clone());
-                    self.send_event(GameEvent::Ping(ping)).await;
+                    self.send_forced_ping(state, ping).await;
                 }
             }
-            GameEvent::PowerupDespawn(_) => state.despawn_powerup(),
-            GameEvent::PlayerCaught(player) => {
-                state.mark_caught(player);
-                state.remove_ping(player);
+            // Spawns are normally rolled locally and this is only here for the history, the host
+            // only sends them when spawning near the hiders
+            GameEvent::PowerupSpawn(_) => {
+                state.apply_event(Self::get_now(), &event);
+                self.state_update_sender
+                    .send_alert(GameAlert::PowerupSpawned);
+            }
+            GameEvent::ForceEnd(host) => {
+                if state.is_player_host(host) && !state.game_ended() {
+                    let sync = state.end_game();
+                    self.state_update_sender.send_alert(GameAlert::GameEnded);
+                    self.send_post_game_sync(state, sync).await;
+                }
             }
             GameEvent::PostGameSync(id, history) => {
                 state.insert_player_location_history(id, history);
             }
+            GameEvent::RequestSync(requester) => {
+                let sync = state.create_full_sync();
+                self.send_event_to(&[state.player_connection(requester)], sync)
+                    .await;
+            }
+            // Only the host checks amendments, everyone applies what they sent so we all agree.
+            // Spectator syncs are only meaningful in [Game::spectate] and are skipped here.
+            _ => state.apply_event(Self::get_now(), &event),
         }
 
         self.state_update_sender.send_update();
@@ -154,16 +613,45 @@ impl<L: LocationService, T: Transport, S: StateUpdateSender> Game<L, T, S> {
     async fn consume_message(
         &self,
         state: &mut GameState,
-        _id: Option<Uuid>,
+        peer: Option<Uuid>,
         msg: TransportMessage,
     ) -> Result<bool> {
         match msg {
             TransportMessage::Game(event) => {
+                // Players that rejoin keep their ID in the game but come back on a new connection
+                if let (GameEvent::PlayerRejoined { player, .. }, Some(peer)) = (&*event, peer) {
+                    state.set_connection(*player, peer);
+                }
                 self.consume_event(state, *event).await;
                 Ok(false)
             }
+            TransportMessage::PeerLeaving(_) => {
+                if let Some(id) = peer.and_then(|peer| state.connection_player(peer)) {
+                    state.player_left(id);
+                    self.state_update_sender.send_update();
+                }
+                Ok(false)
+            }
             TransportMessage::PeerDisconnect(id) => {
-                state.remove_player(id);
+                // An old connection of a player that rejoined finally timing out, they're fine
+                if let Some(id) = state.connection_player(id) {
+                    state.player_disconnected(id, Self::get_now());
+                    self.state_update_sender.send_update();
+                }
+                Ok(false)
+            }
+            // The room is closed to players once the game starts, anyone new is a spectator
+            // unless they're a player coming back before their grace period ran out
+            TransportMessage::PeerConnect(id) => {
+                if state
+                    .connection_player(id)
+                    .is_some_and(|player| state.player_reconnected(player))
+                {
+                    self.state_update_sender.send_update();
+                } else if state.is_host() && state.spectators_allowed() && !state.game_ended() {
+                    let sync = state.create_spectator_sync();
+                    self.send_event_to(&[id], sync).await;
+                }
                 Ok(false)
             }
             TransportMessage::Disconnected => {
@@ -175,15 +663,29 @@ impl<L: LocationService, T: Transport, S: StateUpdateSender> Game<L, T, S> {
         }
     }
 
+    /// Send out a ping we didn't choose to make (e.g. from a [GameEvent::ForcePing]) and let the
+    /// player know they were pinged
+    async fn send_forced_ping(&self, state: &mut GameState, ping: PlayerPing) {
+        state.add_ping(ping.clone());
+        self.send_event(GameEvent::Ping(ping)).await;
+        self.state_update_sender.send_alert(GameAlert::Pinged);
+    }
+
     /// Perform a tick for a specific moment in time
     /// Returns whether the game loop should be broken.
     async fn tick(&self, state: &mut GameState, now: UtcDT) -> bool {
         let mut send_update = false;
 
-        if state.check_end_game() {
+        // Give up on players that didn't reconnect in time, before checking if the game is over
+        // in case they were the last hider
+        if state.expire_disconnects(now) {
+            send_update = true;
+        }
+
+        if let Some(sync) = state.check_end_game() {
             // If we're at the point where the game is over, send out our location history
-            let msg = GameEvent::PostGameSync(state.id, state.location_history.clone());
-            self.send_event(msg).await;
+            self.send_post_game_sync(state, sync).await;
+            self.state_update_sender.send_alert(GameAlert::GameEnded);
             send_update = true;
         }
 
@@ -193,29 +695,98 @@ impl<L: LocationService, T: Transport, S: StateUpdateSender> Game<L, T, S> {
             if send_update {
                 self.state_update_sender.send_update();
             }
-            return state.check_post_game_sync();
+            return state.check_post_game_sync(now);
         }
 
         // Push to location history
-        if let Some(location) = self.location.get_loc() {
+        if !state.is_spectator()
+            && let Some(location) = self.location.get_loc()
+        {
             state.push_loc(location);
         }
 
+        // Left (or came back into) the play area?
+        if let Some(out_of_bounds) = state.update_out_of_bounds() {
+            if out_of_bounds {
+                self.state_update_sender.send_alert(GameAlert::OutOfBounds);
+            }
+            send_update = true;
+        }
+
+        // Forget pings that are too old to be useful
+        if state.expire_pings(now) {
+            send_update = true;
+        }
+
+        // Update warmer/colder hints
+        if state.should_update_proximity_trends() {
+            send_update |= state.update_proximity_trends();
+        }
+
         // Release Seekers?
         if !state.seekers_released() && state.should_release_seekers(now) {
             state.release_seekers(now);
+            self.state_update_sender
+                .send_alert(GameAlert::SeekersReleased);
+            send_update = true;
+        }
+
+        // Unfreeze Seekers?
+        if state.check_freeze_expired(now) {
+            send_update = true;
+        }
+
+        // End stale catch streaks
+        if state.expire_catch_streaks(now) {
             send_update = true;
         }
 
+        // Too late to undo being caught?
+        if state.lock_in_pending_catch(now) {
+            send_update = true;
+        }
+
+        // Spectators don't roll for powerups, unless they're a host refereeing the game who
+        // still has to place the ones that spawn near the hiders
+        if !state.is_spectator() || state.is_host() {
+            // Start Powerup Rolls?
+            if !state.powerups_started() && state.should_start_powerups(now) {
+                state.start_powerups(now);
+                send_update = true;
+            }
+
+            // Should roll for a powerup?
+            if state.should_spawn_powerup(&now) {
+                if let Some(location) = state.try_spawn_powerup(now) {
+                    if state.spawns_powerups_near_hiders() {
+                        self.send_event(GameEvent::PowerupSpawn(location)).await;
+                    }
+                    self.state_update_sender
+                        .send_alert(GameAlert::PowerupSpawned);
+                }
+                send_update = true;
+            }
+        }
+
+        // Spectators don't ping
+        if state.is_spectator() {
+            if send_update {
+                self.state_update_sender.send_update();
+            }
+            return false;
+        }
+
         // Start Pings?
         if !state.pings_started() && state.should_start_pings(now) {
-            state.start_pings(now);
+            state.begin_pings(now);
             send_update = true;
         }
 
         // Do a Ping?
         if state.should_ping(&now) {
-            if let Some(&PowerUpType::PingSeeker) = state.peek_powerup() {
+            if !state.is_seeker()
+                && let Some(&PowerUpType::PingSeeker) = state.peek_powerup()
+            {
                 // We have a powerup that lets us ping a seeker as us, use it.
                 if let Some(seeker) = state.random_seeker() {
                     state.use_powerup();
@@ -229,21 +800,19 @@ impl<L: LocationService, T: Transport, S: StateUpdateSender> Game<L, T, S> {
                     self.send_event(GameEvent::Ping(ping.clone())).await;
                     state.add_ping(ping);
                     state.start_pings(now);
+                    self.state_update_sender.send_alert(GameAlert::Pinged);
                 }
             }
         }
 
-        // Start Powerup Rolls?
-        if !state.powerups_started() && state.should_start_powerups(now) {
-            state.start_powerups(now);
-            send_update = true;
-        }
-
-        // Should roll for a powerup?
-        if state.should_spawn_powerup(&now) {
-            state.try_spawn_powerup(now);
-            send_update = true;
+        // Reveal us for staying in one spot too long?
+        let was_camping = state.revealed_for_camping();
+        if state.should_reveal_for_camping(now)
+            && let Some(ping) = state.create_self_ping()
+        {
+            self.send_forced_ping(state, ping).await;
         }
+        send_update |= was_camping != state.revealed_for_camping();
 
         // Send a state update to the UI?
         if send_update {
@@ -253,6 +822,17 @@ impl<L: LocationService, T: Transport, S: StateUpdateSender> Game<L, T, S> {
         false
     }
 
+    /// Run a tick as if it were `now`, outside of the normal tick interval. Only in debug builds,
+    /// for stepping through timing in a running game without waiting. If this tick ends the game
+    /// [Game::main_loop] still returns on its next tick.
+    #[cfg(debug_assertions)]
+    pub async fn force_tick(&self, now: UtcDT) {
+        let mut state = self.state.write().await;
+        self.tick(&mut state, now).await;
+    }
+
+    /// Abandon the game without going through post-game sync, no history will be produced. See
+    /// [Game::force_end] to end the game for everyone.
     pub async fn quit_game(&self) {
         self.cancel.cancel();
     }
@@ -302,18 +882,44 @@ impl<L: LocationService, T: Transport, S: StateUpdateSender> Game<L, T, S> {
                     let should_break = self.tick(&mut state, Self::get_now()).await;
 
                     if should_break {
-                        let history = state.as_game_history();
+                        let history = state.take_game_history();
                         break Ok(Some(history));
                     }
                 }
             }
         };
 
-        self.transport.disconnect().await;
+        // Keep the transport around after a normal end so it can be reused for a rematch, see
+        // [Game::clone_transport]
+        match res {
+            Ok(None) => {
+                self.transport
+                    .disconnect_with_reason(DisconnectReason::Quit)
+                    .await
+            }
+            Err(_) => self.transport.disconnect().await,
+            Ok(Some(_)) => {}
+        }
 
         res
     }
 
+    /// Get the transport this game is using. After [Game::main_loop] returns a history the
+    /// transport is still connected, it's up to the caller to reuse or disconnect it.
+    pub fn clone_transport(&self) -> Arc<T> {
+        self.transport.clone()
+    }
+
+    /// Whether the local player is the host of the game
+    pub async fn is_host(&self) -> bool {
+        self.state.read().await.is_host()
+    }
+
+    /// Whether we're only spectating this game, see [Game::spectate]
+    pub async fn is_spectator(&self) -> bool {
+        self.state.read().await.is_spectator()
+    }
+
     pub async fn lock_state(&self) -> RwLockWriteGuard<'_, GameState> {
         self.state.write().await
     }
@@ -321,18 +927,18 @@ impl<L: LocationService, T: Transport, S: StateUpdateSender> Game<L, T, S> {
 
 #[cfg(test)]
 mod tests {
-    use std::{collections::HashMap, sync::Arc};
+    use std::sync::Arc;
 
     use crate::{
-        location::Location,
-        settings::PingStartCondition,
-        tests::{DummySender, MockLocation, MockTransport},
+        game_state::{GamePhase, SeekerPin},
+        settings::{PingStartCondition, PingVisibility},
+        tests::{AlertRecorder, MockLocation, MockTransport},
     };
 
     use super::*;
     use tokio::{sync::oneshot, task::yield_now, test};
 
-    type TestGame = Game<MockLocation, MockTransport, DummySender>;
+    type TestGame = Game<MockLocation, MockTransport, AlertRecorder>;
 
     type EndRecv = oneshot::Receiver<Result<Option<GameHistory>>>;
 
@@ -346,12 +952,34 @@ mod tests {
 
     impl MockMatch {
         pub fn new(settings: GameSettings, players: u32, seekers: u32) -> Self {
+            Self::new_with_spectators(settings, players, seekers, 0).0
+        }
+
+        /// Same as [MockMatch::new] but also returns transports for `spectators` extra peers that
+        /// aren't in the game, for use with [Game::spectate]
+        pub fn new_with_spectators(
+            settings: GameSettings,
+            players: u32,
+            seekers: u32,
+            spectators: u32,
+        ) -> (Self, Vec<Arc<MockTransport>>) {
             tokio::time::pause();
-            let (uuids, transports) = MockTransport::create_mesh(players);
+            let (mut uuids, mut transports) = MockTransport::create_mesh(players + spectators);
 
-            let initial_caught_state = (0..players)
+            let spectator_transports = transports
+                .split_off(players as usize)
+                .into_iter()
+                .map(Arc::new)
+                .collect();
+            uuids.truncate(players as usize);
+
+            let mut initial_caught_state = (0..players)
                 .map(|id| (uuids[id as usize], id < seekers))
                 .collect::<HashMap<_, _>>();
+            // Same as the lobby, a refereeing host isn't on a team
+            if !settings.host_plays {
+                initial_caught_state.remove(&uuids[0]);
+            }
 
             let games = transports
                 .into_iter()
@@ -360,24 +988,28 @@ mod tests {
                     let start_info = StartGameInfo {
                         initial_caught_state: initial_caught_state.clone(),
                         settings: settings.clone(),
+                        host: uuids[0],
+                        game_id: GameId::from_u128(1),
                     };
                     let game = TestGame::new(
                         INTERVAL,
                         start_info,
                         Arc::new(transport),
                         location,
-                        DummySender,
+                        AlertRecorder::default(),
                     );
 
                     Arc::new(game)
                 })
                 .collect();
 
-            Self {
+            let mat = Self {
                 settings,
                 games,
                 uuids,
-            }
+            };
+
+            (mat, spectator_transports)
         }
 
         pub async fn start(&self) -> Vec<EndRecv> {
@@ -388,6 +1020,15 @@ mod tests {
                 recvs.push(recv);
                 tokio::spawn(async move {
                     let res = game.main_loop().await;
+                    // The transport is kept after a normal end, not rematching so disconnect it
+                    if let Ok(Some(_)) = res {
+                        assert!(
+                            !game.transport.is_disconnected(),
+                            "Game {} disconnected after a normal end",
+                            game.transport.self_id()
+                        );
+                        game.transport.disconnect().await;
+                    }
                     send.send(res).expect("Failed to send");
                 });
                 yield_now().await;
@@ -452,6 +1093,26 @@ mod tests {
                 long: 0.0,
                 heading: None,
             }],
+            track_catch_scores: true,
+            hot_cold_hints: false,
+            powerup_grab_radius_meters: None,
+            powerup_near_hiders_radius_meters: None,
+            freeze_seconds: 5,
+            jail_location: None,
+            enable_jailbreak: false,
+            safe_zones: vec![],
+            anonymize_profiles: false,
+            allow_spectators: false,
+            play_area: None,
+            auto_seeker_count: None,
+            ping_visible_to: PingVisibility::Everyone,
+            panic_pings: 0,
+            host_plays: true,
+            reverse_pings: false,
+            first_ping_immediate: false,
+            reveal_after_minutes: None,
+            ping_expiry_seconds: None,
+            disconnect_grace_seconds: 0,
         }
     }
 
@@ -466,7 +1127,10 @@ mod tests {
 
         mat.wait_for_seekers().await;
 
-        mat.games[1].mark_caught().await;
+        mat.games[1]
+            .mark_caught(None)
+            .await
+            .expect("Failed to mark caught");
 
         mat.wait_for_transports().await;
 
@@ -498,6 +1162,11 @@ mod tests {
                 Ok(Some(hist)) => {
                     assert!(!hist.locations.is_empty(), "Game {i} has no locations");
                     assert!(!hist.events.is_empty(), "Game {i} has no event");
+                    assert_eq!(
+                        hist.host,
+                        Some(mat.uuids[0]),
+                        "Game {i} doesn't know who hosted"
+                    );
                 }
                 Ok(None) => {
                     panic!("Game {i} exited without a history (did not end via post game sync)");
@@ -510,195 +1179,1901 @@ mod tests {
     }
 
     #[test]
-    async fn test_basic_pinging() {
+    async fn test_host_referees() {
         let mut settings = mk_settings();
-        settings.ping_minutes_interval = 0;
+        settings.host_plays = false;
 
-        let mut mat = MockMatch::new(settings, 4, 1);
+        // Player 0 hosts without playing, 1 is a seeker and 2 a hider
+        let mut mat = MockMatch::new(settings, 3, 2);
 
-        mat.start().await;
+        let recvs = mat.start().await;
 
         mat.wait_for_seekers().await;
 
+        assert!(mat.games[0].is_spectator().await);
+        assert!(mat.games[0].is_host().await);
+        assert!(
+            mat.games[0].concede().await.is_err(),
+            "Refereeing host was able to concede"
+        );
+        assert!(
+            mat.games[0].mark_caught(None).await.is_err(),
+            "Refereeing host was able to get caught"
+        );
         mat.assert_all_states(|i, s| {
-            for id in 0..4 {
-                let ping = s.get_ping(mat.uuids[id]);
-                if id == 0 {
-                    assert!(
-                        ping.is_none(),
-                        "Game {i} has a ping for 0, despite them being a seeker",
-                    );
-                } else {
-                    assert!(
-                        ping.is_some(),
-                        "Game {i} doesn't have a ping for {id}, despite them being a hider",
-                    );
-                }
-            }
+            assert_eq!(
+                s.get_caught(mat.uuids[0]),
+                None,
+                "Host is playing in game {i}"
+            );
         })
         .await;
 
-        mat.games[1].mark_caught().await;
+        mat.games[2]
+            .mark_caught(None)
+            .await
+            .expect("Failed to mark caught");
 
+        mat.wait_for_transports().await;
+        // Tick to process game end
         mat.tick().await;
-
+        // Tick for post-game sync
+        mat.tick().await;
+
+        for (i, recv) in recvs.into_iter().enumerate() {
+            let res = recv.await.expect("Failed to recv");
+            assert!(
+                matches!(res, Ok(Some(_))),
+                "Game {i} didn't end normally: {res:?}"
+            );
+        }
+    }
+
+    #[test]
+    async fn test_referee_spawns_powerups_near_hiders() {
+        let mut settings = mk_settings();
+        settings.host_plays = false;
+        settings.powerup_chance = 100;
+        settings.powerup_near_hiders_radius_meters = Some(50.0);
+
+        let mut mat = MockMatch::new(settings, 3, 2);
+
+        mat.start().await;
+        mat.wait_for_seekers().await;
+
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        mat.tick().await;
+
+        mat.assert_all_states(|i, s| {
+            assert!(
+                s.powerup_location().is_some(),
+                "Game {i} never got a powerup from the refereeing host"
+            );
+        })
+        .await;
+    }
+
+    #[test]
+    async fn test_post_game_sync_sent_once() {
+        let settings = mk_settings();
+        let mut mat = MockMatch::new(settings, 3, 1);
+
+        let recvs = mat.start().await;
+
+        mat.wait_for_seekers().await;
+
+        mat.games[1]
+            .mark_caught(None)
+            .await
+            .expect("Failed to mark caught");
+        mat.games[2]
+            .mark_caught(None)
+            .await
+            .expect("Failed to mark caught");
+
+        mat.wait_for_transports().await;
+
+        // Tick a few times past the end so the end condition gets checked again
+        for _ in 0..3 {
+            mat.tick().await;
+        }
+
+        for recv in recvs {
+            recv.await
+                .expect("Failed to recv")
+                .expect("Game errored")
+                .expect("Game didn't end with a history");
+        }
+
+        for (i, game) in mat.games.iter().enumerate() {
+            let id = mat.uuids[i];
+            let syncs = game.transport.count_sent(|msg| {
+                matches!(msg, TransportMessage::Game(ge) if matches!(**ge, GameEvent::PostGameSync(p, _) if p == id))
+            });
+            assert_eq!(syncs, 1, "Game {i} sent {syncs} post-game syncs");
+        }
+    }
+
+    #[test]
+    async fn test_spectator() {
+        let settings = GameSettings {
+            allow_spectators: true,
+            ..mk_settings()
+        };
+        let (mut mat, mut spectators) = MockMatch::new_with_spectators(settings, 3, 1, 1);
+        let transport = spectators.pop().unwrap();
+        let spectator_id = transport.self_id();
+
+        let recvs = mat.start().await;
+
+        let spectate = tokio::spawn({
+            let transport = transport.clone();
+            async move {
+                TestGame::spectate(INTERVAL, transport, MockLocation, AlertRecorder::default())
+                    .await
+            }
+        });
+        transport.fake_join().await;
+        mat.wait_for_transports().await;
+
+        let spectator = Arc::new(
+            spectate
+                .await
+                .expect("Failed to join")
+                .expect("Failed to spectate"),
+        );
+
+        {
+            let state = spectator.state.read().await;
+            let host_state = mat.games[0].state.read().await;
+            assert!(state.is_spectator());
+            assert_eq!(state.clone_caught_state(), host_state.clone_caught_state());
+        }
+
+        let (send, spectator_recv) = oneshot::channel();
+        tokio::spawn({
+            let spectator = spectator.clone();
+            async move {
+                let res = spectator.main_loop().await;
+                spectator.transport.disconnect().await;
+                send.send(res).ok();
+            }
+        });
+        mat.games.push(spectator.clone());
+
+        mat.wait_for_seekers().await;
+        mat.tick().await;
+
+        mat.assert_all_states(|i, s| {
+            assert_eq!(
+                s.get_caught(spectator_id),
+                None,
+                "Game {i} has the spectator in caught state"
+            );
+        })
+        .await;
+
+        mat.games[1]
+            .mark_caught(None)
+            .await
+            .expect("Failed to mark caught");
+        mat.games[2]
+            .mark_caught(None)
+            .await
+            .expect("Failed to mark caught");
+
+        mat.wait_for_transports().await;
+
+        mat.assert_all_states(|i, s| {
+            assert_eq!(
+                s.get_caught(mat.uuids[2]),
+                Some(true),
+                "Game {i} doesn't see 2 as caught"
+            );
+        })
+        .await;
+
+        for _ in 0..2 {
+            mat.tick().await;
+        }
+
+        let syncs = transport.count_sent(|msg| {
+            matches!(msg, TransportMessage::Game(ge) if matches!(**ge, GameEvent::PostGameSync(..) | GameEvent::Ping(_)))
+        });
+        assert_eq!(syncs, 0, "Spectator sent pings or a post-game sync");
+
+        for recv in recvs.into_iter().chain([spectator_recv]) {
+            let history = recv
+                .await
+                .expect("Failed to recv")
+                .expect("Game errored")
+                .expect("Game didn't end with a history");
+            assert!(
+                !history.locations.iter().any(|(id, _)| *id == spectator_id),
+                "Spectator has a location history"
+            );
+        }
+    }
+
+    #[test]
+    async fn test_resume() {
+        let (mut mat, mut spares) = MockMatch::new_with_spectators(mk_settings(), 3, 1, 1);
+        let transport = spares.pop().unwrap();
+        // Stands in for a connection that doesn't exist yet, throw away everything sent to it
+        let drain = tokio::spawn({
+            let transport = transport.clone();
+            async move {
+                loop {
+                    transport.receive_messages().await.for_each(drop);
+                }
+            }
+        });
+
+        let mut recvs = mat.start().await;
+
+        mat.wait_for_seekers().await;
+
+        // Game 2 crashes, the others drop it when it disconnects
+        let snapshot = mat.games[2].snapshot().await;
+        mat.games[2].quit_game().await;
+        recvs
+            .pop()
+            .unwrap()
+            .await
+            .expect("Failed to recv")
+            .expect("Game errored");
+        mat.wait_for_transports().await;
+
+        mat.assert_all_states(|i, s| {
+            if i != 2 {
+                assert_eq!(s.get_caught(mat.uuids[2]), None, "Game {i} still has 2");
+            }
+        })
+        .await;
+
+        mat.games[1]
+            .mark_caught(None)
+            .await
+            .expect("Failed to mark caught");
+        mat.wait_for_transports().await;
+        drain.abort();
+        drain.await.ok();
+
+        let resumed = Arc::new(
+            TestGame::resume(
+                INTERVAL,
+                snapshot,
+                transport.clone(),
+                MockLocation,
+                AlertRecorder::default(),
+            )
+            .await,
+        );
+        mat.games[2] = resumed.clone();
+        let (send, recv) = oneshot::channel();
+        tokio::spawn(async move {
+            let res = resumed.main_loop().await;
+            resumed.transport.disconnect().await;
+            send.send(res).ok();
+        });
+        recvs.push(recv);
+        mat.wait_for_transports().await;
+        yield_now().await;
+
+        mat.assert_all_states(|i, s| {
+            assert_eq!(s.id, mat.uuids[i], "Game {i} has the wrong ID");
+            assert_eq!(
+                s.get_caught(mat.uuids[2]),
+                Some(false),
+                "Game {i} doesn't see 2 as a hider"
+            );
+            assert_eq!(
+                s.get_caught(mat.uuids[1]),
+                Some(true),
+                "Game {i} missed 1 being caught"
+            );
+        })
+        .await;
+
+        // The old connection timing out late shouldn't take 2 back out
+        for game in mat.games.iter() {
+            game.transport
+                .send_self(TransportMessage::PeerDisconnect(mat.uuids[2]))
+                .await;
+        }
+        mat.wait_for_transports().await;
+        yield_now().await;
+
+        mat.assert_all_states(|i, s| {
+            assert_eq!(
+                s.get_caught(mat.uuids[2]),
+                Some(false),
+                "Game {i} removed 2 when their old connection dropped"
+            );
+        })
+        .await;
+
+        mat.games[2]
+            .mark_caught(None)
+            .await
+            .expect("Failed to mark caught");
+        mat.wait_for_transports().await;
+
+        for _ in 0..2 {
+            mat.tick().await;
+        }
+
+        for recv in recvs {
+            let history = recv
+                .await
+                .expect("Failed to recv")
+                .expect("Game errored")
+                .expect("Game didn't end with a history");
+            assert!(
+                history.locations.iter().any(|(id, _)| *id == mat.uuids[2]),
+                "Resumed player is missing from the history"
+            );
+        }
+    }
+
+    #[test]
+    async fn test_post_game_sync_timeout() {
+        let mut mat = MockMatch::new(mk_settings(), 3, 1);
+
+        // Game 2 hangs, it stays connected but never does anything
+        let hung = mat.games.pop().unwrap();
+        tokio::spawn(async move {
+            loop {
+                hung.transport.receive_messages().await.for_each(drop);
+            }
+        });
+        let hung_id = mat.uuids[2];
+
+        let recvs = mat.start().await;
+
+        mat.wait_for_seekers().await;
+
+        mat.games[0].force_end().await.expect("Failed to end game");
+        mat.wait_for_transports().await;
+
+        mat.assert_all_states(|i, s| {
+            assert!(s.game_ended(), "Game {i} has not ended");
+            assert!(
+                !s.check_post_game_sync(Utc::now()),
+                "Game {i} finished early"
+            );
+        })
+        .await;
+
+        // The tick is way longer than the timeout
+        mat.tick().await;
+
+        for recv in recvs {
+            let history = recv
+                .await
+                .expect("Failed to recv")
+                .expect("Game errored")
+                .expect("Game didn't end with a history");
+            assert_eq!(
+                history.player_track(hung_id),
+                Some([].as_slice()),
+                "Hung player should have an empty track"
+            );
+        }
+    }
+
+    #[test]
+    async fn test_seekers_released_alert() {
+        let settings = mk_settings();
+        let mut mat = MockMatch::new(settings, 2, 1);
+
+        mat.start().await;
+        mat.wait_for_seekers().await;
+
+        mat.tick().await;
+        mat.tick().await;
+
+        for (i, game) in mat.games.iter().enumerate() {
+            let alerts = game.state_update_sender.alerts();
+            assert_eq!(
+                alerts
+                    .iter()
+                    .filter(|a| **a == GameAlert::SeekersReleased)
+                    .count(),
+                1,
+                "Game {i} didn't get exactly one seekers released alert: {alerts:?}"
+            );
+        }
+    }
+
+    #[test]
+    async fn test_event_logging() {
+        let settings = mk_settings();
+        let mut mat = MockMatch::new(settings, 3, 1);
+
+        mat.start().await;
+        mat.wait_for_seekers().await;
+
+        mat.games[1]
+            .mark_caught(None)
+            .await
+            .expect("Failed to mark caught");
+        mat.wait_for_transports().await;
+        mat.tick().await;
+
+        let is_catch = |e: &GameEvent| matches!(e, GameEvent::PlayerCaught { player, .. } if *player == mat.uuids[1]);
+
+        for (i, game) in mat.games.iter().enumerate() {
+            let logged = game.state_update_sender.logged_events();
+            let expected = if i == 1 {
+                EventDirection::Sent
+            } else {
+                EventDirection::Received
+            };
+            assert!(
+                logged.iter().any(|(d, e)| *d == expected && is_catch(e)),
+                "Game {i} didn't log the catch as {expected:?}: {logged:?}"
+            );
+        }
+    }
+
+    #[test]
+    async fn test_force_tick() {
+        let mut settings = mk_settings();
+        settings.hiding_time_seconds = 600;
+
+        let mat = MockMatch::new(settings, 2, 1);
+        mat.start().await;
+        // Not a full tick, those jump far past the hiding time
+        mat.wait_for_transports().await;
+        yield_now().await;
+
+        mat.assert_all_states(|i, s| {
+            assert!(!s.seekers_released(), "Seekers released early on game {i}");
+        })
+        .await;
+
+        mat.games[1]
+            .force_tick(Utc::now() + Duration::from_secs(601))
+            .await;
+
+        mat.assert_all_states(|i, s| {
+            assert_eq!(
+                s.seekers_released(),
+                i == 1,
+                "Only the forced game should have released seekers, game {i} didn't match"
+            );
+        })
+        .await;
+    }
+
+    #[test]
+    async fn test_camping_reveal() {
+        let mut settings = mk_settings();
+        settings.ping_start = PingStartCondition::Minutes(1000);
+        settings.reveal_after_minutes = Some(5);
+
+        let mat = MockMatch::new(settings, 2, 1);
+        let start = Utc::now();
+        mat.start().await;
+        mat.wait_for_transports().await;
+        yield_now().await;
+
+        let hider = &mat.games[1];
+        let at = |seconds: u64| start + Duration::from_secs(seconds);
+        let pings_sent = || {
+            hider.transport.count_sent(|msg| {
+                matches!(msg, TransportMessage::Game(event) if matches!(**event, GameEvent::Ping(_)))
+            })
+        };
+
+        // Release seekers, the hider hasn't moved since the game started
+        hider.force_tick(at(2)).await;
+        hider.force_tick(at(2 + 4 * 60)).await;
+        assert_eq!(pings_sent(), 0, "Pinged before camping long enough");
+
+        hider.force_tick(at(2 + 5 * 60)).await;
+        assert_eq!(pings_sent(), 1, "Not pinged for camping");
+        assert!(hider.state.read().await.revealed_for_camping());
+
+        hider.force_tick(at(2 + 5 * 60 + 30)).await;
+        assert_eq!(pings_sent(), 1, "Pinged again too soon");
+
+        hider.force_tick(at(2 + 6 * 60)).await;
+        assert_eq!(pings_sent(), 2, "Not pinged again while still camping");
+        assert!(
+            hider
+                .state_update_sender
+                .alerts()
+                .contains(&GameAlert::Pinged)
+        );
+
+        // Seekers can camp all they want
+        mat.games[0].force_tick(at(2)).await;
+        mat.games[0].force_tick(at(2 + 10 * 60)).await;
+        assert!(!mat.games[0].state.read().await.revealed_for_camping());
+    }
+
+    #[test]
+    async fn test_seeker_pins() {
+        let mat = MockMatch::new(mk_settings(), 3, 2);
+        mat.start().await;
+        mat.wait_for_transports().await;
+        yield_now().await;
+
+        let pin = Location {
+            lat: 1.0,
+            long: 2.0,
+            heading: None,
+        };
+        mat.games[0]
+            .drop_seeker_pin(pin, Some("  north park ".to_string()))
+            .await
+            .expect("Seeker couldn't drop a pin");
+        mat.wait_for_transports().await;
+        yield_now().await;
+
+        let is_pin = |msg: &TransportMessage| matches!(msg, TransportMessage::Game(event) if matches!(**event, GameEvent::SeekerPin(..)));
+        assert_eq!(
+            mat.games[0].transport.count_sent(is_pin),
+            1,
+            "Pin should only go to the other seeker"
+        );
+
+        let expected = vec![SeekerPin {
+            location: pin,
+            label: Some("north park".to_string()),
+        }];
+        assert_eq!(mat.games[0].state.read().await.seeker_pins(), expected);
+        assert_eq!(mat.games[1].state.read().await.seeker_pins(), expected);
+        assert!(mat.games[2].state.read().await.seeker_pins().is_empty());
+
+        assert!(mat.games[2].drop_seeker_pin(pin, None).await.is_err());
+        assert!(
+            mat.games[0]
+                .drop_seeker_pin(pin, Some("x".repeat(MAX_SEEKER_PIN_LABEL_CHARS + 1)))
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    async fn test_ping_expiry() {
+        let mut settings = mk_settings();
+        settings.ping_start = PingStartCondition::Minutes(1000);
+        settings.panic_pings = 1;
+        settings.ping_expiry_seconds = Some(30);
+
+        let mat = MockMatch::new(settings, 2, 1);
+        let start = Utc::now();
+        mat.start().await;
+        mat.wait_for_transports().await;
+        yield_now().await;
+
+        let hider = mat.uuids[1];
+        let at = |seconds: u64| start + Duration::from_secs(seconds);
+        // Get a location to ping with
+        mat.games[1].force_tick(at(0)).await;
+        mat.games[1]
+            .panic_ping()
+            .await
+            .expect("Failed to panic ping");
+        mat.wait_for_transports().await;
+        yield_now().await;
+
+        let seeker = &mat.games[0];
+        assert!(seeker.state.read().await.get_ping(hider).is_some());
+
+        seeker.force_tick(at(10)).await;
+        assert!(
+            seeker.state.read().await.get_ping(hider).is_some(),
+            "Ping expired too early"
+        );
+
+        seeker.force_tick(at(60)).await;
+        assert!(
+            seeker.state.read().await.get_ping(hider).is_none(),
+            "Ping didn't expire"
+        );
+    }
+
+    /// Drop player 2's connection without them leaving, the others should hold on to them
+    async fn disconnect_with_grace() -> (MockMatch, UtcDT) {
+        let mut settings = mk_settings();
+        settings.disconnect_grace_seconds = 30;
+
+        let mat = MockMatch::new(settings, 3, 1);
+        let start = Utc::now();
+        mat.start().await;
+        mat.wait_for_transports().await;
+        yield_now().await;
+
+        let dropped = mat.uuids[2];
+        mat.games[2]
+            .transport
+            .send_message(TransportMessage::PeerDisconnect(dropped))
+            .await;
+        mat.wait_for_transports().await;
+        yield_now().await;
+
+        mat.assert_all_states(|i, s| {
+            if i != 2 {
+                assert_eq!(s.get_caught(dropped), Some(false), "Game {i} removed 2");
+                assert!(
+                    s.is_disconnected(dropped),
+                    "Game {i} didn't see 2 disconnect"
+                );
+            }
+        })
+        .await;
+
+        (mat, start)
+    }
+
+    #[test]
+    async fn test_disconnect_grace_reconnect() {
+        let (mat, start) = disconnect_with_grace().await;
+        let dropped = mat.uuids[2];
+
+        // Transports re-announce a peer they dropped once it starts talking again, with the
+        // same ID as before
+        mat.games[2]
+            .transport
+            .send_message(TransportMessage::PeerConnect(dropped))
+            .await;
+        mat.wait_for_transports().await;
+        yield_now().await;
+
+        for game in mat.games.iter().take(2) {
+            game.force_tick(start + Duration::from_secs(60)).await;
+        }
+
+        mat.assert_all_states(|i, s| {
+            if i != 2 {
+                assert_eq!(s.get_caught(dropped), Some(false), "Game {i} removed 2");
+                assert!(
+                    !s.is_disconnected(dropped),
+                    "Game {i} missed 2 reconnecting"
+                );
+                assert!(
+                    s.active_players().contains(&dropped),
+                    "Game {i} doesn't count 2 as active"
+                );
+            }
+        })
+        .await;
+    }
+
+    #[test]
+    async fn test_disconnect_grace_timeout() {
+        let (mat, start) = disconnect_with_grace().await;
+        let dropped = mat.uuids[2];
+        let seeker = &mat.games[0];
+
+        seeker.force_tick(start + Duration::from_secs(10)).await;
+        assert_eq!(
+            seeker.state.read().await.get_caught(dropped),
+            Some(false),
+            "Removed 2 before the grace period ran out"
+        );
+
+        seeker.force_tick(start + Duration::from_secs(60)).await;
+        let state = seeker.state.read().await;
+        assert_eq!(state.get_caught(dropped), None, "2 wasn't removed");
+        assert!(!state.is_disconnected(dropped));
+    }
+
+    #[test]
+    async fn test_pinged_alert() {
+        let mut settings = mk_settings();
+        settings.ping_minutes_interval = 0;
+
+        let mut mat = MockMatch::new(settings, 3, 1);
+
+        mat.start().await;
+        mat.wait_for_seekers().await;
+
+        for (i, game) in mat.games.iter().enumerate() {
+            let pinged = game
+                .state_update_sender
+                .alerts()
+                .contains(&GameAlert::Pinged);
+            assert_eq!(pinged, i != 0, "Game {i} pinged alert mismatch");
+        }
+    }
+
+    #[test]
+    async fn test_basic_pinging() {
+        let mut settings = mk_settings();
+        settings.ping_minutes_interval = 0;
+
+        let mut mat = MockMatch::new(settings, 4, 1);
+
+        mat.start().await;
+
+        mat.wait_for_seekers().await;
+
+        mat.assert_all_states(|i, s| {
+            for id in 0..4 {
+                let ping = s.get_ping(mat.uuids[id]);
+                if id == 0 {
+                    assert!(
+                        ping.is_none(),
+                        "Game {i} has a ping for 0, despite them being a seeker",
+                    );
+                } else {
+                    assert!(
+                        ping.is_some(),
+                        "Game {i} doesn't have a ping for {id}, despite them being a hider",
+                    );
+                }
+            }
+        })
+        .await;
+
+        mat.games[1]
+            .mark_caught(None)
+            .await
+            .expect("Failed to mark caught");
+
+        mat.tick().await;
+
+        mat.assert_all_states(|i, s| {
+            for id in 0..4 {
+                let ping = s.get_ping(mat.uuids[id]);
+                if id <= 1 {
+                    assert!(
+                        ping.is_none(),
+                        "Game {i} has a ping for {id}, despite them being a seeker",
+                    );
+                } else {
+                    assert!(
+                        ping.is_some(),
+                        "Game {i} doesn't have a ping for {id}, despite them being a hider",
+                    );
+                }
+            }
+        })
+        .await;
+    }
+
+    #[test]
+    async fn test_reverse_pinging() {
+        let mut settings = mk_settings();
+        settings.ping_minutes_interval = 0;
+        settings.reverse_pings = true;
+
+        let mut mat = MockMatch::new(settings, 4, 2);
+
+        mat.start().await;
+
+        mat.wait_for_seekers().await;
+
+        mat.assert_all_states(|i, s| {
+            for id in 0..4 {
+                assert_eq!(
+                    s.get_ping(mat.uuids[id]).is_some(),
+                    id < 2,
+                    "Game {i} has the wrong ping state for {id} in a reverse game",
+                );
+            }
+        })
+        .await;
+
+        mat.games[2]
+            .mark_caught(None)
+            .await
+            .expect("Failed to mark caught");
+
+        mat.tick().await;
+
+        mat.assert_all_states(|i, s| {
+            for id in 0..4 {
+                assert_eq!(
+                    s.get_ping(mat.uuids[id]).is_some(),
+                    id < 3,
+                    "Game {i} has the wrong ping state for {id} after they were caught",
+                );
+            }
+        })
+        .await;
+    }
+
+    #[test]
+    async fn test_rng_sync() {
+        let mut settings = mk_settings();
+        settings.powerup_chance = 100;
+        settings.powerup_minutes_cooldown = 1;
+        settings.powerup_start = PingStartCondition::Instant;
+        settings.powerup_locations = (1..1000)
+            .map(|x| Location {
+                lat: x as f64,
+                long: 1.0,
+                heading: None,
+            })
+            .collect();
+
+        let mut mat = MockMatch::new(settings, 10, 2);
+
+        mat.start().await;
+        mat.tick().await;
+        mat.wait_for_seekers().await;
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        mat.tick().await;
+
+        let game = mat.games[0].clone();
+        let state = game.state.read().await;
+        let location = state.powerup_location().expect("Powerup didn't spawn");
+
+        drop(state);
+
+        mat.assert_all_states(|i, s| {
+            assert_eq!(
+                s.powerup_location(),
+                Some(location),
+                "Game {i} has a different location than 0",
+            );
+        })
+        .await;
+    }
+
+    #[test]
+    async fn test_powerup_ping_seeker_as_you() {
+        let mut settings = mk_settings();
+        settings.ping_minutes_interval = 1;
+        let mut mat = MockMatch::new(settings, 2, 1);
+
+        mat.start().await;
+        mat.wait_for_seekers().await;
+
+        mat.tick().await;
+
+        tokio::time::sleep(Duration::from_secs(60)).await;
+
+        let game = mat.games[1].clone();
+        let mut state = game.state.write().await;
+        state.force_set_powerup(PowerUpType::PingSeeker);
+        drop(state);
+
+        mat.tick().await;
+
+        mat.assert_all_states(|i, s| {
+            if let Some(ping) = s.get_ping(mat.uuids[1]) {
+                assert_eq!(
+                    ping.real_player, mat.uuids[0],
+                    "Game {i} has a ping for 1, but it wasn't from 0"
+                );
+                assert!(
+                    ping.spoofed,
+                    "Game {i} doesn't know the ping for 1 is spoofed"
+                );
+            } else {
+                panic!("Game {i} has no ping for 1");
+            }
+        })
+        .await;
+    }
+
+    #[test]
+    async fn test_powerup_grab_radius() {
+        let mut settings = mk_settings();
+        settings.powerup_grab_radius_meters = Some(10.0);
+        let mut mat = MockMatch::new(settings, 2, 1);
+
+        mat.start().await;
+        mat.wait_for_seekers().await;
+
+        let game = mat.games[1].clone();
+
+        // MockLocation is always at 0, 0
+        let far = Location {
+            lat: 1.0,
+            long: 0.0,
+            heading: None,
+        };
+        game.state
+            .write()
+            .await
+            .force_set_available_powerup(Some(far));
+        assert!(!game.get_powerup().await, "Grabbed a powerup out of range");
+        assert!(
+            game.state.read().await.peek_powerup().is_none(),
+            "Got a powerup from an out of range grab"
+        );
+
+        let near = Location {
+            lat: 0.00001,
+            long: 0.0,
+            heading: None,
+        };
+        game.state
+            .write()
+            .await
+            .force_set_available_powerup(Some(near));
+        assert!(game.get_powerup().await, "Couldn't grab a powerup in range");
+        assert!(
+            game.state.read().await.peek_powerup().is_some(),
+            "Didn't get a powerup from an in range grab"
+        );
+    }
+
+    #[test]
+    async fn test_powerup_ping_random_hider() {
+        let mut settings = mk_settings();
+        settings.ping_minutes_interval = u32::MAX;
+
+        let mut mat = MockMatch::new(settings, 3, 1);
+
+        mat.start().await;
+        mat.wait_for_seekers().await;
+
+        let game = mat.games[1].clone();
+        let mut state = game.state.write().await;
+        state.force_set_powerup(PowerUpType::ForcePingOther);
+        drop(state);
+
+        game.use_powerup().await.expect("Failed to use powerup");
+        mat.tick().await;
+
+        mat.assert_all_states(|i, s| {
+            // Player 0 is a seeker, player 1 used the powerup, so 2 is the only one that should
+            // have pinged
+            assert!(
+                s.get_ping(mat.uuids[2]).is_some(),
+                "Ping 2 is not present in game {i}"
+            );
+            assert!(
+                s.get_ping(mat.uuids[0]).is_none(),
+                "Ping 0 is present in game {i}"
+            );
+            assert!(
+                s.get_ping(mat.uuids[1]).is_none(),
+                "Ping 1 is present in game {i}"
+            );
+        })
+        .await;
+    }
+
+    #[test]
+    async fn test_powerup_ping_random_hider_agrees() {
+        let mut settings = mk_settings();
+        settings.ping_minutes_interval = u32::MAX;
+
+        let mut mat = MockMatch::new(settings, 5, 1);
+
+        mat.start().await;
+        mat.wait_for_seekers().await;
+
+        let game = mat.games[1].clone();
+        let mut state = game.state.write().await;
+        state.force_set_powerup(PowerUpType::ForcePingOther);
+        drop(state);
+
+        game.use_powerup().await.expect("Failed to use powerup");
+        mat.tick().await;
+
+        let state = mat.games[0].state.read().await;
+        let pinged = mat.uuids[2..]
+            .iter()
+            .copied()
+            .filter(|id| state.get_ping(*id).is_some())
+            .collect::<Vec<_>>();
+        drop(state);
+        assert_eq!(pinged.len(), 1, "Exactly one other hider should be pinged");
+        let target = pinged[0];
+
+        mat.assert_all_states(|i, s| {
+            for (j, id) in mat.uuids.iter().enumerate() {
+                assert_eq!(
+                    s.get_ping(*id).is_some(),
+                    *id == target,
+                    "Game {i} disagrees on whether {j} was pinged"
+                );
+            }
+        })
+        .await;
+    }
+
+    #[test]
+    async fn test_powerup_ping_other_falls_back_to_seeker() {
+        let mut settings = mk_settings();
+        settings.ping_minutes_interval = u32::MAX;
+
+        let mut mat = MockMatch::new(settings, 3, 2);
+
+        mat.start().await;
+        mat.wait_for_seekers().await;
+
+        // Player 2 is the only hider, so there's no other hider to ping
+        let game = mat.games[2].clone();
+        let mut state = game.state.write().await;
+        state.force_set_powerup(PowerUpType::ForcePingOther);
+        drop(state);
+
+        game.use_powerup().await.expect("Failed to use powerup");
+        mat.tick().await;
+
+        let state = mat.games[2].state.read().await;
+        let pinged = mat.uuids[..2]
+            .iter()
+            .copied()
+            .filter(|id| state.get_ping(*id).is_some())
+            .collect::<Vec<_>>();
+        assert!(state.get_ping(mat.uuids[2]).is_none());
+        drop(state);
+        assert_eq!(pinged.len(), 1, "Exactly one seeker should be pinged");
+
+        mat.assert_all_states(|i, s| {
+            for (j, id) in mat.uuids.iter().enumerate() {
+                assert_eq!(
+                    s.get_ping(*id).is_some(),
+                    *id == pinged[0],
+                    "Game {i} disagrees on whether {j} was pinged"
+                );
+            }
+        })
+        .await;
+    }
+
+    #[test]
+    async fn test_powerup_ping_seekers() {
+        let settings = mk_settings();
+
+        let mat = MockMatch::new(settings, 5, 3);
+
+        mat.start().await;
+
+        mat.tick().await;
+
+        let game = mat.games[3].clone();
+        let mut state = game.state.write().await;
+        state.force_set_powerup(PowerUpType::PingAllSeekers);
+        drop(state);
+
+        game.use_powerup().await.expect("Failed to use powerup");
+        // One tick to send out the ForcePing
+        mat.tick().await;
+        // One tick to for the seekers to reply
+        mat.tick().await;
+
+        mat.assert_all_states(|i, s| {
+            for id in 0..3 {
+                assert!(
+                    &s.get_ping(mat.uuids[id]).is_some(),
+                    "Game {i} does not have a ping for {id}, despite the powerup being active",
+                );
+            }
+        })
+        .await;
+    }
+
+    #[test]
+    async fn test_game_phase() {
+        let mut mat = MockMatch::new(mk_settings(), 3, 1);
+        mat.start().await;
+        mat.wait_for_transports().await;
+        yield_now().await;
+
+        mat.assert_all_states(|i, s| {
+            assert_eq!(s.phase(), GamePhase::Hiding, "Game {i} isn't hiding");
+        })
+        .await;
+
+        mat.wait_for_seekers().await;
+        mat.assert_all_states(|i, s| {
+            assert_eq!(s.phase(), GamePhase::Active, "Game {i} isn't active");
+        })
+        .await;
+
+        mat.games[0].force_end().await.expect("Failed to end game");
+        mat.wait_for_transports().await;
+        yield_now().await;
+        mat.assert_all_states(|i, s| {
+            assert_eq!(s.phase(), GamePhase::Ended, "Game {i} didn't end");
+        })
+        .await;
+    }
+
+    /// Have the host (a seeker) ping everyone on the pinged team and check exactly they were
+    async fn check_ping_all_hiders(reverse_pings: bool) {
+        let mut settings = mk_settings();
+        settings.ping_start = PingStartCondition::Minutes(1000);
+        settings.reverse_pings = reverse_pings;
+
+        let mat = MockMatch::new(settings, 4, 1);
+        mat.start().await;
+        mat.tick().await;
+
+        assert!(mat.games[1].ping_all_hiders().await.is_err());
+        mat.games[0]
+            .ping_all_hiders()
+            .await
+            .expect("Host couldn't ping everyone");
+        // One tick to send out the ForcePings
+        mat.tick().await;
+        // One tick for the targets to reply
+        mat.tick().await;
+
+        mat.assert_all_states(|i, s| {
+            for id in 0..4 {
+                let targeted = (id >= 1) != reverse_pings;
+                assert_eq!(
+                    s.get_ping(mat.uuids[id]).is_some(),
+                    targeted,
+                    "Game {i} is wrong about {id} being pinged",
+                );
+            }
+        })
+        .await;
+    }
+
+    #[test]
+    async fn test_ping_all_hiders() {
+        check_ping_all_hiders(false).await;
+    }
+
+    #[test]
+    async fn test_ping_all_hiders_reversed() {
+        check_ping_all_hiders(true).await;
+    }
+
+    #[test]
+    async fn test_powerup_wrong_role() {
+        let mut settings = mk_settings();
+        settings.ping_minutes_interval = u32::MAX;
+
+        let mut mat = MockMatch::new(settings, 3, 1);
+
+        mat.start().await;
+        mat.wait_for_seekers().await;
+
+        let seeker = mat.games[0].clone();
+        let mut state = seeker.state.write().await;
+        state.force_set_powerup(PowerUpType::FreezeSeekers);
+        drop(state);
+
+        seeker
+            .use_powerup()
+            .await
+            .expect_err("Seeker used a hider-only powerup");
+        mat.tick().await;
+
+        assert!(
+            matches!(
+                seeker.state.read().await.peek_powerup(),
+                Some(PowerUpType::FreezeSeekers)
+            ),
+            "Rejected powerup was used up"
+        );
+        mat.assert_all_states(|i, s| {
+            assert!(
+                s.get_frozen_until().is_none(),
+                "Seekers were frozen in game {i}"
+            );
+        })
+        .await;
+
+        // Powerups that work for either role still go through
+        let mut state = seeker.state.write().await;
+        state.force_set_powerup(PowerUpType::ForcePingOther);
+        drop(state);
+
+        seeker
+            .use_powerup()
+            .await
+            .expect("Seeker couldn't use a powerup usable by anyone");
+        assert!(seeker.state.read().await.peek_powerup().is_none());
+    }
+
+    #[test]
+    async fn test_panic_ping() {
+        let mut settings = mk_settings();
+        settings.ping_minutes_interval = u32::MAX;
+        settings.panic_pings = 1;
+
+        let mut mat = MockMatch::new(settings, 3, 1);
+
+        mat.start().await;
+        mat.wait_for_seekers().await;
+
+        mat.games[0]
+            .panic_ping()
+            .await
+            .expect_err("Seeker was able to panic ping");
+
+        mat.games[1]
+            .panic_ping()
+            .await
+            .expect("Hider couldn't panic ping");
+        mat.tick().await;
+
+        mat.assert_all_states(|i, s| {
+            assert!(
+                s.get_ping(mat.uuids[1]).is_some(),
+                "Panic ping missing in game {i}"
+            );
+            assert!(
+                s.get_ping(mat.uuids[2]).is_none(),
+                "Other hider pinged in game {i}"
+            );
+        })
+        .await;
+
+        assert_eq!(mat.games[1].state.read().await.panic_pings_left(), 0);
+        mat.games[1]
+            .panic_ping()
+            .await
+            .expect_err("Hider panic pinged more than allowed");
+        assert_eq!(mat.games[2].state.read().await.panic_pings_left(), 1);
+    }
+
+    #[test]
+    async fn test_freeze_seekers() {
+        let mut settings = mk_settings();
+        // Make sure the freeze outlasts a tick
+        settings.freeze_seconds = INTERVAL.as_secs() as u32 * 2;
+        let freeze_time = Duration::from_secs(settings.freeze_seconds as u64);
+        let mut mat = MockMatch::new(settings, 3, 1);
+
+        mat.start().await;
+
+        mat.wait_for_seekers().await;
+
+        let game = mat.games[1].clone();
+        let mut state = game.state.write().await;
+        state.force_set_powerup(PowerUpType::FreezeSeekers);
+        drop(state);
+
+        game.use_powerup().await.expect("Failed to use powerup");
+        mat.tick().await;
+
+        let now = TestGame::get_now();
+        mat.assert_all_states(|i, s| {
+            assert!(
+                s.seekers_frozen(now),
+                "Game {i} doesn't have seekers frozen after the powerup was used"
+            );
+        })
+        .await;
+
+        tokio::time::sleep(freeze_time).await;
+        mat.tick().await;
+
+        let now = TestGame::get_now();
+        mat.assert_all_states(|i, s| {
+            assert!(
+                !s.seekers_frozen(now),
+                "Game {i} still has seekers frozen after the freeze expired"
+            );
+            assert!(
+                s.get_frozen_until().is_none(),
+                "Game {i} didn't clear the freeze in tick"
+            );
+        })
+        .await;
+    }
+
+    #[test]
+    async fn test_amend_settings() {
+        let settings = mk_settings();
+        let mut mat = MockMatch::new(settings.clone(), 3, 1);
+
+        mat.start().await;
+
+        mat.wait_for_seekers().await;
+
+        let mut amended = settings.clone();
+        amended.ping_minutes_interval = 10;
+        amended.powerup_chance = 50;
+
+        assert!(
+            mat.games[1].amend_settings(amended.clone()).await.is_err(),
+            "Non-host was able to amend settings"
+        );
+
+        let mut late = amended.clone();
+        late.hiding_time_seconds = 100;
+        assert!(
+            mat.games[0].amend_settings(late).await.is_err(),
+            "Host was able to change hiding time after seekers were released"
+        );
+
+        let fixed_at_start = [
+            (
+                "host_plays",
+                GameSettings {
+                    host_plays: !amended.host_plays,
+                    ..amended.clone()
+                },
+            ),
+            (
+                "auto_seeker_count",
+                GameSettings {
+                    auto_seeker_count: Some(2),
+                    ..amended.clone()
+                },
+            ),
+            (
+                "anonymize_profiles",
+                GameSettings {
+                    anonymize_profiles: !amended.anonymize_profiles,
+                    ..amended.clone()
+                },
+            ),
+            (
+                "allow_spectators",
+                GameSettings {
+                    allow_spectators: !amended.allow_spectators,
+                    ..amended.clone()
+                },
+            ),
+        ];
+        for (name, changed) in fixed_at_start {
+            assert!(
+                mat.games[0].amend_settings(changed).await.is_err(),
+                "Host was able to change {name} mid-game"
+            );
+        }
+
+        assert!(mat.games[0].state.read().await.pings_started());
+        let mut changed = amended.clone();
+        changed.first_ping_immediate = !changed.first_ping_immediate;
+        assert!(
+            mat.games[0].amend_settings(changed).await.is_err(),
+            "Host was able to change first_ping_immediate after pings started"
+        );
+
+        mat.games[0]
+            .amend_settings(amended)
+            .await
+            .expect("Host failed to amend settings");
+
+        mat.wait_for_transports().await;
+
+        mat.assert_all_states(|i, s| {
+            let settings = s.clone_settings();
+            assert_eq!(
+                settings.ping_minutes_interval, 10,
+                "Game {i} didn't apply the amendment"
+            );
+            assert_eq!(
+                settings.powerup_chance, 50,
+                "Game {i} didn't apply the amendment"
+            );
+            assert_eq!(
+                settings.hiding_time_seconds, 1,
+                "Game {i} applied a rejected amendment"
+            );
+        })
+        .await;
+
+        // Whatever the host sends out is applied as-is, even if a player's own check would
+        // disagree with it
+        let mut forced = mat.games[0].clone_settings().await;
+        forced.hiding_time_seconds = 100;
+        mat.games[0]
+            .transport
+            .send_message(GameEvent::SettingsAmendment(forced).into())
+            .await;
+        mat.wait_for_transports().await;
+        yield_now().await;
+
+        mat.assert_all_states(|i, s| {
+            if i != 0 {
+                assert_eq!(
+                    s.clone_settings().hiding_time_seconds,
+                    100,
+                    "Game {i} second-guessed the host's amendment"
+                );
+            }
+        })
+        .await;
+    }
+
+    #[test]
+    async fn test_jailbreak() {
+        let mut settings = mk_settings();
+        settings.enable_jailbreak = true;
+        settings.jail_location = Some(Location {
+            lat: 0.0,
+            long: 0.0,
+            heading: None,
+        });
+        let mut mat = MockMatch::new(settings, 3, 1);
+
+        mat.start().await;
+        mat.wait_for_seekers().await;
+
+        mat.games[1]
+            .mark_caught(Some(mat.uuids[0]))
+            .await
+            .expect("Failed to mark caught");
+        mat.wait_for_transports().await;
+
+        assert!(
+            mat.games[0].attempt_jailbreak().await.is_err(),
+            "Seeker was able to free players"
+        );
+
+        let freed = mat.games[2]
+            .attempt_jailbreak()
+            .await
+            .expect("Hider failed to free players");
+        assert_eq!(freed, vec![mat.uuids[1]]);
+
+        mat.wait_for_transports().await;
+
+        mat.assert_all_states(|i, s| {
+            assert_eq!(
+                s.get_caught(mat.uuids[1]),
+                Some(false),
+                "Game {i} sees player 1 as still caught after being freed"
+            );
+            assert_eq!(
+                s.iter_jailed().count(),
+                0,
+                "Game {i} still has players in jail"
+            );
+        })
+        .await;
+
+        // Everyone left is a hider again, the game shouldn't end
+        mat.games[2]
+            .mark_caught(Some(mat.uuids[0]))
+            .await
+            .expect("Failed to mark caught");
+        mat.tick().await;
+
+        mat.assert_all_states(|i, s| {
+            assert!(
+                !s.game_ended(),
+                "Game {i} ended despite player 1 being freed"
+            );
+        })
+        .await;
+    }
+
+    #[test]
+    async fn test_safe_zone_blocks_catch() {
+        let mut settings = mk_settings();
+        // Mock players all sit at 0, 0
+        settings.safe_zones = vec![(
+            Location {
+                lat: 0.0,
+                long: 0.0,
+                heading: None,
+            },
+            50.0,
+        )];
+        let mut mat = MockMatch::new(settings, 2, 1);
+
+        mat.start().await;
+        mat.wait_for_seekers().await;
+
+        assert!(
+            mat.games[1].mark_caught(Some(mat.uuids[0])).await.is_err(),
+            "Hider in a safe zone got caught"
+        );
+        mat.wait_for_transports().await;
+
+        mat.assert_all_states(|i, s| {
+            assert_eq!(
+                s.get_caught(mat.uuids[1]),
+                Some(false),
+                "Game {i} sees 1 as caught"
+            );
+        })
+        .await;
+    }
+
+    #[test]
+    async fn test_jailbreak_out_of_range() {
+        let mut settings = mk_settings();
+        settings.enable_jailbreak = true;
+        settings.jail_location = Some(Location {
+            lat: 1.0,
+            long: 0.0,
+            heading: None,
+        });
+        let mut mat = MockMatch::new(settings, 3, 1);
+
+        mat.start().await;
+        mat.wait_for_seekers().await;
+
+        mat.games[1]
+            .mark_caught(None)
+            .await
+            .expect("Failed to mark caught");
+        mat.wait_for_transports().await;
+
+        assert!(
+            mat.games[2].attempt_jailbreak().await.is_err(),
+            "Hider freed players from far away"
+        );
+    }
+
+    #[test]
+    async fn test_force_end() {
+        let settings = mk_settings();
+        let mut mat = MockMatch::new(settings, 3, 1);
+
+        let recvs = mat.start().await;
+
+        mat.wait_for_seekers().await;
+
+        assert!(
+            mat.games[1].force_end().await.is_err(),
+            "Non-host was able to end the game"
+        );
+
+        mat.games[0]
+            .force_end()
+            .await
+            .expect("Host failed to end the game");
+
+        mat.wait_for_transports().await;
+
         mat.assert_all_states(|i, s| {
-            for id in 0..4 {
-                let ping = s.get_ping(mat.uuids[id]);
-                if id <= 1 {
-                    assert!(
-                        ping.is_none(),
-                        "Game {i} has a ping for {id}, despite them being a seeker",
-                    );
-                } else {
+            assert!(s.game_ended(), "Game {i} has not ended");
+        })
+        .await;
+
+        // Tick for post-game sync
+        mat.tick().await;
+
+        mat.assert_all_transports_disconnected();
+
+        for (i, game) in mat.games.iter().enumerate() {
+            let alerts = game.state_update_sender.alerts();
+            assert_eq!(
+                alerts
+                    .iter()
+                    .filter(|a| **a == GameAlert::GameEnded)
+                    .count(),
+                1,
+                "Game {i} didn't get exactly one game ended alert: {alerts:?}"
+            );
+        }
+
+        for (i, recv) in recvs.into_iter().enumerate() {
+            let res = recv.await.expect("Failed to recv");
+            match res {
+                Ok(Some(hist)) => {
+                    assert!(!hist.locations.is_empty(), "Game {i} has no locations");
                     assert!(
-                        ping.is_some(),
-                        "Game {i} doesn't have a ping for {id}, despite them being a hider",
+                        hist.events
+                            .iter()
+                            .any(|(_, e)| matches!(e, GameEvent::ForceEnd(_))),
+                        "Game {i} doesn't have the force end in its history"
                     );
                 }
+                Ok(None) => {
+                    panic!("Game {i} exited without a history (did not end via post game sync)");
+                }
+                Err(why) => {
+                    panic!("Game {i} encountered error: {why:?}");
+                }
             }
-        })
-        .await;
+        }
     }
 
     #[test]
-    async fn test_rng_sync() {
+    async fn test_force_end_referee() {
         let mut settings = mk_settings();
-        settings.powerup_chance = 100;
-        settings.powerup_minutes_cooldown = 1;
-        settings.powerup_start = PingStartCondition::Instant;
-        settings.powerup_locations = (1..1000)
-            .map(|x| Location {
-                lat: x as f64,
-                long: 1.0,
-                heading: None,
-            })
-            .collect();
+        settings.host_plays = false;
+        let mut mat = MockMatch::new(settings, 3, 2);
 
-        let mut mat = MockMatch::new(settings, 10, 2);
+        let recvs = mat.start().await;
 
-        mat.start().await;
-        mat.tick().await;
         mat.wait_for_seekers().await;
-        tokio::time::sleep(Duration::from_secs(60)).await;
+
+        mat.games[0]
+            .force_end()
+            .await
+            .expect("Host failed to end the game");
+
+        mat.wait_for_transports().await;
+        // Tick for post-game sync
         mat.tick().await;
 
-        let game = mat.games[0].clone();
-        let state = game.state.read().await;
-        let location = state.powerup_location().expect("Powerup didn't spawn");
+        for (i, recv) in recvs.into_iter().enumerate() {
+            let res = recv.await.expect("Failed to recv");
+            let Ok(Some(hist)) = res else {
+                panic!("Game {i} didn't end normally: {res:?}");
+            };
+            assert!(
+                hist.player_track(mat.uuids[0]).is_none(),
+                "Game {i} has the refereeing host in its history"
+            );
+        }
+    }
+
+    #[test]
+    async fn test_concede() {
+        let settings = mk_settings();
+        let mut mat = MockMatch::new(settings, 2, 1);
+
+        mat.start().await;
+        mat.wait_for_seekers().await;
+
+        assert!(
+            mat.games[0].concede().await.is_err(),
+            "Seeker was able to concede"
+        );
+
+        mat.games[1]
+            .concede()
+            .await
+            .expect("Hider failed to concede");
+        mat.wait_for_transports().await;
+
+        mat.assert_all_states(|i, s| {
+            assert_eq!(
+                s.get_caught(mat.uuids[1]),
+                Some(true),
+                "Game {i} sees player 1 as not caught",
+            );
+        })
+        .await;
 
+        let state = mat.games[0].state.read().await;
+        assert!(state.event_history.iter().any(|(_, e)| matches!(
+            e,
+            GameEvent::PlayerCaught { player, reason: CatchReason::Conceded, .. } if *player == mat.uuids[1]
+        )));
         drop(state);
 
+        // Last hider conceded, game should end
+        mat.tick().await;
+
+        mat.assert_all_states(|i, s| {
+            assert!(s.game_ended(), "Game {i} has not ended");
+        })
+        .await;
+    }
+
+    #[test]
+    async fn test_catch_scores() {
+        let settings = mk_settings();
+        let mut mat = MockMatch::new(settings, 3, 1);
+
+        mat.start().await;
+        mat.wait_for_seekers().await;
+
+        mat.games[1]
+            .mark_caught(Some(mat.uuids[0]))
+            .await
+            .expect("Failed to mark caught");
+        mat.games[2]
+            .mark_caught(Some(mat.uuids[0]))
+            .await
+            .expect("Failed to mark caught");
+        mat.wait_for_transports().await;
+
         mat.assert_all_states(|i, s| {
             assert_eq!(
-                s.powerup_location(),
-                Some(location),
-                "Game {i} has a different location than 0",
+                s.get_catches(mat.uuids[0]),
+                2,
+                "Game {i} doesn't see 0 with 2 catches"
             );
         })
         .await;
     }
 
     #[test]
-    async fn test_powerup_ping_seeker_as_you() {
-        let mut settings = mk_settings();
-        settings.ping_minutes_interval = 1;
-        let mut mat = MockMatch::new(settings, 2, 1);
+    async fn test_own_catch_recorded() {
+        let settings = mk_settings();
+        let mut mat = MockMatch::new(settings, 3, 1);
 
         mat.start().await;
         mat.wait_for_seekers().await;
 
-        mat.tick().await;
+        mat.games[1]
+            .mark_caught(Some(mat.uuids[0]))
+            .await
+            .expect("Failed to mark caught");
+        mat.wait_for_transports().await;
 
-        tokio::time::sleep(Duration::from_secs(60)).await;
+        let history = mat.games[1].state.read().await.as_game_history();
+        let own_catches = history
+            .events
+            .iter()
+            .filter(|(_, e)| matches!(e, GameEvent::PlayerCaught { player, .. } if *player == mat.uuids[1]))
+            .count();
+        assert_eq!(own_catches, 1, "Game 1 didn't record its own catch once");
+        assert_eq!(
+            history.catches_by.get(&mat.uuids[0]),
+            Some(&1),
+            "Game 1 counted its own catch more than once"
+        );
+        assert_eq!(
+            history.awards().most_catches,
+            Some((mat.uuids[0], 1)),
+            "Game 1's awards got a different catch count"
+        );
+    }
 
-        let game = mat.games[1].clone();
-        let mut state = game.state.write().await;
-        state.force_set_powerup(PowerUpType::PingSeeker);
-        drop(state);
+    #[test]
+    async fn test_catch_streaks() {
+        let settings = mk_settings();
+        let mut mat = MockMatch::new(settings, 4, 1);
+
+        mat.start().await;
+        mat.wait_for_seekers().await;
+
+        mat.games[1]
+            .mark_caught(Some(mat.uuids[0]))
+            .await
+            .expect("Failed to mark caught");
+        mat.games[2]
+            .mark_caught(Some(mat.uuids[0]))
+            .await
+            .expect("Failed to mark caught");
+        mat.wait_for_transports().await;
+
+        mat.assert_all_states(|i, s| {
+            assert_eq!(
+                s.get_streak(mat.uuids[0]),
+                2,
+                "Game {i} doesn't see 0 with a streak of 2"
+            );
+        })
+        .await;
 
+        // Ticking waits longer than the streak window
         mat.tick().await;
 
         mat.assert_all_states(|i, s| {
-            if let Some(ping) = s.get_ping(mat.uuids[1]) {
-                assert_eq!(
-                    ping.real_player, mat.uuids[0],
-                    "Game {i} has a ping for 1, but it wasn't from 0"
-                );
-            } else {
-                panic!("Game {i} has no ping for 1");
-            }
+            assert_eq!(
+                s.get_streak(mat.uuids[0]),
+                0,
+                "Game {i} didn't end 0's streak after the window lapsed"
+            );
+            assert_eq!(
+                s.as_game_history().max_streaks.get(&mat.uuids[0]),
+                Some(&2),
+                "Game {i} didn't keep 0's max streak"
+            );
         })
         .await;
     }
 
     #[test]
-    async fn test_powerup_ping_random_hider() {
-        let mut settings = mk_settings();
-        settings.ping_minutes_interval = u32::MAX;
-
+    async fn test_undo_caught() {
+        let settings = mk_settings();
         let mut mat = MockMatch::new(settings, 3, 1);
 
         mat.start().await;
         mat.wait_for_seekers().await;
 
-        let game = mat.games[1].clone();
-        let mut state = game.state.write().await;
-        state.force_set_powerup(PowerUpType::ForcePingOther);
-        drop(state);
+        mat.games[1]
+            .mark_caught(Some(mat.uuids[0]))
+            .await
+            .expect("Failed to mark caught");
+        mat.wait_for_transports().await;
 
-        game.use_powerup().await;
-        mat.tick().await;
+        assert!(
+            mat.games[1].state.read().await.undo_catch_until().is_some(),
+            "Game 1 can't undo its catch"
+        );
+
+        mat.games[1]
+            .undo_caught()
+            .await
+            .expect("Failed to undo catch in the window");
+        mat.wait_for_transports().await;
 
         mat.assert_all_states(|i, s| {
-            // Player 0 is a seeker, player 1 used the powerup, so 2 is the only one that should
-            // have pinged
-            assert!(
-                s.get_ping(mat.uuids[2]).is_some(),
-                "Ping 2 is not present in game {i}"
-            );
-            assert!(
-                s.get_ping(mat.uuids[0]).is_none(),
-                "Ping 0 is present in game {i}"
+            assert_eq!(
+                s.get_caught(mat.uuids[1]),
+                Some(false),
+                "Game {i} still sees 1 as caught"
             );
-            assert!(
-                s.get_ping(mat.uuids[1]).is_none(),
-                "Ping 1 is present in game {i}"
+            assert_eq!(
+                s.get_catches(mat.uuids[0]),
+                0,
+                "Game {i} didn't take 0's catch away"
             );
+            assert_eq!(s.get_streak(mat.uuids[0]), 0);
         })
         .await;
+
+        assert!(
+            mat.games[1].undo_caught().await.is_err(),
+            "Game 1 undid the same catch twice"
+        );
     }
 
     #[test]
-    async fn test_powerup_ping_seekers() {
+    async fn test_undo_caught_locked_in() {
         let settings = mk_settings();
-
-        let mat = MockMatch::new(settings, 5, 3);
+        let mut mat = MockMatch::new(settings, 3, 1);
 
         mat.start().await;
+        mat.wait_for_seekers().await;
 
+        mat.games[1]
+            .mark_caught(Some(mat.uuids[0]))
+            .await
+            .expect("Failed to mark caught");
+        // Ticking waits longer than the undo window
         mat.tick().await;
 
-        let game = mat.games[3].clone();
+        assert!(
+            mat.games[1].state.read().await.undo_catch_until().is_none(),
+            "Game 1 can still undo its catch after the window"
+        );
+        assert!(
+            mat.games[1].undo_caught().await.is_err(),
+            "Game 1 undid its catch after the window"
+        );
+
+        mat.assert_all_states(|i, s| {
+            assert_eq!(
+                s.get_caught(mat.uuids[1]),
+                Some(true),
+                "Game {i} doesn't see 1 as caught"
+            );
+            assert_eq!(s.get_catches(mat.uuids[0]), 1);
+        })
+        .await;
+    }
+
+    #[test]
+    async fn test_resync() {
+        let settings = mk_settings();
+        let mut mat = MockMatch::new(settings, 3, 1);
+
+        mat.start().await;
+        mat.wait_for_seekers().await;
+
+        mat.games[2]
+            .mark_caught(Some(mat.uuids[0]))
+            .await
+            .expect("Failed to mark caught");
+        mat.wait_for_transports().await;
+
+        let state = mat.games[0].state.read().await;
+        assert!(state.event_history.iter().any(|(_, e)| matches!(
+            e,
+            GameEvent::PlayerCaught { player, by, .. } if *player == mat.uuids[2] && *by == Some(mat.uuids[0])
+        )));
+        drop(state);
+
+        // Pretend game 1 missed the catch
+        let game = mat.games[1].clone();
         let mut state = game.state.write().await;
-        state.force_set_powerup(PowerUpType::PingAllSeekers);
+        state.force_set_caught(mat.uuids[2], false);
         drop(state);
 
-        game.use_powerup().await;
-        // One tick to send out the ForcePing
+        game.request_resync().await;
+        // One tick for the others to respond, one to process responses
         mat.tick().await;
-        // One tick to for the seekers to reply
         mat.tick().await;
 
         mat.assert_all_states(|i, s| {
-            for id in 0..3 {
-                assert!(
-                    &s.get_ping(mat.uuids[id]).is_some(),
-                    "Game {i} does not have a ping for {id}, despite the powerup being active",
-                );
-            }
+            assert_eq!(
+                s.get_caught(mat.uuids[2]),
+                Some(true),
+                "Game {i} sees player 2 as not caught after resync",
+            );
+            assert!(
+                s.get_ping(mat.uuids[2]).is_none(),
+                "Game {i} still has a ping for 2 after resync"
+            );
         })
         .await;
+
+        // Replies only go to the requester but should still be logged
+        for i in [0, 2] {
+            let logged = mat.games[i].state_update_sender.logged_events();
+            assert!(
+                logged.iter().any(
+                    |(d, e)| *d == EventDirection::Sent && matches!(e, GameEvent::FullSync(..))
+                ),
+                "Game {i} didn't log its sync reply: {logged:?}"
+            );
+        }
     }
 
     #[test]
@@ -1,16 +1,42 @@
+use std::{borrow::Cow, collections::HashMap};
+
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    game::{Id, UtcDT},
+    game::{GameId, Id, UtcDT},
     game_state::PlayerPing,
     location::Location,
+    settings::GameSettings,
 };
 
+/// Why a player became a seeker
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+pub enum CatchReason {
+    /// The player was found by a seeker
+    #[default]
+    Caught,
+    /// The player gave up
+    Conceded,
+}
+
 /// An event used between players to update state
 #[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
 pub enum GameEvent {
-    /// A player has been caught and is now a seeker, contains the ID of the caught player
-    PlayerCaught(Id),
+    /// A player has been caught and is now a seeker
+    PlayerCaught {
+        /// ID of the caught player
+        player: Id,
+        /// ID of the seeker that caught them, [Option::None] if the player reported it themselves.
+        ///
+        /// Histories saved before this field existed won't have it, so default to
+        /// [Option::None] when deserializing.
+        #[serde(default)]
+        by: Option<Id>,
+        /// Why the player became a seeker, older histories will default to
+        /// [CatchReason::Caught]
+        #[serde(default)]
+        reason: CatchReason,
+    },
     /// Public ping from a player revealing location
     Ping(PlayerPing),
     /// Force the player specified in `0` to ping, optionally display the ping as from the user
@@ -21,4 +47,89 @@ pub enum GameEvent {
     /// Contains location history of the given player, used after the game to sync location
     /// histories
     PostGameSync(Id, Vec<(UtcDT, Location)>),
+    /// The host ended the game early, contains the ID of the host
+    ForceEnd(Id),
+    /// A powerup spawned at the given location. Players normally roll spawns locally with the
+    /// shared seed and this is only recorded in the event history for replays. With
+    /// [crate::GameSettings::powerup_near_hiders_radius_meters] set the host picks the location
+    /// and sends this to everyone.
+    PowerupSpawn(Location),
+    /// A player suspects they've missed events and is asking everyone to send a [GameEvent::FullSync],
+    /// contains the ID of the player requesting
+    RequestSync(Id),
+    /// Authoritative caught state and active pings of the sending player, sent in response to
+    /// [GameEvent::RequestSync]
+    FullSync(HashMap<Id, bool>, HashMap<Id, PlayerPing>),
+    /// A hider used a [crate::PowerUpType::FreezeSeekers], seekers are frozen until the given
+    /// time **in UTC**
+    SeekersFrozen(UtcDT),
+    /// The host changed settings mid-game, only settings that haven't taken effect yet may
+    /// differ from the current ones
+    SettingsAmendment(GameSettings),
+    /// A hider broke the given player out of jail, they're a hider again
+    PlayerFreed(Id),
+    /// Sent by the host to a spectator that just connected so they can start watching the game
+    SpectatorSync {
+        settings: GameSettings,
+        host: Id,
+        /// So the spectator saves the game's history under the same ID as everyone else
+        game_id: GameId,
+        /// When the game started **in UTC**, so the spectator's timers line up with the host's
+        game_started: UtcDT,
+        caught_state: HashMap<Id, bool>,
+        pings: HashMap<Id, PlayerPing>,
+    },
+    /// A player took back marking themselves caught shortly after doing it, they're a hider again
+    UncaughtCorrection {
+        /// ID of the player that's a hider again
+        player: Id,
+        /// The seeker that was credited with the catch, they lose the point
+        by: Option<Id>,
+    },
+    /// A player that dropped out picked the game back up from a [crate::GameSnapshot] on a new
+    /// connection. They were removed when they disconnected so this adds them back.
+    PlayerRejoined {
+        /// ID the player had in the game, not their new connection's ID
+        player: Id,
+        /// Whether they're a seeker
+        seeker: bool,
+    },
+    /// A seeker dropped a map pin for the other seekers with an optional label, only ever sent
+    /// to seekers, see [crate::Game::drop_seeker_pin]
+    SeekerPin(Location, Option<String>),
+}
+
+impl GameEvent {
+    /// This event with any pings it carries masked so they don't give away who really made them,
+    /// like [crate::Game::get_ui_state] does while the game is running
+    pub fn masked(&self) -> Cow<'_, Self> {
+        let mask_all = |pings: &HashMap<Id, PlayerPing>| {
+            pings
+                .iter()
+                .map(|(id, ping)| (*id, ping.masked()))
+                .collect::<HashMap<_, _>>()
+        };
+        match self {
+            Self::Ping(ping) if ping.spoofed => Cow::Owned(Self::Ping(ping.masked())),
+            Self::FullSync(caught_state, pings) if pings.values().any(|p| p.spoofed) => {
+                Cow::Owned(Self::FullSync(caught_state.clone(), mask_all(pings)))
+            }
+            Self::SpectatorSync {
+                settings,
+                host,
+                game_id,
+                game_started,
+                caught_state,
+                pings,
+            } if pings.values().any(|p| p.spoofed) => Cow::Owned(Self::SpectatorSync {
+                settings: settings.clone(),
+                host: *host,
+                game_id: *game_id,
+                game_started: *game_started,
+                caught_state: caught_state.clone(),
+                pings: mask_all(pings),
+            }),
+            _ => Cow::Borrowed(self),
+        }
+    }
 }
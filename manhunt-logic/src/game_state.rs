@@ -1,6 +1,6 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use chrono::Utc;
+use chrono::{TimeDelta, Utc};
 use rand::{
     RngExt, SeedableRng,
     distr::{Bernoulli, Distribution},
@@ -11,17 +11,19 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::{
-    game::{Id, UtcDT},
-    game_events::GameEvent,
+    game::{GameId, Id, UtcDT},
+    game_events::{CatchReason, GameEvent},
     location::Location,
     powerups::PowerUpType,
-    settings::{GameSettings, PingStartCondition},
+    profile::PlayerProfile,
+    settings::{GameSettings, PingStartCondition, PingVisibility},
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
 /// An on-map ping of a player
 pub struct PlayerPing {
-    /// Location of the ping
+    /// Location of the ping, if the player's GPS didn't give a heading it will be derived from
+    /// the direction they were moving
     loc: Location,
     /// Time the ping happened
     timestamp: UtcDT,
@@ -29,6 +31,10 @@ pub struct PlayerPing {
     pub display_player: Id,
     /// The actual player that initialized this ping
     pub real_player: Id,
+    /// Whether this ping shows someone other than who made it, e.g. a seeker pinged as a hider
+    /// with [PowerUpType::PingSeeker]. Only revealed after the game, see [PlayerPing::masked].
+    #[serde(default)]
+    pub spoofed: bool,
 }
 
 impl PlayerPing {
@@ -38,8 +44,128 @@ impl PlayerPing {
             display_player,
             real_player,
             timestamp: Utc::now(),
+            spoofed: display_player != real_player,
         }
     }
+
+    /// Copy of this ping that doesn't give away who really made it, for showing during the game
+    pub(crate) fn masked(&self) -> Self {
+        Self {
+            real_player: self.display_player,
+            spoofed: false,
+            ..self.clone()
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+/// A map pin a seeker dropped for the other seekers, see [crate::Game::drop_seeker_pin]
+pub struct SeekerPin {
+    pub location: Location,
+    /// What the pin is for, e.g. "checking the north park"
+    pub label: Option<String>,
+}
+
+/// Whether a seeker is getting closer to or further from a hider's last ping
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+pub enum ProximityTrend {
+    /// Not enough movement since the last ping to tell
+    Unknown,
+    /// Getting closer
+    Warmer,
+    /// Getting further away
+    Colder,
+}
+
+/// Whether global pings have begun, see [GameSettings::ping_start]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+pub enum PingPhase {
+    /// The start condition hasn't been met yet, nobody has been pinged
+    NotStarted,
+    /// Pings are happening every [GameSettings::ping_minutes_interval]
+    Active,
+}
+
+/// Which part of the game we're in, see [GameUiState::phase]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+pub enum GamePhase {
+    /// Seekers are locked while the hiders hide
+    Hiding,
+    /// Seekers have been released and are looking for hiders
+    Active,
+    /// The game is over, histories are being synced or have been
+    Ended,
+}
+
+/// How far a player needs to have moved between locations to derive a heading from them
+const MIN_HEADING_DISTANCE_METERS: f64 = 2.0;
+
+/// Fastest a player could plausibly move between two locations (about a fast bike ride), steps
+/// faster than this are GPS glitches and don't count towards [track_distance]
+const MAX_PLAUSIBLE_SPEED_METERS_PER_SECOND: f64 = 12.0;
+
+/// How far a location track covers in meters, steps faster than
+/// [MAX_PLAUSIBLE_SPEED_METERS_PER_SECOND] are left out
+fn track_distance(track: &[(UtcDT, Location)]) -> f64 {
+    track
+        .windows(2)
+        .map(|pair| {
+            let ((t1, from), (t2, to)) = (pair[0], pair[1]);
+            let distance = from.distance_to(&to);
+            // Updates less than a second apart get a second's worth of leeway
+            let seconds = ((t2 - t1).num_milliseconds() as f64 / 1000.0).max(1.0);
+            if distance / seconds > MAX_PLAUSIBLE_SPEED_METERS_PER_SECOND {
+                0.0
+            } else {
+                distance
+            }
+        })
+        .sum()
+}
+
+/// How far (in meters) a hider needs to move to not count as camping, more than GPS jitter
+const CAMPING_RADIUS_METERS: f64 = 20.0;
+
+/// How often a camping hider is pinged once they've been caught camping, see
+/// [GameSettings::reveal_after_minutes]
+const CAMPING_REVEAL_INTERVAL_MINUTES: i64 = 1;
+
+/// How far a seeker needs to move relative to a ping before the trend changes, keeps GPS jitter
+/// from flipping it back and forth
+const PROXIMITY_TREND_THRESHOLD_METERS: f64 = 10.0;
+
+/// How close (in meters) a hider needs to be to the jail to free the players in it
+const JAILBREAK_RADIUS_METERS: f64 = 15.0;
+
+/// How long a seeker has after a catch to make another one and keep their streak going
+const CATCH_STREAK_WINDOW_MINUTES: i64 = 5;
+
+/// How long a player has after marking themselves caught to take it back, after this the catch
+/// is locked in
+const UNDO_CATCH_WINDOW_SECONDS: i64 = 10;
+
+/// How long to wait for everyone's [GameEvent::PostGameSync] after the game ends, after this
+/// we finish without the histories that haven't arrived
+const POST_GAME_SYNC_TIMEOUT_SECONDS: i64 = 30;
+
+/// Room for this many events before the event history needs to grow, enough for a few pings per
+/// player over a typical game so it doesn't reallocate early on
+const EVENT_HISTORY_CAPACITY: usize = 256;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CatchStreak {
+    /// Number of catches in a row, each within [CATCH_STREAK_WINDOW_MINUTES] of the last
+    count: u32,
+    last_catch: UtcDT,
+}
+
+#[derive(Debug, Clone)]
+struct ProximityTracker {
+    /// Timestamp of the ping this is tracking, used to reset when a new ping comes in
+    ping_timestamp: UtcDT,
+    /// Distance to the ping last time the trend changed
+    reference_distance: f64,
+    trend: ProximityTrend,
 }
 
 #[derive(Debug, Clone)]
@@ -48,9 +174,18 @@ pub struct GameState {
     /// The id of this player in this game
     pub id: Id,
 
+    /// The id of the player that hosted the lobby
+    host: Id,
+
     /// The powerup the player is currently holding
     held_powerup: Option<PowerUpType>,
 
+    /// How many times we've revealed ourselves with [crate::Game::panic_ping]
+    panic_pings_used: u32,
+
+    /// Unique ID of this game, its history is saved under this
+    game_id: GameId,
+
     /// When the game started
     game_started: UtcDT,
 
@@ -64,15 +199,44 @@ pub struct GameState {
     /// When seekers were allowed to begin
     seekers_started: Option<UtcDT>,
 
+    /// Seekers are frozen until this time, set by a [PowerUpType::FreezeSeekers]
+    frozen_until: Option<UtcDT>,
+
     /// Last time we pinged all players
     last_global_ping: Option<UtcDT>,
 
+    /// Pings just began with [GameSettings::first_ping_immediate] on, ping on the next tick
+    /// instead of waiting out the interval
+    first_ping_pending: bool,
+
     /// Last time a powerup was spawned
     last_powerup_spawn: Option<UtcDT>,
 
     /// Hashmap tracking if a player is a seeker (true) or a hider (false)
     caught_state: HashMap<Id, bool>,
 
+    /// Players that started the game as seekers, sorted by ID
+    initial_seekers: Vec<Id>,
+
+    /// Caught players waiting in jail to be freed, only tracked if
+    /// [GameSettings::enable_jailbreak] is set
+    jailed: HashSet<Id>,
+
+    /// Number of players each seeker has caught, only tracked if
+    /// [GameSettings::track_catch_scores] is set
+    catches_by: HashMap<Id, u32>,
+
+    /// Active catch streaks for each seeker, only tracked if [GameSettings::track_catch_scores]
+    /// is set
+    catch_streaks: HashMap<Id, CatchStreak>,
+
+    /// The longest streak each seeker has had this game
+    max_streaks: HashMap<Id, u32>,
+
+    /// Warmer/colder trends for each hider relative to their last ping, only tracked for seekers
+    /// if [GameSettings::hot_cold_hints] is set
+    proximity_trends: HashMap<Id, ProximityTracker>,
+
     /// A map of the latest global ping results for each player
     pings: HashMap<Id, PlayerPing>,
 
@@ -92,35 +256,222 @@ pub struct GameState {
 
     /// A seed with a shared value between all players, should be reproducible
     /// RNG for use in stuff like powerup location selection.
+    ///
+    /// Only choices every player makes on their own at the same time (powerup spawn rolls and
+    /// locations) may draw from this, each draw advances [GameState::shared_random_state] so a
+    /// choice made by only one player would put them out of step with everyone else. Choices one
+    /// player makes and then sends in an event (powerup targets, which powerup was grabbed) use
+    /// [rand::rng] instead.
     shared_random_increment: i64,
 
     /// State for [ChaCha20Rng] to be used and added to when performing shared RNG operations
     shared_random_state: u64,
+
+    /// Whether we're only watching the game, spectators aren't in `caught_state`, never ping,
+    /// and don't send a location history at the end. A host that isn't playing (see
+    /// [GameSettings::host_plays]) is a spectator too.
+    spectator: bool,
+
+    /// When we last marked ourselves caught and the seeker that caught us, kept until
+    /// [UNDO_CATCH_WINDOW_SECONDS] pass so a mis-tap can be undone
+    pending_catch: Option<(UtcDT, Option<Id>)>,
+
+    /// Whether our latest location is outside [GameSettings::play_area]
+    out_of_bounds: bool,
+
+    /// When we were last pinged for camping, [Option::None] if we aren't camping. See
+    /// [GameSettings::reveal_after_minutes].
+    last_camping_reveal: Option<UtcDT>,
+
+    /// Pins seekers dropped for each other, hiders never keep these
+    seeker_pins: Vec<SeekerPin>,
+
+    /// Players that said they were leaving before disconnecting, see
+    /// [crate::TransportMessage::PeerLeaving]
+    left_players: HashSet<Id>,
+
+    /// When players whose connection dropped disconnected, they stay in the game until
+    /// [GameSettings::disconnect_grace_seconds] passes in case they come back
+    disconnected: HashMap<Id, UtcDT>,
+
+    /// Connection IDs of players that came back on a new connection with
+    /// [GameEvent::PlayerRejoined], everyone else's connection ID is their ID in the game
+    connections: HashMap<Id, Id>,
 }
 
 impl GameState {
-    pub fn new(settings: GameSettings, my_id: Id, initial_caught_state: HashMap<Id, bool>) -> Self {
+    pub fn new(
+        settings: GameSettings,
+        my_id: Id,
+        host: Id,
+        initial_caught_state: HashMap<Id, bool>,
+    ) -> Self {
         let mut rand = ChaCha20Rng::seed_from_u64(settings.random_seed as u64);
         let increment = rand.random_range(-100..100);
 
+        let mut initial_seekers = initial_caught_state
+            .iter()
+            .filter_map(|(id, seeker)| seeker.then_some(*id))
+            .collect::<Vec<_>>();
+        initial_seekers.sort();
+
+        // Anyone without a team (e.g. a host that's only refereeing) just watches
+        let spectator = !initial_caught_state.contains_key(&my_id);
+
         Self {
             id: my_id,
+            host,
+            game_id: Uuid::new_v4(),
             game_started: Utc::now(),
-            event_history: Vec::with_capacity(15),
+            event_history: Vec::with_capacity(EVENT_HISTORY_CAPACITY),
             game_ended: None,
             seekers_started: None,
+            frozen_until: None,
             pings: HashMap::with_capacity(initial_caught_state.len()),
             player_histories: HashMap::from_iter(initial_caught_state.keys().map(|id| (*id, None))),
             caught_state: initial_caught_state,
+            initial_seekers,
+            jailed: HashSet::new(),
+            catches_by: HashMap::new(),
+            catch_streaks: HashMap::new(),
+            max_streaks: HashMap::new(),
+            proximity_trends: HashMap::new(),
             available_powerup: None,
             powerup_bernoulli: settings.get_powerup_bernoulli(),
             shared_random_state: settings.random_seed as u64,
             settings,
             last_global_ping: None,
+            first_ping_pending: false,
             last_powerup_spawn: None,
             location_history: Vec::with_capacity(30),
             held_powerup: None,
+            panic_pings_used: 0,
             shared_random_increment: increment,
+            spectator,
+            pending_catch: None,
+            out_of_bounds: false,
+            last_camping_reveal: None,
+            seeker_pins: Vec::new(),
+            left_players: HashSet::new(),
+            disconnected: HashMap::new(),
+            connections: HashMap::new(),
+        }
+    }
+
+    /// Create the state for a spectator from a [GameEvent::SpectatorSync] sent by the host
+    pub fn new_spectator(
+        settings: GameSettings,
+        my_id: Id,
+        host: Id,
+        game_id: GameId,
+        game_started: UtcDT,
+        caught_state: HashMap<Id, bool>,
+        pings: HashMap<Id, PlayerPing>,
+    ) -> Self {
+        let mut state = Self::new(settings, my_id, host, caught_state);
+        state.game_id = game_id;
+        state.game_started = game_started;
+        state.spectator = true;
+        state.merge_full_sync(HashMap::new(), pings);
+        state
+    }
+
+    /// Use the [GameId] the host picked for this game instead of our own, see
+    /// [StartGameInfo::game_id](crate::StartGameInfo::game_id)
+    pub fn set_game_id(&mut self, game_id: GameId) {
+        self.game_id = game_id;
+    }
+
+    /// Whether we're only watching the game
+    pub fn is_spectator(&self) -> bool {
+        self.spectator
+    }
+
+    /// Pick a game back up from a [GameSnapshot], see [GameState::snapshot]
+    pub fn from_snapshot(snapshot: GameSnapshot) -> Self {
+        let mut state = Self::new(
+            snapshot.settings,
+            snapshot.id,
+            snapshot.host,
+            snapshot.caught_state,
+        );
+        state.initial_seekers = snapshot.initial_seekers;
+        state.held_powerup = snapshot.held_powerup;
+        state.panic_pings_used = snapshot.panic_pings_used;
+        state.game_id = snapshot.game_id;
+        state.game_started = snapshot.game_started;
+        state.seekers_started = snapshot.seekers_started;
+        state.frozen_until = snapshot.frozen_until;
+        state.last_global_ping = snapshot.last_global_ping;
+        state.first_ping_pending = snapshot.first_ping_pending;
+        state.last_powerup_spawn = snapshot.last_powerup_spawn;
+        state.jailed = snapshot.jailed;
+        state.catches_by = snapshot.catches_by;
+        state.catch_streaks = snapshot.catch_streaks;
+        state.max_streaks = snapshot.max_streaks;
+        state.pings = snapshot.pings;
+        state.seeker_pins = snapshot.seeker_pins;
+        state.available_powerup = snapshot.available_powerup;
+        state.event_history = snapshot.event_history;
+        state.location_history = snapshot.location_history;
+        state.shared_random_increment = snapshot.shared_random_increment;
+        state.shared_random_state = snapshot.shared_random_state;
+        state
+    }
+
+    /// Save everything needed to pick this game back up if the app closes mid-game, only meant
+    /// to be taken before the game ends. Anything that's quick to work out again (e.g.
+    /// warmer/colder trends) is left out.
+    pub fn snapshot(&self) -> GameSnapshot {
+        GameSnapshot {
+            id: self.id,
+            host: self.host,
+            held_powerup: self.held_powerup,
+            panic_pings_used: self.panic_pings_used,
+            game_id: self.game_id,
+            game_started: self.game_started,
+            seekers_started: self.seekers_started,
+            frozen_until: self.frozen_until,
+            last_global_ping: self.last_global_ping,
+            first_ping_pending: self.first_ping_pending,
+            last_powerup_spawn: self.last_powerup_spawn,
+            caught_state: self.caught_state.clone(),
+            initial_seekers: self.initial_seekers.clone(),
+            jailed: self.jailed.clone(),
+            catches_by: self.catches_by.clone(),
+            catch_streaks: self.catch_streaks.clone(),
+            max_streaks: self.max_streaks.clone(),
+            pings: self.pings.clone(),
+            seeker_pins: self.seeker_pins.clone(),
+            available_powerup: self.available_powerup,
+            event_history: self.event_history.clone(),
+            settings: self.settings.clone(),
+            location_history: self.location_history.clone(),
+            shared_random_increment: self.shared_random_increment,
+            shared_random_state: self.shared_random_state,
+        }
+    }
+
+    /// Add back a player that was removed when they disconnected, see
+    /// [GameEvent::PlayerRejoined]
+    pub fn rejoin_player(&mut self, id: Id, seeker: bool) {
+        self.caught_state.insert(id, seeker);
+        self.left_players.remove(&id);
+        self.disconnected.remove(&id);
+        if self.game_ended.is_none() {
+            self.player_histories.entry(id).or_insert(None);
+        }
+    }
+
+    /// Create a [GameEvent::SpectatorSync] for a spectator that just connected
+    pub fn create_spectator_sync(&self) -> GameEvent {
+        GameEvent::SpectatorSync {
+            settings: self.settings.clone(),
+            host: self.host,
+            game_id: self.game_id,
+            game_started: self.game_started,
+            caught_state: self.caught_state.clone(),
+            pings: self.pings.clone(),
         }
     }
 
@@ -137,18 +488,70 @@ impl GameState {
     /// Spawn a powerup on the map, this **MUST** be called on all players at about the same time.
     /// First rolls to see if we will spawn one with `chance` (chance is percent chance out of 100).
     /// If the roll succeeds, spawn a powerup at one of the given locations.
-    pub fn try_spawn_powerup(&mut self, now: UtcDT) {
+    /// When [GameSettings::powerup_near_hiders_radius_meters] is set only the host places the
+    /// powerup, near the hiders, and needs to send it to everyone else as a
+    /// [GameEvent::PowerupSpawn].
+    /// Returns where a powerup was actually placed on the map, if anywhere.
+    pub fn try_spawn_powerup(&mut self, now: UtcDT) -> Option<Location> {
         let mut shared_rand = self.create_rand_from_shared_seed();
         let roll = self.powerup_bernoulli.sample(&mut shared_rand);
-        if roll {
-            let choice = self
-                .settings
+        if !roll {
+            return None;
+        }
+
+        self.last_powerup_spawn = Some(now);
+
+        let choice = match self.settings.powerup_near_hiders_radius_meters {
+            // Everyone else waits for the host to send where it spawned
+            Some(_) if !self.is_host() => return None,
+            Some(radius) => self.random_location_near_hiders(radius),
+            None => None,
+        }
+        .or_else(|| {
+            self.settings
                 .powerup_locations
                 .choose(&mut shared_rand)
-                .cloned();
-            self.available_powerup = choice;
-            self.last_powerup_spawn = Some(now);
+                .cloned()
+        });
+
+        self.available_powerup = choice;
+        if let Some(location) = choice {
+            self.event_history
+                .push((now, GameEvent::PowerupSpawn(location)));
         }
+        choice
+    }
+
+    /// Whether powerups spawn near the hiders and are sent by the host, see
+    /// [GameSettings::powerup_near_hiders_radius_meters]
+    pub fn spawns_powerups_near_hiders(&self) -> bool {
+        self.settings.powerup_near_hiders_radius_meters.is_some()
+    }
+
+    /// Place a powerup the host spawned near the hiders
+    pub fn place_powerup(&mut self, now: UtcDT, location: Location) {
+        self.available_powerup = Some(location);
+        self.last_powerup_spawn = Some(now);
+    }
+
+    /// Average location of the last pings of everyone still hiding, [Option::None] if none of
+    /// them have been pinged
+    pub fn hider_centroid(&self) -> Option<Location> {
+        Location::centroid(
+            self.pings
+                .values()
+                .filter(|ping| self.caught_state.get(&ping.real_player) == Some(&false))
+                .map(|ping| &ping.loc),
+        )
+    }
+
+    /// Pick a uniformly random point within `radius` meters of [GameState::hider_centroid]
+    fn random_location_near_hiders(&self, radius: f64) -> Option<Location> {
+        let center = self.hider_centroid()?;
+        let mut rand = rand::rng();
+        let distance = radius * rand.random::<f64>().sqrt();
+        let angle = rand.random_range(0.0..std::f64::consts::TAU);
+        Some(center.offset_meters(distance * angle.cos(), distance * angle.sin()))
     }
 
     fn minutes_since_seekers_released(&self, now: UtcDT) -> Option<u32> {
@@ -161,28 +564,60 @@ impl GameState {
         self.last_global_ping.is_some()
     }
 
-    pub fn should_start_pings(&self, now: UtcDT) -> bool {
-        match self.settings.ping_start {
-            PingStartCondition::Players(num) => (self.iter_seekers().count() as u32) >= num,
+    /// Whether the given start condition has been met, shared by pings and powerups so each only
+    /// ever looks at its own condition
+    fn start_condition_met(&self, condition: &PingStartCondition, now: UtcDT) -> bool {
+        match condition {
+            PingStartCondition::Players(num) => (self.iter_seekers().count() as u32) >= *num,
             PingStartCondition::Minutes(minutes) => self
                 .minutes_since_seekers_released(now)
-                .is_some_and(|seekers_released| seekers_released >= minutes),
+                .is_some_and(|seekers_released| seekers_released >= *minutes),
             PingStartCondition::Instant => true,
         }
     }
 
+    /// Whether to start pinging, based on [GameSettings::ping_start]
+    pub fn should_start_pings(&self, now: UtcDT) -> bool {
+        self.start_condition_met(&self.settings.ping_start, now)
+    }
+
+    /// Which part of the game we're in
+    pub fn phase(&self) -> GamePhase {
+        if self.game_ended() {
+            GamePhase::Ended
+        } else if self.seekers_released() {
+            GamePhase::Active
+        } else {
+            GamePhase::Hiding
+        }
+    }
+
+    /// Whether the local player is on the team that gets pinged, hiders unless
+    /// [GameSettings::reverse_pings] is on
+    pub fn on_pinged_team(&self) -> bool {
+        !self.spectator && self.is_seeker() == self.settings.reverse_pings
+    }
+
     /// Whether enough time has passed that we should perform a ping
     pub fn should_ping(&self, now: &UtcDT) -> bool {
-        !self.is_seeker()
+        self.on_pinged_team()
             && self.last_global_ping.as_ref().is_some_and(|last_ping| {
                 let minutes = (*now - *last_ping).num_minutes().unsigned_abs();
-                minutes >= (self.settings.ping_minutes_interval as u64)
+                self.first_ping_pending || minutes >= (self.settings.ping_minutes_interval as u64)
             })
     }
 
-    /// Begin pinging, will start the countdown for global pings. Also refreshes the timeout
+    /// Begin pinging once [GameState::should_start_pings] says so. The first ping is on the next
+    /// tick if [GameSettings::first_ping_immediate] is on, otherwise after one interval.
+    pub fn begin_pings(&mut self, now: UtcDT) {
+        self.last_global_ping = Some(now);
+        self.first_ping_pending = self.settings.first_ping_immediate;
+    }
+
+    /// We just pinged, restart the countdown to the next ping
     pub fn start_pings(&mut self, now: UtcDT) {
         self.last_global_ping = Some(now);
+        self.first_ping_pending = false;
     }
 
     /// Begin spawning powerups
@@ -190,19 +625,10 @@ impl GameState {
         self.last_powerup_spawn = Some(now);
     }
 
-    /// Whether to start spawning powerups
+    /// Whether to start spawning powerups, based on [GameSettings::powerup_start]
     pub fn should_start_powerups(&self, now: UtcDT) -> bool {
-        if self.settings.powerup_locations.is_empty() {
-            false
-        } else {
-            match self.settings.powerup_start {
-                PingStartCondition::Players(num) => (self.iter_seekers().count() as u32) >= num,
-                PingStartCondition::Minutes(mins) => self
-                    .minutes_since_seekers_released(now)
-                    .is_some_and(|seekers_released| seekers_released >= mins),
-                PingStartCondition::Instant => true,
-            }
-        }
+        (!self.settings.powerup_locations.is_empty() || self.spawns_powerups_near_hiders())
+            && self.start_condition_met(&self.settings.powerup_start, now)
     }
 
     pub fn powerups_started(&self) -> bool {
@@ -227,6 +653,12 @@ impl GameState {
         self.available_powerup = None;
     }
 
+    /// When seekers will be released, based on when the game started and
+    /// [GameSettings::hiding_time_seconds]
+    pub fn seekers_release_at(&self) -> UtcDT {
+        self.game_started + TimeDelta::seconds(self.settings.hiding_time_seconds as i64)
+    }
+
     pub fn should_release_seekers(&self, now: UtcDT) -> bool {
         let seconds = (now - self.game_started).num_seconds().unsigned_abs();
         seconds >= (self.settings.hiding_time_seconds as u64)
@@ -242,11 +674,73 @@ impl GameState {
         self.seekers_started.is_some()
     }
 
+    /// Freeze seekers until the given time, extends any freeze already in effect
+    pub fn freeze_seekers(&mut self, until: UtcDT) {
+        self.frozen_until = Some(
+            self.frozen_until
+                .map_or(until, |current| current.max(until)),
+        );
+    }
+
+    /// Create the end time for a freeze starting now
+    pub fn freeze_end(&self, now: UtcDT) -> UtcDT {
+        now + TimeDelta::seconds(self.settings.freeze_seconds as i64)
+    }
+
+    /// If seekers are currently frozen
+    pub fn seekers_frozen(&self, now: UtcDT) -> bool {
+        self.frozen_until.is_some_and(|until| now < until)
+    }
+
+    /// Clear the freeze if it's over, returns whether it was cleared
+    pub fn check_freeze_expired(&mut self, now: UtcDT) -> bool {
+        if self.frozen_until.is_some() && !self.seekers_frozen(now) {
+            self.frozen_until = None;
+            true
+        } else {
+            false
+        }
+    }
+
     /// Add a ping for a specific player
     pub fn add_ping(&mut self, ping: PlayerPing) {
         self.pings.insert(ping.display_player, ping);
     }
 
+    /// Add a pin from another seeker, ignored if we're not a seeker
+    pub fn add_seeker_pin(&mut self, location: Location, label: Option<String>) {
+        if self.is_seeker() {
+            self.seeker_pins.push(SeekerPin { location, label });
+        }
+    }
+
+    /// Remove pings older than [GameSettings::ping_expiry_seconds], returns whether any were
+    /// removed
+    pub fn expire_pings(&mut self, now: UtcDT) -> bool {
+        let Some(expiry) = self.settings.ping_expiry_seconds else {
+            return false;
+        };
+        let before = self.pings.len();
+        let max_age = TimeDelta::seconds(expiry as i64);
+        self.pings.retain(|_, ping| now - ping.timestamp <= max_age);
+        self.pings.len() != before
+    }
+
+    /// Pins from the other seekers, always empty for hiders
+    pub fn seeker_pins(&self) -> Vec<SeekerPin> {
+        if self.is_seeker() {
+            self.seeker_pins.clone()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Whether we're waiting on a player to reconnect
+    #[cfg(test)]
+    pub fn is_disconnected(&self, player: Id) -> bool {
+        self.disconnected.contains_key(&player)
+    }
+
     /// Get a ping for a player
     #[cfg(test)]
     pub fn get_ping(&self, player: Id) -> Option<&PlayerPing> {
@@ -258,20 +752,44 @@ impl GameState {
         self.player_histories.insert(id, Some(history));
     }
 
-    /// Check if we've complete the post-game sync
-    pub fn check_post_game_sync(&self) -> bool {
-        self.game_ended() && self.player_histories.values().all(Option::is_some)
+    /// Check if we've completed the post-game sync, or given up waiting on players that never
+    /// sent their history after [POST_GAME_SYNC_TIMEOUT_SECONDS]
+    pub fn check_post_game_sync(&self, now: UtcDT) -> bool {
+        self.game_ended.is_some_and(|ended| {
+            self.player_histories.values().all(Option::is_some)
+                || now - ended >= TimeDelta::seconds(POST_GAME_SYNC_TIMEOUT_SECONDS)
+        })
+    }
+
+    /// Check if the game should be ended (due to all players being caught), returns the
+    /// [GameEvent::PostGameSync] to send out if the game ended just now
+    pub fn check_end_game(&mut self) -> Option<GameEvent> {
+        if !self.game_ended() && self.caught_state.values().all(|v| *v) {
+            Some(self.end_game())
+        } else {
+            None
+        }
     }
 
-    /// Check if the game should be ended (due to all players being caught)
-    pub fn check_end_game(&mut self) -> bool {
-        let should_end = self.caught_state.values().all(|v| *v);
-        if should_end {
-            self.game_ended = Some(Utc::now());
-            self.player_histories
-                .insert(self.id, Some(self.location_history.clone()));
+    /// End the game and begin post-game sync, returns the [GameEvent::PostGameSync] containing
+    /// our location history to send to everyone else. Our location history is moved out of
+    /// [GameState::location_history] so it's only copied once.
+    pub fn end_game(&mut self) -> GameEvent {
+        self.game_ended = Some(Utc::now());
+        let history = std::mem::take(&mut self.location_history);
+        if !self.spectator {
+            self.player_histories.insert(self.id, Some(history.clone()));
         }
-        should_end
+        GameEvent::PostGameSync(self.id, history)
+    }
+
+    pub fn is_host(&self) -> bool {
+        self.id == self.host
+    }
+
+    /// Check if the given player is the host
+    pub fn is_player_host(&self, player: Id) -> bool {
+        player == self.host
     }
 
     pub fn game_ended(&self) -> bool {
@@ -290,11 +808,10 @@ impl GameState {
             .filter_map(|(k, v)| if *v { Some(*k) } else { None })
     }
 
-    /// Pick a random seeker
+    /// Pick a random seeker, only the caller picks so this doesn't use the shared RNG. Send the
+    /// choice to everyone else in an event.
     pub fn random_seeker(&mut self) -> Option<Id> {
-        let seekers = self.iter_seekers().collect::<Vec<_>>();
-        let mut rand = rand::rng();
-        seekers.choose(&mut rand).copied()
+        self.iter_seekers().choose(&mut rand::rng())
     }
 
     /// Iterate over all hiders in the game
@@ -304,11 +821,18 @@ impl GameState {
             .filter_map(|(k, v)| if !*v { Some(*k) } else { None })
     }
 
+    /// Players on the team that gets pinged, see [GameState::on_pinged_team]
+    pub fn iter_pinged_team(&self) -> impl Iterator<Item = Id> + use<'_> {
+        self.caught_state
+            .iter()
+            .filter_map(|(k, v)| (*v == self.settings.reverse_pings).then_some(*k))
+    }
+
+    /// Pick a random hider that isn't us, see [GameState::random_seeker]
     pub fn random_other_hider(&self) -> Option<Id> {
-        let mut rand = rand::rng();
         self.iter_hiders()
             .filter(|id| *id != self.id)
-            .choose(&mut rand)
+            .choose(&mut rand::rng())
     }
 
     /// Create a [PlayerPing] with the latest location saved for the player
@@ -316,16 +840,141 @@ impl GameState {
         self.create_ping(self.id)
     }
 
+    /// How many more times we can reveal ourselves with [crate::Game::panic_ping], always 0 for
+    /// seekers and spectators
+    pub fn panic_pings_left(&self) -> u32 {
+        if self.spectator || self.is_seeker() {
+            0
+        } else {
+            self.settings
+                .panic_pings
+                .saturating_sub(self.panic_pings_used)
+        }
+    }
+
+    /// Use up one of our panic pings, returns the ping to send out. [Option::None] if we don't
+    /// have any left or don't know where we are, in which case nothing is used up.
+    pub fn use_panic_ping(&mut self) -> Option<PlayerPing> {
+        if self.panic_pings_left() == 0 {
+            return None;
+        }
+        let ping = self.create_self_ping()?;
+        self.panic_pings_used += 1;
+        self.add_ping(ping.clone());
+        Some(ping)
+    }
+
     /// Create a [PlayerPing] with the latest location as another player
     pub fn create_ping(&self, id: Id) -> Option<PlayerPing> {
-        self.get_loc().map(|loc| PlayerPing::new(*loc, id, self.id))
+        self.get_loc_with_heading()
+            .map(|loc| PlayerPing::new(loc, id, self.id))
+    }
+
+    /// Get the latest player location, if GPS didn't give us a heading, derive one from the
+    /// direction we moved between the last two locations
+    fn get_loc_with_heading(&self) -> Option<Location> {
+        let mut loc = *self.get_loc()?;
+
+        // Don't derive a heading from GPS jitter while standing still
+        if loc.heading.is_none()
+            && let [.., (_, prev), _] = self.location_history.as_slice()
+            && prev.distance_to(&loc) >= MIN_HEADING_DISTANCE_METERS
+        {
+            loc.heading = Some(prev.bearing_to(&loc));
+        }
+
+        Some(loc)
+    }
+
+    /// Remember that a player is now on a different connection than the one they started the
+    /// game on, see [GameState::connection_player]
+    pub fn set_connection(&mut self, player: Id, connection: Id) {
+        if player == connection {
+            self.connections.remove(&player);
+        } else {
+            self.connections.insert(player, connection);
+        }
+    }
+
+    /// The connection a player can currently be reached on
+    pub fn player_connection(&self, player: Id) -> Id {
+        self.connections.get(&player).copied().unwrap_or(player)
+    }
+
+    /// The player on the other end of a connection, or [None] if it's an old connection of a
+    /// player that has since rejoined on a new one
+    pub fn connection_player(&self, connection: Id) -> Option<Id> {
+        if let Some((player, _)) = self.connections.iter().find(|(_, c)| **c == connection) {
+            Some(*player)
+        } else if self.connections.contains_key(&connection) {
+            None
+        } else {
+            Some(connection)
+        }
+    }
+
+    /// A player left the game on purpose, they're removed like with [GameState::remove_player]
+    /// but remembered so the UI can tell them apart from players that dropped out
+    pub fn player_left(&mut self, id: Id) {
+        if self.caught_state.contains_key(&id) {
+            self.left_players.insert(id);
+        }
+        self.remove_player(id);
+    }
+
+    /// A player's connection dropped without them saying they were leaving. They're kept in the
+    /// game for [GameSettings::disconnect_grace_seconds] in case they reconnect, unless the game
+    /// is already over. Returns whether they were removed right away.
+    pub fn player_disconnected(&mut self, id: Id, now: UtcDT) -> bool {
+        if self.settings.disconnect_grace_seconds == 0
+            || self.game_ended.is_some()
+            || !self.caught_state.contains_key(&id)
+        {
+            self.remove_player(id);
+            true
+        } else {
+            self.disconnected.entry(id).or_insert(now);
+            false
+        }
+    }
+
+    /// A disconnected player came back on the same connection, returns whether they were
+    /// waiting to be removed
+    pub fn player_reconnected(&mut self, id: Id) -> bool {
+        self.disconnected.remove(&id).is_some()
+    }
+
+    /// Remove disconnected players that didn't come back within
+    /// [GameSettings::disconnect_grace_seconds], or all of them once the game is over since
+    /// there's nothing left to come back to. Returns whether anyone was removed.
+    pub fn expire_disconnects(&mut self, now: UtcDT) -> bool {
+        let grace = TimeDelta::seconds(self.settings.disconnect_grace_seconds as i64);
+        let ended = self.game_ended.is_some();
+        let expired = self
+            .disconnected
+            .iter()
+            .filter(|(_, at)| ended || now - **at >= grace)
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+
+        for id in expired.iter() {
+            self.remove_player(*id);
+        }
+
+        !expired.is_empty()
     }
 
     /// Remove a player from the game by their ID number
     pub fn remove_player(&mut self, id: Id) {
+        self.disconnected.remove(&id);
+        self.connections.remove(&id);
         self.pings.remove(&id);
         self.caught_state.remove(&id);
-        self.player_histories.remove(&id);
+        self.jailed.remove(&id);
+        // Keep histories that already came in, players disconnect once they have everyone's
+        if self.player_histories.get(&id).is_some_and(Option::is_none) {
+            self.player_histories.remove(&id);
+        }
     }
 
     /// Player has gotten a powerup, rolls to see which powerup and stores it
@@ -334,6 +983,27 @@ impl GameState {
         // TODO: Seekers vs Hiders, Weights?
         let choice = PowerUpType::ALL_TYPES.choose(&mut rand).copied();
         self.held_powerup = choice;
+        // We don't receive our own despawn event, record it here so replays know we grabbed it
+        self.event_history
+            .push((Utc::now(), GameEvent::PowerupDespawn(self.id)));
+    }
+
+    /// Check if we're close enough to the available powerup to grab it, always true if
+    /// [GameSettings::powerup_grab_radius_meters] isn't set
+    pub fn in_powerup_range(&self) -> bool {
+        let Some(radius) = self.settings.powerup_grab_radius_meters else {
+            return true;
+        };
+
+        match (self.available_powerup, self.get_loc()) {
+            (Some(powerup), Some(loc)) => loc.distance_to(&powerup) <= radius,
+            _ => false,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn force_set_available_powerup(&mut self, location: Option<Location>) {
+        self.available_powerup = location;
     }
 
     pub fn force_set_powerup(&mut self, powerup_type: PowerUpType) {
@@ -354,69 +1024,1005 @@ impl GameState {
         self.location_history.push((Utc::now(), loc));
     }
 
+    /// How far we've moved this game in meters according to our location history, see
+    /// [track_distance]
+    pub fn distance_traveled(&self) -> f64 {
+        // Our history moves into player_histories when the game ends
+        let track = match self.player_histories.get(&self.id) {
+            Some(Some(history)) => history,
+            _ => &self.location_history,
+        };
+
+        track_distance(track)
+    }
+
     /// Get the latest player location
     fn get_loc(&self) -> Option<&Location> {
         self.location_history.last().map(|(_, l)| l)
     }
 
-    /// Mark a player as caught
-    pub fn mark_caught(&mut self, player: Id) {
-        if let Some(caught) = self.caught_state.get_mut(&player) {
-            *caught = true;
+    /// Latest location we know of for a player, our own location for us and their ping for
+    /// anyone else
+    fn latest_known_loc(&self, player: Id) -> Option<&Location> {
+        if player == self.id {
+            self.get_loc()
+        } else {
+            self.pings.get(&player).map(|ping| &ping.loc)
         }
     }
 
-    /// Gets if a player was caught or not
-    #[cfg(test)]
-    pub fn get_caught(&self, player: Id) -> Option<bool> {
-        self.caught_state.get(&player).copied()
+    /// Whether a location is inside any of [GameSettings::safe_zones]
+    fn in_safe_zone(&self, loc: &Location) -> bool {
+        self.settings
+            .safe_zones
+            .iter()
+            .any(|(center, radius)| loc.distance_to(center) <= *radius)
     }
 
-    pub fn is_seeker(&self) -> bool {
-        self.caught_state.get(&self.id).copied().unwrap_or_default()
+    /// Whether we're a hider that's stayed within [CAMPING_RADIUS_METERS] of our latest location
+    /// for the last [GameSettings::reveal_after_minutes], only time after seekers were released
+    /// counts
+    fn is_camping(&self, now: UtcDT) -> bool {
+        let Some(minutes) = self.settings.reveal_after_minutes else {
+            return false;
+        };
+        if self.spectator || self.is_seeker() {
+            return false;
+        }
+
+        let since = now - TimeDelta::minutes(minutes as i64);
+        if self.seekers_started.is_none_or(|started| started > since) {
+            return false;
+        }
+
+        let Some(current) = self.get_loc() else {
+            return false;
+        };
+
+        // Walk back until we either moved away or have gone back far enough
+        for (time, loc) in self.location_history.iter().rev() {
+            if loc.distance_to(current) > CAMPING_RADIUS_METERS {
+                return false;
+            }
+            if *time <= since {
+                return true;
+            }
+        }
+
+        // Not enough history to tell, e.g. we just resumed
+        false
     }
 
-    pub fn as_game_history(&self) -> GameHistory {
-        GameHistory {
-            my_id: self.id,
-            events: self.event_history.clone(),
-            locations: self
-                .player_histories
-                .iter()
-                .map(|(id, history)| (*id, history.as_ref().cloned().unwrap_or_default()))
-                .collect(),
-            game_started: self.game_started,
-            game_ended: self.game_ended.unwrap_or_default(),
+    /// Check if we should be pinged for camping, at most once every
+    /// [CAMPING_REVEAL_INTERVAL_MINUTES] while we stay put
+    pub fn should_reveal_for_camping(&mut self, now: UtcDT) -> bool {
+        if !self.is_camping(now) {
+            self.last_camping_reveal = None;
+            return false;
+        }
+
+        if self
+            .last_camping_reveal
+            .is_some_and(|last| now - last < TimeDelta::minutes(CAMPING_REVEAL_INTERVAL_MINUTES))
+        {
+            return false;
         }
+
+        self.last_camping_reveal = Some(now);
+        true
     }
 
-    pub fn as_ui_state(&self) -> GameUiState {
-        GameUiState {
-            my_id: self.id,
-            caught_state: self.caught_state.clone(),
-            available_powerup: self.available_powerup,
-            pings: self.pings.clone(),
-            game_started: self.game_started,
-            game_ended: self.game_ended,
-            last_global_ping: self.last_global_ping,
-            last_powerup_spawn: self.last_powerup_spawn,
-            held_powerup: self.held_powerup,
-            seekers_started: self.seekers_started,
+    /// Whether we're being pinged for camping right now
+    pub fn revealed_for_camping(&self) -> bool {
+        self.last_camping_reveal.is_some()
+    }
+
+    /// Check our latest location against [GameSettings::play_area], returns whether we're out of
+    /// bounds if that changed
+    pub fn update_out_of_bounds(&mut self) -> Option<bool> {
+        let out_of_bounds = match (self.settings.play_area.as_ref(), self.get_loc()) {
+            (Some(area), Some(loc)) => !loc.in_polygon(area),
+            _ => false,
+        };
+        if out_of_bounds != self.out_of_bounds {
+            self.out_of_bounds = out_of_bounds;
+            Some(out_of_bounds)
+        } else {
+            None
         }
     }
 
-    pub fn clone_settings(&self) -> GameSettings {
-        self.settings.clone()
+    /// Whether a hider can be caught right now, they can't while in a safe zone. Players we don't
+    /// know the location of are assumed to be catchable.
+    pub fn is_catchable(&self, player: Id) -> bool {
+        !self
+            .latest_known_loc(player)
+            .is_some_and(|loc| self.in_safe_zone(loc))
     }
-}
 
-#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
-pub struct GameHistory {
+    pub fn should_update_proximity_trends(&self) -> bool {
+        self.settings.hot_cold_hints && self.is_seeker()
+    }
+
+    /// Update warmer/colder trends for each hider based on our latest location and their latest
+    /// ping. Returns whether any trend changed.
+    pub fn update_proximity_trends(&mut self) -> bool {
+        let Some(loc) = self.get_loc().copied() else {
+            return false;
+        };
+
+        let hider_pings = self
+            .pings
+            .values()
+            .filter(|p| {
+                !self
+                    .caught_state
+                    .get(&p.display_player)
+                    .copied()
+                    .unwrap_or(true)
+            })
+            .map(|p| (p.display_player, p.loc, p.timestamp))
+            .collect::<Vec<_>>();
+
+        let mut changed = false;
+
+        self.proximity_trends
+            .retain(|id, _| hider_pings.iter().any(|(hider, _, _)| hider == id));
+
+        for (hider, ping_loc, ping_timestamp) in hider_pings {
+            let distance = loc.distance_to(&ping_loc);
+
+            let tracker = self
+                .proximity_trends
+                .entry(hider)
+                .or_insert(ProximityTracker {
+                    ping_timestamp,
+                    reference_distance: distance,
+                    trend: ProximityTrend::Unknown,
+                });
+
+            // A fresh ping means we start over
+            if tracker.ping_timestamp != ping_timestamp {
+                changed |= tracker.trend != ProximityTrend::Unknown;
+                *tracker = ProximityTracker {
+                    ping_timestamp,
+                    reference_distance: distance,
+                    trend: ProximityTrend::Unknown,
+                };
+                continue;
+            }
+
+            let new_trend =
+                if distance < tracker.reference_distance - PROXIMITY_TREND_THRESHOLD_METERS {
+                    Some(ProximityTrend::Warmer)
+                } else if distance > tracker.reference_distance + PROXIMITY_TREND_THRESHOLD_METERS {
+                    Some(ProximityTrend::Colder)
+                } else {
+                    None
+                };
+
+            if let Some(trend) = new_trend {
+                tracker.reference_distance = distance;
+                changed |= tracker.trend != trend;
+                tracker.trend = trend;
+            }
+        }
+
+        changed
+    }
+
+    /// Get the warmer/colder trend for a hider
+    #[cfg(test)]
+    pub fn get_proximity_trend(&self, hider: Id) -> Option<ProximityTrend> {
+        self.proximity_trends.get(&hider).map(|t| t.trend)
+    }
+
+    /// Give a seeker a point for catching a player and extend their streak, does nothing if
+    /// [GameSettings::track_catch_scores] isn't set
+    pub fn credit_catch(&mut self, seeker: Id, now: UtcDT) {
+        if !self.settings.track_catch_scores {
+            return;
+        }
+
+        *self.catches_by.entry(seeker).or_default() += 1;
+
+        let streak = self
+            .catch_streaks
+            .entry(seeker)
+            .and_modify(|streak| {
+                if now - streak.last_catch < TimeDelta::minutes(CATCH_STREAK_WINDOW_MINUTES) {
+                    streak.count += 1;
+                } else {
+                    streak.count = 1;
+                }
+                streak.last_catch = now;
+            })
+            .or_insert(CatchStreak {
+                count: 1,
+                last_catch: now,
+            });
+
+        let max = self.max_streaks.entry(seeker).or_default();
+        *max = (*max).max(streak.count);
+    }
+
+    /// Drop streaks of seekers who haven't caught anyone within the window, returns whether any
+    /// streak ended
+    pub fn expire_catch_streaks(&mut self, now: UtcDT) -> bool {
+        let before = self.catch_streaks.len();
+        self.catch_streaks.retain(|_, streak| {
+            now - streak.last_catch < TimeDelta::minutes(CATCH_STREAK_WINDOW_MINUTES)
+        });
+        before != self.catch_streaks.len()
+    }
+
+    /// Get a seeker's current catch streak
+    #[cfg(test)]
+    pub fn get_streak(&self, seeker: Id) -> u32 {
+        self.catch_streaks
+            .get(&seeker)
+            .map(|streak| streak.count)
+            .unwrap_or_default()
+    }
+
+    /// Get how many players a seeker has caught
+    #[cfg(test)]
+    pub fn get_catches(&self, seeker: Id) -> u32 {
+        self.catches_by.get(&seeker).copied().unwrap_or_default()
+    }
+
+    #[cfg(test)]
+    pub fn get_frozen_until(&self) -> Option<UtcDT> {
+        self.frozen_until
+    }
+
+    /// Mark a player as caught
+    pub fn mark_caught(&mut self, player: Id) {
+        if let Some(caught) = self.caught_state.get_mut(&player) {
+            *caught = true;
+        }
+    }
+
+    /// Revert a catch that was taken back with [GameEvent::UncaughtCorrection], making the player
+    /// a hider again and taking the point away from the seeker that was credited
+    pub fn uncatch_player(&mut self, player: Id, by: Option<Id>) {
+        if let Some(caught) = self.caught_state.get_mut(&player) {
+            *caught = false;
+        }
+        self.jailed.remove(&player);
+        if let Some(by) = by {
+            self.uncredit_catch(by);
+        }
+    }
+
+    /// Take back a point given by [GameState::credit_catch]. The seeker's longest streak is
+    /// lowered along with their current one if the two are the same.
+    fn uncredit_catch(&mut self, seeker: Id) {
+        if !self.settings.track_catch_scores {
+            return;
+        }
+
+        if let Some(catches) = self.catches_by.get_mut(&seeker) {
+            *catches -= 1;
+            if *catches == 0 {
+                self.catches_by.remove(&seeker);
+            }
+        }
+
+        if let Some(streak) = self.catch_streaks.get_mut(&seeker) {
+            if self.max_streaks.get(&seeker) == Some(&streak.count) {
+                if streak.count == 1 {
+                    self.max_streaks.remove(&seeker);
+                } else {
+                    self.max_streaks.insert(seeker, streak.count - 1);
+                }
+            }
+            streak.count -= 1;
+            if streak.count == 0 {
+                self.catch_streaks.remove(&seeker);
+            }
+        }
+    }
+
+    /// Remember that we just marked ourselves caught so it can be undone for a little while
+    pub fn start_pending_catch(&mut self, by: Option<Id>, now: UtcDT) {
+        self.pending_catch = Some((now, by));
+    }
+
+    /// When our pending catch gets locked in, [Option::None] if there's nothing to undo
+    pub fn undo_catch_until(&self) -> Option<UtcDT> {
+        self.pending_catch
+            .filter(|_| !self.game_ended())
+            .map(|(at, _)| at + TimeDelta::seconds(UNDO_CATCH_WINDOW_SECONDS))
+    }
+
+    /// Take our pending catch if it can still be undone, gives the seeker that was credited
+    /// with it
+    pub fn take_pending_catch(&mut self, now: UtcDT) -> Option<Option<Id>> {
+        if self.undo_catch_until().is_some_and(|until| now < until) {
+            self.pending_catch.take().map(|(_, by)| by)
+        } else {
+            None
+        }
+    }
+
+    /// Lock in our pending catch once it can't be undone anymore, returns whether it was
+    /// locked in
+    pub fn lock_in_pending_catch(&mut self, now: UtcDT) -> bool {
+        if self.undo_catch_until().is_some_and(|until| now >= until) {
+            self.pending_catch = None;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn spectators_allowed(&self) -> bool {
+        self.settings.allow_spectators
+    }
+
+    pub fn jailbreak_enabled(&self) -> bool {
+        self.settings.enable_jailbreak
+    }
+
+    /// Send a caught player to jail, does nothing if [GameSettings::enable_jailbreak] isn't set
+    pub fn jail_player(&mut self, player: Id) {
+        if self.settings.enable_jailbreak {
+            self.jailed.insert(player);
+        }
+    }
+
+    /// Free a player from jail, making them a hider again
+    pub fn free_player(&mut self, player: Id) {
+        if self.jailed.remove(&player)
+            && let Some(caught) = self.caught_state.get_mut(&player)
+        {
+            *caught = false;
+        }
+    }
+
+    /// Get every player currently in jail
+    pub fn iter_jailed(&self) -> impl Iterator<Item = Id> + '_ {
+        self.jailed.iter().copied()
+    }
+
+    /// Whether we're close enough to the jail to free the players in it
+    pub fn in_jail_range(&self) -> bool {
+        match (self.settings.jail_location, self.get_loc()) {
+            (Some(jail), Some(loc)) => loc.distance_to(&jail) <= JAILBREAK_RADIUS_METERS,
+            _ => false,
+        }
+    }
+
+    /// Gets if a player was caught or not
+    #[cfg(test)]
+    pub fn get_caught(&self, player: Id) -> Option<bool> {
+        self.caught_state.get(&player).copied()
+    }
+
+    #[cfg(test)]
+    pub fn force_set_caught(&mut self, player: Id, caught: bool) {
+        self.caught_state.insert(player, caught);
+    }
+
+    /// Create a [GameEvent::FullSync] containing our current caught state and pings
+    pub fn create_full_sync(&self) -> GameEvent {
+        GameEvent::FullSync(self.caught_state.clone(), self.pings.clone())
+    }
+
+    /// Merge a [GameEvent::FullSync] from another player into our state. Conflicts are resolved
+    /// by taking the "most caught" union, a player is caught if either side thinks they are.
+    /// Pings are only taken if they're newer than the ones we have.
+    pub fn merge_full_sync(
+        &mut self,
+        caught_state: HashMap<Id, bool>,
+        pings: HashMap<Id, PlayerPing>,
+    ) {
+        for (id, caught) in caught_state {
+            // Only update players we know about, we don't want to re-add players that left
+            if let Some(local_caught) = self.caught_state.get_mut(&id) {
+                *local_caught |= caught;
+            }
+        }
+
+        for (id, ping) in pings {
+            let is_hider = self.caught_state.get(&id).is_some_and(|caught| !caught);
+            let is_newer = self
+                .pings
+                .get(&id)
+                .is_none_or(|local| local.timestamp < ping.timestamp);
+            if is_hider && is_newer {
+                self.pings.insert(id, ping);
+            }
+        }
+
+        let seekers = self.iter_seekers().collect::<Vec<_>>();
+        for seeker in seekers {
+            self.pings.remove(&seeker);
+        }
+    }
+
+    pub fn is_seeker(&self) -> bool {
+        self.caught_state.get(&self.id).copied().unwrap_or_default()
+    }
+
+    /// Copy of the game's history so far, the game itself uses [GameState::take_game_history]
+    #[cfg(test)]
+    pub fn as_game_history(&self) -> GameHistory {
+        GameHistory {
+            my_id: self.id,
+            id: self.game_id,
+            events: self.event_history.clone(),
+            locations: self
+                .player_histories
+                .iter()
+                .map(|(id, history)| (*id, history.as_ref().cloned().unwrap_or_default()))
+                .collect(),
+            game_started: self.game_started,
+            game_ended: self.game_ended.unwrap_or_default(),
+            catches_by: self.catches_by.clone(),
+            max_streaks: self.max_streaks.clone(),
+            initial_seekers: self.initial_seekers.clone(),
+            host: Some(self.host),
+        }
+    }
+
+    /// Move the event and location histories out into a [GameHistory] instead of cloning them,
+    /// only meant to be called once the game is over. Both histories are empty afterwards.
+    pub fn take_game_history(&mut self) -> GameHistory {
+        GameHistory {
+            my_id: self.id,
+            id: self.game_id,
+            events: std::mem::take(&mut self.event_history),
+            locations: self
+                .player_histories
+                .iter_mut()
+                .map(|(id, history)| (*id, history.take().unwrap_or_default()))
+                .collect(),
+            game_started: self.game_started,
+            game_ended: self.game_ended.unwrap_or_default(),
+            catches_by: self.catches_by.clone(),
+            max_streaks: self.max_streaks.clone(),
+            initial_seekers: self.initial_seekers.clone(),
+            host: Some(self.host),
+        }
+    }
+
+    /// Apply the changes an event makes to the state at `time`, without any networking or alerts.
+    /// Used by [crate::Game] for events it receives and to replay events recorded in a
+    /// [GameHistory]. Events that only cause other events (e.g. [GameEvent::ForcePing]) are
+    /// skipped since whatever they caused was recorded too.
+    pub(crate) fn apply_event(&mut self, time: UtcDT, event: &GameEvent) {
+        match event {
+            GameEvent::Ping(ping) => self.add_ping(ping.clone()),
+            GameEvent::PowerupDespawn(_) => self.despawn_powerup(),
+            GameEvent::PowerupSpawn(location) => self.place_powerup(time, *location),
+            GameEvent::ForceEnd(_) => {
+                self.game_ended.get_or_insert(time);
+            }
+            GameEvent::PlayerCaught { player, by, reason } => {
+                self.mark_caught(*player);
+                if *reason == CatchReason::Caught {
+                    self.jail_player(*player);
+                }
+                if let Some(by) = by {
+                    self.credit_catch(*by, time);
+                }
+                self.remove_ping(*player);
+            }
+            GameEvent::FullSync(caught_state, pings) => {
+                self.merge_full_sync(caught_state.clone(), pings.clone());
+            }
+            GameEvent::SeekersFrozen(until) => self.freeze_seekers(*until),
+            GameEvent::PlayerFreed(player) => self.free_player(*player),
+            GameEvent::UncaughtCorrection { player, by } => self.uncatch_player(*player, *by),
+            GameEvent::SettingsAmendment(settings) => self.amend_settings(settings.clone()),
+            GameEvent::PlayerRejoined { player, seeker } => self.rejoin_player(*player, *seeker),
+            GameEvent::SeekerPin(location, label) => self.add_seeker_pin(*location, label.clone()),
+            GameEvent::ForcePing(..)
+            | GameEvent::PostGameSync(..)
+            | GameEvent::RequestSync(_)
+            | GameEvent::SpectatorSync { .. } => {}
+        }
+    }
+
+    /// The state for the UI, spoofed pings are only revealed once the game is over
+    pub fn as_ui_state(&self) -> GameUiState {
+        self.ui_state(self.game_ended.is_some())
+    }
+
+    fn ui_state(&self, reveal_spoofed_pings: bool) -> GameUiState {
+        let mut players = self
+            .caught_state
+            .iter()
+            .map(|(id, seeker)| PlayerView {
+                id: *id,
+                display_name: None,
+                seeker: *seeker,
+                color: None,
+            })
+            .collect::<Vec<_>>();
+        players.sort_by_key(|p| p.id);
+
+        GameUiState {
+            players,
+            my_id: self.id,
+            host: self.host,
+            caught_state: self.caught_state.clone(),
+            in_safe_zone: self
+                .caught_state
+                .iter()
+                .filter(|(_, seeker)| !**seeker)
+                .filter_map(|(id, _)| {
+                    self.latest_known_loc(*id)
+                        .map(|loc| (*id, self.in_safe_zone(loc)))
+                })
+                .collect(),
+            jailed: {
+                let mut jailed = self.jailed.iter().copied().collect::<Vec<_>>();
+                jailed.sort();
+                jailed
+            },
+            catches_by: self.catches_by.clone(),
+            current_streaks: self
+                .catch_streaks
+                .iter()
+                .map(|(id, streak)| (*id, streak.count))
+                .collect(),
+            proximity_trends: self
+                .proximity_trends
+                .iter()
+                .map(|(id, t)| (*id, t.trend))
+                .collect(),
+            available_powerup: self.available_powerup,
+            last_seen: self
+                .visible_pings()
+                .map(|(id, ping)| (*id, ping.timestamp))
+                .collect(),
+            pings: self
+                .visible_pings()
+                .map(|(id, ping)| {
+                    let ping = if reveal_spoofed_pings {
+                        ping.clone()
+                    } else {
+                        ping.masked()
+                    };
+                    (*id, ping)
+                })
+                .collect(),
+            game_started: self.game_started,
+            game_ended: self.game_ended,
+            phase: self.phase(),
+            last_global_ping: self.last_global_ping,
+            pings_active: self.pings_started(),
+            ping_phase: if self.pings_started() {
+                PingPhase::Active
+            } else {
+                PingPhase::NotStarted
+            },
+            next_global_ping: self.last_global_ping.map(|last| {
+                if self.first_ping_pending {
+                    last
+                } else {
+                    last + TimeDelta::minutes(self.settings.ping_minutes_interval as i64)
+                }
+            }),
+            last_powerup_spawn: self.last_powerup_spawn,
+            held_powerup: self.held_powerup,
+            panic_pings_left: self.panic_pings_left(),
+            seekers_release_at: self.seekers_release_at(),
+            seekers_started: self.seekers_started,
+            frozen_until: self.frozen_until,
+            spectating: self.spectator,
+            undo_catch_until: self.undo_catch_until(),
+            out_of_bounds: self.out_of_bounds,
+            revealed_for_camping: self.revealed_for_camping(),
+            seeker_pins: self.seeker_pins(),
+            left_players: {
+                let mut left = self.left_players.iter().copied().collect::<Vec<_>>();
+                left.sort_unstable();
+                left
+            },
+            reconnecting_players: {
+                let mut reconnecting = self.disconnected.keys().copied().collect::<Vec<_>>();
+                reconnecting.sort_unstable();
+                reconnecting
+            },
+        }
+    }
+
+    /// Pings the local player is allowed to see, based on [GameSettings::ping_visible_to].
+    /// Under [PingVisibility::SeekersOnly] the pinged team (see [GameState::on_pinged_team]) only
+    /// sees their own ping and the other team's.
+    fn visible_pings(&self) -> impl Iterator<Item = (&Id, &PlayerPing)> {
+        let hide_teammates =
+            self.settings.ping_visible_to == PingVisibility::SeekersOnly && self.on_pinged_team();
+        let pinged_team = self.settings.reverse_pings;
+        self.pings.iter().filter(move |(id, _)| {
+            !hide_teammates
+                || **id == self.id
+                || self
+                    .caught_state
+                    .get(id)
+                    .is_some_and(|seeker| *seeker != pinged_team)
+        })
+    }
+
+    pub fn clone_settings(&self) -> GameSettings {
+        self.settings.clone()
+    }
+
+    pub fn clone_caught_state(&self) -> HashMap<Id, bool> {
+        self.caught_state.clone()
+    }
+
+    /// IDs of everyone still in the game, sorted. Players that disconnected are left out until
+    /// they reconnect or rejoin.
+    pub fn active_players(&self) -> Vec<Id> {
+        let mut players = self
+            .caught_state
+            .keys()
+            .filter(|id| !self.disconnected.contains_key(id))
+            .copied()
+            .collect::<Vec<_>>();
+        players.sort_unstable();
+        players
+    }
+
+    /// Check if the given settings can replace the current ones mid-game. Settings that have
+    /// already taken effect (e.g. hiding time once seekers are released) can't be changed.
+    /// Returns a list of human-readable problems if not.
+    pub fn check_settings_amendment(&self, new: &GameSettings) -> Result<(), Vec<String>> {
+        let mut errors = new.validate().err().unwrap_or_default();
+
+        if new.random_seed != self.settings.random_seed {
+            errors.push("The random seed can't be changed mid-game".to_string());
+        }
+
+        // Teams, profiles, and who can watch were all settled when the game started
+        if new.host_plays != self.settings.host_plays {
+            errors.push("Whether the host plays can't be changed mid-game".to_string());
+        }
+
+        if new.auto_seeker_count != self.settings.auto_seeker_count {
+            errors.push("The number of random seekers can't be changed mid-game".to_string());
+        }
+
+        if new.anonymize_profiles != self.settings.anonymize_profiles {
+            errors.push("Anonymizing profiles can't be changed mid-game".to_string());
+        }
+
+        if new.allow_spectators != self.settings.allow_spectators {
+            errors.push("Allowing spectators can't be changed mid-game".to_string());
+        }
+
+        if self.seekers_released() && new.hiding_time_seconds != self.settings.hiding_time_seconds {
+            errors.push("Hiding time can't be changed after seekers are released".to_string());
+        }
+
+        if self.pings_started() && new.ping_start != self.settings.ping_start {
+            errors.push("Ping start condition can't be changed after pings started".to_string());
+        }
+
+        // Only read when pings begin, see [GameState::begin_pings]
+        if self.pings_started() && new.first_ping_immediate != self.settings.first_ping_immediate {
+            errors.push(
+                "Whether the first ping is immediate can't be changed after pings started"
+                    .to_string(),
+            );
+        }
+
+        if self.powerups_started() && new.powerup_start != self.settings.powerup_start {
+            errors.push(
+                "Powerup start condition can't be changed after powerups started".to_string(),
+            );
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Replace the current settings, make sure to check with
+    /// [GameState::check_settings_amendment] first
+    pub fn amend_settings(&mut self, settings: GameSettings) {
+        self.powerup_bernoulli = settings.get_powerup_bernoulli();
+        self.settings = settings;
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub struct GameHistory {
     my_id: Uuid,
+    /// Unique ID of this game, histories saved before this existed are given a new one when
+    /// loaded
+    #[serde(default = "Uuid::new_v4")]
+    pub id: GameId,
     pub game_started: UtcDT,
     game_ended: UtcDT,
     pub events: Vec<(UtcDT, GameEvent)>,
     pub locations: Vec<(Uuid, Vec<(UtcDT, Location)>)>,
+    /// Number of players each seeker caught, empty if catch scores weren't tracked
+    #[serde(default)]
+    pub catches_by: HashMap<Uuid, u32>,
+    /// Longest catch streak of each seeker, empty if catch scores weren't tracked
+    #[serde(default)]
+    pub max_streaks: HashMap<Uuid, u32>,
+    /// Players that started the game as seekers, empty for histories saved before this was
+    /// tracked
+    #[serde(default)]
+    pub initial_seekers: Vec<Uuid>,
+    /// ID of the player that hosted the game, [Option::None] for histories saved before this was
+    /// tracked
+    #[serde(default)]
+    pub host: Option<Uuid>,
+}
+
+impl GameHistory {
+    /// Get seekers ranked by how many players they caught, most catches first
+    pub fn catch_ranking(&self) -> Vec<(Uuid, u32)> {
+        let mut ranking = self
+            .catches_by
+            .iter()
+            .map(|(id, catches)| (*id, *catches))
+            .collect::<Vec<_>>();
+        ranking.sort_by(|(a_id, a), (b_id, b)| b.cmp(a).then(a_id.cmp(b_id)));
+        ranking
+    }
+
+    /// Get the IDs of every player that has a location track in this history
+    pub fn player_ids(&self) -> Vec<Uuid> {
+        self.locations.iter().map(|(id, _)| *id).collect()
+    }
+
+    /// Get the location track of a single player, [Option::None] if they don't have one
+    pub fn player_track(&self, id: Uuid) -> Option<&[(UtcDT, Location)]> {
+        self.locations
+            .iter()
+            .find(|(player, _)| *player == id)
+            .map(|(_, track)| track.as_slice())
+    }
+
+    /// Get every powerup that spawned during the game in order, along with when it despawned and
+    /// who grabbed it
+    pub fn powerup_timeline(&self) -> Vec<PowerupTimelineEntry> {
+        let mut timeline = Vec::<PowerupTimelineEntry>::new();
+
+        for (time, event) in self.events.iter() {
+            // A powerup can only despawn if it's the latest one and it's still up
+            let current = timeline.last_mut().filter(|e| e.despawned_at.is_none());
+            match event {
+                GameEvent::PowerupSpawn(location) => {
+                    // New spawns replace the old powerup if nobody grabbed it
+                    if let Some(current) = current {
+                        current.despawned_at = Some(*time);
+                    }
+                    timeline.push(PowerupTimelineEntry {
+                        location: *location,
+                        spawned_at: *time,
+                        despawned_at: None,
+                        grabbed_by: None,
+                    });
+                }
+                GameEvent::PowerupDespawn(player) => {
+                    if let Some(current) = current {
+                        current.despawned_at = Some(*time);
+                        current.grabbed_by = Some(*player);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        timeline
+    }
+
+    /// Rebuild the [GameUiState] as it was after applying the first `index` events of the game,
+    /// for stepping through exactly how state changed when debugging desyncs. Only event-driven
+    /// state is rebuilt, things the game loop does on its own like releasing seekers aren't.
+    /// `settings` should be the settings the game started with.
+    /// [Option::None] if there aren't that many events.
+    pub fn replay_step(&self, settings: GameSettings, index: usize) -> Option<GameUiState> {
+        if index > self.events.len() {
+            return None;
+        }
+
+        let caught_state = self
+            .locations
+            .iter()
+            .map(|(id, _)| (*id, self.initial_seekers.contains(id)))
+            .collect();
+        // Older histories don't know who hosted, it doesn't matter for replaying events anyway
+        let host = self.host.unwrap_or(self.my_id);
+        let mut state = GameState::new(settings, self.my_id, host, caught_state);
+        state.game_started = self.game_started;
+
+        for (time, event) in self.events[..index].iter() {
+            state.apply_event(*time, event);
+        }
+
+        // The game's over, no harm in showing which pings were bait
+        Some(state.ui_state(true))
+    }
+
+    /// Total distance each player traveled in meters according to their location track, see
+    /// [track_distance]
+    pub fn distance_traveled(&self) -> HashMap<Uuid, f64> {
+        self.locations
+            .iter()
+            .map(|(id, track)| (*id, track_distance(track)))
+            .collect()
+    }
+
+    /// How long each player that started as a hider spent hiding. Time spent in jail or as a
+    /// seeker doesn't count, hiding again after being freed or uncaught does.
+    /// Empty if we don't know who started as a seeker.
+    pub fn survival_times(&self) -> HashMap<Uuid, TimeDelta> {
+        if self.initial_seekers.is_empty() {
+            return HashMap::new();
+        }
+
+        let mut hiding_since = self
+            .locations
+            .iter()
+            .map(|(id, _)| *id)
+            .filter(|id| !self.initial_seekers.contains(id))
+            .map(|id| (id, Some(self.game_started)))
+            .collect::<HashMap<_, _>>();
+        let mut times = hiding_since
+            .keys()
+            .map(|id| (*id, TimeDelta::zero()))
+            .collect::<HashMap<_, _>>();
+
+        for (time, event) in self.events.iter() {
+            match event {
+                GameEvent::PlayerCaught { player, .. } => {
+                    if let Some(since) = hiding_since.get_mut(player).and_then(Option::take) {
+                        *times.entry(*player).or_default() += *time - since;
+                    }
+                }
+                GameEvent::PlayerFreed(player) | GameEvent::UncaughtCorrection { player, .. } => {
+                    if let Some(since) = hiding_since.get_mut(player) {
+                        since.get_or_insert(*time);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for (id, since) in hiding_since {
+            if let Some(since) = since {
+                *times.entry(id).or_default() += self.game_ended - since;
+            }
+        }
+
+        times
+    }
+
+    /// Compute the end-of-game awards, see [GameAwards]
+    pub fn awards(&self) -> GameAwards {
+        let mut powerups = HashMap::<Uuid, u32>::new();
+        for entry in self.powerup_timeline() {
+            if let Some(player) = entry.grabbed_by {
+                *powerups.entry(player).or_default() += 1;
+            }
+        }
+
+        GameAwards {
+            most_distance: top_award(self.distance_traveled()),
+            longest_survival: top_award(
+                self.survival_times()
+                    .into_iter()
+                    .map(|(id, time)| (id, time.num_seconds())),
+            ),
+            most_catches: top_award(self.catches_by.iter().map(|(id, c)| (*id, *c))),
+            most_powerups: top_award(powerups),
+        }
+    }
+}
+
+/// Find the player with the highest non-zero value, ties go to the lowest player ID so every
+/// player computes the same winner
+fn top_award<T: PartialOrd + Default + Copy>(
+    values: impl IntoIterator<Item = (Uuid, T)>,
+) -> Option<(Uuid, T)> {
+    values
+        .into_iter()
+        .filter(|(_, value)| *value > T::default())
+        .fold(None, |best, (id, value)| match best {
+            Some((best_id, best_value))
+                if best_value > value || (best_value == value && best_id < id) =>
+            {
+                best
+            }
+            _ => Some((id, value)),
+        })
+}
+
+/// Everything needed to pick a game back up after the app was closed mid-game, see
+/// [GameState::snapshot]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameSnapshot {
+    id: Id,
+    host: Id,
+    held_powerup: Option<PowerUpType>,
+    #[serde(default)]
+    panic_pings_used: u32,
+    #[serde(default = "Uuid::new_v4")]
+    game_id: GameId,
+    game_started: UtcDT,
+    seekers_started: Option<UtcDT>,
+    frozen_until: Option<UtcDT>,
+    last_global_ping: Option<UtcDT>,
+    #[serde(default)]
+    first_ping_pending: bool,
+    last_powerup_spawn: Option<UtcDT>,
+    caught_state: HashMap<Id, bool>,
+    initial_seekers: Vec<Id>,
+    jailed: HashSet<Id>,
+    catches_by: HashMap<Id, u32>,
+    catch_streaks: HashMap<Id, CatchStreak>,
+    max_streaks: HashMap<Id, u32>,
+    pings: HashMap<Id, PlayerPing>,
+    #[serde(default)]
+    seeker_pins: Vec<SeekerPin>,
+    available_powerup: Option<Location>,
+    event_history: Vec<(UtcDT, GameEvent)>,
+    settings: GameSettings,
+    location_history: Vec<(UtcDT, Location)>,
+    shared_random_increment: i64,
+    shared_random_state: u64,
+}
+
+impl GameSnapshot {
+    /// Our ID in the game, this stays the same when rejoining on a new connection
+    pub fn player_id(&self) -> Id {
+        self.id
+    }
+}
+
+/// Fun superlatives for the end of a game, each is the winning player and their value.
+/// An award is [Option::None] if nobody earned it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+pub struct GameAwards {
+    /// Player that traveled the furthest, in meters
+    pub most_distance: Option<(Uuid, f64)>,
+    /// Hider that stayed hidden the longest, in seconds
+    pub longest_survival: Option<(Uuid, i64)>,
+    /// Seeker that caught the most players
+    pub most_catches: Option<(Uuid, u32)>,
+    /// Player that grabbed the most powerups
+    pub most_powerups: Option<(Uuid, u32)>,
+}
+
+/// A single powerup that appeared on the map during a game
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+pub struct PowerupTimelineEntry {
+    /// Where the powerup spawned
+    pub location: Location,
+    /// When the powerup spawned **in UTC**
+    pub spawned_at: UtcDT,
+    /// When the powerup was grabbed or replaced by a new one, [Option::None] if it was still
+    /// up when the game ended
+    pub despawned_at: Option<UtcDT>,
+    /// The player that grabbed this powerup, [Option::None] if nobody did
+    pub grabbed_by: Option<Uuid>,
+}
+
+/// A player in the game along with their role, meant for rendering lists of players in the UI
+#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+pub struct PlayerView {
+    /// ID of the player
+    pub id: Uuid,
+    /// Display name of the player, [Option::None] if we don't have a profile for them
+    pub display_name: Option<String>,
+    /// Whether this player is a seeker
+    pub seeker: bool,
+    /// Hex color for this player's map pin and scoreboard row, [Option::None] if they don't have
+    /// one
+    pub color: Option<String>,
 }
 
 /// Subset of [GameState] that is meant to be sent to a UI frontend
@@ -424,22 +2030,1015 @@ pub struct GameHistory {
 pub struct GameUiState {
     /// ID of the local player
     my_id: Uuid,
+    /// ID of the host, they can end the game early
+    host: Uuid,
     /// A map of player IDs to whether that player is a seeker
     caught_state: HashMap<Uuid, bool>,
+    /// Every player in the game with their display name and role, sorted by ID
+    players: Vec<PlayerView>,
+    /// Caught players waiting in jail to be freed, sorted by ID. Empty if jailbreaks aren't
+    /// enabled
+    jailed: Vec<Uuid>,
+    /// A map of hider IDs to whether they're in a safe zone based on the latest location we know
+    /// of (our own location for us, their last ping for others). Hiders we don't know the
+    /// location of are left out
+    in_safe_zone: HashMap<Uuid, bool>,
+    /// A map of seeker IDs to how many players they've caught, empty if catch scores aren't
+    /// being tracked
+    catches_by: HashMap<Uuid, u32>,
+    /// A map of seeker IDs to how many players they've caught in a row, each catch within a few
+    /// minutes of the last. Empty if catch scores aren't being tracked
+    current_streaks: HashMap<Uuid, u32>,
+    /// A map of hider IDs to whether we're getting warmer or colder relative to their last ping,
+    /// empty unless we're a seeker and hot/cold hints are enabled
+    proximity_trends: HashMap<Uuid, ProximityTrend>,
     /// A powerup that is available on the map
     available_powerup: Option<Location>,
     /// A map of player IDs to an active ping on them
     pings: HashMap<Uuid, PlayerPing>,
+    /// A map of player IDs to when their active ping happened **in UTC**, players that haven't
+    /// been pinged are left out
+    last_seen: HashMap<Uuid, UtcDT>,
     /// When the game was started **in UTC**
     game_started: UtcDT,
     /// When the game ended, when this is Option::Some, the game has ended
     game_ended: Option<UtcDT>,
+    /// Which part of the game we're in, use this instead of working it out from
+    /// [GameUiState::seekers_started] and [GameUiState::game_ended]
+    phase: GamePhase,
     /// The last time all hiders were pinged **in UTC**
     last_global_ping: Option<UtcDT>,
+    /// Whether global pings have started, same as [GameUiState::ping_phase] being
+    /// [PingPhase::Active]
+    pings_active: bool,
+    /// Whether global pings have started, use this to tell the early game apart from being
+    /// between pings
+    ping_phase: PingPhase,
+    /// When hiders will next be pinged **in UTC**, [Option::None] if pings haven't started
+    next_global_ping: Option<UtcDT>,
     /// The last time a powerup was spawned **in UTC**
     last_powerup_spawn: Option<UtcDT>,
     /// The [PowerUpType] the local player is holding
     held_powerup: Option<PowerUpType>,
+    /// How many more times the local player can reveal themselves with a panic ping
+    panic_pings_left: u32,
+    /// When the seekers will be (or were) released **in UTC**, use this to count down the hiding
+    /// phase
+    seekers_release_at: UtcDT,
     /// When the seekers were allowed to start **in UTC**
     seekers_started: Option<UtcDT>,
+    /// When seekers will be unfrozen **in UTC**, [Option::None] if they aren't frozen
+    frozen_until: Option<UtcDT>,
+    /// Whether we're only watching the game, we won't be in `caught_state`
+    spectating: bool,
+    /// Until when **in UTC** we can take back marking ourselves caught, [Option::None] if
+    /// there's nothing to undo
+    undo_catch_until: Option<UtcDT>,
+    /// Whether our latest location is outside [GameSettings::play_area], always false if there
+    /// isn't one
+    out_of_bounds: bool,
+    /// Whether we're being pinged every minute for staying in one spot too long, see
+    /// [GameSettings::reveal_after_minutes]
+    revealed_for_camping: bool,
+    /// Map pins seekers dropped for each other, always empty for hiders
+    seeker_pins: Vec<SeekerPin>,
+    /// Players that left the game on purpose, sorted by ID. Anyone else missing from
+    /// [GameUiState::caught_state] dropped out.
+    left_players: Vec<Id>,
+    /// Players whose connection dropped that we're still waiting on to reconnect, sorted by ID.
+    /// They're still in [GameUiState::caught_state] until
+    /// [GameSettings::disconnect_grace_seconds] runs out.
+    reconnecting_players: Vec<Id>,
+}
+
+impl GameUiState {
+    /// Fill in the display names and colors of [GameUiState::players] from the given profiles
+    pub fn resolve_profiles(&mut self, profiles: &HashMap<Uuid, PlayerProfile>) {
+        for player in self.players.iter_mut() {
+            let profile = profiles.get(&player.id);
+            player.display_name = profile.map(|p| p.display_name.clone());
+            player.color = profile.and_then(|p| p.color.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mk_history(events: Vec<(UtcDT, GameEvent)>) -> GameHistory {
+        GameHistory {
+            my_id: Uuid::from_u128(0),
+            id: Uuid::from_u128(1),
+            game_started: UtcDT::default(),
+            game_ended: UtcDT::default(),
+            events,
+            locations: vec![],
+            catches_by: HashMap::new(),
+            max_streaks: HashMap::new(),
+            initial_seekers: vec![],
+            host: None,
+        }
+    }
+
+    fn loc(lat: f64) -> Location {
+        Location {
+            lat,
+            long: 0.0,
+            heading: None,
+        }
+    }
+
+    #[test]
+    fn test_powerup_timeline() {
+        let t = |s: i64| UtcDT::default() + TimeDelta::seconds(s);
+        let grabber = Uuid::from_u128(1);
+
+        let history = mk_history(vec![
+            (t(0), GameEvent::PowerupSpawn(loc(1.0))),
+            (t(5), GameEvent::PowerupDespawn(grabber)),
+            // Stray despawn with nothing up shouldn't do anything
+            (t(6), GameEvent::PowerupDespawn(grabber)),
+            (t(10), GameEvent::PowerupSpawn(loc(2.0))),
+            (t(20), GameEvent::PowerupSpawn(loc(3.0))),
+        ]);
+
+        let timeline = history.powerup_timeline();
+
+        assert_eq!(
+            timeline,
+            vec![
+                PowerupTimelineEntry {
+                    location: loc(1.0),
+                    spawned_at: t(0),
+                    despawned_at: Some(t(5)),
+                    grabbed_by: Some(grabber),
+                },
+                PowerupTimelineEntry {
+                    location: loc(2.0),
+                    spawned_at: t(10),
+                    despawned_at: Some(t(20)),
+                    grabbed_by: None,
+                },
+                PowerupTimelineEntry {
+                    location: loc(3.0),
+                    spawned_at: t(20),
+                    despawned_at: None,
+                    grabbed_by: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_catch_ranking() {
+        let (a, b, c) = (Uuid::from_u128(1), Uuid::from_u128(2), Uuid::from_u128(3));
+        let mut history = mk_history(vec![]);
+        history.catches_by = HashMap::from_iter([(a, 1), (b, 3), (c, 1)]);
+
+        assert_eq!(history.catch_ranking(), vec![(b, 3), (a, 1), (c, 1)]);
+    }
+
+    #[test]
+    fn test_player_track() {
+        let t = |s: i64| UtcDT::default() + TimeDelta::seconds(s);
+        let (a, b) = (Uuid::from_u128(1), Uuid::from_u128(2));
+        let mut history = mk_history(vec![]);
+        history.locations = vec![
+            (a, vec![(t(0), loc(1.0)), (t(5), loc(2.0))]),
+            (b, vec![(t(0), loc(3.0))]),
+        ];
+
+        assert_eq!(history.player_ids(), vec![a, b]);
+        assert_eq!(
+            history.player_track(a),
+            Some([(t(0), loc(1.0)), (t(5), loc(2.0))].as_slice())
+        );
+        assert_eq!(history.player_track(Uuid::from_u128(3)), None);
+    }
+
+    #[test]
+    fn test_awards() {
+        let t = |s: i64| UtcDT::default() + TimeDelta::seconds(s);
+        let (seeker, a, b) = (Uuid::from_u128(1), Uuid::from_u128(2), Uuid::from_u128(3));
+        let mut history = mk_history(vec![
+            (t(0), GameEvent::PowerupSpawn(loc(1.0))),
+            (t(5), GameEvent::PowerupDespawn(b)),
+            (
+                t(10),
+                GameEvent::PlayerCaught {
+                    player: a,
+                    by: Some(seeker),
+                    reason: CatchReason::Caught,
+                },
+            ),
+            (t(20), GameEvent::PlayerFreed(a)),
+            (
+                t(40),
+                GameEvent::PlayerCaught {
+                    player: b,
+                    by: Some(seeker),
+                    reason: CatchReason::Caught,
+                },
+            ),
+        ]);
+        history.game_ended = t(50);
+        history.initial_seekers = vec![seeker];
+        history.catches_by = HashMap::from_iter([(seeker, 2)]);
+        history.locations = vec![
+            (seeker, vec![(t(0), loc(0.0)), (t(10), loc(0.001))]),
+            (
+                a,
+                vec![(t(0), loc(0.0)), (t(10), loc(0.0009)), (t(20), loc(0.0))],
+            ),
+            // A GPS glitch far away doesn't count
+            (b, vec![(t(0), loc(0.0)), (t(1), loc(0.1))]),
+        ];
+
+        let awards = history.awards();
+        assert_eq!(awards.most_distance.map(|(id, _)| id), Some(a));
+        // a hid for 10s, was jailed for 10s and then hid until the end, b was caught at 40s.
+        // That's 40s each, ties go to the lowest ID.
+        assert_eq!(awards.longest_survival, Some((a, 40)));
+        assert_eq!(awards.most_catches, Some((seeker, 2)));
+        assert_eq!(awards.most_powerups, Some((b, 1)));
+
+        history.catches_by.insert(a, 2);
+        assert_eq!(history.awards().most_catches, Some((seeker, 2)));
+
+        // Without knowing who started as a seeker there's no survival award
+        history.initial_seekers.clear();
+        assert_eq!(history.awards().longest_survival, None);
+    }
+
+    #[test]
+    fn test_replay_step() {
+        let t = |s: i64| UtcDT::default() + TimeDelta::seconds(s);
+        let (seeker, hider) = (Uuid::from_u128(1), Uuid::from_u128(2));
+        let mut history = mk_history(vec![
+            (t(0), GameEvent::PowerupSpawn(loc(1.0))),
+            (
+                t(10),
+                GameEvent::PlayerCaught {
+                    player: hider,
+                    by: Some(seeker),
+                    reason: CatchReason::Caught,
+                },
+            ),
+            (t(20), GameEvent::PlayerFreed(hider)),
+            (t(30), GameEvent::ForceEnd(seeker)),
+        ]);
+        history.initial_seekers = vec![seeker];
+        history.locations = vec![(seeker, vec![]), (hider, vec![])];
+        let settings = GameSettings {
+            enable_jailbreak: true,
+            jail_location: Some(loc(0.0)),
+            track_catch_scores: true,
+            ..Default::default()
+        };
+        let step = |i| {
+            history
+                .replay_step(settings.clone(), i)
+                .expect("Step out of range")
+        };
+
+        let start = step(0);
+        assert!(!start.caught_state[&hider]);
+        assert_eq!(start.available_powerup, None);
+
+        assert_eq!(step(1).available_powerup, Some(loc(1.0)));
+
+        let caught = step(2);
+        assert!(caught.caught_state[&hider]);
+        assert_eq!(caught.jailed, vec![hider]);
+        assert_eq!(caught.catches_by[&seeker], 1);
+
+        let freed = step(3);
+        assert!(!freed.caught_state[&hider]);
+        assert!(freed.jailed.is_empty());
+        assert_eq!(freed.game_ended, None);
+
+        assert_eq!(step(4).game_ended, Some(t(30)));
+        assert!(history.replay_step(settings.clone(), 5).is_none());
+    }
+
+    #[test]
+    fn test_awards_empty() {
+        let awards = mk_history(vec![]).awards();
+        assert_eq!(
+            awards,
+            GameAwards {
+                most_distance: None,
+                longest_survival: None,
+                most_catches: None,
+                most_powerups: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_powerup_near_hiders() {
+        let (seeker, hider_a, hider_b, caught) = (
+            Uuid::from_u128(1),
+            Uuid::from_u128(2),
+            Uuid::from_u128(3),
+            Uuid::from_u128(4),
+        );
+        let loc = |lat, long| Location {
+            lat,
+            long,
+            heading: None,
+        };
+        let settings = GameSettings {
+            powerup_start: PingStartCondition::Instant,
+            powerup_chance: 100,
+            powerup_near_hiders_radius_meters: Some(50.0),
+            ..Default::default()
+        };
+        let caught_state = HashMap::from_iter([
+            (seeker, true),
+            (hider_a, false),
+            (hider_b, false),
+            (caught, true),
+        ]);
+        let mut host = GameState::new(settings.clone(), seeker, seeker, caught_state.clone());
+        let mut other = GameState::new(settings, hider_a, seeker, caught_state);
+
+        assert_eq!(host.hider_centroid(), None);
+        assert!(host.should_start_powerups(Utc::now()));
+
+        let now = Utc::now();
+        // No pings and no fixed locations, nowhere to spawn
+        assert_eq!(host.try_spawn_powerup(now), None);
+
+        host.add_ping(PlayerPing::new(loc(10.0, 20.0), hider_a, hider_a));
+        host.add_ping(PlayerPing::new(loc(10.002, 20.002), hider_b, hider_b));
+        // Seekers' pings don't count, even if shown as a hider's
+        host.add_ping(PlayerPing::new(loc(50.0, 50.0), caught, caught));
+        host.add_ping(PlayerPing::new(loc(-50.0, -50.0), seeker, seeker));
+
+        let center = host.hider_centroid().expect("Hiders have been pinged");
+        assert!((center.lat - 10.001).abs() < 1e-9);
+        assert!((center.long - 20.001).abs() < 1e-9);
+
+        for _ in 0..50 {
+            let spawned = host
+                .try_spawn_powerup(now)
+                .expect("Host should always spawn at 100% chance");
+            assert!(center.distance_to(&spawned) <= 50.5);
+            assert_eq!(host.powerup_location(), Some(spawned));
+        }
+
+        // Everyone else waits for the host
+        assert_eq!(other.try_spawn_powerup(now), None);
+        assert_eq!(other.powerup_location(), None);
+        other.place_powerup(now, center);
+        assert_eq!(other.powerup_location(), Some(center));
+    }
+
+    #[test]
+    fn test_local_picks_keep_shared_rng() {
+        let (seeker, hider, other) = (Uuid::from_u128(1), Uuid::from_u128(2), Uuid::from_u128(3));
+        let caught_state = HashMap::from_iter([(seeker, true), (hider, false), (other, false)]);
+        let settings = GameSettings::default();
+        let mut picker = GameState::new(settings.clone(), hider, seeker, caught_state.clone());
+        let bystander = GameState::new(settings, other, seeker, caught_state);
+
+        assert_eq!(picker.random_seeker(), Some(seeker));
+        assert_eq!(picker.random_other_hider(), Some(other));
+
+        // Only the picker made those choices, the shared RNG must still line up with everyone
+        assert_eq!(picker.shared_random_state, bystander.shared_random_state);
+    }
+
+    #[test]
+    fn test_active_players() {
+        let ids = (1..=3).map(Uuid::from_u128).collect::<Vec<_>>();
+        let mut state = GameState::new(
+            GameSettings::default(),
+            ids[0],
+            ids[0],
+            HashMap::from_iter([(ids[2], false), (ids[0], true), (ids[1], false)]),
+        );
+
+        assert_eq!(state.active_players(), ids);
+
+        state.remove_player(ids[1]);
+        assert_eq!(state.active_players(), vec![ids[0], ids[2]]);
+
+        state.rejoin_player(ids[1], true);
+        assert_eq!(state.active_players(), ids);
+        assert!(state.as_ui_state().left_players.is_empty());
+
+        state.player_left(ids[2]);
+        assert_eq!(state.active_players(), vec![ids[0], ids[1]]);
+        assert_eq!(state.as_ui_state().left_players, vec![ids[2]]);
+
+        state.rejoin_player(ids[2], false);
+        assert!(state.as_ui_state().left_players.is_empty());
+    }
+
+    #[test]
+    fn test_reconnecting_players() {
+        let ids = (1..=3).map(Uuid::from_u128).collect::<Vec<_>>();
+        let mut state = GameState::new(
+            GameSettings::default(),
+            ids[0],
+            ids[0],
+            HashMap::from_iter([(ids[0], true), (ids[1], false), (ids[2], false)]),
+        );
+        let now = Utc::now();
+
+        assert!(!state.player_disconnected(ids[2], now));
+        assert!(!state.player_disconnected(ids[1], now));
+        assert_eq!(state.active_players(), vec![ids[0]]);
+        assert_eq!(
+            state.as_ui_state().reconnecting_players,
+            vec![ids[1], ids[2]]
+        );
+
+        // Coming back with a new connection clears it too
+        state.rejoin_player(ids[1], false);
+        assert_eq!(state.as_ui_state().reconnecting_players, vec![ids[2]]);
+        assert_eq!(state.active_players(), vec![ids[0], ids[1]]);
+
+        assert!(!state.expire_disconnects(now + TimeDelta::seconds(10)));
+        assert!(state.expire_disconnects(now + TimeDelta::seconds(30)));
+        assert_eq!(state.active_players(), vec![ids[0], ids[1]]);
+        assert!(state.as_ui_state().reconnecting_players.is_empty());
+    }
+
+    #[test]
+    fn test_ping_visibility() {
+        let (seeker, hider, other) = (Uuid::from_u128(1), Uuid::from_u128(2), Uuid::from_u128(3));
+        let settings = GameSettings {
+            ping_visible_to: PingVisibility::SeekersOnly,
+            ..Default::default()
+        };
+        let caught_state = HashMap::from_iter([(seeker, true), (hider, false), (other, false)]);
+        let loc = Location {
+            lat: 0.0,
+            long: 0.0,
+            heading: None,
+        };
+        let mut as_hider = GameState::new(settings.clone(), hider, seeker, caught_state.clone());
+        let mut as_seeker = GameState::new(settings, seeker, seeker, caught_state);
+        for state in [&mut as_hider, &mut as_seeker] {
+            for id in [seeker, hider, other] {
+                state.add_ping(PlayerPing::new(loc, id, id));
+            }
+        }
+
+        let ui = as_hider.as_ui_state();
+        assert!(
+            ui.pings.contains_key(&hider),
+            "Hider can't see their own ping"
+        );
+        assert!(
+            ui.pings.contains_key(&seeker),
+            "Hider can't see the seeker's ping"
+        );
+        assert!(
+            !ui.pings.contains_key(&other),
+            "Hider can see another hider's ping"
+        );
+        assert!(!ui.last_seen.contains_key(&other));
+
+        let ui = as_seeker.as_ui_state();
+        assert_eq!(ui.pings.len(), 3, "Seeker should see every ping");
+
+        // Once caught they can see everyone
+        as_hider.mark_caught(hider);
+        assert_eq!(as_hider.as_ui_state().pings.len(), 3);
+
+        // Reverse games hide seekers' pings from each other instead
+        as_seeker.settings.reverse_pings = true;
+        as_seeker.mark_caught(hider);
+        let ui = as_seeker.as_ui_state();
+        assert!(ui.pings.contains_key(&seeker));
+        assert!(
+            ui.pings.contains_key(&other),
+            "Seeker can't see a hider's ping"
+        );
+        assert!(
+            !ui.pings.contains_key(&hider),
+            "Seeker can see another seeker's ping"
+        );
+    }
+
+    #[test]
+    fn test_first_ping_timing() {
+        let (seeker, hider) = (Uuid::from_u128(1), Uuid::from_u128(2));
+        let caught_state = HashMap::from_iter([(seeker, true), (hider, false)]);
+        let now = Utc::now();
+        let later = now + TimeDelta::minutes(5);
+
+        for immediate in [false, true] {
+            let settings = GameSettings {
+                ping_minutes_interval: 5,
+                first_ping_immediate: immediate,
+                ..Default::default()
+            };
+            let mut state = GameState::new(settings, hider, seeker, caught_state.clone());
+
+            state.begin_pings(now);
+            assert_eq!(
+                state.should_ping(&now),
+                immediate,
+                "Wrong first ping timing with first_ping_immediate = {immediate}"
+            );
+            assert!(state.should_ping(&later));
+
+            // After the first ping it's always a full interval
+            state.start_pings(now);
+            assert!(!state.should_ping(&now));
+            assert!(state.should_ping(&later));
+        }
+    }
+
+    #[test]
+    fn test_ping_phase() {
+        let (seeker, hider) = (Uuid::from_u128(1), Uuid::from_u128(2));
+        let settings = GameSettings {
+            ping_minutes_interval: 3,
+            ..Default::default()
+        };
+        let mut state = GameState::new(
+            settings,
+            hider,
+            seeker,
+            HashMap::from_iter([(seeker, true), (hider, false)]),
+        );
+
+        let ui = state.as_ui_state();
+        assert_eq!(ui.ping_phase, PingPhase::NotStarted);
+        assert!(!ui.pings_active);
+        assert_eq!(ui.next_global_ping, None);
+
+        let now = Utc::now();
+        state.start_pings(now);
+
+        let ui = state.as_ui_state();
+        assert_eq!(ui.ping_phase, PingPhase::Active);
+        assert!(ui.pings_active);
+        assert_eq!(ui.next_global_ping, Some(now + TimeDelta::minutes(3)));
+    }
+
+    #[test]
+    fn test_proximity_trends() {
+        let (seeker, hider) = (Uuid::from_u128(1), Uuid::from_u128(2));
+        let settings = GameSettings {
+            hot_cold_hints: true,
+            ..Default::default()
+        };
+        let mut state = GameState::new(
+            settings,
+            seeker,
+            seeker,
+            HashMap::from_iter([(seeker, true), (hider, false)]),
+        );
+
+        assert!(state.should_update_proximity_trends());
+
+        // ~0.001 degrees latitude is ~111 meters
+        state.push_loc(loc(0.0));
+        state.add_ping(PlayerPing::new(loc(0.01), hider, hider));
+        state.update_proximity_trends();
+        assert_eq!(
+            state.get_proximity_trend(hider),
+            Some(ProximityTrend::Unknown)
+        );
+
+        // Small moves shouldn't change anything
+        state.push_loc(loc(0.00001));
+        assert!(!state.update_proximity_trends());
+
+        state.push_loc(loc(0.001));
+        assert!(state.update_proximity_trends());
+        assert_eq!(
+            state.get_proximity_trend(hider),
+            Some(ProximityTrend::Warmer)
+        );
+
+        state.push_loc(loc(-0.001));
+        assert!(state.update_proximity_trends());
+        assert_eq!(
+            state.get_proximity_trend(hider),
+            Some(ProximityTrend::Colder)
+        );
+
+        // A fresh ping resets the trend
+        let mut ping = PlayerPing::new(loc(0.02), hider, hider);
+        ping.timestamp += TimeDelta::seconds(1);
+        state.add_ping(ping);
+        assert!(state.update_proximity_trends());
+        assert_eq!(
+            state.get_proximity_trend(hider),
+            Some(ProximityTrend::Unknown)
+        );
+
+        // Caught hiders aren't tracked
+        state.mark_caught(hider);
+        state.update_proximity_trends();
+        assert_eq!(state.get_proximity_trend(hider), None);
+    }
+
+    #[test]
+    fn test_ping_derived_heading() {
+        let id = Uuid::from_u128(1);
+        let mut state = GameState::new(
+            GameSettings::default(),
+            id,
+            id,
+            HashMap::from_iter([(id, false)]),
+        );
+
+        state.push_loc(loc(0.0));
+        let ping = state.create_self_ping().expect("No ping");
+        assert_eq!(ping.loc.heading, None, "Heading derived without movement");
+
+        // Move east
+        state.push_loc(Location {
+            lat: 0.0,
+            long: 0.001,
+            heading: None,
+        });
+        let ping = state.create_self_ping().expect("No ping");
+        let heading = ping.loc.heading.expect("Heading not derived from movement");
+        assert!(
+            (heading - 0.25).abs() < 0.001,
+            "Heading {heading} is not east"
+        );
+
+        // Raw heading from GPS should be kept
+        state.push_loc(Location {
+            lat: 0.001,
+            long: 0.001,
+            heading: Some(0.5),
+        });
+        let ping = state.create_self_ping().expect("No ping");
+        assert_eq!(ping.loc.heading, Some(0.5));
+    }
+
+    #[test]
+    fn test_last_seen() {
+        let a = Uuid::from_u128(1);
+        let b = Uuid::from_u128(2);
+        let mut state = GameState::new(
+            GameSettings::default(),
+            a,
+            a,
+            HashMap::from_iter([(a, false), (b, false)]),
+        );
+
+        state.push_loc(loc(0.0));
+        let ping = state.create_self_ping().expect("No ping");
+        let timestamp = ping.timestamp;
+        state.add_ping(ping);
+
+        let ui = state.as_ui_state();
+        assert_eq!(ui.last_seen.get(&a), Some(&timestamp));
+        assert!(
+            !ui.last_seen.contains_key(&b),
+            "Player that was never pinged has a last seen time"
+        );
+    }
+
+    #[test]
+    fn test_safe_zones() {
+        let id = Uuid::from_u128(1);
+        let settings = GameSettings {
+            safe_zones: vec![(loc(0.0), 50.0)],
+            ..Default::default()
+        };
+        let mut state = GameState::new(settings, id, id, HashMap::from_iter([(id, false)]));
+
+        state.push_loc(loc(0.0));
+        assert!(
+            !state.is_catchable(id),
+            "Hider inside a safe zone is catchable"
+        );
+        assert_eq!(state.as_ui_state().in_safe_zone.get(&id), Some(&true));
+
+        // About 111m north, well outside the zone
+        state.push_loc(loc(0.001));
+        assert!(
+            state.is_catchable(id),
+            "Hider outside a safe zone isn't catchable"
+        );
+        assert_eq!(state.as_ui_state().in_safe_zone.get(&id), Some(&false));
+    }
+
+    #[test]
+    fn test_out_of_bounds() {
+        let id = Uuid::from_u128(1);
+        let point = |lat, long| Location {
+            lat,
+            long,
+            heading: None,
+        };
+        let settings = GameSettings {
+            play_area: Some(vec![
+                point(-1.0, -1.0),
+                point(-1.0, 1.0),
+                point(1.0, 1.0),
+                point(1.0, -1.0),
+            ]),
+            ..Default::default()
+        };
+        let mut state = GameState::new(settings, id, id, HashMap::from_iter([(id, false)]));
+
+        // No location yet, nothing to warn about
+        assert_eq!(state.update_out_of_bounds(), None);
+
+        state.push_loc(loc(0.0));
+        assert_eq!(state.update_out_of_bounds(), None);
+        assert!(!state.as_ui_state().out_of_bounds);
+
+        state.push_loc(loc(2.0));
+        assert_eq!(state.update_out_of_bounds(), Some(true));
+        assert_eq!(state.update_out_of_bounds(), None, "Warned twice");
+        assert!(state.as_ui_state().out_of_bounds);
+
+        state.push_loc(loc(0.5));
+        assert_eq!(state.update_out_of_bounds(), Some(false));
+        assert!(!state.as_ui_state().out_of_bounds);
+    }
+
+    fn start_state(ping_start: PingStartCondition, powerup_start: PingStartCondition) -> GameState {
+        let settings = GameSettings {
+            ping_start,
+            powerup_start,
+            powerup_locations: vec![loc(0.0)],
+            ..Default::default()
+        };
+        let caught_state = HashMap::from_iter((0..4).map(|i| (Uuid::from_u128(i), i == 0)));
+        GameState::new(
+            settings,
+            Uuid::from_u128(0),
+            Uuid::from_u128(0),
+            caught_state,
+        )
+    }
+
+    #[test]
+    fn test_start_conditions_players() {
+        let mut state = start_state(
+            PingStartCondition::Players(2),
+            PingStartCondition::Players(3),
+        );
+        let now = state.game_started;
+
+        assert!(!state.should_start_pings(now));
+        assert!(!state.should_start_powerups(now));
+
+        state.mark_caught(Uuid::from_u128(1));
+        assert!(
+            state.should_start_pings(now),
+            "Pings didn't start at 2 seekers"
+        );
+        assert!(
+            !state.should_start_powerups(now),
+            "Powerups started using the ping condition"
+        );
+
+        state.mark_caught(Uuid::from_u128(2));
+        assert!(state.should_start_powerups(now));
+    }
+
+    #[test]
+    fn test_start_conditions_independent() {
+        let mut state = start_state(PingStartCondition::Instant, PingStartCondition::Minutes(5));
+        let now = state.game_started;
+
+        assert!(state.should_start_pings(now));
+        assert!(
+            !state.should_start_powerups(now),
+            "Powerups started before seekers were released"
+        );
+
+        state.release_seekers(now);
+        assert!(!state.should_start_powerups(now + TimeDelta::minutes(4)));
+        assert!(state.should_start_powerups(now + TimeDelta::minutes(5)));
+
+        let state = start_state(PingStartCondition::Minutes(5), PingStartCondition::Instant);
+        let now = state.game_started;
+
+        assert!(
+            !state.should_start_pings(now),
+            "Pings started using the powerup condition"
+        );
+        assert!(state.should_start_powerups(now));
+    }
+
+    #[test]
+    fn test_powerups_need_locations() {
+        let mut state = start_state(PingStartCondition::Instant, PingStartCondition::Instant);
+        state.settings.powerup_locations.clear();
+        let now = state.game_started;
+
+        assert!(state.should_start_pings(now));
+        assert!(!state.should_start_powerups(now));
+    }
+
+    #[test]
+    fn test_seekers_release_at() {
+        let id = Uuid::from_u128(1);
+        let settings = GameSettings {
+            hiding_time_seconds: 90,
+            ..Default::default()
+        };
+        let state = GameState::new(settings, id, id, HashMap::from_iter([(id, false)]));
+
+        let release_at = state.seekers_release_at();
+        assert_eq!(release_at - state.game_started, TimeDelta::seconds(90));
+        assert!(!state.should_release_seekers(release_at - TimeDelta::seconds(1)));
+        assert!(state.should_release_seekers(release_at));
+        assert_eq!(state.as_ui_state().seekers_release_at, release_at);
+    }
+
+    #[test]
+    fn test_new_spectator() {
+        let host = Uuid::from_u128(1);
+        let hider = Uuid::from_u128(2);
+        let spectator = Uuid::from_u128(3);
+        let caught_state = HashMap::from_iter([(host, true), (hider, false)]);
+
+        let host_state = GameState::new(GameSettings::default(), host, host, caught_state);
+        let GameEvent::SpectatorSync {
+            settings,
+            host,
+            game_id,
+            game_started,
+            caught_state,
+            pings,
+        } = host_state.create_spectator_sync()
+        else {
+            panic!("Didn't create a spectator sync");
+        };
+
+        let mut state = GameState::new_spectator(
+            settings,
+            spectator,
+            host,
+            game_id,
+            game_started,
+            caught_state,
+            pings,
+        );
+
+        assert!(state.is_spectator());
+        assert!(state.as_ui_state().spectating);
+        assert_eq!(state.game_started, host_state.game_started);
+        assert_eq!(state.game_id, host_state.game_id);
+        assert_eq!(state.get_caught(spectator), None);
+
+        state.end_game();
+        assert!(
+            !state.player_histories.contains_key(&spectator),
+            "Spectator added their own history"
+        );
+    }
+
+    #[test]
+    fn test_distance_traveled() {
+        let id = Uuid::from_u128(1);
+        let caught_state = HashMap::from_iter([(id, false)]);
+        let mut state = GameState::new(GameSettings::default(), id, id, caught_state);
+        assert_eq!(state.distance_traveled(), 0.0);
+
+        let t = |s: i64| UtcDT::default() + TimeDelta::seconds(s);
+        // A degree of latitude is about 111km, so each step is about 111m
+        state.location_history = vec![
+            (t(0), loc(0.0)),
+            (t(60), loc(0.001)),
+            (t(120), loc(0.002)),
+            // GPS glitch a kilometer away and back, both jumps are left out
+            (t(121), loc(0.012)),
+            (t(122), loc(0.002)),
+            (t(180), loc(0.001)),
+        ];
+
+        let expected = 3.0 * loc(0.0).distance_to(&loc(0.001));
+        assert!(
+            (state.distance_traveled() - expected).abs() < 0.01,
+            "Expected {expected}m, got {}m",
+            state.distance_traveled()
+        );
+
+        state.end_game();
+        assert!(
+            (state.distance_traveled() - expected).abs() < 0.01,
+            "Distance reset when the game ended"
+        );
+    }
+
+    #[test]
+    fn test_camping_moved() {
+        let (seeker, hider) = (Uuid::from_u128(1), Uuid::from_u128(2));
+        let caught_state = HashMap::from_iter([(seeker, true), (hider, false)]);
+        let settings = GameSettings {
+            reveal_after_minutes: Some(5),
+            ..Default::default()
+        };
+        let mut state = GameState::new(settings, hider, seeker, caught_state);
+
+        let t = |m: i64| UtcDT::default() + TimeDelta::minutes(m);
+        state.release_seekers(t(0));
+        state.location_history = vec![(t(0), loc(0.0)), (t(5), loc(0.0))];
+        assert!(state.should_reveal_for_camping(t(5)));
+        assert!(state.revealed_for_camping());
+
+        // Moved ~110m, the clock starts over
+        state.location_history.push((t(6), loc(0.001)));
+        assert!(!state.should_reveal_for_camping(t(6)));
+        assert!(!state.as_ui_state().revealed_for_camping);
+        assert!(!state.should_reveal_for_camping(t(10)));
+        state.location_history.push((t(11), loc(0.001)));
+        assert!(state.should_reveal_for_camping(t(11)));
+    }
+
+    #[test]
+    fn test_spoofed_pings() {
+        let (seeker, hider) = (Uuid::from_u128(1), Uuid::from_u128(2));
+        let caught_state = HashMap::from_iter([(seeker, true), (hider, false)]);
+        let mut state = GameState::new(GameSettings::default(), hider, seeker, caught_state);
+
+        // A seeker pinged as the hider, like with PingSeeker
+        let bait = PlayerPing::new(loc(1.0), hider, seeker);
+        assert!(bait.spoofed);
+        assert!(!PlayerPing::new(loc(1.0), hider, hider).spoofed);
+        state.add_ping(bait.clone());
+
+        let live = &state.as_ui_state().pings[&hider];
+        assert_eq!(live.real_player, hider, "Gave away who made the ping");
+        assert!(!live.spoofed, "Gave away the ping is spoofed");
+
+        let mut history = mk_history(vec![(UtcDT::default(), GameEvent::Ping(bait.clone()))]);
+        history.locations = vec![(seeker, vec![]), (hider, vec![])];
+        let replayed = history
+            .replay_step(GameSettings::default(), 1)
+            .expect("Step out of range");
+        assert!(
+            replayed.pings[&hider].spoofed,
+            "Replay didn't reveal the spoof"
+        );
+
+        state.end_game();
+        let ended = &state.as_ui_state().pings[&hider];
+        assert_eq!(ended.real_player, seeker);
+        assert!(ended.spoofed);
+
+        let GameEvent::Ping(masked) = GameEvent::Ping(bait.clone()).masked().into_owned() else {
+            panic!("Masking changed the event");
+        };
+        assert_eq!(
+            masked.real_player, hider,
+            "Masked event gave away the spoof"
+        );
+        let GameEvent::FullSync(_, pings) =
+            GameEvent::FullSync(HashMap::new(), HashMap::from_iter([(hider, bait)]))
+                .masked()
+                .into_owned()
+        else {
+            panic!("Masking changed the event");
+        };
+        assert!(!pings[&hider].spoofed, "Masked sync gave away the spoof");
+    }
+
+    #[test]
+    fn test_game_id() {
+        let id = Uuid::from_u128(1);
+        let caught_state = HashMap::from_iter([(id, true)]);
+
+        let state = GameState::new(GameSettings::default(), id, id, caught_state.clone());
+        let other = GameState::new(GameSettings::default(), id, id, caught_state);
+        assert_ne!(state.game_id, other.game_id);
+
+        let mut restored = GameState::from_snapshot(state.snapshot());
+        assert_eq!(restored.game_id, state.game_id);
+        assert_eq!(restored.take_game_history().id, state.game_id);
+    }
+
+    #[test]
+    fn test_seeker_pins_hidden_from_hiders() {
+        let (seeker, hider) = (Uuid::from_u128(1), Uuid::from_u128(2));
+        let caught_state = HashMap::from_iter([(seeker, true), (hider, false)]);
+        let mut hider_state =
+            GameState::new(GameSettings::default(), hider, seeker, caught_state.clone());
+        let mut seeker_state =
+            GameState::new(GameSettings::default(), seeker, seeker, caught_state);
+
+        let event = GameEvent::SeekerPin(loc(1.0), Some("north park".to_string()));
+        for state in [&mut hider_state, &mut seeker_state] {
+            state.apply_event(UtcDT::default(), &event);
+        }
+
+        assert!(hider_state.as_ui_state().seeker_pins.is_empty());
+        assert_eq!(seeker_state.as_ui_state().seeker_pins.len(), 1);
+        assert_eq!(
+            GameState::from_snapshot(seeker_state.snapshot()).seeker_pins(),
+            seeker_state.seeker_pins()
+        );
+    }
 }
@@ -6,19 +6,29 @@ mod location;
 mod powerups;
 mod profile;
 mod settings;
+pub mod storage;
 #[cfg(test)]
 mod tests;
+mod tiles;
 mod transport;
+mod units;
 
-pub use game::{Game, StateUpdateSender, UtcDT};
-pub use game_events::GameEvent;
-pub use game_state::{GameHistory, GameUiState};
-pub use lobby::{Lobby, LobbyMessage, LobbyState, StartGameInfo};
+pub use game::{EventDirection, Game, GameAlert, GameId, StateUpdateSender, UtcDT};
+pub use game_events::{CatchReason, GameEvent};
+pub use game_state::{
+    GameAwards, GameHistory, GamePhase, GameSnapshot, GameUiState, PingPhase, PlayerView,
+    PowerupTimelineEntry, ProximityTrend,
+};
+pub use lobby::{Lobby, LobbyAlert, LobbyMapPreview, LobbyMessage, LobbyState, StartGameInfo};
 pub use location::{Location, LocationService};
 pub use powerups::PowerUpType;
-pub use profile::PlayerProfile;
-pub use settings::GameSettings;
-pub use transport::{MsgPair, Transport, TransportMessage};
+pub use profile::{PlayerProfile, anonymize_profiles};
+pub use settings::{GameSettings, SettingsSummary};
+pub use tiles::{MAX_TILE_ZOOM, TileBounds, TileCoord};
+pub use transport::{
+    DisconnectReason, MsgPair, PEER_PROTOCOL_VERSION, Transport, TransportMessage,
+};
+pub use units::Units;
 
 pub mod prelude {
     use anyhow::Error as AnyhowError;
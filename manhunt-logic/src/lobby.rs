@@ -1,27 +1,62 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
-use anyhow::anyhow;
+use anyhow::{anyhow, bail};
+use rand::{SeedableRng, seq::IndexedRandom};
+use rand_chacha::ChaCha20Rng;
 use serde::{Deserialize, Serialize};
 use tokio::sync::Mutex;
 use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
 use crate::{
-    game::StateUpdateSender,
+    game::{GameId, StateUpdateSender},
+    location::Location,
     prelude::*,
-    profile::PlayerProfile,
+    profile::{PlayerProfile, assign_colors, is_valid_color},
     settings::GameSettings,
-    transport::{Transport, TransportMessage},
+    tiles::TileBounds,
+    transport::{DisconnectReason, PEER_PROTOCOL_VERSION, Transport, TransportMessage},
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StartGameInfo {
     pub settings: GameSettings,
     pub initial_caught_state: HashMap<Uuid, bool>,
+    /// ID of the player that hosted the lobby, they're the only one allowed to end the game early
+    pub host: Uuid,
+    /// Picked by the host so everyone saves the game's history under the same ID
+    pub game_id: GameId,
+}
+
+/// Randomly pick `count` of the given players to be seekers, everyone else is a hider. The same
+/// players and seed always give the same teams.
+fn auto_assign_teams(
+    players: impl Iterator<Item = Uuid>,
+    count: u32,
+    seed: u32,
+) -> HashMap<Uuid, bool> {
+    let mut players = players.collect::<Vec<_>>();
+    // Sorted so the order players joined in doesn't change the outcome
+    players.sort();
+    let mut rng = ChaCha20Rng::seed_from_u64(seed as u64);
+    let seekers = players
+        .sample(&mut rng, count as usize)
+        .copied()
+        .collect::<Vec<_>>();
+    players
+        .into_iter()
+        .map(|id| (id, seekers.contains(&id)))
+        .collect()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum LobbyMessage {
+    /// Sent to a new peer before anything else, peers with a different protocol version are
+    /// ignored from then on
+    Hello { protocol_version: u32 },
     /// Message sent on a new peer, to sync profiles
     PlayerSync(Uuid, PlayerProfile),
     /// Message sent on a new peer from the host, to sync game settings
@@ -30,8 +65,35 @@ pub enum LobbyMessage {
     StartGame(StartGameInfo),
     /// A player has switched teams
     PlayerSwitch(Uuid, bool),
+    /// Host signals going back to the lobby after a game for a rematch
+    Rematch,
+    /// Host assigned unique colors to players
+    AssignColors(HashMap<Uuid, String>),
+    /// A player thinks they're missing profiles and is asking everyone to send their
+    /// [LobbyMessage::PlayerSync] again, contains the ID of the player requesting
+    RequestProfileSync(Uuid),
+}
+
+/// Someone joining or leaving the lobby, for the UI to make noise about (e.g. with a sound or
+/// toast) without having to diff [LobbyState::profiles]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LobbyAlert {
+    /// A player we didn't know about sent us their profile
+    PlayerJoined { id: Uuid, name: String },
+    /// A player we knew about left on purpose
+    PlayerLeft(Uuid),
+    /// A player we knew about disconnected without saying they were leaving, they probably lost
+    /// connection
+    PlayerDisconnected(Uuid),
+    /// A player is running a version of the app we can't play with, we're ignoring them. Contains
+    /// the [PEER_PROTOCOL_VERSION] they're on.
+    IncompatiblePeer { id: Uuid, protocol_version: u32 },
 }
 
+/// Reported in [LobbyAlert::IncompatiblePeer] for peers that never sent a [LobbyMessage::Hello],
+/// they're from before protocol versions were announced
+pub const UNANNOUNCED_PROTOCOL_VERSION: u32 = 0;
+
 #[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
 pub struct LobbyState {
     profiles: HashMap<Uuid, PlayerProfile>,
@@ -40,12 +102,96 @@ pub struct LobbyState {
     teams: HashMap<Uuid, bool>,
     self_id: Uuid,
     is_host: bool,
+    /// Everything in [LobbyMapPreview] is left out when serialized so big location lists aren't
+    /// sent with every update, get those from the preview instead
+    #[serde(serialize_with = "serialize_without_map")]
     settings: GameSettings,
+    /// Peers that announced an incompatible protocol version
+    #[serde(skip)]
+    incompatible_peers: HashSet<Uuid>,
+    /// Peers that sent a compatible [LobbyMessage::Hello]
+    #[serde(skip)]
+    greeted_peers: HashSet<Uuid>,
+    /// Goes up whenever anything in [LobbyMapPreview] changes, so the map only needs to be
+    /// fetched again when this does
+    #[serde(default)]
+    map_revision: u32,
+}
+
+impl LobbyState {
+    fn set_settings(&mut self, settings: GameSettings) {
+        if LobbyMapPreview::from(&settings) != LobbyMapPreview::from(&self.settings) {
+            self.map_revision = self.map_revision.wrapping_add(1);
+        }
+        self.settings = settings;
+    }
+
+    /// Which team everyone in the lobby is on, true for seekers
+    pub fn teams(&self) -> &HashMap<Uuid, bool> {
+        &self.teams
+    }
+}
+
+fn serialize_without_map<S: serde::Serializer>(
+    settings: &GameSettings,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    GameSettings {
+        play_area: None,
+        powerup_locations: vec![],
+        safe_zones: vec![],
+        jail_location: None,
+        ..settings.clone()
+    }
+    .serialize(serializer)
+}
+
+/// Everything from the lobby's [GameSettings] that goes on a map, for previewing the game area
+/// before it starts. Kept out of [LobbyState] updates, check [LobbyState]'s `map_revision` to know
+/// when to get it again.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+pub struct LobbyMapPreview {
+    /// See [GameSettings::play_area]
+    pub play_area: Option<Vec<Location>>,
+    /// See [GameSettings::powerup_locations]
+    pub powerup_locations: Vec<Location>,
+    /// See [GameSettings::safe_zones]
+    pub safe_zones: Vec<(Location, f64)>,
+    /// See [GameSettings::jail_location]
+    pub jail_location: Option<Location>,
+    /// Smallest area containing everything above, for fitting the map to it. [Option::None] if
+    /// there's nothing to show.
+    pub bounds: Option<TileBounds>,
+}
+
+impl From<&GameSettings> for LobbyMapPreview {
+    fn from(settings: &GameSettings) -> Self {
+        let bounds = TileBounds::around(
+            settings
+                .play_area
+                .iter()
+                .flatten()
+                .chain(settings.powerup_locations.iter())
+                .chain(settings.safe_zones.iter().map(|(center, _)| center))
+                .chain(settings.jail_location.iter()),
+        );
+
+        Self {
+            play_area: settings.play_area.clone(),
+            powerup_locations: settings.powerup_locations.clone(),
+            safe_zones: settings.safe_zones.clone(),
+            jail_location: settings.jail_location,
+            bounds,
+        }
+    }
 }
 
 pub struct Lobby<T: Transport, U: StateUpdateSender> {
     is_host: bool,
     join_code: String,
+    /// Announced to peers with [LobbyMessage::Hello], always [PEER_PROTOCOL_VERSION] outside of
+    /// tests
+    protocol_version: u32,
     state: Mutex<LobbyState>,
     transport: Arc<T>,
     state_updates: U,
@@ -89,6 +235,7 @@ impl<T: Transport, U: StateUpdateSender> Lobby<T, U> {
             transport,
             state_updates,
             is_host,
+            protocol_version: PEER_PROTOCOL_VERSION,
             cancel: CancellationToken::new(),
             join_code: join_code.to_string(),
             state: Mutex::new(LobbyState {
@@ -98,10 +245,100 @@ impl<T: Transport, U: StateUpdateSender> Lobby<T, U> {
                 self_id,
                 is_host,
                 settings,
+                incompatible_peers: HashSet::new(),
+                greeted_peers: HashSet::new(),
+                map_revision: 0,
             }),
         }
     }
 
+    /// Pretend to be a different version of the app
+    #[cfg(test)]
+    fn with_protocol_version(mut self, protocol_version: u32) -> Self {
+        self.protocol_version = protocol_version;
+        self
+    }
+
+    /// Create a lobby for a rematch from the transport of a game that just ended. Everyone from
+    /// the game is carried over (as hiders) since peers won't re-sync on an existing transport.
+    /// Use [Lobby::wait_for_rematch] to know when the host starts it.
+    pub fn rematch(
+        join_code: &str,
+        is_host: bool,
+        profiles: HashMap<Uuid, PlayerProfile>,
+        settings: GameSettings,
+        state_updates: U,
+        transport: Arc<T>,
+    ) -> Arc<Self> {
+        let self_id = transport.self_id();
+        let mut teams = HashMap::from_iter(profiles.keys().map(|id| (*id, false)));
+        teams.insert(self_id, false);
+        // Everyone from the game already said hello, they won't again on the same transport
+        let greeted_peers = profiles.keys().copied().collect();
+        Arc::new(Self {
+            transport,
+            state_updates,
+            is_host,
+            protocol_version: PEER_PROTOCOL_VERSION,
+            cancel: CancellationToken::new(),
+            join_code: join_code.to_string(),
+            state: Mutex::new(LobbyState {
+                teams,
+                join_code: join_code.to_string(),
+                profiles,
+                self_id,
+                is_host,
+                settings,
+                incompatible_peers: HashSet::new(),
+                greeted_peers,
+                map_revision: 0,
+            }),
+        })
+    }
+
+    /// Connect to the same room again with a fresh transport, carrying over our profile, team, and
+    /// settings. Other peers will re-sync with us as they connect to the new transport.
+    pub async fn reconnect(&self, state_updates: U) -> Result<Arc<Self>> {
+        let transport = T::initialize(&self.join_code, self.is_host)
+            .await
+            .context("Failed to connect to lobby")?;
+
+        Ok(self
+            .reconnect_with_transport(state_updates, transport)
+            .await)
+    }
+
+    /// Same as [Lobby::reconnect] but with an already connected transport
+    pub async fn reconnect_with_transport(&self, state_updates: U, transport: Arc<T>) -> Arc<Self> {
+        let state = self.clone_state().await;
+        let profile = state.profiles[&state.self_id].clone();
+        let seeker = state.teams[&state.self_id];
+
+        let lobby = Arc::new(Self::new_with_transport(
+            &self.join_code,
+            self.is_host,
+            profile,
+            state.settings,
+            state_updates,
+            transport,
+        ));
+
+        let mut new_state = lobby.state.lock().await;
+        let id = new_state.self_id;
+        new_state.teams.insert(id, seeker);
+        drop(new_state);
+
+        lobby
+    }
+
+    pub fn is_host(&self) -> bool {
+        self.is_host
+    }
+
+    pub fn join_code(&self) -> &str {
+        &self.join_code
+    }
+
     fn emit_state_update(&self) {
         self.state_updates.send_update();
     }
@@ -114,18 +351,27 @@ impl<T: Transport, U: StateUpdateSender> Lobby<T, U> {
         }
     }
 
-    async fn signaling_mark_started(&self) {
-        self.transport.mark_room_started(&self.join_code).await
+    async fn signaling_mark_started(&self, allow_spectators: bool) {
+        self.transport
+            .mark_room_started(&self.join_code, allow_spectators)
+            .await
     }
 
     async fn handle_lobby(&self, msg: LobbyMessage) -> Option<StartGameInfo> {
         let mut state = self.state.lock().await;
         match msg {
+            // Handled in [Lobby::handle_message] as it needs to know who sent it
+            LobbyMessage::Hello { .. } => {}
             LobbyMessage::PlayerSync(peer, player_profile) => {
-                state.profiles.insert(peer, player_profile);
+                let name = player_profile.display_name.clone();
+                let known = state.profiles.insert(peer, player_profile).is_some();
+                if !known && peer != state.self_id {
+                    self.state_updates
+                        .send_lobby_alert(LobbyAlert::PlayerJoined { id: peer, name });
+                }
             }
             LobbyMessage::HostPush(game_settings) => {
-                state.settings = game_settings;
+                state.set_settings(game_settings);
             }
             LobbyMessage::StartGame(start_game_info) => {
                 return Some(start_game_info);
@@ -133,6 +379,25 @@ impl<T: Transport, U: StateUpdateSender> Lobby<T, U> {
             LobbyMessage::PlayerSwitch(peer, seeker) => {
                 state.teams.insert(peer, seeker);
             }
+            // Only meaningful in [Lobby::wait_for_rematch]
+            LobbyMessage::Rematch => {}
+            LobbyMessage::AssignColors(colors) => {
+                for (id, color) in colors {
+                    if let Some(profile) = state.profiles.get_mut(&id)
+                        && is_valid_color(&color)
+                    {
+                        profile.color = Some(color);
+                    }
+                }
+            }
+            LobbyMessage::RequestProfileSync(peer) => {
+                let id = state.self_id;
+                if peer != id {
+                    let msg = LobbyMessage::PlayerSync(id, state.profiles[&id].clone());
+                    drop(state);
+                    self.send_transport_message(Some(peer), msg).await;
+                }
+            }
         }
         None
     }
@@ -149,11 +414,34 @@ impl<T: Transport, U: StateUpdateSender> Lobby<T, U> {
                 eprintln!("Peer {peer:?} sent a GameEvent???: {game_event:?}");
                 None
             }
-            TransportMessage::Lobby(lobby_message) => self
-                .handle_lobby(*lobby_message)
-                .await
-                .map(|start_game| Ok(Some(start_game))),
+            TransportMessage::Lobby(lobby_message) => match *lobby_message {
+                LobbyMessage::Hello { protocol_version } => {
+                    if let Some(peer) = peer {
+                        self.check_protocol_version(peer, protocol_version).await;
+                    }
+                    None
+                }
+                _ if self.is_incompatible(peer).await => None,
+                // Compatible peers always say hello before these on the same ordered channel
+                LobbyMessage::PlayerSync(..) | LobbyMessage::PlayerSwitch(..)
+                    if !self.has_greeted(peer).await =>
+                {
+                    if let Some(peer) = peer {
+                        self.mark_incompatible(peer, UNANNOUNCED_PROTOCOL_VERSION)
+                            .await;
+                    }
+                    None
+                }
+                msg => self
+                    .handle_lobby(msg)
+                    .await
+                    .map(|start_game| Ok(Some(start_game))),
+            },
             TransportMessage::PeerConnect(peer) => {
+                let hello = LobbyMessage::Hello {
+                    protocol_version: self.protocol_version,
+                };
+                self.send_transport_message(Some(peer), hello).await;
                 let state = self.state.lock().await;
                 let id = state.self_id;
                 let msg = LobbyMessage::PlayerSync(id, state.profiles[&id].clone());
@@ -169,14 +457,76 @@ impl<T: Transport, U: StateUpdateSender> Lobby<T, U> {
                 }
                 None
             }
-            TransportMessage::PeerDisconnect(peer) => {
-                let mut state = self.state.lock().await;
-                if peer != state.self_id {
-                    state.profiles.remove(&peer);
-                    state.teams.remove(&peer);
+            TransportMessage::PeerLeaving(_) => {
+                if let Some(peer) = peer {
+                    self.remove_peer(peer, LobbyAlert::PlayerLeft(peer)).await;
                 }
                 None
             }
+            TransportMessage::PeerDisconnect(peer) => {
+                // Peers that said they were leaving are already gone
+                self.remove_peer(peer, LobbyAlert::PlayerDisconnected(peer))
+                    .await;
+                None
+            }
+        }
+    }
+
+    async fn is_incompatible(&self, peer: Option<Uuid>) -> bool {
+        let Some(peer) = peer else {
+            return false;
+        };
+        self.state.lock().await.incompatible_peers.contains(&peer)
+    }
+
+    /// Whether a peer sent us a compatible [LobbyMessage::Hello], we always trust ourselves
+    async fn has_greeted(&self, peer: Option<Uuid>) -> bool {
+        let Some(peer) = peer else {
+            return true;
+        };
+        let state = self.state.lock().await;
+        peer == state.self_id || state.greeted_peers.contains(&peer)
+    }
+
+    /// Stop talking to a peer if their [LobbyMessage::Hello] says they're on a different protocol
+    /// version than us, they won't be part of the game
+    async fn check_protocol_version(&self, peer: Uuid, protocol_version: u32) {
+        if protocol_version == self.protocol_version {
+            let mut state = self.state.lock().await;
+            state.greeted_peers.insert(peer);
+            // Their hello can come after a broadcast they sent before they saw us connect
+            state.incompatible_peers.remove(&peer);
+            return;
+        }
+
+        self.mark_incompatible(peer, protocol_version).await;
+    }
+
+    /// Ignore a peer from now on and forget what we knew about them
+    async fn mark_incompatible(&self, peer: Uuid, protocol_version: u32) {
+        let mut state = self.state.lock().await;
+        if peer != state.self_id && state.incompatible_peers.insert(peer) {
+            state.profiles.remove(&peer);
+            state.teams.remove(&peer);
+            drop(state);
+            self.state_updates
+                .send_lobby_alert(LobbyAlert::IncompatiblePeer {
+                    id: peer,
+                    protocol_version,
+                });
+        }
+    }
+
+    /// Forget about a peer, sending `alert` if we knew them
+    async fn remove_peer(&self, peer: Uuid, alert: LobbyAlert) {
+        let mut state = self.state.lock().await;
+        if peer != state.self_id {
+            state.incompatible_peers.remove(&peer);
+            state.greeted_peers.remove(&peer);
+            state.teams.remove(&peer);
+            if state.profiles.remove(&peer).is_some() {
+                self.state_updates.send_lobby_alert(alert);
+            }
         }
     }
 
@@ -201,13 +551,79 @@ impl<T: Transport, U: StateUpdateSender> Lobby<T, U> {
             }
         };
 
-        if let Ok(None) | Err(_) = res {
-            self.transport.disconnect().await;
+        match res {
+            Ok(None) => {
+                self.transport
+                    .disconnect_with_reason(DisconnectReason::Quit)
+                    .await
+            }
+            Err(_) => self.transport.disconnect().await,
+            Ok(Some(_)) => {}
         }
 
         res
     }
 
+    /// Wait for the host to start a rematch, returns whether they did. If the user quits first
+    /// the transport is disconnected. Players that leave in the meantime are removed.
+    pub async fn wait_for_rematch(&self) -> Result<bool> {
+        let res = 'wait: loop {
+            tokio::select! {
+                biased;
+
+                msgs = self.transport.receive_messages() => {
+                    for (_, msg) in msgs {
+                        match msg {
+                            TransportMessage::Lobby(msg) if matches!(*msg, LobbyMessage::Rematch) => {
+                                break 'wait Ok(true);
+                            }
+                            TransportMessage::PeerDisconnect(peer) => {
+                                let mut state = self.state.lock().await;
+                                if peer != state.self_id {
+                                    state.profiles.remove(&peer);
+                                    state.teams.remove(&peer);
+                                }
+                            }
+                            TransportMessage::Disconnected => break 'wait Ok(false),
+                            TransportMessage::Error(why) => {
+                                break 'wait Err(anyhow!("Transport error: {why}"));
+                            }
+                            // Stragglers from the game that just ended
+                            _ => {}
+                        }
+                    }
+                }
+
+                _ = self.cancel.cancelled() => {
+                    break Ok(false);
+                }
+            }
+        };
+
+        match res {
+            Ok(false) => {
+                self.transport
+                    .disconnect_with_reason(DisconnectReason::GameOver)
+                    .await
+            }
+            Err(_) => self.transport.disconnect().await,
+            Ok(true) => {}
+        }
+
+        res
+    }
+
+    /// (Host) Bring everyone from the game that just ended back to the lobby
+    pub async fn start_rematch(&self) -> Result {
+        if !self.is_host {
+            bail!("Only the host can start a rematch");
+        }
+        let msg = LobbyMessage::Rematch;
+        self.transport.send_self(msg.clone().into()).await;
+        self.send_transport_message(None, msg).await;
+        Ok(())
+    }
+
     pub fn clone_transport(&self) -> Arc<T> {
         self.transport.clone()
     }
@@ -216,6 +632,15 @@ impl<T: Transport, U: StateUpdateSender> Lobby<T, U> {
         self.state.lock().await.clone()
     }
 
+    pub async fn clone_settings(&self) -> GameSettings {
+        self.state.lock().await.settings.clone()
+    }
+
+    /// What the current settings would put on the map, see [LobbyMapPreview]
+    pub async fn map_preview(&self) -> LobbyMapPreview {
+        LobbyMapPreview::from(&self.state.lock().await.settings)
+    }
+
     pub async fn clone_profiles(&self) -> HashMap<Uuid, PlayerProfile> {
         let state = self.state.lock().await;
         state.profiles.clone()
@@ -234,11 +659,67 @@ impl<T: Transport, U: StateUpdateSender> Lobby<T, U> {
         self.emit_state_update();
     }
 
+    /// Set the color of our own map pin and scoreboard row, fails if it isn't a hex color
+    pub async fn set_color(&self, color: Option<String>) -> Result {
+        if let Some(color) = color.as_ref()
+            && !is_valid_color(color)
+        {
+            bail!("\"{color}\" isn't a valid color, use the form #rrggbb");
+        }
+        let mut state = self.state.lock().await;
+        let id = state.self_id;
+        let profile = state.profiles.get_mut(&id).context("Missing own profile")?;
+        profile.color = color;
+        let msg = LobbyMessage::PlayerSync(id, profile.clone());
+        drop(state);
+        self.send_transport_message(None, msg).await;
+        self.emit_state_update();
+        Ok(())
+    }
+
+    /// Send our profile to everyone again and ask them to do the same, for recovering from
+    /// dropped [LobbyMessage::PlayerSync]s
+    pub async fn resync_profiles(&self) -> Result {
+        let state = self.state.lock().await;
+        let id = state.self_id;
+        let profile = state
+            .profiles
+            .get(&id)
+            .context("Missing own profile")?
+            .clone();
+        drop(state);
+        self.send_transport_message(None, LobbyMessage::PlayerSync(id, profile))
+            .await;
+        self.send_transport_message(None, LobbyMessage::RequestProfileSync(id))
+            .await;
+        Ok(())
+    }
+
+    /// (Host) Give every player in the lobby a unique color, keeping colors players picked
+    /// themselves unless someone else already has it
+    pub async fn assign_colors(&self) -> Result {
+        if !self.is_host {
+            bail!("Only the host can assign colors");
+        }
+        let mut state = self.state.lock().await;
+        let colors = assign_colors(&state.profiles);
+        for (id, color) in colors.iter() {
+            if let Some(profile) = state.profiles.get_mut(id) {
+                profile.color = Some(color.clone());
+            }
+        }
+        drop(state);
+        let msg = LobbyMessage::AssignColors(colors);
+        self.send_transport_message(None, msg).await;
+        self.emit_state_update();
+        Ok(())
+    }
+
     /// (Host) Update game settings
     pub async fn update_settings(&self, new_settings: GameSettings) {
         if self.is_host {
             let mut state = self.state.lock().await;
-            state.settings = new_settings.clone();
+            state.set_settings(new_settings.clone());
             drop(state);
             let msg = LobbyMessage::HostPush(new_settings);
             self.send_transport_message(None, msg).await;
@@ -246,20 +727,48 @@ impl<T: Transport, U: StateUpdateSender> Lobby<T, U> {
         }
     }
 
-    /// (Host) Start the game
-    pub async fn start_game(&self) {
+    /// (Host) Start the game, fails if all players are on the same team. If
+    /// [GameSettings::auto_seeker_count] is set, seekers are picked randomly instead of using the
+    /// teams players chose.
+    pub async fn start_game(&self) -> Result {
         if self.is_host {
             let state = self.state.lock().await;
+            // Every game gets a fresh seed so they don't all play out the same
+            let settings = GameSettings {
+                random_seed: rand::random(),
+                ..state.settings.clone()
+            };
+            let mut players = state.teams.clone();
+            // A refereeing host isn't on a team, they'll watch the game instead
+            if !state.settings.host_plays {
+                players.remove(&state.self_id);
+            }
+            let teams = match state.settings.auto_seeker_count {
+                Some(count) => {
+                    auto_assign_teams(players.keys().copied(), count, settings.random_seed)
+                }
+                None => players,
+            };
+            if teams.values().all(|seeker| *seeker) {
+                bail!("Can't start a game without any hiders");
+            }
+            if teams.values().all(|seeker| !*seeker) {
+                bail!("Can't start a game without any seekers");
+            }
             let start_game_info = StartGameInfo {
-                settings: state.settings.clone(),
-                initial_caught_state: state.teams.clone(),
+                settings,
+                initial_caught_state: teams,
+                host: state.self_id,
+                game_id: Uuid::new_v4(),
             };
+            let allow_spectators = state.settings.allow_spectators;
             drop(state);
             let msg = LobbyMessage::StartGame(start_game_info);
-            self.signaling_mark_started().await;
+            self.signaling_mark_started(allow_spectators).await;
             self.transport.send_self(msg.clone().into()).await;
             self.send_transport_message(None, msg).await;
         }
+        Ok(())
     }
 
     pub async fn quit_lobby(&self) {
@@ -270,12 +779,12 @@ impl<T: Transport, U: StateUpdateSender> Lobby<T, U> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::sync::Arc;
+    use std::{collections::HashSet, sync::Arc, time::Duration};
     use tokio::{sync::oneshot, task::yield_now, test};
 
-    use crate::tests::{DummySender, MockTransport};
+    use crate::tests::{AlertRecorder, MockTransport};
 
-    type MockLobby = Lobby<MockTransport, DummySender>;
+    type MockLobby = Lobby<MockTransport, AlertRecorder>;
 
     type CompleteRecv = oneshot::Receiver<Result<Option<StartGameInfo>>>;
 
@@ -286,8 +795,13 @@ mod tests {
 
     impl MockLobbyPool {
         pub fn new(num_players: u32) -> Self {
+            Self::with_protocol_versions(&vec![PEER_PROTOCOL_VERSION; num_players as usize])
+        }
+
+        /// Make a pool where each lobby announces the given protocol version
+        pub fn with_protocol_versions(versions: &[u32]) -> Self {
             let settings = GameSettings::default();
-            let (uuids, transports) = MockTransport::create_mesh(num_players);
+            let (uuids, transports) = MockTransport::create_mesh(versions.len() as u32);
 
             let lobbies = transports
                 .into_iter()
@@ -296,16 +810,20 @@ mod tests {
                     let profile = PlayerProfile {
                         display_name: format!("Lobby {i} ({})", uuids[i]),
                         pfp_base64: None,
+                        ..Default::default()
                     };
 
-                    Arc::new(MockLobby::new_with_transport(
-                        "aaa",
-                        i == 0,
-                        profile,
-                        settings.clone(),
-                        DummySender,
-                        Arc::new(transport),
-                    ))
+                    Arc::new(
+                        MockLobby::new_with_transport(
+                            "aaa",
+                            i == 0,
+                            profile,
+                            settings.clone(),
+                            AlertRecorder::default(),
+                            Arc::new(transport),
+                        )
+                        .with_protocol_version(versions[i]),
+                    )
                 })
                 .collect();
 
@@ -405,12 +923,203 @@ mod tests {
         .await;
     }
 
+    #[test]
+    async fn test_join_leave_alerts() {
+        let mat = MockLobbyPool::new(3);
+
+        let mut recvs = mat.start_all_loops().await;
+
+        mat.player_join(0).await;
+        mat.player_join(1).await;
+
+        mat.wait().await;
+
+        // Re-syncing a known player shouldn't count as a join
+        mat.lobbies[1]
+            .set_color(Some("#123456".to_string()))
+            .await
+            .expect("Failed to set color");
+
+        mat.wait().await;
+
+        let alerts = mat.lobbies[0].state_updates.lobby_alerts();
+        let joins = alerts
+            .iter()
+            .filter(|a| matches!(a, LobbyAlert::PlayerJoined { id, .. } if *id == mat.uuids[1]))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            joins,
+            vec![&LobbyAlert::PlayerJoined {
+                id: mat.uuids[1],
+                name: format!("Lobby 1 ({})", mat.uuids[1]),
+            }],
+            "0 didn't get exactly one join alert for 1"
+        );
+
+        mat.lobbies[1].quit_lobby().await;
+        recvs.swap_remove(1).await.expect("Failed to recv").ok();
+
+        mat.wait().await;
+
+        let alerts = mat.lobbies[0].state_updates.lobby_alerts();
+        assert_eq!(
+            alerts.last(),
+            Some(&LobbyAlert::PlayerLeft(mat.uuids[1])),
+            "0 didn't get a leave alert for 1"
+        );
+        assert!(
+            mat.lobbies[0]
+                .state_updates
+                .lobby_alerts()
+                .iter()
+                .all(|a| !matches!(a, LobbyAlert::PlayerJoined { id, .. } if *id == mat.uuids[0])),
+            "0 got a join alert for itself"
+        );
+    }
+
+    #[test]
+    async fn test_dropped_player_alert() {
+        let mat = MockLobbyPool::new(3);
+
+        mat.start_all_loops().await;
+
+        mat.player_join(0).await;
+        mat.player_join(1).await;
+        mat.player_join(2).await;
+
+        mat.wait().await;
+
+        // What others see when 1 loses connection, no PeerLeaving first
+        mat.lobbies[1]
+            .clone_transport()
+            .send_message(TransportMessage::PeerDisconnect(mat.uuids[1]))
+            .await;
+
+        mat.wait().await;
+
+        let alerts = mat.lobbies[0].state_updates.lobby_alerts();
+        assert_eq!(
+            alerts.last(),
+            Some(&LobbyAlert::PlayerDisconnected(mat.uuids[1])),
+            "0 didn't get a disconnect alert for 1"
+        );
+        assert!(
+            !alerts.contains(&LobbyAlert::PlayerLeft(mat.uuids[1])),
+            "0 thought 1 left on purpose"
+        );
+    }
+
+    #[test]
+    async fn test_incompatible_peers() {
+        let new_version = PEER_PROTOCOL_VERSION + 1;
+        let mat = MockLobbyPool::with_protocol_versions(&[
+            PEER_PROTOCOL_VERSION,
+            PEER_PROTOCOL_VERSION,
+            new_version,
+        ]);
+
+        mat.start_all_loops().await;
+
+        mat.player_join(0).await;
+        mat.player_join(1).await;
+        mat.player_join(2).await;
+
+        mat.wait().await;
+
+        for i in [0, 1] {
+            mat.assert_state(i, |s| {
+                assert_eq!(
+                    s.profiles.len(),
+                    2,
+                    "{i} doesn't only see the compatible players"
+                );
+                assert!(!s.profiles.contains_key(&mat.uuids[2]), "{i} accepted 2");
+                assert!(!s.teams.contains_key(&mat.uuids[2]), "{i} put 2 on a team");
+            })
+            .await;
+            assert!(
+                mat.lobbies[i].state_updates.lobby_alerts().contains(
+                    &LobbyAlert::IncompatiblePeer {
+                        id: mat.uuids[2],
+                        protocol_version: new_version,
+                    }
+                ),
+                "{i} didn't warn about 2"
+            );
+        }
+
+        mat.assert_state(2, |s| {
+            assert_eq!(s.profiles.len(), 1, "2 accepted older players");
+        })
+        .await;
+        let alerts = mat.lobbies[2].state_updates.lobby_alerts();
+        for i in [0, 1] {
+            assert!(
+                alerts.contains(&LobbyAlert::IncompatiblePeer {
+                    id: mat.uuids[i],
+                    protocol_version: PEER_PROTOCOL_VERSION,
+                }),
+                "2 didn't warn about {i}"
+            );
+        }
+        assert!(
+            !alerts
+                .iter()
+                .any(|a| matches!(a, LobbyAlert::PlayerJoined { .. })),
+            "2 announced an incompatible player joining"
+        );
+    }
+
+    #[test]
+    async fn test_peer_without_hello() {
+        let mat = MockLobbyPool::new(3);
+
+        mat.start_all_loops().await;
+
+        // 2 is from before peers said hello, it goes straight to syncing its profile
+        let transport = &mat.lobbies[2].transport;
+        let profile = PlayerProfile {
+            display_name: "Old Version".to_string(),
+            ..Default::default()
+        };
+        transport
+            .send_message(LobbyMessage::PlayerSync(mat.uuids[2], profile).into())
+            .await;
+        transport
+            .send_message(LobbyMessage::PlayerSwitch(mat.uuids[2], true).into())
+            .await;
+        mat.wait().await;
+
+        for i in [0, 1] {
+            mat.assert_state(i, |s| {
+                assert!(!s.profiles.contains_key(&mat.uuids[2]), "{i} accepted 2");
+                assert!(!s.teams.contains_key(&mat.uuids[2]), "{i} put 2 on a team");
+            })
+            .await;
+            assert!(
+                mat.lobbies[i].state_updates.lobby_alerts().contains(
+                    &LobbyAlert::IncompatiblePeer {
+                        id: mat.uuids[2],
+                        protocol_version: UNANNOUNCED_PROTOCOL_VERSION,
+                    }
+                ),
+                "{i} didn't warn about 2"
+            );
+        }
+    }
+
     #[test]
     async fn test_team_switch() {
         let mat = MockLobbyPool::new(3);
 
         mat.start_all_loops().await;
 
+        // Peers only listen to players that said hello first
+        for i in 0..3 {
+            mat.player_join(i).await;
+        }
+        mat.wait().await;
+
         mat.lobbies[2].switch_teams(true).await;
 
         mat.wait().await;
@@ -450,6 +1159,55 @@ mod tests {
         .await;
     }
 
+    #[test]
+    async fn test_map_preview() {
+        let mat = MockLobbyPool::new(2);
+
+        mat.start_all_loops().await;
+
+        let mut settings = GameSettings::default();
+        let loc = |lat| Location {
+            lat,
+            long: 1.0,
+            heading: None,
+        };
+        settings.powerup_locations = vec![loc(1.0), loc(2.0)];
+        mat.lobbies[0].update_settings(settings.clone()).await;
+        mat.wait().await;
+
+        mat.assert_all_states(|i, s| {
+            assert_eq!(s.map_revision, 1, "{i} didn't see the map change");
+            let serialized = serde_json::to_value(s).expect("Failed to serialize");
+            assert_eq!(
+                serialized["settings"]["powerup_locations"],
+                serde_json::json!([]),
+                "{i} sent the map with the lobby state"
+            );
+        })
+        .await;
+        let preview = mat.lobbies[1].map_preview().await;
+        assert_eq!(preview.powerup_locations, settings.powerup_locations);
+        assert_eq!(
+            preview.bounds,
+            Some(TileBounds {
+                north: 2.0,
+                south: 1.0,
+                east: 1.0,
+                west: 1.0,
+            })
+        );
+
+        // Nothing on the map changed
+        settings.hiding_time_seconds += 1;
+        mat.lobbies[0].update_settings(settings).await;
+        mat.wait().await;
+
+        mat.assert_all_states(|i, s| {
+            assert_eq!(s.map_revision, 1, "{i} thought the map changed");
+        })
+        .await;
+    }
+
     #[test]
     async fn test_update_settings_not_host() {
         let mat = MockLobbyPool::new(2);
@@ -498,17 +1256,25 @@ mod tests {
 
         mat.wait().await;
 
-        mat.lobbies[0].start_game().await;
+        mat.lobbies[0]
+            .start_game()
+            .await
+            .expect("Failed to start game");
 
         mat.wait().await;
 
+        let mut game_ids = HashSet::new();
         for (i, recv) in recvs.into_iter().enumerate() {
             let res = recv.await.expect("Failed to recv");
             match res {
                 Ok(Some(StartGameInfo {
                     settings,
                     initial_caught_state,
+                    host,
+                    game_id,
                 })) => {
+                    game_ids.insert(game_id);
+                    assert_eq!(host, mat.uuids[0], "Lobby {i} does not see 0 as host");
                     assert_eq!(
                         settings.hiding_time_seconds, 45,
                         "Lobby {i} does not match pushed settings"
@@ -548,6 +1314,120 @@ mod tests {
                 }
             }
         }
+        assert_eq!(game_ids.len(), 1, "Lobbies don't agree on the game ID");
+    }
+
+    #[test]
+    async fn test_auto_seekers() {
+        let mat = MockLobbyPool::new(4);
+
+        let recvs = mat.start_all_loops().await;
+
+        for i in 0..4 {
+            mat.player_join(i).await;
+        }
+
+        // Manual teams get ignored
+        mat.lobbies[1].switch_teams(true).await;
+
+        let settings = GameSettings {
+            auto_seeker_count: Some(2),
+            ..Default::default()
+        };
+        mat.lobbies[0].update_settings(settings.clone()).await;
+
+        mat.wait().await;
+
+        mat.lobbies[0]
+            .start_game()
+            .await
+            .expect("Failed to start game");
+
+        mat.wait().await;
+
+        let mut infos = Vec::with_capacity(recvs.len());
+        for recv in recvs {
+            infos.push(
+                recv.await
+                    .expect("Failed to recv")
+                    .expect("Lobby errored")
+                    .expect("Lobby didn't start"),
+            );
+        }
+
+        // Teams come from the seed the host picked for this game, not the lobby's settings
+        let seed = infos[0].settings.random_seed;
+        let expected = auto_assign_teams(mat.uuids.iter().copied(), 2, seed);
+        assert_eq!(expected.values().filter(|seeker| **seeker).count(), 2);
+
+        for (i, info) in infos.into_iter().enumerate() {
+            assert_eq!(
+                info.settings.random_seed, seed,
+                "Lobby {i} got a different seed"
+            );
+            assert_eq!(
+                info.initial_caught_state, expected,
+                "Lobby {i} got different teams"
+            );
+        }
+    }
+
+    #[test]
+    async fn test_host_referees() {
+        let mat = MockLobbyPool::new(3);
+
+        let recvs = mat.start_all_loops().await;
+
+        for i in 0..3 {
+            mat.player_join(i).await;
+        }
+
+        mat.lobbies[1].switch_teams(true).await;
+
+        let settings = GameSettings {
+            host_plays: false,
+            ..Default::default()
+        };
+        mat.lobbies[0].update_settings(settings).await;
+
+        mat.wait().await;
+
+        mat.lobbies[0]
+            .start_game()
+            .await
+            .expect("Failed to start game");
+
+        mat.wait().await;
+
+        let expected = HashMap::from_iter([(mat.uuids[1], true), (mat.uuids[2], false)]);
+
+        for (i, recv) in recvs.into_iter().enumerate() {
+            let info = recv
+                .await
+                .expect("Failed to recv")
+                .expect("Lobby errored")
+                .expect("Lobby didn't start");
+            assert_eq!(
+                info.initial_caught_state, expected,
+                "Host is on a team in lobby {i}"
+            );
+        }
+    }
+
+    #[test]
+    async fn test_auto_assign_teams_deterministic() {
+        let players = (0..10).map(Uuid::from_u128).collect::<Vec<_>>();
+
+        let teams = auto_assign_teams(players.iter().copied(), 3, 42);
+        assert_eq!(teams.len(), 10);
+        assert_eq!(teams.values().filter(|seeker| **seeker).count(), 3);
+
+        // Order players are given in shouldn't matter
+        let reversed = auto_assign_teams(players.iter().rev().copied(), 3, 42);
+        assert_eq!(teams, reversed);
+
+        let everyone = auto_assign_teams(players.iter().copied(), 20, 42);
+        assert!(everyone.values().all(|seeker| *seeker));
     }
 
     #[test]
@@ -584,4 +1464,267 @@ mod tests {
         })
         .await;
     }
+
+    #[test]
+    async fn test_game_start_all_hiders() {
+        let mat = MockLobbyPool::new(3);
+
+        mat.start_all_loops().await;
+        mat.wait().await;
+
+        let res = mat.lobbies[0].start_game().await;
+        assert!(res.is_err(), "Game started with no seekers");
+    }
+
+    #[test]
+    async fn test_game_start_all_seekers() {
+        let mat = MockLobbyPool::new(3);
+
+        mat.start_all_loops().await;
+
+        for lobby in mat.lobbies.iter() {
+            lobby.switch_teams(true).await;
+        }
+
+        mat.wait().await;
+
+        let res = mat.lobbies[0].start_game().await;
+        assert!(res.is_err(), "Game started with no hiders");
+    }
+
+    #[test]
+    async fn test_colors() {
+        let mat = MockLobbyPool::new(3);
+
+        mat.start_all_loops().await;
+
+        for i in 0..3 {
+            mat.player_join(i).await;
+        }
+
+        mat.wait().await;
+
+        assert!(
+            mat.lobbies[1]
+                .set_color(Some("blue".to_string()))
+                .await
+                .is_err(),
+            "Invalid color was accepted"
+        );
+        mat.lobbies[1]
+            .set_color(Some("#123456".to_string()))
+            .await
+            .expect("Failed to set color");
+        mat.lobbies[2]
+            .set_color(Some("#123456".to_string()))
+            .await
+            .expect("Failed to set color");
+
+        mat.wait().await;
+
+        mat.assert_all_states(|i, s| {
+            assert_eq!(
+                s.profiles[&mat.uuids[1]].color.as_deref(),
+                Some("#123456"),
+                "{i} doesn't see 1's color"
+            );
+        })
+        .await;
+
+        assert!(
+            mat.lobbies[1].assign_colors().await.is_err(),
+            "Non-host assigned colors"
+        );
+        mat.lobbies[0]
+            .assign_colors()
+            .await
+            .expect("Failed to assign colors");
+
+        mat.wait().await;
+
+        mat.assert_all_states(|i, s| {
+            let colors = mat
+                .uuids
+                .iter()
+                .map(|id| s.profiles[id].color.clone().expect("Player has no color"))
+                .collect::<HashSet<_>>();
+            assert_eq!(colors.len(), 3, "{i} doesn't see unique colors");
+        })
+        .await;
+    }
+
+    #[test]
+    async fn test_resync_profiles() {
+        let mat = MockLobbyPool::new(3);
+
+        mat.start_all_loops().await;
+
+        for i in 0..3 {
+            mat.player_join(i).await;
+        }
+
+        mat.wait().await;
+
+        // Pretend some PlayerSyncs got dropped
+        for (i, j) in [(0, 1), (0, 2), (1, 0)] {
+            mat.lobbies[i]
+                .state
+                .lock()
+                .await
+                .profiles
+                .remove(&mat.uuids[j]);
+        }
+
+        mat.lobbies[0]
+            .resync_profiles()
+            .await
+            .expect("Failed to resync");
+
+        mat.wait().await;
+
+        mat.assert_all_states(|i, s| {
+            for j in 0..3 {
+                assert!(
+                    s.profiles.contains_key(&mat.uuids[j]),
+                    "{i} doesn't have {j}'s profile after resyncing"
+                );
+            }
+        })
+        .await;
+    }
+
+    #[test]
+    async fn test_reconnect() {
+        let mat = MockLobbyPool::new(2);
+
+        mat.start_all_loops().await;
+
+        mat.lobbies[1].switch_teams(true).await;
+
+        mat.wait().await;
+
+        let old_state = mat.lobbies[1].clone_state().await;
+        let new_lobby = mat.lobbies[1]
+            .reconnect(AlertRecorder::default())
+            .await
+            .expect("Failed to reconnect");
+        let new_state = new_lobby.clone_state().await;
+
+        assert_eq!(
+            new_state.profiles[&new_state.self_id].display_name,
+            old_state.profiles[&old_state.self_id].display_name,
+            "Profile was not carried over"
+        );
+        assert_eq!(
+            new_state.teams.get(&new_state.self_id).copied(),
+            Some(true),
+            "Team was not carried over"
+        );
+        assert_eq!(new_lobby.join_code(), "aaa");
+        assert!(!new_lobby.is_host());
+    }
+
+    #[test]
+    async fn test_rematch() {
+        let settings = GameSettings::default();
+        let (uuids, transports) = MockTransport::create_mesh(3);
+        let profiles = HashMap::from_iter(uuids.iter().enumerate().map(|(i, id)| {
+            (
+                *id,
+                PlayerProfile {
+                    display_name: format!("Lobby {i}"),
+                    pfp_base64: None,
+                    ..Default::default()
+                },
+            )
+        }));
+
+        let lobbies = transports
+            .into_iter()
+            .enumerate()
+            .map(|(i, transport)| {
+                MockLobby::rematch(
+                    "aaa",
+                    i == 0,
+                    profiles.clone(),
+                    settings.clone(),
+                    AlertRecorder::default(),
+                    Arc::new(transport),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let recvs = lobbies
+            .iter()
+            .map(|lobby| {
+                let lobby = lobby.clone();
+                let (send, recv) = oneshot::channel();
+                tokio::spawn(async move {
+                    send.send(lobby.wait_for_rematch().await).ok();
+                });
+                recv
+            })
+            .collect::<Vec<_>>();
+
+        assert!(
+            lobbies[1].start_rematch().await.is_err(),
+            "Non-host was able to start a rematch"
+        );
+
+        lobbies[0]
+            .start_rematch()
+            .await
+            .expect("Host failed to start rematch");
+
+        for (i, recv) in recvs.into_iter().enumerate() {
+            let res = recv.await.expect("Failed to recv");
+            assert!(
+                res.is_ok_and(|rematch| rematch),
+                "Lobby {i} didn't get the rematch"
+            );
+        }
+
+        for (i, lobby) in lobbies.iter().enumerate() {
+            let state = lobby.clone_state().await;
+            assert_eq!(state.profiles.len(), 3, "Lobby {i} is missing players");
+            assert!(
+                uuids.iter().all(|id| state.teams.get(id) == Some(&false)),
+                "Lobby {i} didn't reset everyone to hiders"
+            );
+            assert!(
+                !lobby.transport.is_disconnected(),
+                "Lobby {i} disconnected after rematch"
+            );
+        }
+    }
+
+    #[test]
+    async fn test_quit_start_cycles() {
+        // Quitting right as a lobby starts (or before its loop is even polled) should always
+        // end the loop cleanly instead of hanging
+        for cycle in 0..50 {
+            let mat = MockLobbyPool::new(3);
+            let recvs = mat.start_all_loops().await;
+
+            if cycle % 2 == 0 {
+                mat.player_join(0).await;
+                mat.player_join(1).await;
+            }
+
+            for lobby in mat.lobbies.iter() {
+                lobby.quit_lobby().await;
+            }
+
+            for (i, recv) in recvs.into_iter().enumerate() {
+                let res = tokio::time::timeout(Duration::from_secs(1), recv)
+                    .await
+                    .unwrap_or_else(|_| panic!("Lobby {i} hung quitting on cycle {cycle}"))
+                    .expect("Failed to recv");
+                assert!(
+                    matches!(res, Ok(None)),
+                    "Lobby {i} didn't quit cleanly on cycle {cycle}"
+                );
+            }
+        }
+    }
 }
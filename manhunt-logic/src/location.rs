@@ -14,6 +14,160 @@ pub struct Location {
     pub heading: Option<LocationComponent>,
 }
 
+impl Location {
+    /// Approximate distance to another location in meters, uses the haversine formula
+    pub fn distance_to(&self, other: &Location) -> f64 {
+        const EARTH_RADIUS_METERS: f64 = 6_371_000.0;
+
+        let (lat1, lat2) = (self.lat.to_radians(), other.lat.to_radians());
+        let d_lat = lat2 - lat1;
+        let d_long = (other.long - self.long).to_radians();
+
+        let a =
+            (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_long / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS_METERS * a.sqrt().asin()
+    }
+
+    /// Initial bearing to travel from this location to another, normalized from 0 to 1 like
+    /// [Location::heading]
+    pub fn bearing_to(&self, other: &Location) -> LocationComponent {
+        let (lat1, lat2) = (self.lat.to_radians(), other.lat.to_radians());
+        let d_long = (other.long - self.long).to_radians();
+
+        let y = d_long.sin() * lat2.cos();
+        let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * d_long.cos();
+
+        y.atan2(x).to_degrees().rem_euclid(360.0) / 360.0
+    }
+
+    /// Whether this location is inside the polygon with the given vertices, in order. Uses ray
+    /// casting on latitude and longitude directly, which is accurate enough for play areas that
+    /// don't cross the antimeridian or a pole. Polygons with fewer than 3 vertices contain
+    /// nothing.
+    pub fn in_polygon(&self, polygon: &[Location]) -> bool {
+        if polygon.len() < 3 {
+            return false;
+        }
+
+        let mut inside = false;
+        let mut prev = &polygon[polygon.len() - 1];
+        for vertex in polygon.iter() {
+            // Does a ray going east from us cross the edge from `prev` to `vertex`?
+            if (vertex.lat > self.lat) != (prev.lat > self.lat) {
+                let crossing_long = vertex.long
+                    + (self.lat - vertex.lat) / (prev.lat - vertex.lat) * (prev.long - vertex.long);
+                if self.long < crossing_long {
+                    inside = !inside;
+                }
+            }
+            prev = vertex;
+        }
+
+        inside
+    }
+
+    /// Average position of the given locations, [Option::None] if there are none. Averages
+    /// latitude and longitude directly, so like [Location::in_polygon] this is only meant for
+    /// play-area sized spreads.
+    pub fn centroid<'a>(locations: impl IntoIterator<Item = &'a Location>) -> Option<Location> {
+        let (mut lat, mut long, mut count) = (0.0, 0.0, 0_usize);
+        for loc in locations {
+            lat += loc.lat;
+            long += loc.long;
+            count += 1;
+        }
+
+        (count != 0).then(|| Location {
+            lat: lat / count as f64,
+            long: long / count as f64,
+            heading: None,
+        })
+    }
+
+    /// This location moved the given number of meters north and east (negative to go south and
+    /// west), uses a flat approximation that's fine for short distances
+    pub fn offset_meters(&self, north: f64, east: f64) -> Location {
+        const METERS_PER_DEGREE: f64 = 111_320.0;
+
+        Location {
+            lat: self.lat + north / METERS_PER_DEGREE,
+            long: self.long + east / (METERS_PER_DEGREE * self.lat.to_radians().cos()),
+            heading: None,
+        }
+    }
+}
+
 pub trait LocationService {
     fn get_loc(&self) -> Option<Location>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn loc(lat: f64, long: f64) -> Location {
+        Location {
+            lat,
+            long,
+            heading: None,
+        }
+    }
+
+    #[test]
+    fn test_in_polygon_square() {
+        let square = [loc(0.0, 0.0), loc(0.0, 1.0), loc(1.0, 1.0), loc(1.0, 0.0)];
+
+        assert!(loc(0.5, 0.5).in_polygon(&square));
+        assert!(loc(0.01, 0.99).in_polygon(&square));
+        assert!(!loc(1.5, 0.5).in_polygon(&square));
+        assert!(!loc(0.5, -0.5).in_polygon(&square));
+        assert!(!loc(-0.01, 0.5).in_polygon(&square));
+    }
+
+    #[test]
+    fn test_in_polygon_concave() {
+        // A "U" shape, the notch at the top is outside
+        let u = [
+            loc(0.0, 0.0),
+            loc(0.0, 3.0),
+            loc(3.0, 3.0),
+            loc(3.0, 2.0),
+            loc(1.0, 2.0),
+            loc(1.0, 1.0),
+            loc(3.0, 1.0),
+            loc(3.0, 0.0),
+        ];
+
+        assert!(loc(0.5, 1.5).in_polygon(&u));
+        assert!(loc(2.0, 0.5).in_polygon(&u));
+        assert!(loc(2.0, 2.5).in_polygon(&u));
+        assert!(!loc(2.0, 1.5).in_polygon(&u));
+    }
+
+    #[test]
+    fn test_centroid() {
+        let points = [loc(0.0, 0.0), loc(0.0, 2.0), loc(2.0, 2.0), loc(2.0, 0.0)];
+        assert_eq!(Location::centroid(&points), Some(loc(1.0, 1.0)));
+        assert_eq!(Location::centroid(&points[..1]), Some(loc(0.0, 0.0)));
+        assert_eq!(Location::centroid(&[]), None);
+    }
+
+    #[test]
+    fn test_offset_meters() {
+        let origin = loc(45.0, -90.0);
+        let north = origin.offset_meters(100.0, 0.0);
+        let east = origin.offset_meters(0.0, 100.0);
+
+        assert!((origin.distance_to(&north) - 100.0).abs() < 1.0);
+        assert!((origin.distance_to(&east) - 100.0).abs() < 1.0);
+        assert!(north.lat > origin.lat);
+        assert!(east.long > origin.long);
+    }
+
+    #[test]
+    fn test_in_polygon_degenerate() {
+        let line = [loc(0.0, 0.0), loc(1.0, 1.0)];
+        assert!(!loc(0.5, 0.5).in_polygon(&line));
+        assert!(!loc(0.0, 0.0).in_polygon(&[]));
+    }
+}
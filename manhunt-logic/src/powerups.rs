@@ -11,12 +11,26 @@ pub enum PowerUpType {
 
     /// Ping another random hider instantly
     ForcePingOther,
+
+    /// Freeze all seekers in place for [crate::GameSettings::freeze_seconds]
+    FreezeSeekers,
 }
 
 impl PowerUpType {
-    pub const ALL_TYPES: [Self; 3] = [
+    pub const ALL_TYPES: [Self; 4] = [
         PowerUpType::ForcePingOther,
         PowerUpType::PingAllSeekers,
         PowerUpType::PingSeeker,
+        PowerUpType::FreezeSeekers,
     ];
+
+    /// Whether this powerup does anything when used as a seeker (`true`) or a hider (`false`)
+    pub fn usable_as(&self, seeker: bool) -> bool {
+        match self {
+            PowerUpType::ForcePingOther => true,
+            PowerUpType::PingSeeker | PowerUpType::PingAllSeekers | PowerUpType::FreezeSeekers => {
+                !seeker
+            }
+        }
+    }
 }
@@ -1,7 +1,200 @@
+use std::collections::{HashMap, HashSet};
+
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::units::Units;
 
 #[derive(Clone, Default, Debug, Serialize, Deserialize, specta::Type)]
+/// Anything missing from a saved profile gets its default so older profiles still load
+#[serde(default)]
 pub struct PlayerProfile {
     pub display_name: String,
     pub pfp_base64: Option<String>,
+    /// Units the player wants distances shown in
+    #[serde(default)]
+    pub units: Units,
+    /// Color to draw this player's map pin and scoreboard row in, as a hex string like `#ff0000`
+    #[serde(default)]
+    pub color: Option<String>,
+}
+
+/// Colors handed out by [assign_colors], picked to be easy to tell apart on a map
+pub const PLAYER_COLORS: [&str; 12] = [
+    "#e6194b", "#3cb44b", "#ffe119", "#4363d8", "#f58231", "#911eb4", "#42d4f4", "#f032e6",
+    "#bfef45", "#fabed4", "#469990", "#9a6324",
+];
+
+/// Whether the given string is a hex color in the form `#rrggbb`
+pub fn is_valid_color(color: &str) -> bool {
+    color.len() == 7 && color.starts_with('#') && color[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+impl PlayerProfile {
+    /// Check the profile makes sense, returns a list of human-readable problems if not
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+
+        if let Some(color) = self.color.as_ref()
+            && !is_valid_color(color)
+        {
+            errors.push(format!(
+                "\"{color}\" isn't a valid color, use the form #rrggbb"
+            ));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Give every player a unique color. Players keep their color if it's valid and nobody with a
+/// lower ID already has it, everyone else gets the next unused color from [PLAYER_COLORS]. Once
+/// those run out, generated colors are used instead.
+pub fn assign_colors(profiles: &HashMap<Uuid, PlayerProfile>) -> HashMap<Uuid, String> {
+    let mut ids = profiles.keys().copied().collect::<Vec<_>>();
+    ids.sort_unstable();
+
+    let mut used = HashSet::with_capacity(ids.len());
+    let mut needs_color = Vec::new();
+    let mut colors = HashMap::with_capacity(ids.len());
+
+    for id in ids {
+        match profiles[&id].color.as_ref() {
+            Some(color) if is_valid_color(color) && used.insert(color.to_ascii_lowercase()) => {
+                colors.insert(id, color.clone());
+            }
+            _ => needs_color.push(id),
+        }
+    }
+
+    let palette = PLAYER_COLORS.iter().map(|c| c.to_string());
+    let generated = (1..=u32::MAX).map(|i| format!("#{:06x}", i.wrapping_mul(0x9e3779) & 0xffffff));
+    let mut available = palette
+        .chain(generated)
+        .filter(|color| !used.contains(color));
+
+    for id in needs_color {
+        if let Some(color) = available.next() {
+            colors.insert(id, color);
+        }
+    }
+
+    colors
+}
+
+/// Replace display names with generic labels ("Hider 1", "Seeker 2") and strip profile pictures,
+/// for when the host doesn't want real names showing up in the game. `initial_caught_state` is
+/// used to pick labels, players are numbered by ID within their team so the labels are the same
+/// for everyone in the game. Anyone not on a team (a refereeing host) is labelled "Host".
+pub fn anonymize_profiles(
+    profiles: &HashMap<Uuid, PlayerProfile>,
+    initial_caught_state: &HashMap<Uuid, bool>,
+) -> HashMap<Uuid, PlayerProfile> {
+    let mut ids = profiles.keys().copied().collect::<Vec<_>>();
+    ids.sort_unstable();
+
+    let mut hiders = 0;
+    let mut seekers = 0;
+
+    ids.into_iter()
+        .map(|id| {
+            let label = match initial_caught_state.get(&id) {
+                Some(true) => {
+                    seekers += 1;
+                    format!("Seeker {seekers}")
+                }
+                Some(false) => {
+                    hiders += 1;
+                    format!("Hider {hiders}")
+                }
+                None => "Host".to_string(),
+            };
+            let profile = PlayerProfile {
+                display_name: label,
+                pfp_base64: None,
+                units: profiles[&id].units,
+                color: profiles[&id].color.clone(),
+            };
+            (id, profile)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_anonymize_profiles() {
+        let ids = (0..4).map(Uuid::from_u128).collect::<Vec<_>>();
+        let profiles = ids
+            .iter()
+            .map(|id| {
+                let profile = PlayerProfile {
+                    display_name: format!("Real Name {id}"),
+                    pfp_base64: Some("data".to_string()),
+                    ..Default::default()
+                };
+                (*id, profile)
+            })
+            .collect::<HashMap<_, _>>();
+        let caught_state = HashMap::from_iter([(ids[0], false), (ids[1], true), (ids[2], false)]);
+
+        let anon = anonymize_profiles(&profiles, &caught_state);
+
+        assert_eq!(anon[&ids[0]].display_name, "Hider 1");
+        assert_eq!(anon[&ids[1]].display_name, "Seeker 1");
+        assert_eq!(anon[&ids[2]].display_name, "Hider 2");
+        assert_eq!(anon[&ids[3]].display_name, "Host");
+        assert!(anon.values().all(|p| p.pfp_base64.is_none()));
+
+        let again = anonymize_profiles(&profiles, &caught_state);
+        for id in ids.iter() {
+            assert_eq!(anon[id].display_name, again[id].display_name);
+        }
+    }
+
+    #[test]
+    fn test_validate_color() {
+        for color in ["#ff0000", "#A1b2C3"] {
+            assert!(is_valid_color(color), "{color} wasn't valid");
+        }
+        for color in ["ff0000", "#ff00", "#gg0000", "#ff00000", ""] {
+            assert!(!is_valid_color(color), "{color} was valid");
+        }
+
+        let profile = PlayerProfile {
+            color: Some("red".to_string()),
+            ..Default::default()
+        };
+        assert!(profile.validate().is_err());
+        assert_eq!(PlayerProfile::default().validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_assign_colors() {
+        let ids = (0..20).map(Uuid::from_u128).collect::<Vec<_>>();
+        let mut profiles = ids
+            .iter()
+            .map(|id| (*id, PlayerProfile::default()))
+            .collect::<HashMap<_, _>>();
+        profiles.get_mut(&ids[0]).unwrap().color = Some("#123456".to_string());
+        // Duplicate of 0's color, should be replaced
+        profiles.get_mut(&ids[1]).unwrap().color = Some("#123456".to_string());
+        profiles.get_mut(&ids[2]).unwrap().color = Some("not a color".to_string());
+
+        let colors = assign_colors(&profiles);
+
+        assert_eq!(colors.len(), ids.len(), "Not every player got a color");
+        assert_eq!(colors[&ids[0]], "#123456", "Valid color wasn't kept");
+        assert_ne!(colors[&ids[1]], "#123456", "Duplicate color was kept");
+        assert!(colors.values().all(|c| is_valid_color(c)));
+
+        let unique = colors.values().collect::<HashSet<_>>();
+        assert_eq!(unique.len(), ids.len(), "Colors aren't unique");
+    }
 }
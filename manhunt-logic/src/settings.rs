@@ -3,7 +3,14 @@ use serde::{Deserialize, Serialize};
 
 use super::location::Location;
 
-#[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
+/// Longest hiding phase [GameSettings::validate] allows, anything longer is almost certainly a
+/// typo
+const MAX_HIDING_TIME_SECONDS: u32 = 2 * 60 * 60;
+
+/// Hiding phases longer than this are allowed but get a warning in [GameSettings::warnings]
+const LONG_HIDING_TIME_SECONDS: u32 = 30 * 60;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
 /// The starting condition for global pings to begin
 pub enum PingStartCondition {
     /// Wait For X players to be caught before beginning global pings
@@ -14,17 +21,29 @@ pub enum PingStartCondition {
     Instant,
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+/// Who gets to see hiders' pings on the map, in a reverse game ([GameSettings::reverse_pings])
+/// the teams are swapped
+pub enum PingVisibility {
+    /// Only seekers see hiders' pings, hiders only see their own
+    SeekersOnly,
+    /// Everyone sees every ping
+    #[default]
+    Everyone,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, specta::Type)]
 /// Settings for the game, host is the only person able to change these
 pub struct GameSettings {
-    /// The random seed used for shared rng
+    /// The random seed used for shared rng, the host replaces this with a fresh one when each game
+    /// starts
     pub random_seed: u32,
     /// The number of seconds to wait before seekers are allowed to go
     pub hiding_time_seconds: u32,
     /// Condition to wait for global pings to begin
     pub ping_start: PingStartCondition,
-    /// Time between pings after the condition is met (first ping is either after the interval or
-    /// instantly after the condition is met depending on the condition)
+    /// Time between pings after the condition is met, see [GameSettings::first_ping_immediate]
+    /// for when the first one happens
     pub ping_minutes_interval: u32,
     /// Condition for powerups to start spawning
     pub powerup_start: PingStartCondition,
@@ -34,12 +53,372 @@ pub struct GameSettings {
     pub powerup_minutes_cooldown: u32,
     /// Locations that powerups may spawn at
     pub powerup_locations: Vec<Location>,
+    /// Whether to award seekers points for each player they catch
+    #[serde(default)]
+    pub track_catch_scores: bool,
+    /// Whether seekers get a "warmer/colder" hint for each hider between pings, based on the
+    /// hider's last ping
+    #[serde(default)]
+    pub hot_cold_hints: bool,
+    /// How close (in meters) a player needs to be to a powerup to grab it, [Option::None] to let
+    /// players grab powerups from anywhere
+    #[serde(default)]
+    pub powerup_grab_radius_meters: Option<f64>,
+    /// Spawn powerups at a random point within this many meters of the hiders' last pings
+    /// instead of at [GameSettings::powerup_locations], so they stay reachable in games that move
+    /// around. The host picks the point and sends it to everyone. [Option::None] to only use
+    /// [GameSettings::powerup_locations].
+    #[serde(default)]
+    pub powerup_near_hiders_radius_meters: Option<f64>,
+    /// How long (in seconds) seekers stay frozen after a hider uses a
+    /// [crate::PowerUpType::FreezeSeekers] powerup
+    #[serde(default = "default_freeze_seconds")]
+    pub freeze_seconds: u32,
+    /// Where caught players go to wait to be freed, required for
+    /// [GameSettings::enable_jailbreak]
+    #[serde(default)]
+    pub jail_location: Option<Location>,
+    /// Whether hiders can free caught players by going to [GameSettings::jail_location]
+    #[serde(default)]
+    pub enable_jailbreak: bool,
+    /// Areas (center and radius in meters) where hiders can't be caught
+    #[serde(default)]
+    pub safe_zones: Vec<(Location, f64)>,
+    /// Replace display names and profile pictures with generic labels for the game, for
+    /// streaming or privacy
+    #[serde(default)]
+    pub anonymize_profiles: bool,
+    /// Let people join the room to watch the game after it starts
+    #[serde(default)]
+    pub allow_spectators: bool,
+    /// Vertices of the area players should stay in, in order. Leaving it only warns the player,
+    /// it's up to house rules what happens then. [Option::None] for no boundary.
+    #[serde(default)]
+    pub play_area: Option<Vec<Location>>,
+    /// Randomly pick this many players to be seekers when the game starts, ignoring the teams
+    /// players picked. [Option::None] to use the teams players picked.
+    #[serde(default)]
+    pub auto_seeker_count: Option<u32>,
+    /// Who can see hiders' pings, pings of seekers (e.g. from
+    /// [crate::PowerUpType::PingAllSeekers]) are always shown to everyone
+    #[serde(default)]
+    pub ping_visible_to: PingVisibility,
+    /// How many times each hider can instantly reveal their location to everyone with a panic
+    /// ping, outside the normal ping schedule. 0 to turn panic pings off.
+    #[serde(default)]
+    pub panic_pings: u32,
+    /// Whether the host is on a team, if not they only referee: they watch the game like a
+    /// spectator but can still end it or change settings
+    #[serde(default = "default_host_plays")]
+    pub host_plays: bool,
+    /// Ping seekers instead of hiders, so hiders always know roughly where seekers are and can
+    /// move around freely
+    #[serde(default)]
+    pub reverse_pings: bool,
+    /// Whether the first ping happens as soon as [GameSettings::ping_start] is met, otherwise
+    /// it's one [GameSettings::ping_minutes_interval] later
+    #[serde(default)]
+    pub first_ping_immediate: bool,
+    /// Ping hiders that stay in one spot for this many minutes after seekers are released, and
+    /// keep pinging them every minute until they move. [Option::None] to let hiders camp.
+    #[serde(default)]
+    pub reveal_after_minutes: Option<u32>,
+    /// Take pings off the map once they're this many seconds old so nobody chases a hider that's
+    /// long gone. [Option::None] to keep each ping until the player is pinged again.
+    #[serde(default)]
+    pub ping_expiry_seconds: Option<u32>,
+    /// How many seconds to wait for a player whose connection dropped to come back before
+    /// they're removed from the game, 0 to remove them right away
+    #[serde(default = "default_disconnect_grace_seconds")]
+    pub disconnect_grace_seconds: u32,
+}
+
+fn default_freeze_seconds() -> u32 {
+    60
+}
+
+fn default_disconnect_grace_seconds() -> u32 {
+    30
+}
+
+fn default_host_plays() -> bool {
+    true
+}
+
+/// Plain-language explanation of what a set of [GameSettings] will do during a game, meant for
+/// showing hosts before they start
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, specta::Type)]
+pub struct SettingsSummary {
+    /// How long hiders get before seekers are released
+    pub hiding: String,
+    /// When pings start and how often they happen after that
+    pub pings: String,
+    /// When powerups start spawning and how often, or that they won't
+    pub powerups: String,
+    /// One line for each optional rule that's turned on
+    pub extras: Vec<String>,
+    /// Settings that are allowed but probably a mistake, see [GameSettings::warnings]
+    pub warnings: Vec<String>,
+}
+
+/// "1 minute", "5 minutes", etc.
+fn plural(count: u32, unit: &str) -> String {
+    if count == 1 {
+        format!("1 {unit}")
+    } else {
+        format!("{count} {unit}s")
+    }
+}
+
+/// Format a number of seconds as minutes and seconds, leaving out parts that are 0
+fn format_seconds(seconds: u32) -> String {
+    match (seconds / 60, seconds % 60) {
+        (0, secs) => plural(secs, "second"),
+        (mins, 0) => plural(mins, "minute"),
+        (mins, secs) => format!("{} {}", plural(mins, "minute"), plural(secs, "second")),
+    }
+}
+
+impl PingStartCondition {
+    /// When this condition is met, e.g. "5 minutes after seekers are released"
+    fn describe(&self) -> String {
+        match self {
+            PingStartCondition::Players(num) => {
+                format!("once there are {}", plural(*num, "seeker"))
+            }
+            PingStartCondition::Minutes(minutes) => {
+                format!("{} after seekers are released", plural(*minutes, "minute"))
+            }
+            PingStartCondition::Instant => "as soon as seekers are released".to_string(),
+        }
+    }
 }
 
 impl GameSettings {
     pub fn get_powerup_bernoulli(&self) -> Bernoulli {
         Bernoulli::from_ratio(self.powerup_chance, 100).unwrap()
     }
+
+    /// Explain what these settings will do during a game in plain terms
+    pub fn describe(&self) -> SettingsSummary {
+        let hiding = format!(
+            "Hiders get {} to hide before seekers are released",
+            format_seconds(self.hiding_time_seconds)
+        );
+
+        let interval = plural(self.ping_minutes_interval, "minute");
+        let first = if self.first_ping_immediate {
+            "right away".to_string()
+        } else {
+            format!("{interval} later")
+        };
+        let pings = format!(
+            "Pings start {}, the first one {first} and then every {interval}",
+            self.ping_start.describe()
+        );
+
+        let powerups = if (self.powerup_locations.is_empty()
+            && self.powerup_near_hiders_radius_meters.is_none())
+            || self.powerup_chance == 0
+        {
+            "Powerups are off".to_string()
+        } else {
+            format!(
+                "Powerups begin {}, then every {} there's a {}% chance one spawns",
+                self.powerup_start.describe(),
+                plural(self.powerup_minutes_cooldown.max(1), "minute"),
+                self.powerup_chance
+            )
+        };
+
+        let mut extras = Vec::new();
+        if let Some(count) = self.auto_seeker_count {
+            extras.push(format!(
+                "{} will be picked at random when the game starts",
+                plural(count, "seeker")
+            ));
+        }
+        if let Some(radius) = self.powerup_grab_radius_meters {
+            extras.push(format!(
+                "Players need to be within {radius:.0} m of a powerup to grab it"
+            ));
+        }
+        if let Some(radius) = self.powerup_near_hiders_radius_meters {
+            extras.push(format!(
+                "Powerups spawn within {radius:.0} m of where the hiders were last pinged"
+            ));
+        }
+        if self.track_catch_scores {
+            extras.push("Seekers score a point for each player they catch".to_string());
+        }
+        if self.hot_cold_hints {
+            extras.push("Seekers get warmer/colder hints between pings".to_string());
+        }
+        if self.panic_pings > 0 {
+            extras.push(format!(
+                "Each hider can reveal themselves with {}",
+                plural(self.panic_pings, "panic ping")
+            ));
+        }
+        if self.ping_visible_to == PingVisibility::SeekersOnly {
+            extras.push("Hiders can't see each other's pings".to_string());
+        }
+        if self.enable_jailbreak {
+            extras.push("Caught players go to jail and hiders can break them out".to_string());
+        }
+        if !self.safe_zones.is_empty() {
+            extras.push(format!(
+                "Hiders can't be caught in {}",
+                plural(self.safe_zones.len() as u32, "safe zone")
+            ));
+        }
+        if self.play_area.is_some() {
+            extras.push("Players are warned when they leave the play area".to_string());
+        }
+        if self.reverse_pings {
+            extras.push("Seekers get pinged instead of hiders".to_string());
+        }
+        if let Some(minutes) = self.reveal_after_minutes {
+            extras.push(format!(
+                "Hiders that stay in one spot for {} get pinged until they move",
+                plural(minutes, "minute")
+            ));
+        }
+        if let Some(seconds) = self.ping_expiry_seconds {
+            extras.push(format!(
+                "Pings disappear from the map after {}",
+                plural(seconds, "second")
+            ));
+        }
+        if self.disconnect_grace_seconds == 0 {
+            extras.push("Players that lose connection are removed right away".to_string());
+        }
+        if !self.host_plays {
+            extras.push("The host referees instead of playing".to_string());
+        }
+        if self.anonymize_profiles {
+            extras.push("Names and pictures are hidden during the game".to_string());
+        }
+        if self.allow_spectators {
+            extras.push("People can join to spectate once the game starts".to_string());
+        }
+
+        SettingsSummary {
+            hiding,
+            pings,
+            powerups,
+            extras,
+            warnings: self.warnings(),
+        }
+    }
+
+    /// Settings that are valid but unusual enough that they're probably a mistake, the host
+    /// should double check these before starting
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        if self.hiding_time_seconds > LONG_HIDING_TIME_SECONDS
+            && self.hiding_time_seconds <= MAX_HIDING_TIME_SECONDS
+        {
+            warnings.push(format!(
+                "The hiding phase is unusually long ({})",
+                format_seconds(self.hiding_time_seconds)
+            ));
+        }
+
+        warnings
+    }
+
+    /// Check if these settings make sense, returns a list of human-readable problems if not
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+
+        if self.hiding_time_seconds > MAX_HIDING_TIME_SECONDS {
+            errors.push(format!(
+                "Hiding time can't be longer than {}",
+                format_seconds(MAX_HIDING_TIME_SECONDS)
+            ));
+        }
+
+        if self.ping_minutes_interval == 0 {
+            errors.push("Ping interval must be at least 1 minute".to_string());
+        }
+
+        if self.powerup_chance > 100 {
+            errors.push("Powerup chance must be between 0 and 100".to_string());
+        }
+
+        if self
+            .powerup_grab_radius_meters
+            .is_some_and(|radius| radius.is_nan() || radius < 0.0)
+        {
+            errors.push("Powerup grab radius can't be negative".to_string());
+        }
+
+        if self
+            .powerup_near_hiders_radius_meters
+            .is_some_and(|radius| radius.is_nan() || radius < 0.0)
+        {
+            errors.push("Powerup spawn radius can't be negative".to_string());
+        }
+
+        if self.enable_jailbreak && self.jail_location.is_none() {
+            errors.push("Jailbreaks need a jail location".to_string());
+        }
+
+        for (i, (center, radius)) in self.safe_zones.iter().enumerate() {
+            if !(-90.0..=90.0).contains(&center.lat) || !(-180.0..=180.0).contains(&center.long) {
+                errors.push(format!("Safe zone {} is not a valid location", i + 1));
+            }
+            if radius.is_nan() || *radius <= 0.0 {
+                errors.push(format!("Safe zone {} needs a positive radius", i + 1));
+            }
+        }
+
+        if self.freeze_seconds == 0 {
+            errors.push("Freeze duration must be at least 1 second".to_string());
+        }
+
+        for (i, loc) in self.powerup_locations.iter().enumerate() {
+            if !(-90.0..=90.0).contains(&loc.lat) || !(-180.0..=180.0).contains(&loc.long) {
+                errors.push(format!(
+                    "Powerup location {} is not a valid location",
+                    i + 1
+                ));
+            }
+        }
+
+        if self.reveal_after_minutes == Some(0) {
+            errors.push(
+                "Hiders need at least 1 minute before being revealed for camping".to_string(),
+            );
+        }
+
+        if self.ping_expiry_seconds == Some(0) {
+            errors.push("Pings need to stay on the map for at least 1 second".to_string());
+        }
+
+        if self.auto_seeker_count == Some(0) {
+            errors.push("At least 1 seeker needs to be picked".to_string());
+        }
+
+        if let Some(play_area) = self.play_area.as_ref() {
+            if play_area.len() < 3 {
+                errors.push("The play area needs at least 3 points".to_string());
+            }
+            if play_area.iter().any(|loc| {
+                !(-90.0..=90.0).contains(&loc.lat) || !(-180.0..=180.0).contains(&loc.long)
+            }) {
+                errors.push("The play area has a point that isn't a valid location".to_string());
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
 }
 
 impl Default for GameSettings {
@@ -53,6 +432,197 @@ impl Default for GameSettings {
             powerup_chance: 25,
             powerup_minutes_cooldown: 5,
             powerup_locations: vec![],
+            track_catch_scores: false,
+            hot_cold_hints: false,
+            powerup_grab_radius_meters: None,
+            powerup_near_hiders_radius_meters: None,
+            freeze_seconds: default_freeze_seconds(),
+            jail_location: None,
+            enable_jailbreak: false,
+            safe_zones: vec![],
+            anonymize_profiles: false,
+            allow_spectators: false,
+            play_area: None,
+            auto_seeker_count: None,
+            ping_visible_to: PingVisibility::Everyone,
+            panic_pings: 0,
+            host_plays: default_host_plays(),
+            reverse_pings: false,
+            first_ping_immediate: false,
+            reveal_after_minutes: None,
+            ping_expiry_seconds: None,
+            disconnect_grace_seconds: default_disconnect_grace_seconds(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_valid() {
+        assert_eq!(GameSettings::default().validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_validate() {
+        let settings = GameSettings {
+            ping_minutes_interval: 0,
+            powerup_chance: 101,
+            powerup_locations: vec![
+                Location {
+                    lat: 0.0,
+                    long: 0.0,
+                    heading: None,
+                },
+                Location {
+                    lat: 91.0,
+                    long: 0.0,
+                    heading: None,
+                },
+            ],
+            ..Default::default()
+        };
+
+        let errors = settings
+            .validate()
+            .expect_err("Invalid settings passed validation");
+        assert_eq!(errors.len(), 3, "Unexpected errors: {errors:?}");
+    }
+
+    #[test]
+    fn test_describe_ping_start() {
+        let describe = |ping_start| {
+            GameSettings {
+                ping_start,
+                ping_minutes_interval: 3,
+                ..Default::default()
+            }
+            .describe()
+            .pings
+        };
+
+        assert_eq!(
+            describe(PingStartCondition::Instant),
+            "Pings start as soon as seekers are released, the first one 3 minutes later and then \
+             every 3 minutes"
+        );
+        assert_eq!(
+            describe(PingStartCondition::Minutes(1)),
+            "Pings start 1 minute after seekers are released, the first one 3 minutes later and \
+             then every 3 minutes"
+        );
+        assert_eq!(
+            describe(PingStartCondition::Players(2)),
+            "Pings start once there are 2 seekers, the first one 3 minutes later and then every 3 \
+             minutes"
+        );
+
+        let immediate = GameSettings {
+            ping_start: PingStartCondition::Instant,
+            ping_minutes_interval: 3,
+            first_ping_immediate: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            immediate.describe().pings,
+            "Pings start as soon as seekers are released, the first one right away and then every \
+             3 minutes"
+        );
+    }
+
+    #[test]
+    fn test_describe() {
+        let settings = GameSettings {
+            hiding_time_seconds: 90,
+            powerup_start: PingStartCondition::Minutes(5),
+            powerup_chance: 10,
+            powerup_minutes_cooldown: 1,
+            powerup_locations: vec![Location {
+                lat: 0.0,
+                long: 0.0,
+                heading: None,
+            }],
+            track_catch_scores: true,
+            ..Default::default()
+        };
+
+        let summary = settings.describe();
+        assert_eq!(
+            summary.hiding,
+            "Hiders get 1 minute 30 seconds to hide before seekers are released"
+        );
+        assert_eq!(
+            summary.powerups,
+            "Powerups begin 5 minutes after seekers are released, then every 1 minute there's a \
+             10% chance one spawns"
+        );
+        assert_eq!(
+            summary.extras,
+            vec!["Seekers score a point for each player they catch".to_string()]
+        );
+
+        let no_powerups = GameSettings {
+            powerup_locations: vec![],
+            ..settings
+        };
+        assert_eq!(no_powerups.describe().powerups, "Powerups are off");
+    }
+
+    #[test]
+    fn test_hiding_time_bounds() {
+        let with_hiding = |hiding_time_seconds| GameSettings {
+            hiding_time_seconds,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            with_hiding(LONG_HIDING_TIME_SECONDS).warnings(),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            with_hiding(LONG_HIDING_TIME_SECONDS + 1)
+                .describe()
+                .warnings,
+            vec!["The hiding phase is unusually long (30 minutes 1 second)".to_string()]
+        );
+
+        let longest = with_hiding(MAX_HIDING_TIME_SECONDS);
+        assert_eq!(longest.validate(), Ok(()));
+        assert_eq!(longest.warnings().len(), 1);
+
+        // Someone typed 36000 meaning 3600
+        let absurd = with_hiding(36000);
+        assert_eq!(
+            absurd.validate(),
+            Err(vec![
+                "Hiding time can't be longer than 120 minutes".to_string()
+            ])
+        );
+        assert_eq!(
+            with_hiding(MAX_HIDING_TIME_SECONDS + 1)
+                .validate()
+                .map_err(|e| e.len()),
+            Err(1)
+        );
+    }
+
+    #[test]
+    fn test_validate_play_area() {
+        let point = |lat| Location {
+            lat,
+            long: 0.0,
+            heading: None,
+        };
+        let settings = GameSettings {
+            play_area: Some(vec![point(0.0), point(100.0)]),
+            ..Default::default()
+        };
+
+        let errors = settings
+            .validate()
+            .expect_err("Invalid play area passed validation");
+        assert_eq!(errors.len(), 2, "Unexpected errors: {errors:?}");
+    }
+}
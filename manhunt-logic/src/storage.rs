@@ -0,0 +1,117 @@
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::{Map, Value};
+
+/// Version of payloads written with [to_stored]. Bump this and add a step to [MIGRATIONS]
+/// whenever a stored type changes in a way `#[serde(default)]` can't cover.
+///
+/// - Version 1: Everything saved before payloads were versioned, just the bare payload
+/// - Version 2: Payload wrapped with its version, the payload itself is the same as version 1
+pub const STORAGE_VERSION: u32 = 2;
+
+/// Steps to upgrade a payload, the step at index `i` goes from version `i + 1` to `i + 2`
+const MIGRATIONS: [fn(Value) -> Value; STORAGE_VERSION as usize - 1] = [
+    // Version 2 only added the wrapper
+    |data| data,
+];
+
+const VERSION_KEY: &str = "version";
+const DATA_KEY: &str = "data";
+
+/// Serialize `data` to be saved, tagged with [STORAGE_VERSION] so it can be upgraded when read
+/// back with [from_stored]
+pub fn to_stored<T: Serialize>(data: &T) -> serde_json::Result<Value> {
+    Ok(Value::Object(Map::from_iter([
+        (VERSION_KEY.to_string(), Value::from(STORAGE_VERSION)),
+        (DATA_KEY.to_string(), serde_json::to_value(data)?),
+    ])))
+}
+
+/// Read back something saved with [to_stored], or saved before payloads were versioned.
+/// Older versions are migrated up to [STORAGE_VERSION] first. Payloads from a newer version
+/// are still attempted, so fields the types default will load.
+pub fn from_stored<T: DeserializeOwned>(value: Value) -> serde_json::Result<T> {
+    let (version, data) = split_version(value);
+    serde_json::from_value(migrate(version, data))
+}
+
+/// Separate a stored value into its version and payload
+fn split_version(value: Value) -> (u32, Value) {
+    match value {
+        Value::Object(mut map)
+            if map.len() == 2
+                && map.get(VERSION_KEY).is_some_and(Value::is_u64)
+                && map.contains_key(DATA_KEY) =>
+        {
+            let version = map
+                .get(VERSION_KEY)
+                .and_then(Value::as_u64)
+                .and_then(|v| u32::try_from(v).ok())
+                .unwrap_or(STORAGE_VERSION);
+            let data = map.remove(DATA_KEY).unwrap_or_default();
+            (version, data)
+        }
+        // Anything else was saved before versioning
+        other => (1, other),
+    }
+}
+
+/// Upgrade a payload from `version` to [STORAGE_VERSION] one step at a time
+fn migrate(version: u32, data: Value) -> Value {
+    MIGRATIONS
+        .iter()
+        .skip(version.saturating_sub(1) as usize)
+        .fold(data, |data, step| step(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{PlayerProfile, Units};
+
+    #[test]
+    fn test_roundtrip() {
+        let profile = PlayerProfile {
+            display_name: "Test".to_string(),
+            color: Some("#ff0000".to_string()),
+            ..Default::default()
+        };
+
+        let stored = to_stored(&profile).expect("Failed to store");
+        assert_eq!(stored[VERSION_KEY], json!(STORAGE_VERSION));
+
+        let read = from_stored::<PlayerProfile>(stored).expect("Failed to read");
+        assert_eq!(read.display_name, "Test");
+        assert_eq!(read.color.as_deref(), Some("#ff0000"));
+    }
+
+    #[test]
+    fn test_read_v1_profile() {
+        // Profiles saved before versioning, units and colors didn't exist yet
+        let v1 = json!({
+            "display_name": "Old",
+            "pfp_base64": null,
+        });
+
+        let read = from_stored::<PlayerProfile>(v1).expect("Failed to read v1 profile");
+        assert_eq!(read.display_name, "Old");
+        assert_eq!(read.units, Units::default());
+        assert_eq!(read.color, None);
+    }
+
+    #[test]
+    fn test_read_profile_missing_and_extra_fields() {
+        let stored = json!({
+            "version": STORAGE_VERSION,
+            "data": {
+                "display_name": "Future",
+                "some_new_field": 5,
+            },
+        });
+
+        let read = from_stored::<PlayerProfile>(stored).expect("Failed to read profile");
+        assert_eq!(read.display_name, "Future");
+        assert_eq!(read.pfp_base64, None);
+    }
+}
@@ -1,4 +1,7 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex as StdMutex},
+};
 
 use tokio::{
     sync::{Mutex, mpsc},
@@ -7,7 +10,9 @@ use tokio::{
 use uuid::Uuid;
 
 use crate::{
-    MsgPair, StateUpdateSender, Transport, TransportMessage,
+    GameEvent, MsgPair, StateUpdateSender, Transport, TransportMessage,
+    game::{EventDirection, GameAlert},
+    lobby::LobbyAlert,
     location::{Location, LocationService},
     prelude::*,
 };
@@ -19,6 +24,8 @@ pub struct MockTransport {
     id: Uuid,
     rx: Mutex<GameEventRx>,
     txs: HashMap<Uuid, GameEventTx>,
+    /// Every message sent to other peers, for checking what a game sent out
+    sent: StdMutex<Vec<TransportMessage>>,
 }
 
 impl MockTransport {
@@ -69,11 +76,22 @@ impl MockTransport {
         self.txs[&self.id].is_closed()
     }
 
+    /// Count the messages this transport sent to other peers that match `f`
+    pub fn count_sent(&self, f: impl Fn(&TransportMessage) -> bool) -> usize {
+        self.sent
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|msg| f(msg))
+            .count()
+    }
+
     fn new(id: Uuid, rx: GameEventRx, txs: HashMap<Uuid, GameEventTx>) -> Self {
         Self {
             id,
             rx: Mutex::new(rx),
             txs,
+            sent: StdMutex::new(Vec::new()),
         }
     }
 }
@@ -85,6 +103,7 @@ impl Transport for MockTransport {
             id: Uuid::default(),
             rx: Mutex::new(rx),
             txs: HashMap::default(),
+            sent: StdMutex::new(Vec::new()),
         }))
     }
 
@@ -103,12 +122,14 @@ impl Transport for MockTransport {
     }
 
     async fn send_message(&self, msg: TransportMessage) {
+        self.sent.lock().unwrap().push(msg.clone());
         for (_id, tx) in self.txs.iter().filter(|(id, _)| **id != self.id) {
             tx.send((Some(self.id), msg.clone())).await.ok();
         }
     }
 
     async fn send_message_single(&self, peer: Uuid, msg: TransportMessage) {
+        self.sent.lock().unwrap().push(msg.clone());
         if let Some(tx) = self.txs.get(&peer) {
             tx.send((Some(self.id), msg)).await.ok();
         }
@@ -135,8 +156,44 @@ impl LocationService for MockLocation {
     }
 }
 
-pub struct DummySender;
+/// [StateUpdateSender] that keeps track of every [GameAlert], [LobbyAlert], and logged
+/// [GameEvent] it gets
+#[derive(Default)]
+pub struct AlertRecorder {
+    alerts: StdMutex<Vec<GameAlert>>,
+    lobby_alerts: StdMutex<Vec<LobbyAlert>>,
+    logged_events: StdMutex<Vec<(EventDirection, GameEvent)>>,
+}
+
+impl AlertRecorder {
+    pub fn alerts(&self) -> Vec<GameAlert> {
+        self.alerts.lock().unwrap().clone()
+    }
+
+    pub fn lobby_alerts(&self) -> Vec<LobbyAlert> {
+        self.lobby_alerts.lock().unwrap().clone()
+    }
+
+    pub fn logged_events(&self) -> Vec<(EventDirection, GameEvent)> {
+        self.logged_events.lock().unwrap().clone()
+    }
+}
 
-impl StateUpdateSender for DummySender {
+impl StateUpdateSender for AlertRecorder {
     fn send_update(&self) {}
+
+    fn send_alert(&self, alert: GameAlert) {
+        self.alerts.lock().unwrap().push(alert);
+    }
+
+    fn send_lobby_alert(&self, alert: LobbyAlert) {
+        self.lobby_alerts.lock().unwrap().push(alert);
+    }
+
+    fn log_event(&self, direction: EventDirection, event: &GameEvent) {
+        self.logged_events
+            .lock()
+            .unwrap()
+            .push((direction, event.clone()));
+    }
 }
@@ -0,0 +1,244 @@
+use serde::{Deserialize, Serialize};
+
+use crate::location::{Location, LocationComponent};
+
+/// Web mercator can't show anything past this latitude, tiles stop here
+const MAX_LATITUDE: LocationComponent = 85.051_128_78;
+
+/// Highest zoom level map servers generally have tiles for
+pub const MAX_TILE_ZOOM: u8 = 19;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, specta::Type)]
+/// A rectangle on the map, in degrees
+pub struct TileBounds {
+    pub north: LocationComponent,
+    pub south: LocationComponent,
+    pub east: LocationComponent,
+    pub west: LocationComponent,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, specta::Type)]
+/// A single slippy map tile
+pub struct TileCoord {
+    pub z: u8,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl TileCoord {
+    /// Tile containing the given point at the given zoom
+    pub fn containing(lat: LocationComponent, long: LocationComponent, z: u8) -> Self {
+        let n = 2_f64.powi(z as i32);
+        let max = (1_u32 << z) - 1;
+
+        let lat = lat.clamp(-MAX_LATITUDE, MAX_LATITUDE).to_radians();
+        let x = ((long + 180.0) / 360.0 * n).floor();
+        let y = ((1.0 - lat.tan().asinh() / std::f64::consts::PI) / 2.0 * n).floor();
+
+        Self {
+            z,
+            x: (x.max(0.0) as u32).min(max),
+            y: (y.max(0.0) as u32).min(max),
+        }
+    }
+
+    /// Path of this tile relative to a tile cache or server, in the usual `z/x/y.png` layout
+    pub fn path(&self) -> String {
+        format!("{}/{}/{}.png", self.z, self.x, self.y)
+    }
+
+    /// Parse a path made by [TileCoord::path], [Option::None] if it isn't one or the tile is off
+    /// the edge of the map
+    pub fn from_path(path: &str) -> Option<Self> {
+        let mut parts = path.strip_suffix(".png")?.split('/');
+        let z = parts.next()?.parse::<u8>().ok()?;
+        let x = parts.next()?.parse::<u32>().ok()?;
+        let y = parts.next()?.parse::<u32>().ok()?;
+        if parts.next().is_some() || z > MAX_TILE_ZOOM || x >> z != 0 || y >> z != 0 {
+            return None;
+        }
+        Some(Self { z, x, y })
+    }
+
+    /// URL of this tile on a tile server, `template` has `{z}`, `{x}`, and `{y}` filled in
+    pub fn url(&self, template: &str) -> String {
+        template
+            .replace("{z}", &self.z.to_string())
+            .replace("{x}", &self.x.to_string())
+            .replace("{y}", &self.y.to_string())
+    }
+}
+
+impl TileBounds {
+    /// Smallest bounds containing all the given locations, [Option::None] if there are none
+    pub fn around<'a>(locations: impl IntoIterator<Item = &'a Location>) -> Option<Self> {
+        locations.into_iter().fold(None, |bounds, loc| {
+            Some(match bounds {
+                None => Self {
+                    north: loc.lat,
+                    south: loc.lat,
+                    east: loc.long,
+                    west: loc.long,
+                },
+                Some(b) => Self {
+                    north: b.north.max(loc.lat),
+                    south: b.south.min(loc.lat),
+                    east: b.east.max(loc.long),
+                    west: b.west.min(loc.long),
+                },
+            })
+        })
+    }
+
+    /// Check these bounds make sense, returns a list of human-readable problems
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::with_capacity(2);
+
+        if [self.north, self.south, self.east, self.west]
+            .iter()
+            .any(|c| !c.is_finite())
+            || !(-90.0..=90.0).contains(&self.north)
+            || !(-90.0..=90.0).contains(&self.south)
+            || !(-180.0..=180.0).contains(&self.east)
+            || !(-180.0..=180.0).contains(&self.west)
+        {
+            errors.push("Map bounds must be a valid latitude and longitude".to_string());
+        } else if self.north < self.south || self.east < self.west {
+            errors.push("Map bounds must have north above south and east of west".to_string());
+        }
+
+        errors
+    }
+
+    /// Every tile covering these bounds at zoom `z`, row by row from the north west corner
+    pub fn tiles(&self, z: u8) -> impl Iterator<Item = TileCoord> {
+        let nw = TileCoord::containing(self.north, self.west, z);
+        let se = TileCoord::containing(self.south, self.east, z);
+        (nw.y..=se.y).flat_map(move |y| (nw.x..=se.x).map(move |x| TileCoord { z, x, y }))
+    }
+
+    /// How many tiles cover these bounds at zoom `z`
+    pub fn tile_count(&self, z: u8) -> u64 {
+        let nw = TileCoord::containing(self.north, self.west, z);
+        let se = TileCoord::containing(self.south, self.east, z);
+        (se.x - nw.x + 1) as u64 * (se.y - nw.y + 1) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn loc(lat: f64, long: f64) -> Location {
+        Location {
+            lat,
+            long,
+            heading: None,
+        }
+    }
+
+    #[test]
+    fn test_containing() {
+        assert_eq!(
+            TileCoord::containing(0.0, 0.0, 0),
+            TileCoord { z: 0, x: 0, y: 0 }
+        );
+        // Rochester, NY
+        assert_eq!(
+            TileCoord::containing(43.1566, -77.6088, 12),
+            TileCoord {
+                z: 12,
+                x: 1164,
+                y: 1502
+            }
+        );
+        // Corners clamp to the edge of the map
+        assert_eq!(
+            TileCoord::containing(90.0, 180.0, 2),
+            TileCoord { z: 2, x: 3, y: 0 }
+        );
+        assert_eq!(
+            TileCoord::containing(-90.0, -180.0, 2),
+            TileCoord { z: 2, x: 0, y: 3 }
+        );
+    }
+
+    #[test]
+    fn test_from_path() {
+        let tile = TileCoord {
+            z: 12,
+            x: 1164,
+            y: 1502,
+        };
+        assert_eq!(TileCoord::from_path(&tile.path()), Some(tile));
+        assert_eq!(
+            tile.url("https://tiles.example.com/{z}/{x}/{y}.png"),
+            "https://tiles.example.com/12/1164/1502.png"
+        );
+
+        // Off the edge of the map, too zoomed in, or not a tile path at all
+        assert_eq!(TileCoord::from_path("2/4/0.png"), None);
+        assert_eq!(TileCoord::from_path("20/0/0.png"), None);
+        assert_eq!(TileCoord::from_path("1/0/0/0.png"), None);
+        assert_eq!(TileCoord::from_path("../../secret"), None);
+    }
+
+    #[test]
+    fn test_around() {
+        assert_eq!(TileBounds::around(&[]), None);
+
+        let bounds = TileBounds::around(&[loc(1.0, -2.0), loc(-1.0, 3.0), loc(0.5, 0.0)]);
+        assert_eq!(
+            bounds,
+            Some(TileBounds {
+                north: 1.0,
+                south: -1.0,
+                east: 3.0,
+                west: -2.0,
+            })
+        );
+    }
+
+    #[test]
+    fn test_tiles() {
+        let bounds = TileBounds {
+            north: 43.17,
+            south: 43.14,
+            east: -77.58,
+            west: -77.63,
+        };
+
+        for z in [0, 10, 14, 16] {
+            let tiles = bounds.tiles(z).collect::<Vec<_>>();
+            assert_eq!(tiles.len() as u64, bounds.tile_count(z));
+            assert!(tiles.iter().all(|t| t.z == z));
+        }
+
+        assert_eq!(bounds.tile_count(0), 1);
+        assert!(bounds.tile_count(16) > bounds.tile_count(14));
+    }
+
+    #[test]
+    fn test_validate() {
+        let good = TileBounds {
+            north: 1.0,
+            south: 0.0,
+            east: 1.0,
+            west: 0.0,
+        };
+        assert!(good.validate().is_empty());
+
+        let flipped = TileBounds {
+            north: 0.0,
+            south: 1.0,
+            ..good
+        };
+        assert_eq!(flipped.validate().len(), 1);
+
+        let invalid = TileBounds {
+            east: f64::NAN,
+            ..good
+        };
+        assert_eq!(invalid.validate().len(), 1);
+    }
+}
@@ -4,6 +4,11 @@ use super::{game_events::GameEvent, lobby::LobbyMessage};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+/// Version of the messages peers send each other, announced with [LobbyMessage::Hello]. Bump this
+/// whenever a change to [TransportMessage], [LobbyMessage], or [GameEvent] would make older
+/// clients misunderstand newer ones.
+pub const PEER_PROTOCOL_VERSION: u32 = 3;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum TransportMessage {
     /// Message related to the actual game
@@ -15,6 +20,10 @@ pub enum TransportMessage {
     PeerConnect(Uuid),
     /// Internal message when peer disconnects
     PeerDisconnect(Uuid),
+    /// Sent by a peer right before it disconnects on purpose, see
+    /// [Transport::disconnect_with_reason]. A [TransportMessage::PeerDisconnect] without this
+    /// first means the peer dropped out.
+    PeerLeaving(DisconnectReason),
     /// Event sent when the transport gets disconnected, used to help consumers know when to stop
     /// consuming messages. Note this should represent a success state, the disconnect was
     /// triggered by user action.
@@ -37,6 +46,15 @@ impl From<LobbyMessage> for TransportMessage {
 
 pub type MsgPair = (Option<Uuid>, TransportMessage);
 
+/// Why a peer is disconnecting on purpose
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DisconnectReason {
+    /// The user chose to leave
+    Quit,
+    /// The game is over and the peer isn't staying for a rematch
+    GameOver,
+}
+
 pub trait Transport: Send + Sync {
     /// Start the transport loop, This is expected to spawn a new job that will loop until
     /// cancelled or an error occurs.
@@ -51,7 +69,8 @@ pub trait Transport: Send + Sync {
         async { true }
     }
     /// Request a room be marked unjoinable (due to a game starting), the host user will call this.
-    fn mark_room_started(&self, _code: &str) -> impl Future<Output = ()> {
+    /// If `allow_spectators` is set, people can still join to spectate the game.
+    fn mark_room_started(&self, _code: &str, _allow_spectators: bool) -> impl Future<Output = ()> {
         async {}
     }
     /// Receive an event
@@ -62,8 +81,18 @@ pub trait Transport: Send + Sync {
     fn send_message(&self, msg: TransportMessage) -> impl Future<Output = ()>;
     /// Send a message to the local user
     fn send_self(&self, msg: TransportMessage) -> impl Future<Output = ()>;
-    /// Disconnect from the transport
+    /// Disconnect from the transport without telling anyone, to other peers this looks the same
+    /// as losing connection. Use [Transport::disconnect_with_reason] when leaving on purpose.
     fn disconnect(&self) -> impl Future<Output = ()> {
         async {}
     }
+    /// Tell other peers why we're leaving with a [TransportMessage::PeerLeaving], then
+    /// [Transport::disconnect]
+    fn disconnect_with_reason(&self, reason: DisconnectReason) -> impl Future<Output = ()> {
+        async move {
+            self.send_message(TransportMessage::PeerLeaving(reason))
+                .await;
+            self.disconnect().await;
+        }
+    }
 }
@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+
+const FEET_PER_METER: f64 = 3.28084;
+
+/// Units to show distances in, distances are always stored in meters and only converted for
+/// display (see `formatDistance` in the frontend)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+pub enum Units {
+    /// Meters and kilometers
+    #[default]
+    Metric,
+    /// Feet and miles
+    Imperial,
+}
+
+impl Units {
+    /// Convert meters to the small unit of this system (meters or feet)
+    pub fn from_meters(self, meters: f64) -> f64 {
+        match self {
+            Units::Metric => meters,
+            Units::Imperial => meters * FEET_PER_METER,
+        }
+    }
+
+    /// Convert a value in the small unit of this system (meters or feet) back to meters
+    pub fn to_meters(self, value: f64) -> f64 {
+        match self {
+            Units::Metric => value,
+            Units::Imperial => value / FEET_PER_METER,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        for units in [Units::Metric, Units::Imperial] {
+            let meters = units.to_meters(units.from_meters(123.4));
+            assert!((meters - 123.4).abs() < 1e-9, "{units:?} didn't round trip");
+        }
+    }
+
+    #[test]
+    fn test_from_meters() {
+        assert_eq!(Units::Metric.from_meters(10.0), 10.0);
+        assert!((Units::Imperial.from_meters(10.0) - 32.8084).abs() < 1e-9);
+    }
+}
@@ -2,25 +2,38 @@ mod state;
 mod topology;
 
 use axum::{
-    extract::Path,
+    Json,
+    extract::{ConnectInfo, Path, Query},
     http::StatusCode,
     response::IntoResponse,
     routing::{get, post},
 };
 use log::{debug, info};
+use manhunt_common::PROTOCOL_VERSION;
+use matchbox_protocol::PeerId;
 use matchbox_signaling::SignalingServerBuilder;
+use serde::Serialize;
 
 use anyhow::Context;
 use std::{
+    collections::HashMap,
     net::{IpAddr, Ipv4Addr, SocketAddr},
     result::Result as StdResult,
 };
 
-use state::ServerState;
+use state::{RoomError, ServerState};
 use topology::ServerTopology;
+use uuid::Uuid;
 
 type Result<T = (), E = anyhow::Error> = StdResult<T, E>;
 
+/// Response of the `/info` route, lets clients check they're compatible before connecting
+#[derive(Serialize)]
+struct ServerInfo {
+    version: &'static str,
+    protocol_version: u32,
+}
+
 #[tokio::main]
 async fn main() -> Result {
     colog::init();
@@ -44,7 +57,22 @@ async fn main() -> Result {
 
                 let err = if let Some(room_code) = connection.path.clone() {
                     let create = connection.query_params.contains_key("create");
-                    match state.handle_room(create, connection.origin, room_code) {
+                    let spectate = connection.query_params.contains_key("spectate");
+                    // Rejoining players say which player they were when the game started
+                    let rejoin = connection.query_params.get("rejoin");
+                    let res = if spectate {
+                        state.handle_spectate(connection.origin, room_code)
+                    } else if let Some(player) = rejoin {
+                        match player.parse::<Uuid>() {
+                            Ok(player) => {
+                                state.handle_rejoin(connection.origin, room_code, PeerId(player))
+                            }
+                            Err(_) => Err(RoomError::NotFound),
+                        }
+                    } else {
+                        state.handle_room(create, connection.origin, room_code)
+                    };
+                    match res {
                         Ok(_) => None,
                         Err(err) => Some(err.into()),
                     }
@@ -67,28 +95,53 @@ async fn main() -> Result {
                 router
                     .route(
                         "/room_exists/{id}",
-                        get(move |Path(room_id): Path<String>| async move {
-                            if state.room_is_open(&room_id) {
-                                StatusCode::OK
-                            } else {
-                                StatusCode::NOT_FOUND
-                            }
-                        }),
+                        // With `?started` this checks for a started game to rejoin instead
+                        get(
+                            move |Path(room_id): Path<String>,
+                                  Query(params): Query<HashMap<String, String>>| async move {
+                                let exists = if params.contains_key("started") {
+                                    state.room_is_started(&room_id)
+                                } else {
+                                    state.room_is_open(&room_id)
+                                };
+                                if exists {
+                                    StatusCode::OK
+                                } else {
+                                    StatusCode::NOT_FOUND
+                                }
+                            },
+                        ),
                     )
                     .route(
                         "/mark_started/{id}",
-                        post(move |Path(room_id): Path<String>| async move {
-                            state2.mark_started(&room_id);
-                            StatusCode::OK
+                        post(
+                            move |Path(room_id): Path<String>,
+                                  Query(params): Query<HashMap<String, String>>| async move {
+                                let allow_spectators = params.contains_key("spectators");
+                                state2.mark_started(&room_id, allow_spectators);
+                                StatusCode::OK
+                            },
+                        ),
+                    )
+                    .route(
+                        "/info",
+                        get(|| async {
+                            Json(ServerInfo {
+                                version: env!("CARGO_PKG_VERSION"),
+                                protocol_version: PROTOCOL_VERSION,
+                            })
                         }),
                     )
                     .route(
                         "/gen_code",
-                        get(move || async move {
-                            state3
-                                .generate_room_code()
-                                .map_err(|_| StatusCode::CONFLICT)
-                        }),
+                        // Codes are reserved for whoever asked, only they can create the room
+                        get(
+                            move |ConnectInfo(caller): ConnectInfo<SocketAddr>| async move {
+                                state3
+                                    .generate_room_code(caller.ip())
+                                    .map_err(|_| StatusCode::CONFLICT)
+                            },
+                        ),
                     )
             }
         })
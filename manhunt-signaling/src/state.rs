@@ -1,9 +1,11 @@
 use std::{
     collections::{HashMap, HashSet},
-    net::SocketAddr,
+    net::{IpAddr, SocketAddr},
+    time::{Duration, Instant},
 };
 
 use axum::{Error as AxumError, extract::ws::Message, http::StatusCode};
+use manhunt_common::{ROOM_CODE_CHAR_POOL, ROOM_CODE_LEN};
 use matchbox_protocol::PeerId;
 use matchbox_signaling::{
     SignalingError, SignalingState,
@@ -19,8 +21,12 @@ pub type Sender = UnboundedSender<Result<Message, AxumError>>;
 #[derive(Debug, Clone)]
 struct Match {
     pub open_lobby: bool,
+    /// Whether spectators can join after the game has started
+    pub allow_spectators: bool,
     cancel: CancellationToken,
     pub players: HashSet<PeerId>,
+    /// Players in the room when the game started, only they're allowed to rejoin
+    pub started_players: HashSet<PeerId>,
 }
 
 #[derive(Debug, Clone)]
@@ -33,8 +39,10 @@ impl Match {
     pub fn new() -> Self {
         Self {
             open_lobby: true,
+            allow_spectators: false,
             cancel: CancellationToken::new(),
             players: HashSet::with_capacity(10),
+            started_players: HashSet::new(),
         }
     }
 }
@@ -44,6 +52,9 @@ pub struct ServerState {
     waiting_clients: StateObj<HashMap<SocketAddr, (RoomId, bool)>>,
     queued_clients: StateObj<HashMap<PeerId, (RoomId, bool)>>,
     matches: StateObj<HashMap<RoomId, Match>>,
+    /// Codes handed out by [ServerState::generate_room_code] whose host hasn't created the room
+    /// yet, who they were handed out to and when. Always lock [ServerState::matches] first.
+    reserved_codes: StateObj<HashMap<RoomId, (IpAddr, Instant)>>,
     clients: StateObj<HashMap<PeerId, Peer>>,
 }
 
@@ -66,9 +77,9 @@ impl From<RoomError> for StatusCode {
     }
 }
 
-const ROOM_CODE_CHAR_POOL: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ1234567890";
-const ROOM_CODE_LEN: usize = 6;
 const MAX_ROOM_TRIES: usize = 25;
+/// How long a generated code is held for its host before someone else can be given it
+const CODE_RESERVATION_TIMEOUT: Duration = Duration::from_secs(120);
 
 #[derive(Debug, Default, Clone, Copy)]
 pub struct NoRoomsError;
@@ -82,16 +93,19 @@ impl ServerState {
             .collect()
     }
 
-    fn check_room_taken(&self, code: &RoomId) -> bool {
-        self.matches.lock().unwrap().contains_key(code)
-    }
+    /// Pick a code no room is using and reserve it for `caller` so no other host can create a
+    /// room with it before they do
+    pub fn generate_room_code(&self, caller: IpAddr) -> Result<RoomId, NoRoomsError> {
+        let matches = self.matches.lock().unwrap();
+        let mut reserved = self.reserved_codes.lock().unwrap();
+        reserved.retain(|_, (_, at)| at.elapsed() < CODE_RESERVATION_TIMEOUT);
 
-    pub fn generate_room_code(&self) -> Result<RoomId, NoRoomsError> {
         let mut rng = rand::rng();
         for _ in 0..MAX_ROOM_TRIES {
             let code = Self::random_room_code(&mut rng);
 
-            if !self.check_room_taken(&code) {
+            if !matches.contains_key(&code) && !reserved.contains_key(&code) {
+                reserved.insert(code.clone(), (caller, Instant::now()));
                 return Ok(code);
             }
         }
@@ -113,21 +127,49 @@ impl ServerState {
             .is_some_and(|m| m.open_lobby)
     }
 
-    /// Mark a match as started, disallowing others from joining
-    pub fn mark_started(&mut self, room: &RoomId) {
+    /// Mark a match as started, disallowing others from joining. If `allow_spectators` is set,
+    /// people can still join the match as spectators with [ServerState::handle_spectate].
+    pub fn mark_started(&mut self, room: &RoomId, allow_spectators: bool) {
         if let Some(mat) = self.matches.lock().unwrap().get_mut(room) {
             mat.open_lobby = false;
+            mat.allow_spectators = allow_spectators;
+            mat.started_players = mat.players.clone();
         }
     }
 
+    /// Whether a room has started and is letting spectators join
+    pub fn room_is_spectatable(&self, room_id: &str) -> bool {
+        self.matches
+            .lock()
+            .unwrap()
+            .get(room_id)
+            .is_some_and(|m| !m.open_lobby && m.allow_spectators)
+    }
+
+    /// Whether a room exists and its game has started
+    pub fn room_is_started(&self, room_id: &str) -> bool {
+        self.matches
+            .lock()
+            .unwrap()
+            .get(room_id)
+            .is_some_and(|m| !m.open_lobby)
+    }
+
     /// Create a new room with the given code, should be called when someone wants to host a game.
-    /// Returns false if a room with that code already exists.
+    /// Returns false if a room with that code already exists, or the code is reserved for someone
+    /// else.
     fn create_room(&mut self, origin: SocketAddr, code: RoomId) -> bool {
         let mut matches = self.matches.lock().unwrap();
-        if matches.contains_key(&code) {
+        let mut reserved = self.reserved_codes.lock().unwrap();
+        let reserved_for_other = reserved.get(&code).is_some_and(|(caller, at)| {
+            *caller != origin.ip() && at.elapsed() < CODE_RESERVATION_TIMEOUT
+        });
+        if matches.contains_key(&code) || reserved_for_other {
             false
         } else {
             matches.insert(code.clone(), Match::new());
+            reserved.remove(&code);
+            drop(reserved);
             drop(matches);
             self.add_client(origin, code, true);
             true
@@ -166,6 +208,46 @@ impl ServerState {
         }
     }
 
+    /// Try to join a started room as a spectator, only works if the host allowed spectators when
+    /// marking the room started
+    pub fn handle_spectate(&mut self, origin: SocketAddr, code: RoomId) -> Result<(), RoomError> {
+        if self.room_is_spectatable(&code) {
+            self.waiting_clients
+                .lock()
+                .unwrap()
+                .insert(origin, (code, false));
+            Ok(())
+        } else {
+            Err(RoomError::NotFound)
+        }
+    }
+
+    /// Try to rejoin a started room as a player that dropped out of the game, e.g. because their
+    /// app crashed. `player` is the ID they had when the game started, anyone that wasn't in the
+    /// game is turned away.
+    pub fn handle_rejoin(
+        &mut self,
+        origin: SocketAddr,
+        code: RoomId,
+        player: PeerId,
+    ) -> Result<(), RoomError> {
+        let was_playing = self
+            .matches
+            .lock()
+            .unwrap()
+            .get(&code)
+            .is_some_and(|m| !m.open_lobby && m.started_players.contains(&player));
+        if was_playing {
+            self.waiting_clients
+                .lock()
+                .unwrap()
+                .insert(origin, (code, false));
+            Ok(())
+        } else {
+            Err(RoomError::NotFound)
+        }
+    }
+
     /// Assign a peer an id
     pub fn assign_peer_id(&mut self, origin: SocketAddr, peer_id: PeerId) {
         let target_room = self
@@ -470,7 +552,7 @@ mod tests {
         quick_create(&mut state, code, 1);
         quick_join(&mut state, code, 2);
 
-        state.mark_started(&code.to_string());
+        state.mark_started(&code.to_string(), false);
 
         let others = state.remove_peer(peer(1), true);
 
@@ -503,6 +585,77 @@ mod tests {
         assert_eq!(res, Err(RoomError::Exists));
     }
 
+    #[test]
+    fn test_generate_concurrent() {
+        let state = ServerState::default();
+
+        let threads = (0..8)
+            .map(|_| {
+                let state = state.clone();
+                std::thread::spawn(move || {
+                    (0..500)
+                        .map(|_| {
+                            state
+                                .generate_room_code(origin(1).ip())
+                                .expect("Ran out of codes")
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let codes = threads
+            .into_iter()
+            .flat_map(|t| t.join().expect("Generating thread panicked"))
+            .collect::<Vec<_>>();
+        let unique = codes.iter().collect::<HashSet<_>>();
+
+        assert_eq!(unique.len(), codes.len(), "A code was handed out twice");
+        assert_eq!(state.reserved_codes.lock().unwrap().len(), codes.len());
+    }
+
+    #[test]
+    fn test_create_reserved() {
+        let mut state = ServerState::default();
+
+        let code = state
+            .generate_room_code(origin(1).ip())
+            .expect("Failed to generate code");
+
+        // Someone else can't take a code that was handed out to another host
+        let other = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)), 1);
+        assert_eq!(
+            state.handle_room(true, other, code.clone()),
+            Err(RoomError::Exists),
+            "Created a room with a code reserved for someone else"
+        );
+
+        state
+            .handle_room(true, origin(1), code.clone())
+            .expect("Couldn't create room with a generated code");
+        assert!(state.reserved_codes.lock().unwrap().is_empty());
+
+        // Reservations that were never used are given back
+        let expired = (other.ip(), Instant::now() - CODE_RESERVATION_TIMEOUT);
+        state
+            .reserved_codes
+            .lock()
+            .unwrap()
+            .insert("old".to_string(), expired);
+        state
+            .handle_room(true, origin(2), "old".to_string())
+            .expect("Expired reservation kept someone from using the code");
+        state
+            .reserved_codes
+            .lock()
+            .unwrap()
+            .insert("older".to_string(), expired);
+        state
+            .generate_room_code(origin(1).ip())
+            .expect("Failed to generate code");
+        assert!(!state.reserved_codes.lock().unwrap().contains_key("older"));
+    }
+
     #[test]
     fn test_join_started() {
         let mut state = ServerState::default();
@@ -512,7 +665,7 @@ mod tests {
         quick_create(&mut state, code, 1);
         quick_join(&mut state, code, 2);
 
-        state.mark_started(&code.to_string());
+        state.mark_started(&code.to_string(), false);
 
         assert!(
             state
@@ -526,4 +679,93 @@ mod tests {
         let res = state.handle_room(false, origin(3), code.to_string());
         assert_eq!(res, Err(RoomError::NotFound));
     }
+
+    #[test]
+    fn test_spectate() {
+        let mut state = ServerState::default();
+
+        let code = "spectate";
+
+        quick_create(&mut state, code, 1);
+        quick_join(&mut state, code, 2);
+
+        let res = state.handle_spectate(origin(3), code.to_string());
+        assert_eq!(
+            res,
+            Err(RoomError::NotFound),
+            "Spectated a room that hasn't started"
+        );
+
+        state.mark_started(&code.to_string(), true);
+
+        state
+            .handle_spectate(origin(3), code.to_string())
+            .expect("Failed to spectate");
+        state.assign_peer_id(origin(3), peer(3));
+        let (host, _, mut others) = state.add_peer(peer(3), dummy_sender());
+        others.sort();
+
+        assert!(!host);
+        assert_eq!(others, vec![peer(1), peer(2)]);
+
+        let res = state.handle_room(false, origin(4), code.to_string());
+        assert_eq!(
+            res,
+            Err(RoomError::NotFound),
+            "Joined a started room as a player"
+        );
+    }
+
+    #[test]
+    fn test_rejoin() {
+        let mut state = ServerState::default();
+
+        let code = "rejoin";
+
+        quick_create(&mut state, code, 1);
+        quick_join(&mut state, code, 2);
+
+        let res = state.handle_rejoin(origin(3), code.to_string(), peer(2));
+        assert_eq!(
+            res,
+            Err(RoomError::NotFound),
+            "Rejoined a room that hasn't started"
+        );
+
+        state.mark_started(&code.to_string(), false);
+
+        let res = state.handle_rejoin(origin(3), code.to_string(), peer(5));
+        assert_eq!(
+            res,
+            Err(RoomError::NotFound),
+            "Someone that wasn't in the game rejoined"
+        );
+
+        state
+            .handle_rejoin(origin(3), code.to_string(), peer(2))
+            .expect("Failed to rejoin");
+        state.assign_peer_id(origin(3), peer(3));
+        let (host, _, mut others) = state.add_peer(peer(3), dummy_sender());
+        others.sort();
+
+        assert!(!host);
+        assert_eq!(others, vec![peer(1), peer(2)]);
+
+        let res = state.handle_rejoin(origin(4), "missing".to_string(), peer(2));
+        assert_eq!(res, Err(RoomError::NotFound));
+    }
+
+    #[test]
+    fn test_spectate_not_allowed() {
+        let mut state = ServerState::default();
+
+        let code = "nospectate";
+
+        quick_create(&mut state, code, 1);
+
+        state.mark_started(&code.to_string(), false);
+
+        let res = state.handle_spectate(origin(2), code.to_string());
+        assert_eq!(res, Err(RoomError::NotFound));
+    }
 }
@@ -1,17 +1,20 @@
 #![allow(clippy::result_large_err)]
 
 use manhunt_logic::{
-    Game as BaseGame, GameSettings, Lobby as BaseLobby, Location, LocationService, PlayerProfile,
-    StartGameInfo, StateUpdateSender,
+    DisconnectReason, Game as BaseGame, GameSettings, Lobby as BaseLobby, Location,
+    LocationService, PlayerProfile, StartGameInfo, StateUpdateSender, Transport,
 };
 use manhunt_test_shared::*;
-use manhunt_transport::{MatchboxTransport, request_room_code};
+use manhunt_transport::{AnyTransport, LoopbackTransport, MatchboxTransport, request_room_code};
+use scenario::run_scenario;
 use std::{sync::Arc, time::Duration};
 use tokio::{
     io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
     sync::{Mutex, mpsc},
 };
 
+mod scenario;
+
 struct DummyLocationService;
 
 impl LocationService for DummyLocationService {
@@ -35,8 +38,8 @@ impl StateUpdateSender for UpdateSender {
     }
 }
 
-type Game = BaseGame<DummyLocationService, MatchboxTransport, UpdateSender>;
-type Lobby = BaseLobby<MatchboxTransport, UpdateSender>;
+type Game = BaseGame<DummyLocationService, AnyTransport, UpdateSender>;
+type Lobby = BaseLobby<AnyTransport, UpdateSender>;
 
 #[derive(Default)]
 enum DaemonScreen {
@@ -61,21 +64,30 @@ type StateHandle = Arc<Mutex<DaemonState>>;
 struct DaemonState {
     screen: DaemonScreen,
     profile: PlayerProfile,
+    /// Connect over a [LoopbackTransport] instead of the signaling server, only players in
+    /// this daemon's process (i.e. scenarios) can be reached
+    loopback: bool,
     responses: mpsc::Sender<TestingResponse>,
     updates: (mpsc::Sender<()>, Mutex<mpsc::Receiver<()>>),
 }
 
 impl DaemonState {
-    pub fn new(name: impl Into<String>, responses: mpsc::Sender<TestingResponse>) -> Self {
+    pub fn new(
+        name: impl Into<String>,
+        loopback: bool,
+        responses: mpsc::Sender<TestingResponse>,
+    ) -> Self {
         tokio::time::pause();
         let screen = DaemonScreen::default();
         let (tx, rx) = mpsc::channel(2);
         Self {
             screen,
+            loopback,
             responses,
             profile: PlayerProfile {
                 display_name: name.into(),
                 pfp_base64: None,
+                ..Default::default()
             },
             updates: (tx, Mutex::new(rx)),
         }
@@ -118,6 +130,10 @@ impl DaemonState {
                 let mut state = handle.lock().await;
                 match res {
                     Ok(Some(history)) => {
+                        // No rematches here, drop the connection
+                        game.clone_transport()
+                            .disconnect_with_reason(DisconnectReason::GameOver)
+                            .await;
                         state.push_resp(history).await;
                     }
                     Ok(None) => {}
@@ -156,14 +172,45 @@ impl DaemonState {
         }
     }
 
-    pub async fn create_lobby(&mut self, handle: StateHandle, settings: GameSettings) -> Result {
-        let sender = self.sender();
+    async fn room_code(&self) -> Result<String> {
+        if self.loopback {
+            Ok(format!("LOOP-{}", uuid::Uuid::new_v4().simple()))
+        } else {
+            request_room_code().await.context("Failed to get room code")
+        }
+    }
 
-        let code = request_room_code()
-            .await
-            .context("Failed to get room code")?;
+    async fn connect(&self, code: &str, host: bool) -> Result<Arc<AnyTransport>> {
+        let transport = if self.loopback {
+            LoopbackTransport::new(code, host)?.into()
+        } else {
+            MatchboxTransport::new(code, host).await?.into()
+        };
+        Ok(Arc::new(transport))
+    }
 
-        let lobby = Lobby::new(&code, true, self.profile.clone(), settings, sender)
+    async fn open_lobby(
+        &self,
+        code: &str,
+        host: bool,
+        settings: GameSettings,
+    ) -> Result<Arc<Lobby>> {
+        let transport = self.connect(code, host).await?;
+        Ok(Arc::new(Lobby::new_with_transport(
+            code,
+            host,
+            self.profile.clone(),
+            settings,
+            self.sender(),
+            transport,
+        )))
+    }
+
+    pub async fn create_lobby(&mut self, handle: StateHandle, settings: GameSettings) -> Result {
+        let code = self.room_code().await?;
+
+        let lobby = self
+            .open_lobby(&code, true, settings)
             .await
             .context("Failed to start lobby")?;
 
@@ -174,11 +221,11 @@ impl DaemonState {
     }
 
     pub async fn join_lobby(&mut self, handle: StateHandle, code: &str) -> Result {
-        let sender = self.sender();
         // TODO: Lobby should not require this on join, use an [Option]?
         let settings = GameSettings::default();
 
-        let lobby = Lobby::new(code, false, self.profile.clone(), settings, sender)
+        let lobby = self
+            .open_lobby(code, false, settings)
             .await
             .context("Failed to join lobby")?;
 
@@ -196,28 +243,31 @@ impl DaemonState {
         }
     }
 
-    async fn process_lobby_req(&mut self, req: LobbyRequest) {
+    async fn process_lobby_req(&mut self, req: LobbyRequest) -> Result<(), TestingResponse> {
         if let DaemonScreen::Lobby(lobby) = &self.screen {
             let lobby = lobby.clone();
             match req {
                 LobbyRequest::SwitchTeams(seeker) => lobby.switch_teams(seeker).await,
-                LobbyRequest::HostStartGame => lobby.start_game().await,
+                LobbyRequest::HostStartGame => lobby.start_game().await?,
                 LobbyRequest::HostUpdateSettings(game_settings) => {
-                    lobby.update_settings(game_settings).await
+                    lobby.update_settings(*game_settings).await
                 }
                 LobbyRequest::Leave => lobby.quit_lobby().await,
             }
         }
+        Ok(())
     }
 
-    async fn process_game_req(&mut self, req: GameRequest) {
+    async fn process_game_req(&mut self, req: GameRequest) -> Result<(), TestingResponse> {
         if let DaemonScreen::Game(game) = &self.screen {
             let game = game.clone();
             match req {
                 GameRequest::NextTick => tokio::time::sleep(Self::INTERVAL).await,
-                GameRequest::MarkCaught => game.mark_caught().await,
-                GameRequest::GetPowerup => game.get_powerup().await,
-                GameRequest::UsePowerup => game.use_powerup().await,
+                GameRequest::MarkCaught { by } => game.mark_caught(by).await?,
+                GameRequest::GetPowerup => {
+                    game.get_powerup().await;
+                }
+                GameRequest::UsePowerup => game.use_powerup().await?,
                 GameRequest::ForcePowerup(power_up_type) => {
                     let mut state = game.lock_state().await;
                     state.force_set_powerup(power_up_type);
@@ -225,6 +275,7 @@ impl DaemonState {
                 GameRequest::Quit => game.quit_game().await,
             }
         }
+        Ok(())
     }
 
     pub async fn process_req(
@@ -243,11 +294,21 @@ impl DaemonState {
             }
             TestingRequest::LobbyReq(lobby_request) => {
                 self.assert_screen(ScreenUpdate::Lobby)?;
-                self.process_lobby_req(lobby_request).await;
+                self.process_lobby_req(lobby_request).await?;
             }
             TestingRequest::GameReq(game_request) => {
                 self.assert_screen(ScreenUpdate::Game)?;
-                self.process_game_req(game_request).await;
+                self.process_game_req(game_request).await?;
+            }
+            TestingRequest::RunScenario(scenario) => {
+                self.assert_screen(ScreenUpdate::PreConnect)?;
+                let code = self.room_code().await?;
+                let res = if self.loopback {
+                    run_scenario::<LoopbackTransport>(&code, *scenario).await
+                } else {
+                    run_scenario::<MatchboxTransport>(&code, *scenario).await
+                };
+                self.push_resp(res).await;
             }
         }
         Ok(())
@@ -256,19 +317,24 @@ impl DaemonState {
 
 use interprocess::local_socket::{ListenerOptions, tokio::prelude::*};
 
-const CLI_MSG: &str = "Usage: manhunt-test-daemon SOCKET_NAME PLAYER_NAME";
+const CLI_MSG: &str = "Usage: manhunt-test-daemon SOCKET_NAME PLAYER_NAME [--loopback]";
 
 #[tokio::main(flavor = "current_thread")]
 pub async fn main() -> Result {
     let args = std::env::args().collect::<Vec<_>>();
     let raw_socket_name = args.get(1).cloned().expect(CLI_MSG);
     let player_name = args.get(2).cloned().expect(CLI_MSG);
+    let loopback = match args.get(3).map(String::as_str) {
+        None => false,
+        Some("--loopback") => true,
+        Some(_) => panic!("{CLI_MSG}"),
+    };
     let socket_name = get_socket_name(raw_socket_name)?;
     let opts = ListenerOptions::new().name(socket_name);
     let listener = opts.create_tokio().context("Failed to bind to socket")?;
     let (resp_tx, mut resp_rx) = mpsc::channel::<TestingResponse>(40);
 
-    let handle = Arc::new(Mutex::new(DaemonState::new(player_name, resp_tx)));
+    let handle = Arc::new(Mutex::new(DaemonState::new(player_name, loopback, resp_tx)));
 
     eprintln!("Testing Daemon Ready");
 
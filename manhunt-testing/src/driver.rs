@@ -2,6 +2,7 @@ use clap::{Parser, Subcommand, ValueEnum};
 use interprocess::local_socket::{tokio::Stream, traits::tokio::Stream as _};
 use manhunt_logic::PowerUpType;
 use manhunt_test_shared::{get_socket_name, prelude::*};
+use uuid::Uuid;
 
 #[derive(Parser)]
 struct Cli {
@@ -39,6 +40,7 @@ enum PowerUpTypeValue {
     PingSeeker,
     PingAllSeekers,
     ForcePingOther,
+    FreezeSeekers,
 }
 
 impl From<PowerUpTypeValue> for PowerUpType {
@@ -47,6 +49,7 @@ impl From<PowerUpTypeValue> for PowerUpType {
             PowerUpTypeValue::PingSeeker => PowerUpType::PingSeeker,
             PowerUpTypeValue::PingAllSeekers => PowerUpType::PingAllSeekers,
             PowerUpTypeValue::ForcePingOther => PowerUpType::ForcePingOther,
+            PowerUpTypeValue::FreezeSeekers => PowerUpType::FreezeSeekers,
         }
     }
 }
@@ -54,7 +57,11 @@ impl From<PowerUpTypeValue> for PowerUpType {
 #[derive(Subcommand)]
 enum GameCommand {
     /// Mark the local player as caught for everyone
-    MarkCaught,
+    MarkCaught {
+        /// ID of the seeker that caught us
+        #[arg(long)]
+        by: Option<Uuid>,
+    },
     /// Get a currently available powerup
     GetPowerup,
     /// Use the held powerup of the local player
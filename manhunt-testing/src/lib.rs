@@ -1,6 +1,7 @@
 use interprocess::local_socket::{GenericNamespaced, Name, ToNsName};
 use manhunt_logic::{GameHistory, GameSettings, GameUiState, LobbyState, PowerUpType};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 pub mod prelude {
     pub use anyhow::{Context, anyhow, bail};
@@ -19,26 +20,104 @@ pub fn get_socket_name(base_name: String) -> Result<Name<'static>> {
 pub enum LobbyRequest {
     SwitchTeams(bool),
     HostStartGame,
-    HostUpdateSettings(GameSettings),
+    HostUpdateSettings(Box<GameSettings>),
     Leave,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum GameRequest {
     NextTick,
-    MarkCaught,
+    /// Mark ourselves caught, optionally by the seeker with the given ID
+    MarkCaught {
+        by: Option<Uuid>,
+    },
     GetPowerup,
     UsePowerup,
     ForcePowerup(PowerUpType),
     Quit,
 }
 
+/// One step of a [Scenario]. Players are referred to by index in the order they joined, the
+/// host is always player 0.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ScenarioStep {
+    /// Host a lobby with the scenario's settings as player 0
+    StartLobby,
+    /// Have this many more players join the lobby
+    JoinPlayers(usize),
+    /// Put a player on the seekers (true) or hiders (false)
+    SetTeam { player: usize, seeker: bool },
+    /// Have the host start the game once they see everyone on the right team
+    StartGame,
+    /// Let this many game ticks pass
+    AdvanceTicks(u32),
+    /// Have a player mark themselves caught, optionally crediting the seeker at index `by`
+    MarkCaught { player: usize, by: Option<usize> },
+    /// Check every player agrees on which team a player is on
+    AssertSeeker { player: usize, seeker: bool },
+    /// Check the game ended normally for every player
+    AssertEnded,
+}
+
+/// A scripted match run start to finish by the daemon, see [TestingRequest::RunScenario]. The
+/// daemon runs every player itself so no other daemons are needed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scenario {
+    /// Settings the host starts the lobby with
+    pub settings: GameSettings,
+    /// What to do, in order. The scenario fails at the first step that errors.
+    pub steps: Vec<ScenarioStep>,
+}
+
+impl Scenario {
+    /// One seeker catches two hiders one after the other and the game ends
+    pub fn catch_everyone() -> Self {
+        use ScenarioStep::*;
+
+        Self {
+            settings: GameSettings::default(),
+            steps: vec![
+                StartLobby,
+                JoinPlayers(2),
+                SetTeam {
+                    player: 0,
+                    seeker: true,
+                },
+                StartGame,
+                AdvanceTicks(2),
+                MarkCaught {
+                    player: 1,
+                    by: Some(0),
+                },
+                AdvanceTicks(1),
+                AssertSeeker {
+                    player: 1,
+                    seeker: true,
+                },
+                AssertSeeker {
+                    player: 2,
+                    seeker: false,
+                },
+                MarkCaught {
+                    player: 2,
+                    by: Some(0),
+                },
+                AdvanceTicks(2),
+                AssertEnded,
+            ],
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum TestingRequest {
     StartLobby(GameSettings),
     JoinLobby(String),
     LobbyReq(LobbyRequest),
     GameReq(GameRequest),
+    /// Run a whole [Scenario], answered with [TestingResponse::ScenarioPassed] or
+    /// [TestingResponse::ScenarioFailed]
+    RunScenario(Box<Scenario>),
 }
 
 impl From<LobbyRequest> for TestingRequest {
@@ -88,16 +167,22 @@ pub enum ScreenUpdate {
 pub enum TestingResponse {
     Complete,
     ScreenChanged(ScreenUpdate),
-    LobbyStateUpdate(LobbyState),
-    GameStateUpdate(GameUiState),
-    GameOver(GameHistory),
+    LobbyStateUpdate(Box<LobbyState>),
+    GameStateUpdate(Box<GameUiState>),
+    GameOver(Box<GameHistory>),
     WrongScreen,
     Error(String),
+    ScenarioPassed,
+    /// The [Scenario] step at index `step` failed
+    ScenarioFailed {
+        step: usize,
+        why: String,
+    },
 }
 
 impl From<GameHistory> for TestingResponse {
     fn from(val: GameHistory) -> Self {
-        TestingResponse::GameOver(val)
+        TestingResponse::GameOver(Box::new(val))
     }
 }
 
@@ -115,12 +200,12 @@ impl From<ScreenUpdate> for TestingResponse {
 
 impl From<LobbyState> for TestingResponse {
     fn from(val: LobbyState) -> Self {
-        TestingResponse::LobbyStateUpdate(val)
+        TestingResponse::LobbyStateUpdate(Box::new(val))
     }
 }
 
 impl From<GameUiState> for TestingResponse {
     fn from(val: GameUiState) -> Self {
-        TestingResponse::GameStateUpdate(val)
+        TestingResponse::GameStateUpdate(Box::new(val))
     }
 }
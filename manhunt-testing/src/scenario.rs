@@ -0,0 +1,307 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use manhunt_logic::{
+    DisconnectReason, Game as BaseGame, GameHistory, GameSettings, Lobby as BaseLobby,
+    PlayerProfile, StartGameInfo, StateUpdateSender, Transport,
+};
+use manhunt_test_shared::*;
+use tokio::task::{JoinHandle, LocalSet, spawn_local};
+use uuid::Uuid;
+
+use crate::DummyLocationService;
+
+/// Nobody is watching the players in a scenario, their updates go nowhere
+struct NoUpdates;
+
+impl StateUpdateSender for NoUpdates {
+    fn send_update(&self) {}
+}
+
+type Game<T> = BaseGame<DummyLocationService, T, NoUpdates>;
+type Lobby<T> = BaseLobby<T, NoUpdates>;
+
+const INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long a step can wait on the other players before it fails
+const STEP_TIMEOUT: Duration = Duration::from_secs(30);
+
+enum ScenarioPlayer<T: Transport> {
+    Lobby(Arc<Lobby<T>>, JoinHandle<Result<Option<StartGameInfo>>>),
+    Game(Arc<Game<T>>, JoinHandle<Result<Option<GameHistory>>>),
+    /// The game ended normally, the transport is kept until the scenario is over
+    Ended(Arc<T>),
+}
+
+impl<T: Transport + 'static> ScenarioPlayer<T> {
+    fn lobby(lobby: Arc<Lobby<T>>) -> Self {
+        let handle = spawn_local({
+            let lobby = lobby.clone();
+            async move { lobby.main_loop().await }
+        });
+        Self::Lobby(lobby, handle)
+    }
+
+    fn game(game: Game<T>) -> Self {
+        let game = Arc::new(game);
+        let handle = spawn_local({
+            let game = game.clone();
+            async move { game.main_loop().await }
+        });
+        Self::Game(game, handle)
+    }
+
+    fn transport(&self) -> Arc<T> {
+        match self {
+            Self::Lobby(lobby, _) => lobby.clone_transport(),
+            Self::Game(game, _) => game.clone_transport(),
+            Self::Ended(transport) => transport.clone(),
+        }
+    }
+
+    fn as_game(&self) -> Result<&Arc<Game<T>>> {
+        match self {
+            Self::Game(game, _) => Ok(game),
+            Self::Lobby(..) => bail!("Player is still in the lobby"),
+            Self::Ended(_) => bail!("Player's game already ended"),
+        }
+    }
+}
+
+struct ScenarioRunner<T: Transport> {
+    code: String,
+    settings: GameSettings,
+    players: Vec<ScenarioPlayer<T>>,
+    ids: Vec<Uuid>,
+    /// The teams the scenario asked for, the host has to see these before starting
+    teams: HashMap<Uuid, bool>,
+}
+
+impl<T: Transport + 'static> ScenarioRunner<T> {
+    fn new(code: &str, settings: GameSettings) -> Self {
+        Self {
+            code: code.to_string(),
+            settings,
+            players: Vec::new(),
+            ids: Vec::new(),
+            teams: HashMap::new(),
+        }
+    }
+
+    fn player(&self, i: usize) -> Result<&ScenarioPlayer<T>> {
+        self.players
+            .get(i)
+            .with_context(|| format!("There's no player {i}"))
+    }
+
+    fn lobby(&self, i: usize) -> Result<&Arc<Lobby<T>>> {
+        match self.player(i)? {
+            ScenarioPlayer::Lobby(lobby, _) => Ok(lobby),
+            _ => bail!("Player {i} isn't in the lobby"),
+        }
+    }
+
+    async fn join(&mut self) -> Result {
+        let i = self.players.len();
+        let host = i == 0;
+        let profile = PlayerProfile {
+            display_name: format!("Player {i}"),
+            ..Default::default()
+        };
+        let settings = if host {
+            self.settings.clone()
+        } else {
+            GameSettings::default()
+        };
+
+        let lobby = Lobby::<T>::new(&self.code, host, profile, settings, NoUpdates)
+            .await
+            .with_context(|| format!("Player {i} couldn't connect"))?;
+
+        let id = lobby.clone_transport().self_id();
+        self.ids.push(id);
+        self.teams.insert(id, false);
+        self.players.push(ScenarioPlayer::lobby(lobby));
+        Ok(())
+    }
+
+    async fn start_game(&mut self) -> Result {
+        let host = self.lobby(0)?.clone();
+
+        tokio::time::timeout(STEP_TIMEOUT, async {
+            while host.clone_state().await.teams() != &self.teams {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .context("The host never saw everyone on the right team")?;
+
+        host.start_game().await?;
+
+        for (i, player) in std::mem::take(&mut self.players).into_iter().enumerate() {
+            let ScenarioPlayer::Lobby(lobby, handle) = player else {
+                bail!("Player {i} isn't in the lobby");
+            };
+            let start = tokio::time::timeout(STEP_TIMEOUT, handle)
+                .await
+                .with_context(|| format!("Player {i}'s game never started"))?
+                .context("Lobby panicked")??
+                .with_context(|| format!("Player {i} left the lobby"))?;
+            let game = Game::new(
+                INTERVAL,
+                start,
+                lobby.clone_transport(),
+                DummyLocationService,
+                NoUpdates,
+            );
+            self.players.push(ScenarioPlayer::game(game));
+        }
+
+        Ok(())
+    }
+
+    async fn assert_seeker(&self, player: usize, seeker: bool) -> Result {
+        let id = *self
+            .ids
+            .get(player)
+            .with_context(|| format!("There's no player {player}"))?;
+
+        for (i, p) in self.players.iter().enumerate() {
+            let is_seeker = p
+                .as_game()?
+                .lock_state()
+                .await
+                .iter_seekers()
+                .any(|s| s == id);
+            if is_seeker != seeker {
+                bail!("Player {i} thinks player {player} is a seeker: {is_seeker}");
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn assert_ended(&mut self) -> Result {
+        for (i, player) in std::mem::take(&mut self.players).into_iter().enumerate() {
+            let ScenarioPlayer::Game(game, handle) = player else {
+                bail!("Player {i} never started a game");
+            };
+            tokio::time::timeout(STEP_TIMEOUT, handle)
+                .await
+                .with_context(|| format!("Player {i}'s game didn't end"))?
+                .context("Game panicked")??
+                .with_context(|| format!("Player {i} quit the game"))?;
+            self.players
+                .push(ScenarioPlayer::Ended(game.clone_transport()));
+        }
+
+        Ok(())
+    }
+
+    async fn run_step(&mut self, step: ScenarioStep) -> Result {
+        match step {
+            ScenarioStep::StartLobby => {
+                if !self.players.is_empty() {
+                    bail!("The lobby was already started");
+                }
+                self.join().await?;
+            }
+            ScenarioStep::JoinPlayers(count) => {
+                if self.players.is_empty() {
+                    bail!("There's no lobby to join");
+                }
+                for _ in 0..count {
+                    self.join().await?;
+                }
+            }
+            ScenarioStep::SetTeam { player, seeker } => {
+                self.lobby(player)?.switch_teams(seeker).await;
+                self.teams.insert(self.ids[player], seeker);
+            }
+            ScenarioStep::StartGame => self.start_game().await?,
+            ScenarioStep::AdvanceTicks(ticks) => {
+                for _ in 0..ticks {
+                    tokio::time::sleep(INTERVAL).await;
+                }
+            }
+            ScenarioStep::MarkCaught { player, by } => {
+                let by = by
+                    .map(|by| {
+                        self.ids
+                            .get(by)
+                            .copied()
+                            .with_context(|| format!("There's no player {by}"))
+                    })
+                    .transpose()?;
+                self.player(player)?.as_game()?.mark_caught(by).await?;
+            }
+            ScenarioStep::AssertSeeker { player, seeker } => {
+                self.assert_seeker(player, seeker).await?
+            }
+            ScenarioStep::AssertEnded => self.assert_ended().await?,
+        }
+        Ok(())
+    }
+
+    /// Stop every player and leave the room
+    async fn shutdown(self) {
+        for player in self.players {
+            let transport = player.transport();
+            let reason = match player {
+                ScenarioPlayer::Lobby(_, handle) => {
+                    handle.abort();
+                    DisconnectReason::Quit
+                }
+                ScenarioPlayer::Game(_, handle) => {
+                    handle.abort();
+                    DisconnectReason::Quit
+                }
+                ScenarioPlayer::Ended(_) => DisconnectReason::GameOver,
+            };
+            transport.disconnect_with_reason(reason).await;
+        }
+    }
+}
+
+/// Run every step of a scenario in a room with the given code, players connect with `T`
+pub async fn run_scenario<T: Transport + 'static>(
+    code: &str,
+    scenario: Scenario,
+) -> TestingResponse {
+    // Transports don't promise their futures are Send, keep every player on this thread
+    LocalSet::new()
+        .run_until(async {
+            let mut runner = ScenarioRunner::<T>::new(code, scenario.settings);
+            let mut res = TestingResponse::ScenarioPassed;
+
+            for (i, step) in scenario.steps.into_iter().enumerate() {
+                if let Err(why) = runner.run_step(step).await {
+                    res = TestingResponse::ScenarioFailed {
+                        step: i,
+                        why: format!("{why:#}"),
+                    };
+                    break;
+                }
+            }
+
+            runner.shutdown().await;
+            res
+        })
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use manhunt_transport::LoopbackTransport;
+
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_catch_everyone() {
+        let res =
+            run_scenario::<LoopbackTransport>("SCENARIO-CATCH", Scenario::catch_everyone()).await;
+        assert!(
+            matches!(res, TestingResponse::ScenarioPassed),
+            "Scenario failed: {res:?}"
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use manhunt_logic::{MsgPair, Transport, TransportMessage, prelude::*};
+
+use crate::{LoopbackTransport, MatchboxTransport};
+
+/// A [Transport] picked at runtime, so the same game and lobby types can run over the signaling
+/// server or, for testing, over a [LoopbackTransport] in the same process.
+///
+/// [Transport::initialize] always connects through the signaling server, wrap a
+/// [LoopbackTransport] with [From] to use that instead.
+pub enum AnyTransport {
+    Matchbox(Arc<MatchboxTransport>),
+    Loopback(Arc<LoopbackTransport>),
+}
+
+impl From<Arc<MatchboxTransport>> for AnyTransport {
+    fn from(value: Arc<MatchboxTransport>) -> Self {
+        Self::Matchbox(value)
+    }
+}
+
+impl From<Arc<LoopbackTransport>> for AnyTransport {
+    fn from(value: Arc<LoopbackTransport>) -> Self {
+        Self::Loopback(value)
+    }
+}
+
+impl Transport for AnyTransport {
+    async fn initialize(code: &str, host: bool) -> Result<Arc<Self>> {
+        MatchboxTransport::initialize(code, host)
+            .await
+            .map(|transport| Arc::new(transport.into()))
+    }
+
+    fn self_id(&self) -> Uuid {
+        match self {
+            Self::Matchbox(transport) => transport.self_id(),
+            Self::Loopback(transport) => transport.self_id(),
+        }
+    }
+
+    async fn room_joinable(&self, code: &str) -> bool {
+        match self {
+            Self::Matchbox(transport) => transport.room_joinable(code).await,
+            Self::Loopback(transport) => transport.room_joinable(code).await,
+        }
+    }
+
+    async fn mark_room_started(&self, code: &str, allow_spectators: bool) {
+        match self {
+            Self::Matchbox(transport) => transport.mark_room_started(code, allow_spectators).await,
+            Self::Loopback(transport) => transport.mark_room_started(code, allow_spectators).await,
+        }
+    }
+
+    async fn receive_messages(&self) -> impl Iterator<Item = MsgPair> {
+        match self {
+            Self::Matchbox(transport) => transport.receive_messages().await.collect::<Vec<_>>(),
+            Self::Loopback(transport) => transport.receive_messages().await.collect::<Vec<_>>(),
+        }
+        .into_iter()
+    }
+
+    async fn send_message_single(&self, peer: Uuid, msg: TransportMessage) {
+        match self {
+            Self::Matchbox(transport) => transport.send_message_single(peer, msg).await,
+            Self::Loopback(transport) => transport.send_message_single(peer, msg).await,
+        }
+    }
+
+    async fn send_message(&self, msg: TransportMessage) {
+        match self {
+            Self::Matchbox(transport) => transport.send_message(msg).await,
+            Self::Loopback(transport) => transport.send_message(msg).await,
+        }
+    }
+
+    async fn send_self(&self, msg: TransportMessage) {
+        match self {
+            Self::Matchbox(transport) => transport.send_self(msg).await,
+            Self::Loopback(transport) => transport.send_self(msg).await,
+        }
+    }
+
+    async fn disconnect(&self) {
+        match self {
+            Self::Matchbox(transport) => transport.disconnect().await,
+            Self::Loopback(transport) => transport.disconnect().await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use manhunt_logic::{DisconnectReason, GameEvent};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_loopback() {
+        let host = AnyTransport::from(LoopbackTransport::new("ANY-LOOP", true).unwrap());
+        let player = AnyTransport::from(LoopbackTransport::new("ANY-LOOP", false).unwrap());
+
+        assert!(host.room_joinable("ANY-LOOP").await);
+
+        player
+            .send_message_single(
+                host.self_id(),
+                GameEvent::RequestSync(player.self_id()).into(),
+            )
+            .await;
+        let received = host.receive_messages().await.collect::<Vec<_>>();
+        assert!(
+            received.iter().any(|(from, msg)| *from == Some(player.self_id())
+                && matches!(msg, TransportMessage::Game(event) if matches!(**event, GameEvent::RequestSync(id) if id == player.self_id()))),
+            "Host didn't get the player's message: {received:?}"
+        );
+
+        player.disconnect_with_reason(DisconnectReason::Quit).await;
+        let received = host.receive_messages().await.collect::<Vec<_>>();
+        assert!(
+            received.iter().any(|(_, msg)| matches!(
+                msg,
+                TransportMessage::PeerLeaving(DisconnectReason::Quit)
+            )),
+            "Host didn't hear the player leave: {received:?}"
+        );
+    }
+}
@@ -1,6 +1,14 @@
+mod any;
+mod loopback;
 mod matchbox;
 mod packets;
 mod server;
 
-pub use matchbox::MatchboxTransport;
-pub use server::{request_room_code, room_exists};
+pub use any::AnyTransport;
+pub use loopback::LoopbackTransport;
+pub use manhunt_common::{PROTOCOL_VERSION, ROOM_CODE_CHAR_POOL, ROOM_CODE_LEN};
+pub use matchbox::{IceServerConfig, MatchboxTransport, RoomCodeTakenError};
+pub use server::{
+    RoomStatus, ServerInfo, is_valid_code_format, request_room_code, room_status, server_info,
+    started_room_status,
+};
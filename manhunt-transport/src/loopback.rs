@@ -0,0 +1,376 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock, Mutex as StdMutex},
+};
+
+use anyhow::bail;
+use tokio::sync::{Mutex, mpsc};
+use uuid::Uuid;
+
+use manhunt_logic::{MsgPair, Transport, TransportMessage, prelude::*};
+
+type Inbox = mpsc::UnboundedSender<MsgPair>;
+
+#[derive(Default)]
+struct Room {
+    peers: HashMap<Uuid, Inbox>,
+    started: bool,
+    allow_spectators: bool,
+}
+
+/// Every open room in this process, keyed by join code
+static ROOMS: LazyLock<StdMutex<HashMap<String, Room>>> = LazyLock::new(Default::default);
+
+/// A [Transport] that connects instances running in the same process through a shared registry
+/// of rooms instead of a signaling server. Meant for running several players in one process
+/// for end-to-end tests, it can't reach instances in other processes.
+pub struct LoopbackTransport {
+    my_id: Uuid,
+    code: String,
+    inbox: Inbox,
+    incoming: Mutex<mpsc::UnboundedReceiver<MsgPair>>,
+}
+
+impl LoopbackTransport {
+    /// Create (as host) or join a room, fails if the host's room already exists or if the room
+    /// to join doesn't exist or has already started
+    pub fn new(code: &str, is_host: bool) -> Result<Arc<Self>> {
+        Self::connect(code, is_host, false)
+    }
+
+    /// Join a room that has already started as a spectator, fails if the host didn't allow
+    /// spectators
+    pub fn new_spectator(code: &str) -> Result<Arc<Self>> {
+        Self::connect(code, false, true)
+    }
+
+    fn connect(code: &str, is_host: bool, spectate: bool) -> Result<Arc<Self>> {
+        let mut rooms = ROOMS.lock().unwrap();
+
+        if is_host {
+            if rooms.contains_key(code) {
+                bail!("Room {code} already exists");
+            }
+            rooms.insert(code.to_string(), Room::default());
+        }
+
+        let room = rooms.get_mut(code).context("Room doesn't exist")?;
+
+        if spectate && !(room.started && room.allow_spectators) {
+            bail!("Room {code} can't be spectated");
+        } else if !spectate && room.started {
+            bail!("Room {code} has already started");
+        }
+
+        let my_id = Uuid::new_v4();
+        let (inbox, incoming) = mpsc::unbounded_channel();
+
+        for (peer, peer_inbox) in room.peers.iter() {
+            peer_inbox
+                .send((Some(my_id), TransportMessage::PeerConnect(my_id)))
+                .ok();
+            inbox
+                .send((Some(*peer), TransportMessage::PeerConnect(*peer)))
+                .ok();
+        }
+
+        room.peers.insert(my_id, inbox.clone());
+
+        Ok(Arc::new(Self {
+            my_id,
+            code: code.to_string(),
+            inbox,
+            incoming: Mutex::new(incoming),
+        }))
+    }
+
+    fn send_to(&self, peer: Option<Uuid>, msg: TransportMessage) {
+        let rooms = ROOMS.lock().unwrap();
+        let Some(room) = rooms.get(&self.code) else {
+            return;
+        };
+        let targets = room
+            .peers
+            .iter()
+            .filter(|(id, _)| **id != self.my_id && peer.is_none_or(|peer| peer == **id));
+        for (_, peer_inbox) in targets {
+            peer_inbox.send((Some(self.my_id), msg.clone())).ok();
+        }
+    }
+}
+
+impl Transport for LoopbackTransport {
+    async fn initialize(code: &str, host: bool) -> Result<Arc<Self>> {
+        Self::new(code, host)
+    }
+
+    fn self_id(&self) -> Uuid {
+        self.my_id
+    }
+
+    async fn room_joinable(&self, code: &str) -> bool {
+        ROOMS
+            .lock()
+            .unwrap()
+            .get(code)
+            .is_some_and(|room| !room.started)
+    }
+
+    async fn mark_room_started(&self, code: &str, allow_spectators: bool) {
+        if let Some(room) = ROOMS.lock().unwrap().get_mut(code) {
+            room.started = true;
+            room.allow_spectators = allow_spectators;
+        }
+    }
+
+    async fn receive_messages(&self) -> impl Iterator<Item = MsgPair> {
+        let mut incoming = self.incoming.lock().await;
+        let mut buffer = Vec::with_capacity(60);
+        incoming.recv_many(&mut buffer, 60).await;
+        buffer.into_iter()
+    }
+
+    async fn send_message_single(&self, peer: Uuid, msg: TransportMessage) {
+        self.send_to(Some(peer), msg);
+    }
+
+    async fn send_message(&self, msg: TransportMessage) {
+        self.send_to(None, msg);
+    }
+
+    async fn send_self(&self, msg: TransportMessage) {
+        self.inbox.send((Some(self.my_id), msg)).ok();
+    }
+
+    async fn disconnect(&self) {
+        {
+            let mut rooms = ROOMS.lock().unwrap();
+            if let Some(room) = rooms.get_mut(&self.code) {
+                room.peers.remove(&self.my_id);
+                for peer_inbox in room.peers.values() {
+                    peer_inbox
+                        .send((
+                            Some(self.my_id),
+                            TransportMessage::PeerDisconnect(self.my_id),
+                        ))
+                        .ok();
+                }
+                if room.peers.is_empty() {
+                    rooms.remove(&self.code);
+                }
+            }
+        }
+
+        self.inbox
+            .send((Some(self.my_id), TransportMessage::Disconnected))
+            .ok();
+        self.incoming.lock().await.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use manhunt_logic::{
+        CatchReason, GameEvent, GameSettings, Lobby, PlayerProfile, StateUpdateSender,
+    };
+
+    use super::*;
+
+    struct DummySender;
+
+    impl StateUpdateSender for DummySender {
+        fn send_update(&self) {}
+    }
+
+    async fn recv_one(transport: &LoopbackTransport) -> MsgPair {
+        transport
+            .incoming
+            .lock()
+            .await
+            .recv()
+            .await
+            .expect("No messages")
+    }
+
+    fn assert_no_incoming(transport: &LoopbackTransport) {
+        assert!(
+            transport.incoming.try_lock().unwrap().is_empty(),
+            "Transport has unexpected messages"
+        );
+    }
+
+    fn caught(player: Uuid) -> TransportMessage {
+        GameEvent::PlayerCaught {
+            player,
+            by: None,
+            reason: CatchReason::Caught,
+        }
+        .into()
+    }
+
+    #[tokio::test]
+    async fn test_connect_and_message() {
+        let t1 = LoopbackTransport::new("LOOP-MSG", true).expect("Failed to host");
+        let t2 = LoopbackTransport::new("LOOP-MSG", false).expect("Failed to join");
+        let t3 = LoopbackTransport::new("LOOP-MSG", false).expect("Failed to join");
+
+        for (t, expected) in [(&t1, 2), (&t2, 2), (&t3, 2)] {
+            for _ in 0..expected {
+                let (_, msg) = recv_one(t).await;
+                assert!(matches!(msg, TransportMessage::PeerConnect(_)));
+            }
+            assert_no_incoming(t);
+        }
+
+        t1.send_message(caught(t1.my_id)).await;
+
+        assert_no_incoming(&t1);
+        for t in [&t2, &t3] {
+            let (from, msg) = recv_one(t).await;
+            assert_eq!(from, Some(t1.my_id));
+            assert!(
+                matches!(msg, TransportMessage::Game(ge) if matches!(*ge, GameEvent::PlayerCaught { player, .. } if player == t1.my_id))
+            );
+        }
+
+        t2.send_message_single(t3.my_id, caught(t2.my_id)).await;
+
+        assert_no_incoming(&t1);
+        let (from, _) = recv_one(&t3).await;
+        assert_eq!(from, Some(t2.my_id));
+
+        for t in [t1, t2, t3] {
+            t.disconnect().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_disconnect() {
+        let t1 = LoopbackTransport::new("LOOP-DC", true).expect("Failed to host");
+        let t2 = LoopbackTransport::new("LOOP-DC", false).expect("Failed to join");
+
+        recv_one(&t1).await;
+        recv_one(&t2).await;
+
+        t2.disconnect().await;
+
+        let (_, msg) = recv_one(&t2).await;
+        assert!(matches!(msg, TransportMessage::Disconnected));
+
+        let (_, msg) = recv_one(&t1).await;
+        assert!(matches!(msg, TransportMessage::PeerDisconnect(id) if id == t2.my_id));
+
+        t1.disconnect().await;
+
+        assert!(
+            !ROOMS.lock().unwrap().contains_key("LOOP-DC"),
+            "Empty room wasn't removed"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_room_checks() {
+        assert!(
+            LoopbackTransport::new("LOOP-MISSING", false).is_err(),
+            "Joined a room that doesn't exist"
+        );
+
+        let t1 = LoopbackTransport::new("LOOP-CHECK", true).expect("Failed to host");
+
+        assert!(
+            LoopbackTransport::new("LOOP-CHECK", true).is_err(),
+            "Hosted a room that already exists"
+        );
+        assert!(t1.room_joinable("LOOP-CHECK").await);
+
+        assert!(
+            LoopbackTransport::new_spectator("LOOP-CHECK").is_err(),
+            "Spectated a room that hasn't started"
+        );
+
+        t1.mark_room_started("LOOP-CHECK", false).await;
+
+        assert!(!t1.room_joinable("LOOP-CHECK").await);
+        assert!(
+            LoopbackTransport::new("LOOP-CHECK", false).is_err(),
+            "Joined a room that already started"
+        );
+        assert!(
+            LoopbackTransport::new_spectator("LOOP-CHECK").is_err(),
+            "Spectated a room that doesn't allow it"
+        );
+
+        t1.mark_room_started("LOOP-CHECK", true).await;
+
+        let spectator = LoopbackTransport::new_spectator("LOOP-CHECK").expect("Failed to spectate");
+        spectator.disconnect().await;
+
+        t1.disconnect().await;
+    }
+
+    #[tokio::test]
+    async fn test_lobby_over_loopback() {
+        type LoopbackLobby = Lobby<LoopbackTransport, DummySender>;
+
+        let profile = |name: &str| PlayerProfile {
+            display_name: name.to_string(),
+            ..Default::default()
+        };
+
+        let host = LoopbackLobby::new(
+            "LOOP-LOBBY",
+            true,
+            profile("Host"),
+            GameSettings::default(),
+            DummySender,
+        )
+        .await
+        .expect("Failed to host lobby");
+        let client = LoopbackLobby::new(
+            "LOOP-LOBBY",
+            false,
+            profile("Client"),
+            GameSettings::default(),
+            DummySender,
+        )
+        .await
+        .expect("Failed to join lobby");
+
+        let host_loop = tokio::spawn({
+            let host = host.clone();
+            async move { host.main_loop().await }
+        });
+        let client_loop = tokio::spawn({
+            let client = client.clone();
+            async move { client.main_loop().await }
+        });
+
+        client.switch_teams(true).await;
+
+        let client_id = client.clone_transport().self_id();
+
+        // Starting fails until the host sees the client switch to seeker
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while host.start_game().await.is_err() {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("Host never saw the client switch teams");
+
+        for (name, handle) in [("Host", host_loop), ("Client", client_loop)] {
+            let start = tokio::time::timeout(Duration::from_secs(5), handle)
+                .await
+                .expect("Lobby didn't finish")
+                .expect("Lobby panicked")
+                .expect("Lobby errored")
+                .unwrap_or_else(|| panic!("{name} lobby didn't start a game"));
+            assert_eq!(start.initial_caught_state.get(&client_id), Some(&true));
+        }
+
+        host.clone_transport().disconnect().await;
+        client.clone_transport().disconnect().await;
+    }
+}
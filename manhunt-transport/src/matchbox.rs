@@ -1,24 +1,43 @@
-use std::{collections::HashSet, marker::PhantomData, pin::Pin, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    marker::PhantomData,
+    pin::Pin,
+    sync::Arc,
+    time::Duration,
+};
 
 use anyhow::{Context, anyhow};
 use futures::{
-    SinkExt, Stream, StreamExt,
+    Stream, StreamExt,
     channel::mpsc::{UnboundedReceiver, UnboundedSender},
 };
-use log::{error, info};
-use matchbox_socket::{Error as SocketError, PeerId, PeerState, WebRtcSocket};
+use log::{error, info, warn};
+use matchbox_socket::{Error as SocketError, PeerId, PeerState, RtcIceServerConfig, WebRtcSocket};
 use tokio::{
     sync::{Mutex, mpsc},
     task::yield_now,
+    time::{Instant, MissedTickBehavior},
 };
 use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
 use manhunt_logic::{Transport, TransportMessage, prelude::*};
 
-use crate::{packets::PacketHandler, server};
+use crate::{
+    packets::{HEARTBEAT_PACKET, PacketHandler},
+    server,
+};
+
+/// How often to send a heartbeat to every peer
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How long a peer can go without sending us anything before we treat them as disconnected,
+/// matchbox can be slow to notice (or miss) peers that drop without saying goodbye
+const PEER_TIMEOUT: Duration = Duration::from_secs(30);
 
-type QueuePair<T> = (mpsc::Sender<T>, Mutex<mpsc::Receiver<T>>);
+/// Unbounded so a burst of messages never stalls the transport loop (and its heartbeats) waiting
+/// on the logic to catch up, and nothing important like a catch gets dropped
+type QueuePair<T> = (mpsc::UnboundedSender<T>, Mutex<mpsc::UnboundedReceiver<T>>);
 type MsgPair = (Option<Uuid>, TransportMessage);
 type Queue = QueuePair<MsgPair>;
 
@@ -38,9 +57,51 @@ type MatchboxSender = UnboundedSender<MatchboxMsgPair>;
 type MatchboxReceiver = UnboundedReceiver<MatchboxMsgPair>;
 type MatchboxChannel = (MatchboxSender, MatchboxReceiver);
 
+/// STUN/TURN servers to use when connecting to peers. Without a TURN server to relay through,
+/// players behind strict NATs (some mobile carriers, corporate networks) may never connect to
+/// each other.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IceServerConfig {
+    /// `stun:` and `turn:` URLs of the servers
+    pub urls: Vec<String>,
+    pub username: Option<String>,
+    pub credential: Option<String>,
+}
+
+impl From<IceServerConfig> for RtcIceServerConfig {
+    fn from(value: IceServerConfig) -> Self {
+        Self {
+            urls: value.urls,
+            username: value.username,
+            credential: value.credential,
+        }
+    }
+}
+
+/// The signaling server wouldn't create a room because another room has its code, or it's
+/// reserved for a different host. Look for it with [anyhow::Error::downcast_ref].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoomCodeTakenError;
+
+impl std::fmt::Display for RoomCodeTakenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "That room code is already in use")
+    }
+}
+
+impl std::error::Error for RoomCodeTakenError {}
+
 fn map_socket_error(err: SocketError) -> anyhow::Error {
     match err {
-        SocketError::ConnectionFailed(e) => anyhow!("Connection to server failed: {e:?}"),
+        // The server answers `?create` for a taken code with a 409, matchbox only gives us the
+        // websocket handshake's response buried in its error
+        SocketError::ConnectionFailed(e) if format!("{e:?}").contains("status: 409") => {
+            RoomCodeTakenError.into()
+        }
+        SocketError::ConnectionFailed(e) => anyhow!(
+            "Connection to server failed: {e:?}\n\nIf you're on a restrictive network, you may \
+             need to configure a TURN server to relay through"
+        ),
         SocketError::Disconnected(e) => anyhow!("Connection to server lost: {e:?}"),
     }
 }
@@ -49,30 +110,77 @@ type FutPin<T> = Pin<Box<dyn Future<Output = T> + Send>>;
 type MessageLoopFuture = FutPin<LoopFutRes>;
 type PeerMsg = (PeerId, PeerState);
 
+/// Index of the reliable data channel all messages are currently sent over
+const RELIABLE_CHANNEL: usize = 0;
+
 pub trait SocketImpl: Unpin + Send + Sync + Sized + Stream<Item = PeerMsg> {
-    fn new(room_url: &str) -> (Self, MessageLoopFuture);
+    fn new(room_url: &str, ice_servers: Option<IceServerConfig>) -> (Self, MessageLoopFuture);
     fn get_id(&mut self) -> Option<PeerId>;
-    fn take_channel(&mut self) -> MatchboxChannel;
+    /// Take the sender and receiver for the data channel at `index`, each channel can only be
+    /// taken once
+    fn take_channel(&mut self, index: usize) -> Result<MatchboxChannel>;
 }
 
 impl SocketImpl for WebRtcSocket {
-    fn new(room_url: &str) -> (Self, MessageLoopFuture) {
-        Self::new_reliable(room_url)
+    fn new(room_url: &str, ice_servers: Option<IceServerConfig>) -> (Self, MessageLoopFuture) {
+        if let Some(ice_servers) = ice_servers {
+            Self::builder(room_url)
+                .ice_server(ice_servers.into())
+                .add_reliable_channel()
+                .build()
+        } else {
+            Self::new_reliable(room_url)
+        }
     }
 
     fn get_id(&mut self) -> Option<PeerId> {
         self.id()
     }
 
-    fn take_channel(&mut self) -> MatchboxChannel {
-        self.take_channel(0).expect("Failed to get channel").split()
+    fn take_channel(&mut self, index: usize) -> Result<MatchboxChannel> {
+        self.take_channel(index)
+            .map(|channel| channel.split())
+            .map_err(|err| anyhow!("Failed to get channel {index}: {err:?}"))
     }
 }
 
 impl<S: SocketImpl + 'static> MatchboxTransport<S> {
     pub async fn new(join_code: &str, is_host: bool) -> Result<Arc<Self>> {
+        Self::new_with_ice_servers(join_code, is_host, None).await
+    }
+
+    /// Connect using the given STUN/TURN servers instead of matchbox's defaults
+    pub async fn new_with_ice_servers(
+        join_code: &str,
+        is_host: bool,
+        ice_servers: Option<IceServerConfig>,
+    ) -> Result<Arc<Self>> {
         let ws_url = server::room_url(join_code, is_host);
-        let (socket, loop_fut) = S::new(&ws_url);
+        let (socket, loop_fut) = S::new(&ws_url, ice_servers);
+        Self::from_socket_and_loop_fut(socket, loop_fut).await
+    }
+
+    /// Join a room whose game has already started as a spectator, only works if the host
+    /// allowed spectators
+    pub async fn new_spectator(
+        join_code: &str,
+        ice_servers: Option<IceServerConfig>,
+    ) -> Result<Arc<Self>> {
+        let ws_url = server::spectate_url(join_code);
+        let (socket, loop_fut) = S::new(&ws_url, ice_servers);
+        Self::from_socket_and_loop_fut(socket, loop_fut).await
+    }
+
+    /// Rejoin a room whose game has already started as a player that dropped out, see
+    /// [manhunt_logic::Game::resume]. `player` is the ID we had when the game started, the
+    /// server only lets players that were there back in.
+    pub async fn new_rejoin(
+        join_code: &str,
+        player: Uuid,
+        ice_servers: Option<IceServerConfig>,
+    ) -> Result<Arc<Self>> {
+        let ws_url = server::rejoin_url(join_code, player);
+        let (socket, loop_fut) = S::new(&ws_url, ice_servers);
         Self::from_socket_and_loop_fut(socket, loop_fut).await
     }
 
@@ -80,8 +188,8 @@ impl<S: SocketImpl + 'static> MatchboxTransport<S> {
         mut socket: S,
         mut loop_fut: MessageLoopFuture,
     ) -> Result<Arc<Self>> {
-        let (itx, irx) = mpsc::channel(15);
-        let (mtx, mrx) = socket.take_channel();
+        let (itx, irx) = mpsc::unbounded_channel();
+        let (mtx, mrx) = socket.take_channel(RELIABLE_CHANNEL)?;
 
         let res = loop {
             tokio::select! {
@@ -138,12 +246,14 @@ impl<S: SocketImpl + 'static> MatchboxTransport<S> {
         }
     }
 
-    async fn push_incoming(&self, id: Option<Uuid>, msg: TransportMessage) {
-        self.incoming
-            .0
-            .send((id, msg))
-            .await
-            .expect("Failed to push to incoming queue");
+    fn push_incoming(&self, id: Option<Uuid>, msg: TransportMessage) {
+        // Only fails once we've disconnected and closed the queue, nobody is listening anymore
+        if let Err(why) = self.incoming.0.send((id, msg)) {
+            warn!(
+                "Dropping message from {:?}, transport is disconnected",
+                why.0.0
+            );
+        }
     }
 
     async fn main_loop(
@@ -154,6 +264,12 @@ impl<S: SocketImpl + 'static> MatchboxTransport<S> {
     ) {
         tokio::pin!(loop_fut);
         let mut packet_handler = PacketHandler::default();
+        let mut last_seen = HashMap::<Uuid, Instant>::with_capacity(5);
+        // Peers we dropped for going silent that matchbox still thinks are connected
+        let mut silent_peers = HashSet::<Uuid>::with_capacity(5);
+        let mut heartbeat =
+            tokio::time::interval_at(Instant::now() + HEARTBEAT_INTERVAL, HEARTBEAT_INTERVAL);
+        heartbeat.set_missed_tick_behavior(MissedTickBehavior::Delay);
 
         info!("Starting transport loop");
 
@@ -179,19 +295,38 @@ impl<S: SocketImpl + 'static> MatchboxTransport<S> {
 
                 Some((peer, state)) = socket.next() => {
                     info!("Handling peer {peer}: {state:?}");
+                    silent_peers.remove(&peer.0);
+                    match state {
+                        PeerState::Connected => {
+                            last_seen.insert(peer.0, Instant::now());
+                        }
+                        PeerState::Disconnected => {
+                            last_seen.remove(&peer.0);
+                        }
+                    }
                     self.handle_peer(peer, state).await;
                 }
 
                 Some(data) = mrx.next() => {
                     info!("Handling new packet from {}", data.0);
+                    if let Some(seen) = last_seen.get_mut(&data.0.0) {
+                        *seen = Instant::now();
+                    } else if silent_peers.remove(&data.0.0) {
+                        info!("Peer {} is talking again, reconnecting them", data.0);
+                        last_seen.insert(data.0.0, Instant::now());
+                        self.handle_peer(data.0, PeerState::Connected).await;
+                    }
                     self.handle_recv(data, &mut packet_handler).await;
                 }
 
-
+                _ = heartbeat.tick() => {
+                    self.send_heartbeats().await;
+                    self.drop_silent_peers(&mut last_seen, &mut silent_peers).await;
+                }
             }
         };
 
-        self.push_incoming(Some(self.my_id), msg).await;
+        self.push_incoming(Some(self.my_id), msg);
 
         self.msg_sender.close_channel();
         self.incoming.1.lock().await.close();
@@ -213,12 +348,48 @@ impl<S: SocketImpl + 'static> MatchboxTransport<S> {
                 TransportMessage::PeerConnect(peer.0)
             }
             PeerState::Disconnected => {
-                all_peers.remove(&peer.0);
+                if !all_peers.remove(&peer.0) {
+                    // Already dropped them for going silent
+                    return;
+                }
                 TransportMessage::PeerDisconnect(peer.0)
             }
         };
         drop(all_peers);
-        self.push_incoming(Some(peer.0), msg).await;
+        self.push_incoming(Some(peer.0), msg);
+    }
+
+    async fn send_heartbeats(&self) {
+        let all_peers = self.all_peers.lock().await;
+        for peer in all_peers.iter().copied() {
+            let packet = HEARTBEAT_PACKET.to_vec().into_boxed_slice();
+            if let Err(why) = self.msg_sender.unbounded_send((PeerId(peer), packet)) {
+                error!("Error sending heartbeat: {why}");
+            }
+        }
+    }
+
+    /// Treat any peer we haven't heard from in [PEER_TIMEOUT] as disconnected, they're added to
+    /// `silent_peers` so they can be reconnected if they start talking again
+    async fn drop_silent_peers(
+        &self,
+        last_seen: &mut HashMap<Uuid, Instant>,
+        silent_peers: &mut HashSet<Uuid>,
+    ) {
+        let now = Instant::now();
+        let silent = last_seen
+            .iter()
+            .filter(|(_, seen)| now.duration_since(**seen) > PEER_TIMEOUT)
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+
+        for peer in silent {
+            warn!("Haven't heard from peer {peer} in a while, dropping them");
+            last_seen.remove(&peer);
+            silent_peers.insert(peer);
+            self.handle_peer(PeerId(peer), PeerState::Disconnected)
+                .await;
+        }
     }
 
     async fn handle_recv(
@@ -228,7 +399,7 @@ impl<S: SocketImpl + 'static> MatchboxTransport<S> {
     ) {
         match handler.consume_packet(peer, packet.into_vec()) {
             Ok(Some(msg)) => {
-                self.push_incoming(Some(peer), msg).await;
+                self.push_incoming(Some(peer), msg);
             }
             Ok(None) => {
                 // Non complete message
@@ -240,37 +411,47 @@ impl<S: SocketImpl + 'static> MatchboxTransport<S> {
     }
 
     pub async fn send_transport_message(&self, peer: Option<Uuid>, msg: TransportMessage) {
-        let mut tx = self.msg_sender.clone();
-
-        match PacketHandler::message_to_packets(&msg) {
-            Ok(packets) => {
-                if let Some(peer) = peer {
-                    let mut stream = futures::stream::iter(
-                        packets
-                            .into_iter()
-                            .map(|p| Ok((PeerId(peer), p.into_boxed_slice()))),
-                    );
-                    if let Err(why) = tx.send_all(&mut stream).await {
-                        error!("Error sending packet: {why}");
-                    }
-                } else {
-                    let all_peers = self.all_peers.lock().await;
-                    for peer in all_peers.iter().copied() {
-                        let packets = packets.clone();
-                        let mut stream = futures::stream::iter(
-                            packets
-                                .into_iter()
-                                .map(|p| Ok((PeerId(peer), p.into_boxed_slice()))),
-                        );
-                        if let Err(why) = tx.send_all(&mut stream).await {
-                            error!("Error sending packet: {why}");
-                        }
-                    }
-                }
-            }
+        let packets = match PacketHandler::message_to_packets(&msg) {
+            Ok(packets) => packets
+                .into_iter()
+                .map(Vec::into_boxed_slice)
+                .collect::<Vec<_>>(),
             Err(why) => {
                 error!("Error encoding message: {why}");
+                return;
+            }
+        };
+
+        let peers = if let Some(peer) = peer {
+            vec![peer]
+        } else {
+            self.all_peers.lock().await.iter().copied().collect()
+        };
+
+        self.send_packets(&peers, packets);
+    }
+
+    /// Send the same packets to every peer in `peers`. Every peer but the last gets a copy of
+    /// each packet, the last one is given the originals.
+    fn send_packets(&self, peers: &[Uuid], packets: Vec<Box<[u8]>>) {
+        let Some((last, rest)) = peers.split_last() else {
+            return;
+        };
+
+        let send = |peer: Uuid, packet: Box<[u8]>| {
+            if let Err(why) = self.msg_sender.unbounded_send((PeerId(peer), packet)) {
+                error!("Error sending packet: {why}");
             }
+        };
+
+        for peer in rest.iter().copied() {
+            for packet in packets.iter() {
+                send(peer, packet.clone());
+            }
+        }
+
+        for packet in packets {
+            send(*last, packet);
         }
     }
 
@@ -320,15 +501,15 @@ impl<S: SocketImpl + 'static> Transport for MatchboxTransport<S> {
     }
 
     async fn send_self(&self, msg: TransportMessage) {
-        self.push_incoming(Some(self.my_id), msg).await;
+        self.push_incoming(Some(self.my_id), msg);
     }
 
     async fn room_joinable(&self, code: &str) -> bool {
-        server::room_exists(code).await.unwrap_or(false)
+        server::room_status(code).await == server::RoomStatus::Exists
     }
 
-    async fn mark_room_started(&self, code: &str) {
-        if let Err(why) = server::mark_room_started(code).await {
+    async fn mark_room_started(&self, code: &str, allow_spectators: bool) {
+        if let Err(why) = server::mark_room_started(code, allow_spectators).await {
             error!("Failed to mark room {code} as started: {why:?}");
         }
     }
@@ -346,10 +527,11 @@ impl<S: SocketImpl + 'static> Transport for MatchboxTransport<S> {
 mod tests {
 
     use futures::{
+        SinkExt,
         channel::{mpsc, oneshot},
         lock::Mutex as FutMutex,
     };
-    use manhunt_logic::{GameEvent, LobbyMessage, PlayerProfile};
+    use manhunt_logic::{CatchReason, GameEvent, LobbyMessage, PlayerProfile};
     use matchbox_socket::SignalingError;
 
     use super::*;
@@ -364,14 +546,14 @@ mod tests {
     struct MockSocket {
         peer_recv: PeerRx,
         id: IdHandle,
-        channel: Option<MatchboxChannel>,
+        channels: Vec<Option<MatchboxChannel>>,
         cancel: CancellationToken,
     }
 
     impl MockSocket {
         pub fn new(
             peer_recv: PeerRx,
-            channel: MatchboxChannel,
+            channels: Vec<MatchboxChannel>,
             id: IdHandle,
         ) -> (
             Self,
@@ -383,7 +565,7 @@ mod tests {
             let cancel = CancellationToken::new();
             let sock = Self {
                 peer_recv,
-                channel: Some(channel),
+                channels: channels.into_iter().map(Some).collect(),
                 id,
                 cancel: cancel.clone(),
             };
@@ -413,21 +595,40 @@ mod tests {
     }
 
     impl SocketImpl for MockSocket {
-        fn new(_room_url: &str) -> (Self, MessageLoopFuture) {
-            unreachable!("Tests should use [MatchboxTransport::from_socket_and_loop_fut]")
+        /// Records the ICE servers it's given in [LAST_ICE_SERVERS] and returns a socket that
+        /// fails to connect, tests that need a working socket should use
+        /// [MatchboxTransport::from_socket_and_loop_fut]
+        fn new(_room_url: &str, ice_servers: Option<IceServerConfig>) -> (Self, MessageLoopFuture) {
+            *LAST_ICE_SERVERS.lock().unwrap() = Some(ice_servers);
+            let (_, peer_rx) = mpsc::unbounded();
+            let (itx, irx) = mpsc::unbounded();
+            let socket = Self {
+                peer_recv: peer_rx,
+                id: Arc::new(StdMutex::new(None)),
+                channels: vec![Some((itx, irx))],
+                cancel: CancellationToken::new(),
+            };
+            (socket, Box::pin(async { Ok(()) }))
         }
 
         fn get_id(&mut self) -> Option<PeerId> {
             *self.id.lock().unwrap()
         }
 
-        fn take_channel(&mut self) -> (MatchboxSender, MatchboxReceiver) {
-            self.channel.take().expect("Channel already taken")
+        fn take_channel(&mut self, index: usize) -> Result<MatchboxChannel> {
+            self.channels
+                .get_mut(index)
+                .context("Channel not found")?
+                .take()
+                .context("Channel already taken")
         }
     }
 
     type MatchboxTransport = super::MatchboxTransport<MockSocket>;
 
+    /// ICE servers given to the last [MockSocket] made with [SocketImpl::new]
+    static LAST_ICE_SERVERS: StdMutex<Option<Option<IceServerConfig>>> = StdMutex::new(None);
+
     struct WaitingPeer {
         incoming: MatchboxSender,
         outgoing: MatchboxReceiver,
@@ -464,14 +665,28 @@ mod tests {
         ) -> (
             WaitingPeer,
             FutPin<Result<Arc<MatchboxTransport>, anyhow::Error>>,
+        ) {
+            self.client_connect_with_channels(id, vec![])
+        }
+
+        /// Like [MockSignaling::client_connect], but the socket has `extra` channels after the
+        /// reliable one that signaling doesn't route anything through
+        fn client_connect_with_channels(
+            &self,
+            id: Uuid,
+            extra: Vec<MatchboxChannel>,
+        ) -> (
+            WaitingPeer,
+            FutPin<Result<Arc<MatchboxTransport>, anyhow::Error>>,
         ) {
             let (itx, irx) = mpsc::unbounded();
             let (otx, orx) = mpsc::unbounded();
             let (peer_tx, peer_rx) = mpsc::unbounded();
             let id_handle = Arc::new(StdMutex::new(None));
 
+            let channels = std::iter::once((otx, irx)).chain(extra).collect();
             let (sock, fut, disconnect, cancel) =
-                MockSocket::new(peer_rx, (otx, irx), id_handle.clone());
+                MockSocket::new(peer_rx, channels, id_handle.clone());
 
             let transport_fut = Box::pin(MatchboxTransport::from_socket_and_loop_fut(sock, fut));
 
@@ -563,6 +778,23 @@ mod tests {
             self.broadcast_peer_leave(PeerId(id)).await;
         }
 
+        /// Stop delivering anything from a peer without telling anyone they left, like a
+        /// peer that lost signal
+        fn silence_peer(&self, id: Uuid) {
+            let (_, _, cancel, _) = self.peers.get(&PeerId(id)).expect("Peer not connected");
+            cancel.cancel();
+        }
+
+        /// Deliver a packet to `to` as if it came from `from`, even if `from` was silenced
+        async fn send_as(&self, from: Uuid, to: Uuid, packet: Box<[u8]>) {
+            let mut senders = self.senders.lock().await;
+            let sender = senders.get_mut(&PeerId(to)).expect("Failed to find peer");
+            sender
+                .send((PeerId(from), packet))
+                .await
+                .expect("Failed to send");
+        }
+
         async fn wait_for_socket_drop(&self, id: Uuid) {
             let cancel = self.peers.get(&PeerId(id)).unwrap().3.clone();
             cancel.cancelled().await;
@@ -605,6 +837,105 @@ mod tests {
         sig.wait_for_client_disconnected(id(1)).await;
     }
 
+    #[test]
+    async fn test_ice_servers_passed_to_socket() {
+        let ice = IceServerConfig {
+            urls: vec!["turn:turn.example.com:3478".to_string()],
+            username: Some("user".to_string()),
+            credential: Some("pass".to_string()),
+        };
+
+        let res = MatchboxTransport::new_with_ice_servers("ABCD", true, Some(ice.clone())).await;
+
+        assert!(res.is_err(), "Mock socket shouldn't connect");
+        assert_eq!(*LAST_ICE_SERVERS.lock().unwrap(), Some(Some(ice)));
+    }
+
+    #[test]
+    async fn test_take_multiple_channels() {
+        let (_peer_tx, peer_rx) = mpsc::unbounded();
+        let (reliable_tx, reliable_rx) = mpsc::unbounded();
+        let (unreliable_tx, unreliable_rx) = mpsc::unbounded();
+        let (mut sock, _fut, _stop, _cancel) = MockSocket::new(
+            peer_rx,
+            vec![(reliable_tx, reliable_rx), (unreliable_tx, unreliable_rx)],
+            Arc::new(StdMutex::new(None)),
+        );
+
+        let (mut tx0, mut rx0) = sock.take_channel(0).expect("Failed to take channel 0");
+        let (mut tx1, mut rx1) = sock.take_channel(1).expect("Failed to take channel 1");
+
+        assert!(sock.take_channel(0).is_err(), "Took channel 0 twice");
+        assert!(
+            sock.take_channel(2).is_err(),
+            "Took a channel that doesn't exist"
+        );
+
+        tx0.send((PeerId(id(1)), Box::new([0]))).await.unwrap();
+        tx1.send((PeerId(id(1)), Box::new([1]))).await.unwrap();
+
+        let (_, packet) = rx0.next().await.unwrap();
+        assert_eq!(&*packet, &[0]);
+        let (_, packet) = rx1.next().await.unwrap();
+        assert_eq!(&*packet, &[1]);
+
+        assert!(rx0.try_recv().is_err(), "Channel 0 got channel 1's packet");
+        assert!(rx1.try_recv().is_err(), "Channel 1 got channel 0's packet");
+    }
+
+    #[test]
+    async fn test_transport_uses_reliable_channel() {
+        let mut sig = MockSignaling::new();
+
+        let (extra_out_tx, mut extra_out_rx) = mpsc::unbounded();
+        let (mut extra_in_tx, extra_in_rx) = mpsc::unbounded();
+        let (wait, fut) =
+            sig.client_connect_with_channels(id(1), vec![(extra_out_tx, extra_in_rx)]);
+        sig.assign_id(wait).await;
+        let t1 = fut.await.expect("Transport init failed");
+        let t2 = sig.quick_join(id(2)).await;
+
+        sig.wait().await;
+        t1.force_recv_msg().await;
+        t2.force_recv_msg().await;
+
+        // Anything arriving on the other channel isn't read as a message
+        let stray = TransportMessage::from(LobbyMessage::PlayerSwitch(id(2), false));
+        for packet in PacketHandler::message_to_packets(&stray).expect("Failed to encode") {
+            extra_in_tx
+                .send((PeerId(id(2)), packet.into_boxed_slice()))
+                .await
+                .expect("Failed to send");
+        }
+
+        t2.send_transport_message(Some(id(1)), LobbyMessage::PlayerSwitch(id(2), true).into())
+            .await;
+        t1.send_transport_message(Some(id(2)), LobbyMessage::PlayerSwitch(id(1), true).into())
+            .await;
+
+        sig.wait().await;
+
+        let (_, msg) = t1.force_recv_msg().await;
+        assert!(
+            matches!(msg, TransportMessage::Lobby(lm) if matches!(*lm, LobbyMessage::PlayerSwitch(i, b) if i == id(2) && b)),
+            "Transport didn't get the packet from the reliable channel"
+        );
+        let (_, msg) = t2.force_recv_msg().await;
+        assert!(
+            matches!(msg, TransportMessage::Lobby(lm) if matches!(*lm, LobbyMessage::PlayerSwitch(i, b) if i == id(1) && b)),
+            "Transport didn't send over the reliable channel"
+        );
+
+        assert!(
+            extra_out_rx.try_recv().is_err(),
+            "Transport sent over the other channel"
+        );
+        assert!(
+            t1.incoming.1.lock().await.try_recv().is_err(),
+            "Transport read a packet from the other channel"
+        );
+    }
+
     #[test]
     async fn test_dc_pre_assign() {
         let sig = MockSignaling::new();
@@ -725,15 +1056,23 @@ mod tests {
         assert!(matches!(msg, TransportMessage::PeerConnect(pid) if pid == id(2)));
         assert!(matches!(msg2, TransportMessage::PeerConnect(pid) if pid == id(1)));
 
-        t1.send_transport_message(Some(id(2)), GameEvent::PlayerCaught(id(1)).into())
-            .await;
+        t1.send_transport_message(
+            Some(id(2)),
+            GameEvent::PlayerCaught {
+                player: id(1),
+                by: None,
+                reason: CatchReason::Caught,
+            }
+            .into(),
+        )
+        .await;
 
         sig.wait().await;
 
         let (_, msg) = t2.force_recv_msg().await;
 
         assert!(
-            matches!(msg, TransportMessage::Game(ge) if matches!(*ge, GameEvent::PlayerCaught(i) if i == id(1)))
+            matches!(msg, TransportMessage::Game(ge) if matches!(*ge, GameEvent::PlayerCaught { player, .. } if player == id(1)))
         );
 
         t2.send_transport_message(None, LobbyMessage::PlayerSwitch(id(2), true).into())
@@ -748,6 +1087,54 @@ mod tests {
         );
     }
 
+    #[test]
+    async fn test_incoming_flood() {
+        let mut sig = MockSignaling::new();
+
+        let t1 = sig.quick_join(id(1)).await;
+        let t2 = sig.quick_join(id(2)).await;
+
+        sig.wait().await;
+
+        t2.force_recv_msg().await;
+
+        // Way more than the logic would read in one go, with a catch buried in the middle
+        const FLOOD: usize = 500;
+        for i in 0..FLOOD {
+            let msg = if i == FLOOD / 2 {
+                GameEvent::PlayerCaught {
+                    player: id(1),
+                    by: None,
+                    reason: CatchReason::Caught,
+                }
+            } else {
+                GameEvent::RequestSync(id(1))
+            };
+            t1.send_transport_message(Some(id(2)), msg.into()).await;
+        }
+
+        sig.wait().await;
+
+        let mut received = Vec::with_capacity(FLOOD);
+        while received.len() < FLOOD {
+            received.extend(t2.recv_transport_messages().await);
+        }
+        t2.assert_no_incoming().await;
+
+        let catches = received
+            .iter()
+            .filter(|(_, msg)| {
+                matches!(msg, TransportMessage::Game(ge) if matches!(**ge, GameEvent::PlayerCaught { .. }))
+            })
+            .count();
+        assert_eq!(catches, 1, "Catch didn't make it through the flood");
+
+        // Pushing after disconnecting shouldn't panic either
+        t2.disconnect().await;
+        sig.wait_for_client_disconnected(id(2)).await;
+        t2.send_self(TransportMessage::Disconnected).await;
+    }
+
     #[test]
     async fn test_msg_broadcast() {
         let mut sig = MockSignaling::new();
@@ -770,7 +1157,15 @@ mod tests {
         }
 
         ts[0]
-            .send_transport_message(None, GameEvent::PlayerCaught(id(1)).into())
+            .send_transport_message(
+                None,
+                GameEvent::PlayerCaught {
+                    player: id(1),
+                    by: None,
+                    reason: CatchReason::Caught,
+                }
+                .into(),
+            )
             .await;
 
         sig.wait().await;
@@ -781,11 +1176,50 @@ mod tests {
             let (pid, msg) = t.force_recv_msg().await;
             assert_eq!(pid, Some(id(1)));
             assert!(
-                matches!(msg, TransportMessage::Game(ge) if matches!(*ge, GameEvent::PlayerCaught(i) if i == id(1)))
+                matches!(msg, TransportMessage::Game(ge) if matches!(*ge, GameEvent::PlayerCaught { player, .. } if player == id(1)))
             );
         }
     }
 
+    #[test]
+    async fn test_broadcast_many_peers() {
+        const PEERS: u128 = 10;
+
+        let mut sig = MockSignaling::new();
+
+        let mut ts = Vec::with_capacity(PEERS as usize);
+        for i in 1..=PEERS {
+            ts.push(sig.quick_join(id(i)).await);
+        }
+
+        sig.wait().await;
+
+        for t in ts.iter() {
+            // Eat the PeerConnected messages
+            for _ in 0..(ts.len() - 1) {
+                t.force_recv_msg().await;
+            }
+        }
+
+        // Big enough to be split into multiple packets
+        let big_string = "a".repeat(65535 * 2);
+
+        ts[0]
+            .send_transport_message(None, TransportMessage::Error(big_string.clone()))
+            .await;
+
+        sig.wait().await;
+
+        ts[0].assert_no_incoming().await;
+
+        for t in ts.iter().skip(1) {
+            let (pid, msg) = t.force_recv_msg().await;
+            assert_eq!(pid, Some(id(1)));
+            assert!(matches!(msg, TransportMessage::Error(s) if s == big_string));
+            t.assert_no_incoming().await;
+        }
+    }
+
     #[test]
     async fn test_direct_msg() {
         let mut sig = MockSignaling::new();
@@ -807,7 +1241,15 @@ mod tests {
         }
 
         ts[0]
-            .send_transport_message(Some(id(2)), GameEvent::PlayerCaught(id(1)).into())
+            .send_transport_message(
+                Some(id(2)),
+                GameEvent::PlayerCaught {
+                    player: id(1),
+                    by: None,
+                    reason: CatchReason::Caught,
+                }
+                .into(),
+            )
             .await;
 
         sig.wait().await;
@@ -818,7 +1260,7 @@ mod tests {
         let (pid, msg) = ts[1].force_recv_msg().await;
         assert_eq!(pid, Some(id(1)));
         assert!(
-            matches!(msg, TransportMessage::Game(ge) if matches!(*ge, GameEvent::PlayerCaught(i) if i == id(1)))
+            matches!(msg, TransportMessage::Game(ge) if matches!(*ge, GameEvent::PlayerCaught { player, .. } if player == id(1)))
         );
     }
 
@@ -857,6 +1299,80 @@ mod tests {
         }
     }
 
+    #[test]
+    async fn test_silent_peer_dropped() {
+        let mut sig = MockSignaling::new();
+
+        let t1 = sig.quick_join(id(1)).await;
+        let t2 = sig.quick_join(id(2)).await;
+        let t3 = sig.quick_join(id(3)).await;
+
+        sig.wait().await;
+
+        let ts = [t1, t2, t3];
+
+        for t in ts.iter() {
+            // Eat the PeerConnected messages
+            for _ in 0..(ts.len() - 1) {
+                t.force_recv_msg().await;
+            }
+        }
+
+        sig.silence_peer(id(2));
+
+        tokio::time::sleep(PEER_TIMEOUT + HEARTBEAT_INTERVAL * 2).await;
+
+        for t in [&ts[0], &ts[2]] {
+            let (_, msg) = t.force_recv_msg().await;
+            assert!(matches!(msg, TransportMessage::PeerDisconnect(i) if i == id(2)));
+            t.assert_no_incoming().await;
+
+            let all = t.all_peers.lock().await;
+            assert!(!all.contains(&id(2)));
+            assert_eq!(all.len(), 1, "Peers that are still talking were dropped");
+        }
+
+        // Matchbox finally noticing shouldn't cause a second disconnect
+        sig.disconnect_peer(id(2), Ok(())).await;
+        sig.wait().await;
+
+        ts[0].assert_no_incoming().await;
+        ts[2].assert_no_incoming().await;
+    }
+
+    #[test]
+    async fn test_silent_peer_reconnects() {
+        let mut sig = MockSignaling::new();
+
+        let t1 = sig.quick_join(id(1)).await;
+        let _t2 = sig.quick_join(id(2)).await;
+
+        sig.wait().await;
+        t1.force_recv_msg().await;
+
+        sig.silence_peer(id(2));
+        tokio::time::sleep(PEER_TIMEOUT + HEARTBEAT_INTERVAL * 2).await;
+
+        let (_, msg) = t1.force_recv_msg().await;
+        assert!(matches!(msg, TransportMessage::PeerDisconnect(i) if i == id(2)));
+
+        // Their signal comes back before matchbox ever noticed they were gone
+        sig.send_as(id(2), id(1), HEARTBEAT_PACKET.to_vec().into_boxed_slice())
+            .await;
+        sig.wait().await;
+
+        let (_, msg) = t1.force_recv_msg().await;
+        assert!(matches!(msg, TransportMessage::PeerConnect(i) if i == id(2)));
+        t1.assert_no_incoming().await;
+        assert!(t1.all_peers.lock().await.contains(&id(2)));
+
+        // Only peers we dropped ourselves are reconnected this way
+        sig.send_as(id(3), id(1), HEARTBEAT_PACKET.to_vec().into_boxed_slice())
+            .await;
+        sig.wait().await;
+        t1.assert_no_incoming().await;
+    }
+
     #[test]
     async fn test_big_message() {
         // Just a random string that's bigger than the max packet size
@@ -880,6 +1396,7 @@ mod tests {
                 PlayerProfile {
                     display_name: "asdf".to_string(),
                     pfp_base64: Some(pfp2),
+                    ..Default::default()
                 },
             )
             .into(),
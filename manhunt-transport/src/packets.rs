@@ -21,6 +21,10 @@ const MATCHBOX_MAX_SIZE: usize = 65535;
 const PACKET_SIZE: usize = MATCHBOX_MAX_SIZE - SEQ_HEADER_SIZE;
 const MAX_NUM_PACKETS: u64 = u64::MAX - 1;
 
+/// Sent periodically to let peers know we're still here. It's shorter than a sequence header so
+/// it can never be mistaken for part of a message.
+pub const HEARTBEAT_PACKET: [u8; 1] = [0];
+
 impl Packet {
     pub fn from_raw_bytes(mut bytes: PacketEncoded) -> Result<Self> {
         // First [SEQ_HEADER_SIZE] bytes are our sequence header, in little endian.
@@ -108,6 +112,11 @@ impl PacketHandler {
         peer: Uuid,
         bytes: PacketEncoded,
     ) -> Result<Option<TransportMessage>> {
+        if bytes == HEARTBEAT_PACKET {
+            // Heartbeats can arrive in the middle of a multipart message, don't let them break it
+            return Ok(None);
+        }
+
         match Packet::from_raw_bytes(bytes).context("Failed to decode packet") {
             Ok(Packet {
                 remaining_packets,
@@ -219,4 +228,28 @@ mod tests {
             panic!("Decoded is the wrong type or wasn't completed");
         }
     }
+
+    #[test]
+    fn test_heartbeat_mid_multipart() {
+        let big_string = "a".repeat(MATCHBOX_MAX_SIZE + 35);
+        let big_message = TransportMessage::Error(big_string.clone());
+
+        let mut packets =
+            PacketHandler::message_to_packets(&big_message).expect("Failed to encode");
+        packets.insert(1, HEARTBEAT_PACKET.to_vec());
+
+        let mut handler = PacketHandler::default();
+        let mut res = None;
+
+        for pack in packets {
+            res = handler
+                .consume_packet(Uuid::default(), pack)
+                .expect("Failed to decode");
+        }
+
+        assert!(
+            matches!(res, Some(TransportMessage::Error(s)) if s == big_string),
+            "Heartbeat broke the multipart message"
+        );
+    }
 }
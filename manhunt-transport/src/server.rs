@@ -1,5 +1,9 @@
+use log::warn;
 use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
+use manhunt_common::{PROTOCOL_VERSION, ROOM_CODE_CHAR_POOL, ROOM_CODE_LEN};
 use manhunt_logic::prelude::*;
 
 const fn server_host() -> &'static str {
@@ -44,21 +48,136 @@ const SERVER_SOCKET: &str = const_str::concat!(SERVER_HOST, ":", SERVER_PORT);
 const SERVER_WEBSOCKET_URL: &str = const_str::concat!(SERVER_WS_PROTO, "://", SERVER_SOCKET);
 const SERVER_HTTP_URL: &str = const_str::concat!(SERVER_HTTP_PROTO, "://", SERVER_SOCKET);
 
+/// Whether a code could be a room code at all, without asking the signaling server. Case
+/// insensitive as codes are uppercased before joining. Use [room_status] to check the room
+/// actually exists.
+pub fn is_valid_code_format(code: &str) -> bool {
+    code.len() == ROOM_CODE_LEN
+        && code
+            .bytes()
+            .all(|c| ROOM_CODE_CHAR_POOL.contains(&c.to_ascii_uppercase()))
+}
+
 pub fn room_url(code: &str, host: bool) -> String {
     let query_param = if host { "?create" } else { "" };
     format!("{SERVER_WEBSOCKET_URL}/{code}{query_param}")
 }
 
-pub async fn room_exists(code: &str) -> Result<bool> {
-    let url = format!("{SERVER_HTTP_URL}/room_exists/{code}");
-    reqwest::get(url)
+/// URL to join an already started room as a spectator
+pub fn spectate_url(code: &str) -> String {
+    format!("{SERVER_WEBSOCKET_URL}/{code}?spectate")
+}
+
+/// URL to rejoin an already started room as a player that dropped out of the game, `player` is
+/// the ID we had when the game started
+pub fn rejoin_url(code: &str, player: Uuid) -> String {
+    format!("{SERVER_WEBSOCKET_URL}/{code}?rejoin={player}")
+}
+
+/// Whether a room can be joined, according to the signaling server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+pub enum RoomStatus {
+    /// The room exists and can be joined
+    Exists,
+    /// There's no room with this code, or its game already started. For
+    /// [started_room_status], there's no room with this code or its game hasn't started.
+    NotFound,
+    /// Couldn't get an answer from the signaling server. For [started_room_status], this is
+    /// also the answer when the server is too old to check for started games.
+    ServerUnreachable,
+}
+
+/// What the signaling server's `/info` route responds with
+#[derive(Deserialize)]
+struct InfoResponse {
+    version: String,
+    protocol_version: u32,
+}
+
+/// Version info of the signaling server, see [server_info]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, specta::Type)]
+pub struct ServerInfo {
+    /// Version of the signaling server itself
+    pub server_version: String,
+    /// The server's [PROTOCOL_VERSION]
+    pub protocol_version: u32,
+    /// Whether the server's protocol version matches ours, hosting or joining will probably
+    /// fail if not
+    pub compatible: bool,
+}
+
+/// Ask the signaling server what version it's running, use this to warn about a mismatched
+/// server before hosting or joining
+pub async fn server_info() -> Result<ServerInfo> {
+    server_info_at(SERVER_HTTP_URL).await
+}
+
+async fn server_info_at(base_url: &str) -> Result<ServerInfo> {
+    let info = reqwest::get(format!("{base_url}/info"))
         .await
-        .map(|resp| resp.status() == StatusCode::OK)
-        .context("Failed to make request")
+        .context("Failed to contact signaling server")?
+        .error_for_status()
+        .context("Server returned an error")?
+        .json::<InfoResponse>()
+        .await
+        .context("Failed to decode response")?;
+
+    Ok(ServerInfo {
+        compatible: info.protocol_version == PROTOCOL_VERSION,
+        server_version: info.version,
+        protocol_version: info.protocol_version,
+    })
+}
+
+pub async fn room_status(code: &str) -> RoomStatus {
+    room_status_at(SERVER_HTTP_URL, code, false).await
+}
+
+/// Check whether a room's game is still going so we can rejoin it, [room_status] only finds
+/// rooms that haven't started yet
+pub async fn started_room_status(code: &str) -> RoomStatus {
+    started_room_status_at(SERVER_HTTP_URL, code).await
+}
+
+async fn started_room_status_at(base_url: &str, code: &str) -> RoomStatus {
+    // Servers from before PROTOCOL_VERSION 2 ignore `?started` and say started rooms don't
+    // exist, so only ask servers that will give a real answer
+    match server_info_at(base_url).await {
+        Ok(info) if info.compatible => room_status_at(base_url, code, true).await,
+        Ok(info) => {
+            warn!(
+                "Server is on protocol version {}, can't check room {code} started",
+                info.protocol_version
+            );
+            RoomStatus::ServerUnreachable
+        }
+        Err(why) => {
+            warn!("Failed to get server info checking room {code}: {why:?}");
+            RoomStatus::ServerUnreachable
+        }
+    }
+}
+
+async fn room_status_at(base_url: &str, code: &str, started: bool) -> RoomStatus {
+    let query_param = if started { "?started" } else { "" };
+    let url = format!("{base_url}/room_exists/{code}{query_param}");
+    match reqwest::get(url).await {
+        Ok(resp) if resp.status() == StatusCode::OK => RoomStatus::Exists,
+        Ok(resp) if resp.status() == StatusCode::NOT_FOUND => RoomStatus::NotFound,
+        Ok(resp) => {
+            warn!("Unexpected status checking room {code}: {}", resp.status());
+            RoomStatus::ServerUnreachable
+        }
+        Err(why) => {
+            warn!("Failed to reach server checking room {code}: {why:?}");
+            RoomStatus::ServerUnreachable
+        }
+    }
 }
 
-pub async fn mark_room_started(code: &str) -> Result {
-    let url = format!("{SERVER_HTTP_URL}/mark_started/{code}");
+pub async fn mark_room_started(code: &str, allow_spectators: bool) -> Result {
+    let query_param = if allow_spectators { "?spectators" } else { "" };
+    let url = format!("{SERVER_HTTP_URL}/mark_started/{code}{query_param}");
     let client = reqwest::Client::builder().build()?;
     client
         .post(url)
@@ -82,3 +201,143 @@ pub async fn request_room_code() -> Result<String> {
         .await
         .context("Failed to decode response")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+    };
+
+    /// Answer a single HTTP request with the given status line, returns the base URL to use
+    fn serve_once(status: &'static str) -> String {
+        serve_once_with_body(status, "")
+    }
+
+    /// [serve_once] but responding with a JSON body
+    fn serve_once_with_body(status: &'static str, body: &str) -> String {
+        serve_in_order(vec![(status, body.to_string())])
+    }
+
+    /// Answer one HTTP request for each of the given status lines and JSON bodies, in order
+    fn serve_in_order(responses: Vec<(&'static str, String)>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind");
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for (status, body) in responses {
+                let (mut stream, _) = listener.accept().expect("Failed to accept");
+                let mut buf = [0; 1024];
+                let _ = stream.read(&mut buf);
+                let resp = format!(
+                    "HTTP/1.1 {status}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                stream
+                    .write_all(resp.as_bytes())
+                    .expect("Failed to respond");
+            }
+        });
+        format!("http://{addr}")
+    }
+
+    fn info_body(protocol_version: u32) -> String {
+        format!(r#"{{"version":"1.2.3","protocol_version":{protocol_version}}}"#)
+    }
+
+    #[test]
+    fn test_code_format() {
+        assert!(is_valid_code_format("ABC123"));
+        assert!(is_valid_code_format("abc123"));
+        assert!(!is_valid_code_format("ABC12"));
+        assert!(!is_valid_code_format("ABC1234"));
+        assert!(!is_valid_code_format("ABC-12"));
+        assert!(!is_valid_code_format("ÄBC12"));
+        assert!(!is_valid_code_format(""));
+    }
+
+    #[tokio::test]
+    async fn test_room_exists() {
+        let url = serve_once("200 OK");
+        assert_eq!(
+            room_status_at(&url, "ABCD", false).await,
+            RoomStatus::Exists
+        );
+        let url = serve_once("200 OK");
+        assert_eq!(room_status_at(&url, "ABCD", true).await, RoomStatus::Exists);
+    }
+
+    #[tokio::test]
+    async fn test_room_not_found() {
+        let url = serve_once("404 Not Found");
+        assert_eq!(
+            room_status_at(&url, "ABCD", false).await,
+            RoomStatus::NotFound
+        );
+    }
+
+    #[tokio::test]
+    async fn test_server_info() {
+        let url = serve_once_with_body("200 OK", &info_body(PROTOCOL_VERSION));
+        let info = server_info_at(&url).await.expect("Failed to get info");
+        assert_eq!(info.server_version, "1.2.3");
+        assert!(info.compatible);
+
+        let url = serve_once_with_body("200 OK", r#"{"version":"9.0.0","protocol_version":99}"#);
+        let info = server_info_at(&url).await.expect("Failed to get info");
+        assert_eq!(info.protocol_version, 99);
+        assert!(!info.compatible);
+
+        // Servers from before the info route existed
+        let url = serve_once("404 Not Found");
+        assert!(server_info_at(&url).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_started_room_status() {
+        let url = serve_in_order(vec![
+            ("200 OK", info_body(PROTOCOL_VERSION)),
+            ("200 OK", String::new()),
+        ]);
+        assert_eq!(
+            started_room_status_at(&url, "ABCD").await,
+            RoomStatus::Exists
+        );
+
+        let url = serve_in_order(vec![
+            ("200 OK", info_body(PROTOCOL_VERSION)),
+            ("404 Not Found", String::new()),
+        ]);
+        assert_eq!(
+            started_room_status_at(&url, "ABCD").await,
+            RoomStatus::NotFound
+        );
+
+        // Older servers would say the room doesn't exist, that can't be trusted
+        let url = serve_once_with_body("200 OK", &info_body(1));
+        assert_eq!(
+            started_room_status_at(&url, "ABCD").await,
+            RoomStatus::ServerUnreachable
+        );
+        let url = serve_once("404 Not Found");
+        assert_eq!(
+            started_room_status_at(&url, "ABCD").await,
+            RoomStatus::ServerUnreachable
+        );
+    }
+
+    #[tokio::test]
+    async fn test_server_unreachable() {
+        // Grab a free port and close it again so nothing is listening there
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let url = format!("http://{addr}");
+        assert_eq!(
+            room_status_at(&url, "ABCD", false).await,
+            RoomStatus::ServerUnreachable
+        );
+    }
+}
@@ -10,6 +10,7 @@ rustPlatform.buildRustPackage {
       root = ../../.;
       fileset = unions [
         ../../manhunt-app
+        ../../manhunt-common
         ../../manhunt-logic
         ../../manhunt-transport
         ../../manhunt-signaling